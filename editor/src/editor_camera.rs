@@ -0,0 +1,439 @@
+//! Editor viewport camera
+//!
+//! While editing, the viewport shows the scene through a camera owned by the
+//! editor instead of a `Camera` entity, so looking around never changes the
+//! scene or ends up in a saved file. The camera orbits a focus point kept in
+//! the large-world space of `CameraWorldPosition`, and can fly, pan, frame the
+//! selection and switch to orthographic top, front and side views.
+
+use crate::gizmo::GizmoTarget;
+use crate::play_mode::PlayState;
+use engine::core::camera::{Camera, CameraView, ProjectionMode};
+use engine::core::entity::World;
+use engine::graphics::{MeshId, AABB};
+use glam::{DVec3, EulerRot, Quat, Vec2, Vec3};
+use std::f32::consts::FRAC_PI_2;
+
+/// Radians the camera turns per pixel of mouse movement
+pub const ROTATE_SPEED: f32 = 0.005;
+/// Closest the camera gets to its focus point
+pub const MIN_DISTANCE: f32 = 0.05;
+/// Speed multiplier for flying with Shift held
+pub const FLY_BOOST: f32 = 4.0;
+/// Distance change per mouse wheel step
+const ZOOM_FACTOR: f32 = 0.85;
+/// Largest pitch of free views, just short of straight up or down
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+/// How far orthographic views sit behind the focus point
+const ORTHO_CLEARANCE: f32 = 5000.0;
+/// Radius framed for entities without a mesh
+const DEFAULT_RADIUS: f32 = 0.5;
+
+/// Direction the editor camera looks from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewAxis {
+    /// Free perspective view
+    Perspective,
+    /// Orthographic view looking down
+    Top,
+    /// Orthographic view looking along -Z
+    Front,
+    /// Orthographic view looking along -X
+    Side,
+}
+
+impl ViewAxis {
+    /// All views, in the order the toolbar lists them
+    pub const ALL: [ViewAxis; 4] = [Self::Perspective, Self::Top, Self::Front, Self::Side];
+
+    /// Name shown in the viewport toolbar
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Perspective => "Perspective",
+            Self::Top => "Top",
+            Self::Front => "Front",
+            Self::Side => "Side",
+        }
+    }
+
+    /// Whether the view uses an orthographic projection
+    pub fn is_orthographic(self) -> bool {
+        self != Self::Perspective
+    }
+
+    /// Yaw and pitch of the fixed views
+    fn angles(self) -> Option<(f32, f32)> {
+        match self {
+            Self::Perspective => None,
+            Self::Top => Some((0.0, -FRAC_PI_2)),
+            Self::Front => Some((0.0, 0.0)),
+            Self::Side => Some((FRAC_PI_2, 0.0)),
+        }
+    }
+}
+
+/// Mouse drag currently moving the editor camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Navigation {
+    /// Alt and left mouse button turn around the focus point
+    Orbit,
+    /// Middle mouse button moves the focus point sideways
+    Pan,
+    /// Right mouse button looks around, WASD and QE fly
+    Fly,
+}
+
+/// Camera the editor views the scene through
+///
+/// It isn't an entity, so it is never serialized with the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EditorCamera {
+    /// Point the camera orbits and zooms around
+    pub focus: DVec3,
+    /// Distance from the focus point, also the size of orthographic views
+    pub distance: f32,
+    /// Rotation around the world Y axis in radians
+    pub yaw: f32,
+    /// Rotation up and down in radians
+    pub pitch: f32,
+    /// Current view direction and projection
+    pub view_axis: ViewAxis,
+    /// Vertical field of view in degrees
+    pub fov_y_degrees: f32,
+    /// Flying speed in world units per second
+    pub fly_speed: f32,
+    /// Mouse drag in progress, if any
+    pub navigation: Option<Navigation>,
+    /// Width divided by height of the viewport
+    aspect_ratio: f32,
+}
+
+impl Default for EditorCamera {
+    fn default() -> Self {
+        Self {
+            focus: DVec3::ZERO,
+            distance: 10.0,
+            yaw: 0.0,
+            pitch: -0.35,
+            view_axis: ViewAxis::Perspective,
+            fov_y_degrees: 60.0,
+            fly_speed: 5.0,
+            navigation: None,
+            aspect_ratio: 16.0 / 9.0,
+        }
+    }
+}
+
+impl EditorCamera {
+    /// Create a camera looking at the origin
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a camera with the pose of a scene camera
+    ///
+    /// The focus point is placed in front of the camera at the default distance.
+    pub fn from_view(view: &CameraView) -> Self {
+        let (yaw, pitch, _) = view.rotation.to_euler(EulerRot::YXZ);
+        let mut camera = Self {
+            yaw,
+            pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+            aspect_ratio: view.camera.aspect_ratio,
+            ..Self::default()
+        };
+        if view.camera.projection_mode == ProjectionMode::Perspective {
+            camera.fov_y_degrees = view.camera.fov_y_radians.to_degrees();
+        }
+        camera.focus = view.world_position + (camera.forward() * camera.distance).as_dvec3();
+        camera
+    }
+
+    /// Camera orientation
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0)
+    }
+
+    /// Direction the camera looks in
+    pub fn forward(&self) -> Vec3 {
+        self.rotation() * Vec3::NEG_Z
+    }
+
+    /// Eye position of the perspective view
+    fn orbit_position(&self) -> DVec3 {
+        self.focus - (self.forward() * self.distance).as_dvec3()
+    }
+
+    /// Camera position in large-world coordinates
+    ///
+    /// Orthographic views are pulled far back so nothing in front of the focus
+    /// point is clipped, their size doesn't depend on the distance.
+    pub fn world_position(&self) -> DVec3 {
+        if self.view_axis.is_orthographic() {
+            self.focus - (self.forward() * ORTHO_CLEARANCE).as_dvec3()
+        } else {
+            self.orbit_position()
+        }
+    }
+
+    /// Match the viewport's width divided by height
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        if aspect_ratio.is_finite() && aspect_ratio > 0.0 {
+            self.aspect_ratio = aspect_ratio;
+        }
+    }
+
+    /// Height in world units of the area visible at the focus point
+    fn view_height(&self) -> f32 {
+        2.0 * self.distance * (self.fov_y_degrees.to_radians() * 0.5).tan()
+    }
+
+    /// Projection parameters of the current view
+    pub fn camera(&self) -> Camera {
+        if self.view_axis.is_orthographic() {
+            Camera::orthographic(
+                self.view_height(),
+                self.aspect_ratio,
+                1.0,
+                ORTHO_CLEARANCE * 2.0,
+            )
+        } else {
+            // The near plane follows the distance so close-ups don't clip
+            let z_near = (self.distance * 0.01).clamp(0.01, 1.0);
+            Camera::perspective(self.fov_y_degrees, self.aspect_ratio, z_near, 100_000.0)
+        }
+    }
+
+    /// View to render, pick and place gizmos with
+    pub fn view(&self) -> CameraView {
+        CameraView::new(self.camera(), self.world_position(), self.rotation())
+    }
+
+    /// Turn around the focus point by a mouse movement in pixels
+    ///
+    /// Leaves the orthographic views for a free perspective view.
+    pub fn orbit(&mut self, delta: Vec2) {
+        self.view_axis = ViewAxis::Perspective;
+        self.turn(delta);
+    }
+
+    /// Move the focus point sideways by a mouse movement in pixels
+    ///
+    /// The scene follows the cursor at the depth of the focus point.
+    pub fn pan(&mut self, delta: Vec2, viewport_height: f32) {
+        if viewport_height <= 0.0 {
+            return;
+        }
+        let units_per_pixel = self.view_height() / viewport_height;
+        let offset = self.rotation() * Vec3::new(-delta.x, delta.y, 0.0) * units_per_pixel;
+        self.focus += offset.as_dvec3();
+    }
+
+    /// Move towards or away from the focus point by mouse wheel steps
+    pub fn zoom(&mut self, steps: f32) {
+        self.distance = (self.distance * ZOOM_FACTOR.powf(steps)).max(MIN_DISTANCE);
+    }
+
+    /// Look around from the camera position and fly through the scene
+    ///
+    /// `look` is a mouse movement in pixels and `movement` a direction in
+    /// camera space, with -Z forward. The focus point moves along so orbiting
+    /// afterwards turns around the point in front of the camera.
+    pub fn fly(&mut self, look: Vec2, movement: Vec3, dt: f32, boost: bool) {
+        self.view_axis = ViewAxis::Perspective;
+        let eye = self.orbit_position();
+        self.turn(look);
+
+        let speed = self.fly_speed * if boost { FLY_BOOST } else { 1.0 };
+        let step = self.rotation() * movement.normalize_or_zero() * speed * dt;
+        self.focus = eye + (step + self.forward() * self.distance).as_dvec3();
+    }
+
+    /// Speed flying up or down by mouse wheel steps
+    pub fn adjust_fly_speed(&mut self, steps: f32) {
+        self.fly_speed = (self.fly_speed * ZOOM_FACTOR.powf(-steps)).clamp(0.1, 1000.0);
+    }
+
+    /// Frame a sphere, keeping the view direction
+    pub fn focus_on(&mut self, center: DVec3, radius: f32) {
+        let half_fov_y = self.fov_y_degrees.to_radians() * 0.5;
+        let half_fov_x = (half_fov_y.tan() * self.aspect_ratio).atan();
+        let half_fov = half_fov_y.min(half_fov_x);
+
+        self.focus = center;
+        self.distance = (radius.max(MIN_DISTANCE) / half_fov.sin()).max(MIN_DISTANCE);
+    }
+
+    /// Switch views, fixed views look at the current focus point
+    pub fn set_view_axis(&mut self, axis: ViewAxis) {
+        if let Some((yaw, pitch)) = axis.angles() {
+            self.yaw = yaw;
+            self.pitch = pitch;
+        }
+        self.view_axis = axis;
+    }
+
+    /// Change yaw and pitch by a mouse movement in pixels
+    fn turn(&mut self, delta: Vec2) {
+        self.yaw -= delta.x * ROTATE_SPEED;
+        self.pitch = (self.pitch - delta.y * ROTATE_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+}
+
+/// View the viewport shows the scene through
+///
+/// The editor camera while editing, the scene's active camera while the game
+/// runs. Returns None if the game has no camera.
+pub fn viewport_view(
+    world: &World,
+    editor_camera: &EditorCamera,
+    play_state: PlayState,
+) -> Option<CameraView> {
+    match play_state {
+        PlayState::Editing => Some(editor_camera.view()),
+        PlayState::Playing | PlayState::Paused => CameraView::resolve(world, None),
+    }
+}
+
+/// Bounding sphere of entities, as center and radius
+///
+/// Meshes are bounded by their AABB from `mesh_aabb`, other entities by a
+/// small sphere around their position. Returns None if none of the entities
+/// has a transform.
+pub fn selection_bounds(
+    world: &World,
+    entities: &[hecs::Entity],
+    mesh_aabb: impl Fn(&MeshId) -> Option<AABB>,
+) -> Option<(DVec3, f32)> {
+    let mut bounds: Option<(DVec3, DVec3)> = None;
+
+    for &entity in entities {
+        let Some(target) = GizmoTarget::capture(world, entity) else {
+            continue;
+        };
+        let aabb = world
+            .get::<MeshId>(entity)
+            .ok()
+            .and_then(|mesh_id| mesh_aabb(&*mesh_id));
+        let (center, radius) = match aabb {
+            Some(aabb) => {
+                let offset = target.world_rotation() * (aabb.center() * target.scale);
+                let radius = (aabb.size() * 0.5 * target.scale).length();
+                (target.world_position() + offset.as_dvec3(), radius)
+            }
+            None => (target.world_position(), DEFAULT_RADIUS),
+        };
+
+        let extent = DVec3::splat(radius as f64);
+        let (min, max) = (center - extent, center + extent);
+        bounds = Some(match bounds {
+            Some((bounds_min, bounds_max)) => (bounds_min.min(min), bounds_max.max(max)),
+            None => (min, max),
+        });
+    }
+
+    bounds.map(|(min, max)| ((min + max) * 0.5, ((max - min) * 0.5).length() as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::core::entity::components::WorldTransform;
+    use engine::core::entity::Transform;
+
+    fn assert_close(a: DVec3, b: DVec3) {
+        assert!(a.distance(b) < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_from_view_keeps_pose() {
+        let rotation = Quat::from_euler(EulerRot::YXZ, 0.7, -0.3, 0.0);
+        let position = DVec3::new(1.0e9, 20.0, -5.0);
+        let view = CameraView::new(Camera::default(), position, rotation);
+
+        let camera = EditorCamera::from_view(&view);
+        assert_close(camera.world_position(), position);
+        assert!(camera.rotation().angle_between(rotation) < 1e-4);
+        assert!((camera.fov_y_degrees - 60.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_orbit_pan_and_fly() {
+        let mut camera = EditorCamera::new();
+        camera.set_view_axis(ViewAxis::Front);
+        assert_close(camera.focus, DVec3::ZERO);
+
+        // Dragging right moves the camera left, so the scene follows the mouse
+        camera.pan(Vec2::new(100.0, 0.0), 1000.0);
+        assert!(camera.focus.x < 0.0);
+        assert_eq!(camera.focus.y, 0.0);
+
+        // Orbiting keeps the focus and distance but leaves the fixed view
+        let focus = camera.focus;
+        camera.orbit(Vec2::new(50.0, 20.0));
+        assert_eq!(camera.view_axis, ViewAxis::Perspective);
+        assert_close(camera.focus, focus);
+        assert!((camera.world_position().distance(focus) - 10.0).abs() < 1e-4);
+
+        // Flying forward moves the eye along the view direction
+        let eye = camera.world_position();
+        let forward = camera.forward().as_dvec3();
+        camera.fly(Vec2::ZERO, Vec3::NEG_Z, 1.0, false);
+        assert_close(camera.world_position(), eye + forward * 5.0);
+    }
+
+    #[test]
+    fn test_fixed_views_are_orthographic() {
+        let mut camera = EditorCamera::new();
+        camera.focus = DVec3::new(3.0, 0.0, 3.0);
+        camera.set_view_axis(ViewAxis::Top);
+
+        assert!(camera.forward().distance(Vec3::NEG_Y) < 1e-5);
+        assert!(matches!(
+            camera.camera().projection_mode,
+            ProjectionMode::Orthographic { .. }
+        ));
+        // Far above the focus so tall objects aren't clipped
+        assert!(camera.world_position().y > 1000.0);
+
+        // The focus point is in the middle of the view
+        let view = camera.view();
+        let relative = (camera.focus - view.world_position).as_vec3();
+        let clip = view.view_projection() * relative.extend(1.0);
+        assert!(clip.x.abs() < 1e-3 && clip.y.abs() < 1e-3);
+        assert!(clip.z > 0.0 && clip.z < clip.w);
+
+        camera.set_view_axis(ViewAxis::Side);
+        assert!(camera.forward().distance(Vec3::NEG_X) < 1e-5);
+    }
+
+    #[test]
+    fn test_focus_on_selection() {
+        let mut world = World::new();
+        let near = world.spawn((Transform::from_position(Vec3::new(-2.0, 0.0, 0.0)),));
+        let far = world.spawn((WorldTransform::from_position(DVec3::new(1.0e10, 0.0, 0.0)),));
+        let cube = world.spawn((
+            Transform::from_position(Vec3::new(2.0, 0.0, 0.0)),
+            MeshId("cube".to_string()),
+        ));
+        let unit_cube = |_: &MeshId| Some(AABB::new(Vec3::splat(-1.0), Vec3::splat(1.0)));
+
+        let (center, radius) = selection_bounds(&world, &[near, cube], unit_cube).unwrap();
+        // Spans from the edge of the empty at -2.5 to the cube corner sphere
+        let expected_x = (-2.5 + 2.0 + 3f64.sqrt()) * 0.5;
+        assert_close(center, DVec3::new(expected_x, 0.0, 0.0));
+        assert!(radius > 3.0);
+
+        // Large world positions keep their precision
+        let (center, _) = selection_bounds(&world, &[far], unit_cube).unwrap();
+        assert_close(center, DVec3::new(1.0e10, 0.0, 0.0));
+
+        // The whole sphere fits in the view
+        let mut camera = EditorCamera::new();
+        camera.focus_on(DVec3::ZERO, 2.0);
+        let view = camera.view();
+        let edge = camera.rotation() * Vec3::Y * 2.0;
+        let relative = (DVec3::ZERO - view.world_position).as_vec3() + edge;
+        let clip = view.view_projection() * relative.extend(1.0);
+        assert!(clip.y / clip.w < 1.0);
+    }
+}
@@ -3,10 +3,15 @@
 //! This module contains the EditorState struct which manages the imgui context,
 //! render target for viewport, and all editor UI state.
 
+use crate::editor_camera::{self, EditorCamera};
+use crate::gizmo::GizmoState;
 use crate::panel_state::PanelManager;
+use crate::picking::{self, ViewportPicker};
+use crate::play_mode::{PlayMode, PlayState};
 use crate::safe_imgui_renderer::SafeImGuiRenderer;
 use crate::settings::EditorSettings;
 use crate::shared_state::EditorSharedState;
+use engine::core::camera::CameraView;
 use engine::core::entity::World;
 use engine::graphics::{context::RenderContext, render_target::RenderTarget, RenderTargetInfo};
 use engine::profile_zone;
@@ -34,6 +39,12 @@ struct MenuActions {
     save_scene: bool,
     save_scene_as: bool,
     exit: bool,
+    undo: bool,
+    redo: bool,
+    play: bool,
+    pause: bool,
+    step: bool,
+    stop: bool,
     save_layout: bool,
     load_layout: bool,
     reset_layout: bool,
@@ -98,6 +109,18 @@ pub struct EditorState {
     pub error_message: Option<String>,
     /// Pending scene operation to be performed by main loop
     pub pending_scene_operation: Option<SceneOperation>,
+    /// Play mode state for running the game inside the editor
+    pub play_mode: PlayMode,
+    /// Transform gizmo of the viewport
+    pub gizmo: GizmoState,
+    /// Click and box selection in the viewport
+    pub picker: ViewportPicker,
+    /// Camera the viewport shows the scene through while editing
+    pub editor_camera: EditorCamera,
+    /// Move the editor camera to the scene camera on the next viewport render
+    reset_editor_camera: bool,
+    /// Frame the selection with the editor camera on the next viewport render
+    focus_selection: bool,
     /// Surface format for rendering
     surface_format: wgpu::TextureFormat,
     /// Panel manager
@@ -282,6 +305,12 @@ impl EditorState {
             pending_action: None,
             error_message: None,
             pending_scene_operation: None,
+            play_mode: PlayMode::new(),
+            gizmo: GizmoState::new(),
+            picker: ViewportPicker::new(),
+            editor_camera: EditorCamera::new(),
+            reset_editor_camera: true,
+            focus_selection: false,
             surface_format,
             panel_manager: PanelManager::with_layout_file(PanelManager::default_layout_path()),
             window_size: (
@@ -415,6 +444,44 @@ impl EditorState {
                                 self.show_settings_dialog = true;
                                 return true;
                             }
+                            PhysicalKey::Code(KeyCode::KeyP) => {
+                                if shift {
+                                    info!("Ctrl+Shift+P pressed - Pause/Resume");
+                                    match self.play_mode.state() {
+                                        PlayState::Playing => self.pause_action(),
+                                        PlayState::Paused => self.play_action(),
+                                        PlayState::Editing => {}
+                                    }
+                                } else {
+                                    info!("Ctrl+P pressed - Play/Stop");
+                                    if self.play_mode.is_editing() {
+                                        self.play_action();
+                                    } else {
+                                        self.stop_action();
+                                    }
+                                }
+                                return true;
+                            }
+                            // Text fields keep their own undo while focused
+                            PhysicalKey::Code(KeyCode::KeyZ)
+                                if !self.imgui_context.io().want_text_input =>
+                            {
+                                if shift {
+                                    info!("Ctrl+Shift+Z pressed - Redo");
+                                    self.redo_action();
+                                } else {
+                                    info!("Ctrl+Z pressed - Undo");
+                                    self.undo_action();
+                                }
+                                return true;
+                            }
+                            PhysicalKey::Code(KeyCode::KeyY)
+                                if !self.imgui_context.io().want_text_input =>
+                            {
+                                info!("Ctrl+Y pressed - Redo");
+                                self.redo_action();
+                                return true;
+                            }
                             _ => {}
                         }
                     }
@@ -437,17 +504,17 @@ impl EditorState {
                     return true;
                 }
 
-                // Check for Delete key to delete selected entity
+                // Check for Delete key to delete the selected entities
                 if let PhysicalKey::Code(KeyCode::Delete) = key_event.physical_key {
                     if self.ui_mode {
-                        if let Some(entity) = self.shared_state.selected_entity() {
-                            self.shared_state.with_world_write(|world| {
-                                if world.despawn(entity).is_ok() {
-                                    info!("Deleted entity {:?} via Delete key", entity);
-                                    self.shared_state.set_selected_entity(None);
-                                    self.shared_state.mark_scene_modified();
-                                }
-                            });
+                        let selection = self.shared_state.selected_entities();
+                        if !selection.is_empty() {
+                            let deleted = self.shared_state.despawn_entities(&selection);
+                            if deleted > 0 {
+                                info!("Deleted {} entities via Delete key", deleted);
+                                self.shared_state.set_selected_entity(None);
+                                self.shared_state.mark_scene_modified();
+                            }
                             return true;
                         }
                     }
@@ -574,17 +641,66 @@ impl EditorState {
             "Rendering game to viewport texture, render_target size: {:?}",
             self.render_target.size
         );
-        // Get the selected entity from shared state
-        let selected_entity = self.shared_state.selected_entity();
+        // A new scene starts out seen from its own camera
+        if std::mem::take(&mut self.reset_editor_camera) {
+            if let Some(view) = CameraView::resolve(world, None) {
+                self.editor_camera = EditorCamera::from_view(&view);
+            }
+        }
+        let (width, height) = self.render_target.size;
+        self.editor_camera
+            .set_aspect_ratio(width as f32 / height.max(1) as f32);
+
+        // Apply the last viewport click or box selection before drawing its outlines
+        let play_state = self.play_mode.state();
+        let view = editor_camera::viewport_view(world, &self.editor_camera, play_state);
+        if let Some(request) = self.picker.take_request() {
+            if let Some(view) = &view {
+                picking::resolve_pick(
+                    request,
+                    renderer,
+                    world,
+                    &self.shared_state,
+                    view,
+                    self.render_target.size,
+                    self.settings.viewport.gpu_picking,
+                );
+            }
+        }
 
-        // Render the game to our render target texture with selection highlighting
-        if let Err(e) =
-            renderer.render_to_target_with_selection(world, &self.render_target, selected_entity)
-        {
+        // Get the selected entities from shared state
+        let selected = self.shared_state.selected_entities();
+
+        if std::mem::take(&mut self.focus_selection) {
+            let bounds =
+                editor_camera::selection_bounds(world, &selected, |id| renderer.get_mesh_aabb(id));
+            if let Some((center, radius)) = bounds {
+                self.editor_camera.focus_on(center, radius);
+            }
+        }
+
+        // Render the game to our render target texture with selection highlighting,
+        // through the editor camera unless the game is running
+        let result = if play_state == PlayState::Editing {
+            renderer.render_view_to_target(
+                world,
+                &self.render_target,
+                &self.editor_camera.view(),
+                &selected,
+            )
+        } else {
+            renderer.render_to_target_with_selection(world, &self.render_target, &selected)
+        };
+        if let Err(e) = result {
             tracing::error!("Failed to render to viewport: {e:?}");
         }
     }
 
+    /// Move the editor camera to the scene camera, after a scene was created or loaded
+    pub fn reset_editor_camera(&mut self) {
+        self.reset_editor_camera = true;
+    }
+
     /// Render editor UI, then draw ImGui to the surface ---------------------------------
     pub fn render_ui_and_draw(
         &mut self,
@@ -616,6 +732,24 @@ impl EditorState {
             if actions.save_scene_as {
                 self.save_scene_as_action();
             }
+            if actions.undo {
+                self.undo_action();
+            }
+            if actions.redo {
+                self.redo_action();
+            }
+            if actions.play {
+                self.play_action();
+            }
+            if actions.pause {
+                self.pause_action();
+            }
+            if actions.step {
+                self.step_action();
+            }
+            if actions.stop {
+                self.stop_action();
+            }
             if actions.exit {
                 if self.scene_modified {
                     self.show_unsaved_dialog = true;
@@ -743,6 +877,12 @@ impl EditorState {
             let mut action_save_scene = false;
             let mut action_save_scene_as = false;
             let mut action_exit = false;
+            let mut action_undo = false;
+            let mut action_redo = false;
+            let mut action_play = false;
+            let mut action_pause = false;
+            let mut action_step = false;
+            let mut action_stop = false;
             let mut action_save_layout = false;
             let mut action_load_layout = false;
             let mut action_reset_layout = false;
@@ -772,6 +912,26 @@ impl EditorState {
                         action_exit = true;
                     }
                 });
+                ui.menu("Edit", || {
+                    let (can_undo, can_redo) = self
+                        .shared_state
+                        .with_history(|history| (history.can_undo(), history.can_redo()))
+                        .unwrap_or((false, false));
+                    if ui
+                        .menu_item_config("Undo##Ctrl+Z")
+                        .enabled(can_undo)
+                        .build()
+                    {
+                        action_undo = true;
+                    }
+                    if ui
+                        .menu_item_config("Redo##Ctrl+Y")
+                        .enabled(can_redo)
+                        .build()
+                    {
+                        action_redo = true;
+                    }
+                });
                 ui.menu("View", || {
                     if ui.menu_item("Save Layout") {
                         action_save_layout = true;
@@ -816,6 +976,12 @@ impl EditorState {
                     &mut self.panel_manager,
                     self.window_size,
                 );
+                crate::panels::render_history_panel(
+                    ui,
+                    &self.shared_state,
+                    &mut self.panel_manager,
+                    self.window_size,
+                );
 
                 // Central viewport that displays the 3D scene
                 crate::panels::render_viewport_panel(
@@ -826,9 +992,18 @@ impl EditorState {
                     &mut self.panel_manager,
                     self.window_size,
                     &mut self.performance_metrics,
+                    self.play_mode.state(),
+                    &mut self.gizmo,
+                    &mut self.picker,
+                    &mut self.editor_camera,
                 )
             };
 
+            // A finished drag or text edit becomes its own undo step
+            if !ui.is_any_item_active() && !self.gizmo.is_dragging() {
+                self.shared_state.end_command_coalescing();
+            }
+
             // Handle viewport actions
             if let Some(action) = viewport_action {
                 match action {
@@ -846,6 +1021,13 @@ impl EditorState {
                             self.pending_scene_operation = Some(SceneOperation::LoadScene(path));
                         }
                     }
+                    crate::panels::viewport::ViewportAction::Play => action_play = true,
+                    crate::panels::viewport::ViewportAction::Pause => action_pause = true,
+                    crate::panels::viewport::ViewportAction::Step => action_step = true,
+                    crate::panels::viewport::ViewportAction::Stop => action_stop = true,
+                    crate::panels::viewport::ViewportAction::FocusSelection => {
+                        self.focus_selection = true;
+                    }
                 }
             }
 
@@ -969,6 +1151,17 @@ impl EditorState {
                         }
                     }
 
+                    if ui.collapsing_header("Viewport", imgui::TreeNodeFlags::DEFAULT_OPEN) {
+                        if ui.checkbox("GPU picking", &mut self.settings.viewport.gpu_picking) {
+                            self.settings_modified = true;
+                        }
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(
+                                "Pick clicked entities from an ID render pass, exact but slower",
+                            );
+                        }
+                    }
+
                     ui.separator();
 
                     // Dialog buttons
@@ -1012,6 +1205,12 @@ impl EditorState {
                 save_scene: action_save_scene,
                 save_scene_as: action_save_scene_as,
                 exit: action_exit,
+                undo: action_undo,
+                redo: action_redo,
+                play: action_play,
+                pause: action_pause,
+                step: action_step,
+                stop: action_stop,
                 save_layout: action_save_layout,
                 load_layout: action_load_layout,
                 reset_layout: action_reset_layout,
@@ -1061,6 +1260,16 @@ impl EditorState {
                 ui.separator();
                 ui.same_line();
 
+                // Play state
+                match self.play_mode.state() {
+                    PlayState::Editing => ui.text("Editing"),
+                    PlayState::Playing => ui.text_colored([0.2, 0.8, 0.2, 1.0], "Playing"),
+                    PlayState::Paused => ui.text_colored([1.0, 0.6, 0.0, 1.0], "Paused"),
+                }
+                ui.same_line();
+                ui.separator();
+                ui.same_line();
+
                 // Entity count
                 let entity_count = self
                     .shared_state
@@ -1373,6 +1582,12 @@ impl EditorState {
 
     /// Save scene to specific path
     fn save_scene_to_path(&mut self, path: &PathBuf) -> bool {
+        // The world holds gameplay state while playing, not the authored scene
+        if !self.play_mode.is_editing() {
+            self.error_message = Some("Stop play mode before saving the scene".to_string());
+            return false;
+        }
+
         info!("Saving scene to: {:?}", path);
         self.pending_scene_operation = Some(SceneOperation::SaveScene(path.clone()));
         self.current_scene_path = Some(path.clone());
@@ -1399,6 +1614,42 @@ impl EditorState {
             .pick_file()
     }
 
+    /// Undo the most recent scene edit
+    pub fn undo_action(&mut self) {
+        if self.shared_state.undo().is_some() {
+            self.mark_scene_modified();
+        }
+    }
+
+    /// Redo the most recently undone scene edit
+    pub fn redo_action(&mut self) {
+        if self.shared_state.redo().is_some() {
+            self.mark_scene_modified();
+        }
+    }
+
+    /// Enter play mode, or resume when paused
+    pub fn play_action(&mut self) {
+        self.play_mode.play(&self.shared_state, self.scene_modified);
+    }
+
+    /// Pause the running game
+    pub fn pause_action(&mut self) {
+        self.play_mode.pause();
+    }
+
+    /// Advance the game by a single frame
+    pub fn step_action(&mut self) {
+        self.play_mode.step();
+    }
+
+    /// Stop playing and restore the scene from before play mode
+    pub fn stop_action(&mut self) {
+        if let Some(scene_modified) = self.play_mode.stop(&self.shared_state) {
+            self.scene_modified = scene_modified;
+        }
+    }
+
     /// Mark the scene as modified
     pub fn mark_scene_modified(&mut self) {
         self.scene_modified = true;
@@ -0,0 +1,746 @@
+//! Translate, rotate and scale gizmos for the viewport
+//!
+//! The gizmo works in camera-relative space like the renderer, so it stays
+//! precise for `WorldTransform` entities far from the origin. Drags are always
+//! evaluated against the transform captured when the drag started, and the
+//! result is converted back into the entity's local transform so parented
+//! entities move correctly.
+
+use crate::shared_state::EditorSharedState;
+use engine::core::camera::CameraView;
+use engine::core::entity::components::{GlobalWorldTransform, WorldTransform};
+use engine::core::entity::{GlobalTransform, Parent, Transform, World};
+use glam::{DMat4, DVec3, Mat4, Quat, Vec3};
+use tracing::debug;
+
+/// Length of the gizmo axes in pixels
+pub const GIZMO_SIZE: f32 = 90.0;
+
+/// Distance in pixels within which the mouse grabs an axis
+pub const HIT_DISTANCE: f32 = 8.0;
+
+/// Number of line segments used to draw a rotation ring
+const RING_SEGMENTS: usize = 64;
+
+/// Smallest scale factor a drag can produce, keeps scale from reaching zero
+const MIN_SCALE_FACTOR: f32 = 0.01;
+
+/// Axis colors, X red, Y green, Z blue
+pub const AXIS_COLORS: [[f32; 4]; 3] = [
+    [0.9, 0.2, 0.2, 1.0],
+    [0.3, 0.85, 0.3, 1.0],
+    [0.25, 0.45, 1.0, 1.0],
+];
+
+/// Color of the hovered or dragged axis
+pub const HIGHLIGHT_COLOR: [f32; 4] = [1.0, 0.85, 0.1, 1.0];
+
+/// What the gizmo manipulates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GizmoMode {
+    /// Move along an axis
+    #[default]
+    Translate,
+    /// Rotate around an axis
+    Rotate,
+    /// Scale along a local axis
+    Scale,
+}
+
+/// Orientation of the gizmo axes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GizmoSpace {
+    /// Axes aligned with the world
+    #[default]
+    World,
+    /// Axes aligned with the entity's rotation
+    Local,
+}
+
+impl GizmoSpace {
+    /// The other space
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::World => Self::Local,
+            Self::Local => Self::World,
+        }
+    }
+}
+
+/// Snapping increments applied while dragging
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GizmoSnap {
+    /// Whether drags snap to the increments
+    pub enabled: bool,
+    /// Translation increment in world units
+    pub translate: f32,
+    /// Rotation increment in degrees
+    pub rotate_degrees: f32,
+    /// Scale factor increment
+    pub scale: f32,
+}
+
+impl Default for GizmoSnap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            translate: 0.5,
+            rotate_degrees: 15.0,
+            scale: 0.1,
+        }
+    }
+}
+
+/// Round a value to the nearest multiple of `increment`
+fn snap_value(value: f32, increment: f32) -> f32 {
+    if increment > 0.0 {
+        (value / increment).round() * increment
+    } else {
+        value
+    }
+}
+
+/// Transform component the gizmo writes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetKind {
+    Transform,
+    WorldTransform,
+}
+
+/// Local transform of the selected entity together with its parent's world matrix
+#[derive(Debug, Clone, Copy)]
+pub struct GizmoTarget {
+    /// Entity being manipulated
+    pub entity: hecs::Entity,
+    kind: TargetKind,
+    /// Local position, in f64 so `WorldTransform` keeps its precision
+    pub position: DVec3,
+    /// Local rotation
+    pub rotation: Quat,
+    /// Local scale
+    pub scale: Vec3,
+    /// World matrix of the parent, identity for root entities
+    pub parent_matrix: DMat4,
+}
+
+impl GizmoTarget {
+    /// Read the transform of an entity
+    ///
+    /// Entities with a `WorldTransform` are edited through it, everything else
+    /// through `Transform`. Returns None for entities without either.
+    pub fn capture(world: &World, entity: hecs::Entity) -> Option<Self> {
+        let (kind, position, rotation, scale) =
+            if let Ok(transform) = world.get::<WorldTransform>(entity) {
+                (
+                    TargetKind::WorldTransform,
+                    transform.position,
+                    transform.rotation,
+                    transform.scale,
+                )
+            } else {
+                let transform = world.get::<Transform>(entity).ok()?;
+                (
+                    TargetKind::Transform,
+                    transform.position.as_dvec3(),
+                    transform.rotation,
+                    transform.scale,
+                )
+            };
+
+        // Large world parents keep their matrix in f64
+        let parent_matrix = world
+            .get::<Parent>(entity)
+            .ok()
+            .and_then(|parent| {
+                let parent = parent.0;
+                world
+                    .get::<GlobalWorldTransform>(parent)
+                    .map(|global| global.matrix)
+                    .or_else(|_| {
+                        world
+                            .get::<GlobalTransform>(parent)
+                            .map(|global| global.matrix.as_dmat4())
+                    })
+                    .ok()
+            })
+            .unwrap_or(DMat4::IDENTITY);
+
+        Some(Self {
+            entity,
+            kind,
+            position,
+            rotation,
+            scale,
+            parent_matrix,
+        })
+    }
+
+    /// Name of the component the gizmo edits, used for undo
+    pub fn component_name(&self) -> &'static str {
+        match self.kind {
+            TargetKind::Transform => "Transform",
+            TargetKind::WorldTransform => "WorldTransform",
+        }
+    }
+
+    /// World rotation of the parent
+    fn parent_rotation(&self) -> Quat {
+        let (_, rotation, _) = self.parent_matrix.to_scale_rotation_translation();
+        rotation.as_quat()
+    }
+
+    /// World-space position of the entity
+    pub fn world_position(&self) -> DVec3 {
+        self.parent_matrix.transform_point3(self.position)
+    }
+
+    /// World-space rotation of the entity
+    pub fn world_rotation(&self) -> Quat {
+        (self.parent_rotation() * self.rotation).normalize()
+    }
+
+    /// Move the entity to a world-space position
+    pub fn set_world_position(&mut self, position: DVec3) {
+        self.position = self.parent_matrix.inverse().transform_point3(position);
+    }
+
+    /// Give the entity a world-space rotation
+    pub fn set_world_rotation(&mut self, rotation: Quat) {
+        self.rotation = (self.parent_rotation().inverse() * rotation).normalize();
+    }
+
+    /// Write the local transform back to the entity
+    pub fn write(&self, world: &mut World) {
+        match self.kind {
+            TargetKind::Transform => {
+                if let Ok(transform) = world.query_one_mut::<&mut Transform>(self.entity) {
+                    transform.position = self.position.as_vec3();
+                    transform.rotation = self.rotation;
+                    transform.scale = self.scale;
+                }
+            }
+            TargetKind::WorldTransform => {
+                if let Ok(transform) = world.query_one_mut::<&mut WorldTransform>(self.entity) {
+                    transform.position = self.position;
+                    transform.rotation = self.rotation;
+                    transform.scale = self.scale;
+                }
+            }
+        }
+    }
+}
+
+/// Maps between the world and the pixels of the viewport image
+#[derive(Debug, Clone, Copy)]
+pub struct ViewportProjection {
+    /// Camera-relative view-projection matrix
+    view_proj: Mat4,
+    /// Camera position in world space
+    camera_position: DVec3,
+    /// Camera orientation
+    camera_rotation: Quat,
+    /// Top-left corner of the viewport image in screen pixels
+    rect_min: [f32; 2],
+    /// Size of the viewport image in screen pixels
+    rect_size: [f32; 2],
+}
+
+impl ViewportProjection {
+    /// Create a projection for a camera drawn into a screen rectangle
+    pub fn new(view: &CameraView, rect_min: [f32; 2], rect_size: [f32; 2]) -> Self {
+        Self {
+            view_proj: view.view_projection(),
+            camera_position: view.world_position,
+            camera_rotation: view.rotation,
+            rect_min,
+            rect_size,
+        }
+    }
+
+    /// Position relative to the camera, precise enough for f32 math
+    pub fn relative(&self, world_position: DVec3) -> Vec3 {
+        (world_position - self.camera_position).as_vec3()
+    }
+
+    /// Project a camera-relative point to screen pixels
+    ///
+    /// Returns None for points behind the camera.
+    pub fn project(&self, relative: Vec3) -> Option<[f32; 2]> {
+        let clip = self.view_proj * relative.extend(1.0);
+        if clip.w <= 1e-6 {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        Some([
+            self.rect_min[0] + (ndc.x * 0.5 + 0.5) * self.rect_size[0],
+            self.rect_min[1] + (0.5 - ndc.y * 0.5) * self.rect_size[1],
+        ])
+    }
+
+    /// Camera-relative ray through a screen pixel, as origin and direction
+    pub fn ray(&self, screen: [f32; 2]) -> (Vec3, Vec3) {
+        let ndc_x = (screen[0] - self.rect_min[0]) / self.rect_size[0] * 2.0 - 1.0;
+        let ndc_y = 1.0 - (screen[1] - self.rect_min[1]) / self.rect_size[1] * 2.0;
+        let inverse = self.view_proj.inverse();
+
+        // Far planes can be huge, so aim through a point close to the near plane
+        let near = inverse.project_point3(Vec3::new(ndc_x, ndc_y, 0.0));
+        let ahead = inverse.project_point3(Vec3::new(ndc_x, ndc_y, 0.5));
+        (near, (ahead - near).normalize_or_zero())
+    }
+
+    /// Size in world units of one screen pixel at a camera-relative point
+    pub fn world_per_pixel(&self, relative: Vec3) -> Option<f32> {
+        let center = self.project(relative)?;
+        let up = self.project(relative + self.camera_rotation * Vec3::Y)?;
+        let pixels = ((up[0] - center[0]).powi(2) + (up[1] - center[1]).powi(2)).sqrt();
+        (pixels > 1e-6).then_some(1.0 / pixels)
+    }
+}
+
+/// Parameter of the point on an axis line closest to a ray
+///
+/// Returns None when the axis points along the ray, where the drag would be
+/// unstable.
+fn closest_axis_parameter(origin: Vec3, axis: Vec3, ray: (Vec3, Vec3)) -> Option<f32> {
+    let (ray_origin, ray_dir) = ray;
+    let w = origin - ray_origin;
+    let b = axis.dot(ray_dir);
+    let denom = 1.0 - b * b;
+    if denom < 1e-4 {
+        return None;
+    }
+    let d = axis.dot(w);
+    let e = ray_dir.dot(w);
+    Some((b * e - d) / denom)
+}
+
+/// Angle around `axis` of the point where the ray hits the rotation plane
+fn ring_angle(origin: Vec3, axis: Vec3, ray: (Vec3, Vec3)) -> Option<f32> {
+    let (ray_origin, ray_dir) = ray;
+    let denom = axis.dot(ray_dir);
+    if denom.abs() < 1e-4 {
+        return None;
+    }
+    let distance = axis.dot(origin - ray_origin) / denom;
+    let hit = ray_origin + ray_dir * distance - origin;
+
+    let u = axis.any_orthonormal_vector();
+    let v = axis.cross(u);
+    Some(hit.dot(v).atan2(hit.dot(u)))
+}
+
+/// Distance in pixels from a point to a polyline
+fn distance_to_polyline(point: [f32; 2], points: &[[f32; 2]]) -> f32 {
+    points
+        .windows(2)
+        .map(|segment| {
+            let (a, b) = (segment[0], segment[1]);
+            let ab = [b[0] - a[0], b[1] - a[1]];
+            let ap = [point[0] - a[0], point[1] - a[1]];
+            let length_sq = ab[0] * ab[0] + ab[1] * ab[1];
+            let t = if length_sq > 0.0 {
+                ((ap[0] * ab[0] + ap[1] * ab[1]) / length_sq).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let closest = [a[0] + ab[0] * t, a[1] + ab[1] * t];
+            ((point[0] - closest[0]).powi(2) + (point[1] - closest[1]).powi(2)).sqrt()
+        })
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Screen-space shape of the gizmo for one frame
+#[derive(Debug, Clone)]
+pub struct GizmoHandles {
+    /// Mode the handles were built for
+    pub mode: GizmoMode,
+    /// Gizmo center in screen pixels
+    pub origin: [f32; 2],
+    /// Polyline of each axis handle in screen pixels
+    pub axes: [Vec<[f32; 2]>; 3],
+    /// Axis under the mouse or being dragged
+    pub active_axis: Option<usize>,
+}
+
+/// Frame input for the gizmo, in screen pixels
+#[derive(Debug, Clone, Copy)]
+pub struct GizmoInput {
+    /// Mouse position
+    pub mouse: [f32; 2],
+    /// Whether the mouse is over the viewport image
+    pub hovered: bool,
+    /// Whether the left button was pressed this frame
+    pub clicked: bool,
+    /// Whether the left button is held
+    pub down: bool,
+}
+
+/// An axis drag in progress
+#[derive(Debug, Clone, Copy)]
+struct GizmoDrag {
+    /// Dragged axis index
+    axis: usize,
+    /// Entity transform when the drag started
+    start: GizmoTarget,
+    /// World direction of the dragged axis
+    direction: Vec3,
+    /// Gizmo length in world units when the drag started
+    length: f32,
+    /// Axis parameter or ring angle under the mouse when the drag started
+    start_value: f32,
+}
+
+/// Gizmo mode, settings and drag state of the viewport
+#[derive(Debug, Default)]
+pub struct GizmoState {
+    /// Active manipulation mode
+    pub mode: GizmoMode,
+    /// Axis orientation for translation and rotation
+    pub space: GizmoSpace,
+    /// Snapping settings
+    pub snap: GizmoSnap,
+    /// Drag in progress, if any
+    drag: Option<GizmoDrag>,
+}
+
+impl GizmoState {
+    /// Create a gizmo in translate mode with world axes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether an axis is being dragged
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// World directions of the gizmo axes for a target
+    fn axis_directions(&self, target: &GizmoTarget) -> [Vec3; 3] {
+        // Scale is always applied along the entity's own axes
+        let rotation = if self.space == GizmoSpace::Local || self.mode == GizmoMode::Scale {
+            target.world_rotation()
+        } else {
+            Quat::IDENTITY
+        };
+        [
+            (rotation * Vec3::X).normalize(),
+            (rotation * Vec3::Y).normalize(),
+            (rotation * Vec3::Z).normalize(),
+        ]
+    }
+
+    /// Build the screen-space handles for a target
+    fn build_handles(
+        &self,
+        projection: &ViewportProjection,
+        target: &GizmoTarget,
+    ) -> Option<(GizmoHandles, f32)> {
+        let origin = projection.relative(target.world_position());
+        let screen_origin = projection.project(origin)?;
+        let length = projection.world_per_pixel(origin)? * GIZMO_SIZE;
+        let directions = self.axis_directions(target);
+
+        let axes = directions.map(|direction| match self.mode {
+            GizmoMode::Translate | GizmoMode::Scale => projection
+                .project(origin + direction * length)
+                .map(|tip| vec![screen_origin, tip])
+                .unwrap_or_default(),
+            GizmoMode::Rotate => {
+                let u = direction.any_orthonormal_vector();
+                let v = direction.cross(u);
+                (0..=RING_SEGMENTS)
+                    .filter_map(|i| {
+                        let angle = i as f32 / RING_SEGMENTS as f32 * std::f32::consts::TAU;
+                        let offset = (u * angle.cos() + v * angle.sin()) * length;
+                        projection.project(origin + offset)
+                    })
+                    .collect()
+            }
+        });
+
+        Some((
+            GizmoHandles {
+                mode: self.mode,
+                origin: screen_origin,
+                axes,
+                active_axis: None,
+            },
+            length,
+        ))
+    }
+
+    /// Axis parameter or ring angle under the mouse
+    fn drag_value(
+        &self,
+        projection: &ViewportProjection,
+        origin: Vec3,
+        direction: Vec3,
+        mouse: [f32; 2],
+    ) -> Option<f32> {
+        let ray = projection.ray(mouse);
+        match self.mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                closest_axis_parameter(origin, direction, ray)
+            }
+            GizmoMode::Rotate => ring_angle(origin, direction, ray),
+        }
+    }
+
+    /// Transform resulting from dragging the mouse to `value`
+    fn dragged_target(&self, drag: &GizmoDrag, value: f32) -> GizmoTarget {
+        let mut target = drag.start;
+        let delta = value - drag.start_value;
+
+        match self.mode {
+            GizmoMode::Translate => {
+                let distance = if self.snap.enabled {
+                    snap_value(delta, self.snap.translate)
+                } else {
+                    delta
+                };
+                let position =
+                    drag.start.world_position() + drag.direction.as_dvec3() * distance as f64;
+                target.set_world_position(position);
+            }
+            GizmoMode::Rotate => {
+                let angle = if self.snap.enabled {
+                    snap_value(delta, self.snap.rotate_degrees.to_radians())
+                } else {
+                    delta
+                };
+                let rotation = Quat::from_axis_angle(drag.direction, angle);
+                target.set_world_rotation(rotation * drag.start.world_rotation());
+            }
+            GizmoMode::Scale => {
+                let mut change = delta / drag.length;
+                if self.snap.enabled {
+                    change = snap_value(change, self.snap.scale);
+                }
+                let factor = (1.0 + change).max(MIN_SCALE_FACTOR);
+                target.scale[drag.axis] = drag.start.scale[drag.axis] * factor;
+            }
+        }
+
+        target
+    }
+
+    /// Run the gizmo for one frame
+    ///
+    /// Hit tests the handles of the selected entity, starts and ends drags, and
+    /// writes dragged transforms to the world as undoable edits. Returns the
+    /// handles to draw, or None when nothing is selected or the entity is
+    /// behind the camera.
+    pub fn update(
+        &mut self,
+        shared_state: &EditorSharedState,
+        projection: &ViewportProjection,
+        input: GizmoInput,
+    ) -> Option<GizmoHandles> {
+        let selected = shared_state.selected_entity();
+        let target = selected.and_then(|entity| {
+            shared_state
+                .with_world_read(|world| GizmoTarget::capture(world, entity))
+                .flatten()
+        });
+
+        let Some(target) = target else {
+            self.drag = None;
+            return None;
+        };
+
+        // Drop drags of an entity that is no longer selected
+        if self
+            .drag
+            .is_some_and(|drag| drag.start.entity != target.entity)
+        {
+            self.drag = None;
+        }
+
+        if let Some(drag) = self.drag {
+            if !input.down {
+                debug!(entity = ?target.entity, mode = ?self.mode, "Finished gizmo drag");
+                self.drag = None;
+                shared_state.end_command_coalescing();
+            } else {
+                let origin = projection.relative(drag.start.world_position());
+                if let Some(value) =
+                    self.drag_value(projection, origin, drag.direction, input.mouse)
+                {
+                    let dragged = self.dragged_target(&drag, value);
+                    shared_state.edit_component(
+                        dragged.entity,
+                        dragged.component_name(),
+                        |world| dragged.write(world),
+                    );
+                    shared_state.mark_scene_modified();
+                }
+            }
+        }
+
+        let (mut handles, length) = self.build_handles(projection, &target)?;
+
+        if let Some(drag) = self.drag {
+            handles.active_axis = Some(drag.axis);
+            return Some(handles);
+        }
+
+        if input.hovered {
+            handles.active_axis = handles
+                .axes
+                .iter()
+                .enumerate()
+                .map(|(axis, points)| (axis, distance_to_polyline(input.mouse, points)))
+                .filter(|(_, distance)| *distance <= HIT_DISTANCE)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(axis, _)| axis);
+        }
+
+        if input.clicked {
+            if let Some(axis) = handles.active_axis {
+                let direction = self.axis_directions(&target)[axis];
+                let origin = projection.relative(target.world_position());
+                if let Some(start_value) =
+                    self.drag_value(projection, origin, direction, input.mouse)
+                {
+                    debug!(entity = ?target.entity, mode = ?self.mode, axis, "Started gizmo drag");
+                    // Each drag is its own undo step
+                    shared_state.end_command_coalescing();
+                    self.drag = Some(GizmoDrag {
+                        axis,
+                        start: target,
+                        direction,
+                        length,
+                        start_value,
+                    });
+                }
+            }
+        }
+
+        Some(handles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::core::camera::Camera;
+    use engine::core::entity::Name;
+
+    fn projection() -> ViewportProjection {
+        let view = CameraView::new(
+            Camera::perspective(60.0, 1.0, 0.1, 1000.0),
+            DVec3::new(0.0, 0.0, 10.0),
+            Quat::IDENTITY,
+        );
+        ViewportProjection::new(&view, [0.0, 0.0], [800.0, 800.0])
+    }
+
+    #[test]
+    fn test_projection_round_trip() {
+        let projection = projection();
+
+        // The world origin is straight ahead of the camera
+        let center = projection
+            .project(projection.relative(DVec3::ZERO))
+            .unwrap();
+        assert!((center[0] - 400.0).abs() < 1e-3 && (center[1] - 400.0).abs() < 1e-3);
+
+        let (origin, direction) = projection.ray([400.0, 400.0]);
+        assert!(origin.x.abs() < 1e-4 && origin.y.abs() < 1e-4);
+        assert!((direction - Vec3::NEG_Z).length() < 1e-4);
+
+        // Points behind the camera are not drawn
+        assert!(projection.project(Vec3::new(0.0, 0.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn test_closest_axis_parameter() {
+        let ray = (Vec3::new(2.0, 0.0, 0.0), Vec3::NEG_Z);
+        let t = closest_axis_parameter(Vec3::new(0.0, 0.0, -10.0), Vec3::X, ray).unwrap();
+        assert!((t - 2.0).abs() < 1e-4);
+
+        // An axis pointing into the screen can't be dragged
+        assert!(closest_axis_parameter(Vec3::ZERO, Vec3::Z, ray).is_none());
+    }
+
+    #[test]
+    fn test_snap_value() {
+        assert_eq!(snap_value(1.3, 0.5), 1.5);
+        assert_eq!(snap_value(-0.2, 0.5), 0.0);
+        assert_eq!(snap_value(0.37, 0.0), 0.37);
+    }
+
+    #[test]
+    fn test_parented_world_position() {
+        let mut world = World::new();
+        let parent_transform = Transform {
+            position: Vec3::new(5.0, 0.0, 0.0),
+            rotation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            scale: Vec3::splat(2.0),
+        };
+        let parent = world.spawn((
+            parent_transform,
+            GlobalTransform::from_matrix(parent_transform.to_matrix()),
+        ));
+        let child = world.spawn((
+            Name::new("Child"),
+            Transform::from_position(Vec3::X),
+            Parent(parent),
+        ));
+
+        // The parent turns local +X into world -Z and doubles its length
+        let mut target = GizmoTarget::capture(&world, child).unwrap();
+        let expected = Vec3::new(5.0, 0.0, -2.0);
+        assert!((target.world_position().as_vec3() - expected).length() < 1e-4);
+
+        // Moving one unit up in world space is half a unit in the scaled parent
+        target.set_world_position(target.world_position() + DVec3::Y);
+        target.write(&mut world);
+        let local = world.get::<Transform>(child).unwrap().position;
+        assert!((local - Vec3::new(1.0, 0.5, 0.0)).length() < 1e-4);
+    }
+
+    #[test]
+    fn test_world_transform_target_keeps_precision() {
+        let mut world = World::new();
+        let entity = world.spawn((WorldTransform::from_position(DVec3::new(1.0e9, 0.0, 0.0)),));
+
+        let mut target = GizmoTarget::capture(&world, entity).unwrap();
+        assert_eq!(target.component_name(), "WorldTransform");
+        target.set_world_position(target.world_position() + DVec3::new(0.25, 0.0, 0.0));
+        target.write(&mut world);
+
+        let position = world.get::<WorldTransform>(entity).unwrap().position;
+        assert_eq!(position, DVec3::new(1.0e9 + 0.25, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_snapped_rotation_drag() {
+        let mut world = World::new();
+        let entity = world.spawn((Transform::default(),));
+        let target = GizmoTarget::capture(&world, entity).unwrap();
+
+        let gizmo = GizmoState {
+            mode: GizmoMode::Rotate,
+            snap: GizmoSnap {
+                enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let drag = GizmoDrag {
+            axis: 1,
+            start: target,
+            direction: Vec3::Y,
+            length: 1.0,
+            start_value: 0.0,
+        };
+
+        // 20 degrees snaps to 15
+        let dragged = gizmo.dragged_target(&drag, 20f32.to_radians());
+        dragged.write(&mut world);
+        let rotation = world.get::<Transform>(entity).unwrap().rotation;
+        assert!(rotation.angle_between(Quat::from_rotation_y(15f32.to_radians())) < 1e-4);
+    }
+}
@@ -0,0 +1,724 @@
+//! Undoable editor command history
+//!
+//! Panels describe every scene edit as an [`EditorCommand`] and record it in the
+//! [`CommandHistory`] after applying it to the world. Undo and redo replay the
+//! recorded state through the component registry, so any registered component
+//! can be restored without the history knowing its concrete type.
+
+use engine::component_system::ComponentRegistryExt;
+use engine::core::entity::{update_hierarchy_system, Parent, Transform, World};
+use engine::io::component_registry::ComponentRegistry;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+/// Maximum number of commands kept on the undo stack
+pub const MAX_HISTORY_LEN: usize = 200;
+
+/// Serialized state of a single entity, used to bring it back after a despawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySnapshot {
+    /// Entity handle, reused when the entity is respawned
+    pub entity: hecs::Entity,
+    /// Parent entity, if any
+    pub parent: Option<hecs::Entity>,
+    /// Registered components by name
+    pub components: Vec<(String, Value)>,
+}
+
+impl EntitySnapshot {
+    /// Capture all registered components of an entity
+    ///
+    /// Returns None if the entity doesn't exist.
+    pub fn capture(
+        world: &World,
+        registry: &ComponentRegistry,
+        entity: hecs::Entity,
+    ) -> Option<Self> {
+        if !world.contains(entity) {
+            return None;
+        }
+
+        let mut components = Vec::new();
+        for metadata in registry.iter_metadata() {
+            match (metadata.serialize_component)(world, entity) {
+                Some(Ok(value)) => components.push((metadata.name.to_string(), value)),
+                Some(Err(e)) => {
+                    warn!(entity = ?entity, component = metadata.name, error = %e, "Failed to snapshot component");
+                }
+                None => {}
+            }
+        }
+        components.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Some(Self {
+            entity,
+            parent: world.get::<Parent>(entity).ok().map(|parent| parent.0),
+            components,
+        })
+    }
+
+    /// Display name taken from the Name component, if any
+    pub fn name(&self) -> Option<&str> {
+        self.components
+            .iter()
+            .find(|(name, _)| name == "Name")
+            .and_then(|(_, value)| value.as_str())
+    }
+}
+
+/// Serialize one registered component of an entity, None if it's absent
+pub fn component_value(
+    world: &World,
+    registry: &ComponentRegistry,
+    entity: hecs::Entity,
+    component: &str,
+) -> Option<Value> {
+    let metadata = registry.get_metadata_by_name(component)?;
+    match (metadata.serialize_component)(world, entity)? {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!(entity = ?entity, component, error = %e, "Failed to serialize component");
+            None
+        }
+    }
+}
+
+/// Parent and local transform of an entity, before or after a reparent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParentState {
+    /// Parent entity, or None for a root entity
+    pub parent: Option<hecs::Entity>,
+    /// Local transform that keeps the entity's world position under this parent
+    pub transform: Option<Transform>,
+}
+
+impl ParentState {
+    /// Read the current parent state of an entity
+    pub fn capture(world: &World, entity: hecs::Entity) -> Self {
+        Self {
+            parent: world.get::<Parent>(entity).ok().map(|parent| parent.0),
+            transform: world
+                .get::<Transform>(entity)
+                .ok()
+                .map(|transform| *transform),
+        }
+    }
+
+    fn apply(&self, world: &mut World, entity: hecs::Entity) {
+        let _ = world.inner_mut().remove_one::<Parent>(entity);
+        if let Some(parent) = self.parent.filter(|&parent| world.contains(parent)) {
+            let _ = world.insert_one(entity, Parent(parent));
+        }
+        if let Some(transform) = self.transform {
+            let _ = world.insert_one(entity, transform);
+        }
+        update_hierarchy_system(world);
+    }
+}
+
+/// A single undoable edit
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditorCommand {
+    /// Edit, add or remove one registered component, `None` meaning absent
+    SetComponent {
+        entity: hecs::Entity,
+        component: String,
+        before: Option<Value>,
+        after: Option<Value>,
+    },
+    /// Entities created in the editor (new entity, duplicate, model import)
+    Spawn {
+        label: String,
+        snapshots: Vec<EntitySnapshot>,
+    },
+    /// Entities deleted in the editor
+    Despawn {
+        label: String,
+        snapshots: Vec<EntitySnapshot>,
+    },
+    /// Move an entity to a different parent
+    Reparent {
+        entity: hecs::Entity,
+        before: ParentState,
+        after: ParentState,
+    },
+    /// Several commands applied together, e.g. one edit of a multi-selection
+    Batch {
+        label: String,
+        commands: Vec<EditorCommand>,
+    },
+}
+
+impl EditorCommand {
+    /// Record a change to one component between two snapshots of its value
+    pub fn set_component(
+        entity: hecs::Entity,
+        component: impl Into<String>,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Self {
+        Self::SetComponent {
+            entity,
+            component: component.into(),
+            before,
+            after,
+        }
+    }
+
+    /// Group commands into one undo step
+    ///
+    /// A single command is returned unchanged.
+    pub fn batch(label: impl Into<String>, mut commands: Vec<EditorCommand>) -> Self {
+        if commands.len() == 1 {
+            return commands.remove(0);
+        }
+        Self::Batch {
+            label: label.into(),
+            commands,
+        }
+    }
+
+    /// Short description shown in the history panel
+    pub fn label(&self) -> String {
+        match self {
+            Self::SetComponent {
+                component,
+                before,
+                after,
+                ..
+            } => match (before, after) {
+                (None, Some(_)) => format!("Add {component}"),
+                (Some(_), None) => format!("Remove {component}"),
+                _ => format!("Edit {component}"),
+            },
+            Self::Spawn { label, .. } | Self::Despawn { label, .. } | Self::Batch { label, .. } => {
+                label.clone()
+            }
+            Self::Reparent { after, .. } => {
+                if after.parent.is_some() {
+                    "Reparent Entity".to_string()
+                } else {
+                    "Unparent Entity".to_string()
+                }
+            }
+        }
+    }
+
+    /// Whether applying the command would leave the world unchanged
+    pub fn is_noop(&self) -> bool {
+        match self {
+            Self::SetComponent { before, after, .. } => before == after,
+            Self::Spawn { snapshots, .. } | Self::Despawn { snapshots, .. } => snapshots.is_empty(),
+            Self::Reparent { before, after, .. } => before == after,
+            Self::Batch { commands, .. } => commands.iter().all(EditorCommand::is_noop),
+        }
+    }
+
+    /// Whether the command only edits components and may absorb follow-up edits
+    fn is_component_edit(&self) -> bool {
+        match self {
+            Self::SetComponent { .. } => true,
+            Self::Batch { commands, .. } => commands.iter().all(EditorCommand::is_component_edit),
+            _ => false,
+        }
+    }
+
+    /// Fold a follow-up edit of the same component into this command
+    ///
+    /// Returns false if the commands can't be merged.
+    fn merge(&mut self, next: &EditorCommand) -> bool {
+        match (self, next) {
+            (
+                Self::SetComponent {
+                    entity,
+                    component,
+                    after,
+                    ..
+                },
+                Self::SetComponent {
+                    entity: next_entity,
+                    component: next_component,
+                    after: next_after,
+                    ..
+                },
+            ) if entity == next_entity && component == next_component => {
+                *after = next_after.clone();
+                true
+            }
+            // Batches merge pairwise when they edit the same components
+            (Self::Batch { commands, .. }, Self::Batch { commands: next, .. })
+                if commands.len() == next.len()
+                    && commands.iter().zip(next).all(|(a, b)| a.same_target(b)) =>
+            {
+                for (command, next) in commands.iter_mut().zip(next) {
+                    command.merge(next);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether both commands edit the same component of the same entity
+    fn same_target(&self, other: &EditorCommand) -> bool {
+        match (self, other) {
+            (
+                Self::SetComponent {
+                    entity, component, ..
+                },
+                Self::SetComponent {
+                    entity: other_entity,
+                    component: other_component,
+                    ..
+                },
+            ) => entity == other_entity && component == other_component,
+            _ => false,
+        }
+    }
+
+    /// Apply the command to the world (redo)
+    pub fn apply(&self, world: &mut World, registry: &ComponentRegistry) {
+        match self {
+            Self::SetComponent {
+                entity,
+                component,
+                after,
+                ..
+            } => set_component(world, registry, *entity, component, after.as_ref()),
+            Self::Spawn { snapshots, .. } => restore_entities(world, registry, snapshots),
+            Self::Despawn { snapshots, .. } => despawn_entities(world, snapshots),
+            Self::Reparent { entity, after, .. } => after.apply(world, *entity),
+            Self::Batch { commands, .. } => {
+                for command in commands {
+                    command.apply(world, registry);
+                }
+            }
+        }
+    }
+
+    /// Revert the command in the world (undo)
+    pub fn revert(&self, world: &mut World, registry: &ComponentRegistry) {
+        match self {
+            Self::SetComponent {
+                entity,
+                component,
+                before,
+                ..
+            } => set_component(world, registry, *entity, component, before.as_ref()),
+            Self::Spawn { snapshots, .. } => despawn_entities(world, snapshots),
+            Self::Despawn { snapshots, .. } => restore_entities(world, registry, snapshots),
+            Self::Reparent { entity, before, .. } => before.apply(world, *entity),
+            Self::Batch { commands, .. } => {
+                for command in commands.iter().rev() {
+                    command.revert(world, registry);
+                }
+            }
+        }
+    }
+}
+
+/// Set a registered component to a serialized value, or remove it for `None`
+fn set_component(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    entity: hecs::Entity,
+    component: &str,
+    value: Option<&Value>,
+) {
+    let Some(metadata) = registry.get_metadata_by_name(component) else {
+        warn!(component, "Component not registered, can't restore it");
+        return;
+    };
+
+    let result = match value {
+        Some(value) => (metadata.insert_component)(world, entity, value),
+        None => (metadata.remove_component)(world, entity),
+    };
+    if let Err(e) = result {
+        warn!(entity = ?entity, component, error = %e, "Failed to restore component");
+    }
+}
+
+/// Respawn entities from snapshots, keeping their original handles
+fn restore_entities(world: &mut World, registry: &ComponentRegistry, snapshots: &[EntitySnapshot]) {
+    // Spawn everything first so parents inside the same command exist
+    for snapshot in snapshots {
+        world.inner_mut().spawn_at(snapshot.entity, ());
+        for (component, value) in &snapshot.components {
+            set_component(world, registry, snapshot.entity, component, Some(value));
+        }
+    }
+
+    for snapshot in snapshots {
+        if let Some(parent) = snapshot.parent.filter(|&parent| world.contains(parent)) {
+            let _ = world.insert_one(snapshot.entity, Parent(parent));
+        }
+    }
+
+    update_hierarchy_system(world);
+}
+
+fn despawn_entities(world: &mut World, snapshots: &[EntitySnapshot]) {
+    for snapshot in snapshots.iter().rev() {
+        if world.despawn(snapshot.entity).is_err() {
+            debug!(entity = ?snapshot.entity, "Entity already gone");
+        }
+    }
+}
+
+/// Undo and redo stacks of editor commands
+#[derive(Debug, Clone, Default)]
+pub struct CommandHistory {
+    /// Applied commands, oldest first
+    undo_stack: Vec<EditorCommand>,
+    /// Undone commands, most recently undone last
+    redo_stack: Vec<EditorCommand>,
+    /// Whether the newest command still absorbs edits of the same component
+    coalescing: bool,
+}
+
+impl CommandHistory {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a command that has already been applied to the world
+    ///
+    /// Consecutive edits of the same component, or of the same components of
+    /// a multi-selection, are merged into one command until [`end_coalescing`](Self::end_coalescing) is called, so a slider
+    /// drag undoes in one step.
+    pub fn push(&mut self, command: EditorCommand) {
+        if self.coalescing {
+            if let Some(last) = self.undo_stack.last_mut() {
+                if last.merge(&command) {
+                    // Dragging back to the starting value leaves nothing to undo
+                    if last.is_noop() {
+                        self.undo_stack.pop();
+                        self.coalescing = false;
+                    }
+                    return;
+                }
+            }
+        }
+
+        if command.is_noop() {
+            return;
+        }
+
+        debug!(command = %command.label(), "Recorded editor command");
+        self.coalescing = command.is_component_edit();
+        self.redo_stack.clear();
+        self.undo_stack.push(command);
+        if self.undo_stack.len() > MAX_HISTORY_LEN {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Stop merging edits into the newest command
+    ///
+    /// Called once no widget is being edited any more.
+    pub fn end_coalescing(&mut self) {
+        self.coalescing = false;
+    }
+
+    /// Revert the newest command, returning its label
+    pub fn undo(&mut self, world: &mut World, registry: &ComponentRegistry) -> Option<String> {
+        self.coalescing = false;
+        let command = self.undo_stack.pop()?;
+        command.revert(world, registry);
+        let label = command.label();
+        self.redo_stack.push(command);
+        Some(label)
+    }
+
+    /// Re-apply the most recently undone command, returning its label
+    pub fn redo(&mut self, world: &mut World, registry: &ComponentRegistry) -> Option<String> {
+        self.coalescing = false;
+        let command = self.redo_stack.pop()?;
+        command.apply(world, registry);
+        let label = command.label();
+        self.undo_stack.push(command);
+        Some(label)
+    }
+
+    /// Whether there is a command to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Whether there is a command to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Applied commands, oldest first
+    pub fn undo_commands(&self) -> &[EditorCommand] {
+        &self.undo_stack
+    }
+
+    /// Undone commands, next to redo first
+    pub fn redo_commands(&self) -> impl Iterator<Item = &EditorCommand> {
+        self.redo_stack.iter().rev()
+    }
+
+    /// Drop all commands, e.g. after loading a different scene
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.coalescing = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::core::entity::{GlobalTransform, Name};
+    use glam::Vec3;
+
+    fn registry() -> ComponentRegistry {
+        ComponentRegistry::with_default_components()
+    }
+
+    fn transform_value(world: &World, entity: hecs::Entity) -> Option<Value> {
+        world
+            .get::<Transform>(entity)
+            .ok()
+            .map(|transform| serde_json::to_value(*transform).unwrap())
+    }
+
+    #[test]
+    fn test_undo_redo_component_edit() {
+        let registry = registry();
+        let mut world = World::new();
+        let entity = world.spawn((Name::new("Cube"), Transform::default()));
+        let mut history = CommandHistory::new();
+
+        let before = transform_value(&world, entity);
+        world
+            .query_one_mut::<&mut Transform>(entity)
+            .unwrap()
+            .position = Vec3::X;
+        let after = transform_value(&world, entity);
+        history.push(EditorCommand::set_component(
+            entity,
+            "Transform",
+            before,
+            after,
+        ));
+
+        assert_eq!(
+            history.undo(&mut world, &registry).as_deref(),
+            Some("Edit Transform")
+        );
+        assert_eq!(world.get::<Transform>(entity).unwrap().position, Vec3::ZERO);
+
+        assert_eq!(
+            history.redo(&mut world, &registry).as_deref(),
+            Some("Edit Transform")
+        );
+        assert_eq!(world.get::<Transform>(entity).unwrap().position, Vec3::X);
+    }
+
+    #[test]
+    fn test_drag_edits_coalesce() {
+        let registry = registry();
+        let mut world = World::new();
+        let entity = world.spawn((Transform::default(),));
+        let mut history = CommandHistory::new();
+
+        // Three frames of a drag, then a separate edit after release
+        for x in [1.0, 2.0, 3.0] {
+            let before = transform_value(&world, entity);
+            world
+                .query_one_mut::<&mut Transform>(entity)
+                .unwrap()
+                .position
+                .x = x;
+            let after = transform_value(&world, entity);
+            history.push(EditorCommand::set_component(
+                entity,
+                "Transform",
+                before,
+                after,
+            ));
+        }
+        history.end_coalescing();
+
+        let before = transform_value(&world, entity);
+        world
+            .query_one_mut::<&mut Transform>(entity)
+            .unwrap()
+            .position
+            .y = 5.0;
+        let after = transform_value(&world, entity);
+        history.push(EditorCommand::set_component(
+            entity,
+            "Transform",
+            before,
+            after,
+        ));
+
+        assert_eq!(history.undo_commands().len(), 2);
+
+        history.undo(&mut world, &registry);
+        assert_eq!(
+            world.get::<Transform>(entity).unwrap().position,
+            Vec3::new(3.0, 0.0, 0.0)
+        );
+        history.undo(&mut world, &registry);
+        assert_eq!(world.get::<Transform>(entity).unwrap().position, Vec3::ZERO);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_undo_despawn_restores_entity() {
+        let registry = registry();
+        let mut world = World::new();
+        let parent = world.spawn((Name::new("Parent"), Transform::default()));
+        let child = world.spawn((
+            Name::new("Child"),
+            Transform::from_position(Vec3::Y),
+            GlobalTransform::default(),
+            Parent(parent),
+        ));
+        let mut history = CommandHistory::new();
+
+        let snapshot = EntitySnapshot::capture(&world, &registry, child).unwrap();
+        assert_eq!(snapshot.name(), Some("Child"));
+        world.despawn(child).unwrap();
+        history.push(EditorCommand::Despawn {
+            label: "Delete Child".to_string(),
+            snapshots: vec![snapshot],
+        });
+
+        history.undo(&mut world, &registry);
+        assert!(world.contains(child));
+        assert_eq!(world.get::<Name>(child).unwrap().0, "Child");
+        assert_eq!(world.get::<Transform>(child).unwrap().position, Vec3::Y);
+        assert_eq!(world.get::<Parent>(child).unwrap().0, parent);
+
+        history.redo(&mut world, &registry);
+        assert!(!world.contains(child));
+    }
+
+    #[test]
+    fn test_undo_spawn_and_reparent() {
+        let registry = registry();
+        let mut world = World::new();
+        let parent = world.spawn((Name::new("Parent"), Transform::from_position(Vec3::X)));
+        let entity = world.spawn((Name::new("New Entity"), Transform::default()));
+        let mut history = CommandHistory::new();
+
+        history.push(EditorCommand::Spawn {
+            label: "Create Entity".to_string(),
+            snapshots: vec![EntitySnapshot::capture(&world, &registry, entity).unwrap()],
+        });
+
+        let before = ParentState::capture(&world, entity);
+        world.insert_one(entity, Parent(parent)).unwrap();
+        world
+            .query_one_mut::<&mut Transform>(entity)
+            .unwrap()
+            .position = -Vec3::X;
+        history.push(EditorCommand::Reparent {
+            entity,
+            before,
+            after: ParentState::capture(&world, entity),
+        });
+
+        history.undo(&mut world, &registry);
+        assert!(world.get::<Parent>(entity).is_err());
+        assert_eq!(world.get::<Transform>(entity).unwrap().position, Vec3::ZERO);
+
+        history.undo(&mut world, &registry);
+        assert!(!world.contains(entity));
+
+        // Redo both, the entity comes back with its original handle
+        history.redo(&mut world, &registry);
+        history.redo(&mut world, &registry);
+        assert_eq!(world.get::<Parent>(entity).unwrap().0, parent);
+        assert_eq!(world.get::<Transform>(entity).unwrap().position, -Vec3::X);
+    }
+
+    #[test]
+    fn test_new_command_clears_redo() {
+        let registry = registry();
+        let mut world = World::new();
+        let entity = world.spawn((Name::new("A"),));
+        let mut history = CommandHistory::new();
+
+        let name = |value: &str| Some(Value::String(value.to_string()));
+        history.push(EditorCommand::set_component(
+            entity,
+            "Name",
+            name("A"),
+            name("B"),
+        ));
+        history.undo(&mut world, &registry);
+        assert!(history.can_redo());
+
+        history.push(EditorCommand::set_component(
+            entity,
+            "Name",
+            name("A"),
+            name("C"),
+        ));
+        assert!(!history.can_redo());
+
+        // Recording an unchanged value is ignored
+        history.end_coalescing();
+        history.push(EditorCommand::set_component(
+            entity,
+            "Name",
+            name("C"),
+            name("C"),
+        ));
+        assert_eq!(history.undo_commands().len(), 1);
+    }
+
+    #[test]
+    fn test_batch_edits_undo_together() {
+        let registry = registry();
+        let mut world = World::new();
+        let a = world.spawn((Transform::default(),));
+        let b = world.spawn((Transform::from_position(Vec3::Y),));
+        let mut history = CommandHistory::new();
+
+        // Two frames of a drag moving both entities along X
+        for x in [1.0, 2.0] {
+            let commands = [a, b]
+                .into_iter()
+                .map(|entity| {
+                    let before = transform_value(&world, entity);
+                    world
+                        .query_one_mut::<&mut Transform>(entity)
+                        .unwrap()
+                        .position
+                        .x = x;
+                    let after = transform_value(&world, entity);
+                    EditorCommand::set_component(entity, "Transform", before, after)
+                })
+                .collect();
+            history.push(EditorCommand::batch(
+                "Edit Transform (2 Entities)",
+                commands,
+            ));
+        }
+
+        assert_eq!(history.undo_commands().len(), 1);
+        assert_eq!(
+            history.undo(&mut world, &registry).as_deref(),
+            Some("Edit Transform (2 Entities)")
+        );
+        assert_eq!(world.get::<Transform>(a).unwrap().position, Vec3::ZERO);
+        assert_eq!(world.get::<Transform>(b).unwrap().position, Vec3::Y);
+
+        history.redo(&mut world, &registry);
+        assert_eq!(world.get::<Transform>(a).unwrap().position, Vec3::X * 2.0);
+        assert_eq!(
+            world.get::<Transform>(b).unwrap().position,
+            Vec3::new(2.0, 1.0, 0.0)
+        );
+    }
+}
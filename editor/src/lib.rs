@@ -5,9 +5,14 @@
 
 pub mod component_registry_ui;
 pub mod dpi_utils;
+pub mod editor_camera;
 pub mod editor_state;
+pub mod gizmo;
+pub mod history;
 pub mod panel_state;
 pub mod panels;
+pub mod picking;
+pub mod play_mode;
 pub mod safe_imgui_renderer;
 pub mod scene_operations;
 pub mod settings;
@@ -110,6 +110,7 @@ impl PanelManager {
             PanelState::new("inspector", "Inspector"),
             PanelState::new("assets", "Assets"),
             PanelState::new("viewport", "Viewport"),
+            PanelState::new("history", "History"),
         ];
 
         for panel in default_panels {
@@ -178,8 +179,9 @@ impl PanelManager {
         let content = std::fs::read_to_string(&path)?;
         let layouts: Vec<PanelLayout> = serde_json::from_str(&content)?;
 
-        // Clear existing panels and load from file
-        self.panels.clear();
+        // Start from the default panels so panels added after the layout
+        // was saved still show up, then apply the saved layout
+        self.panels = Self::new().panels;
 
         for layout in layouts {
             let panel = PanelState::from_layout(layout);
@@ -305,4 +307,24 @@ mod tests {
         assert_eq!(layout.size, deserialized.size);
         assert_eq!(layout.is_visible, deserialized.is_visible);
     }
+
+    #[test]
+    fn test_load_layout_keeps_new_default_panels() {
+        // A layout saved before the history panel existed
+        let layouts = [PanelState::new("hierarchy", "Scene Hierarchy").to_layout()];
+        let temp_file = NamedTempFile::new().expect("Failed to create temp file");
+        std::fs::write(temp_file.path(), serde_json::to_string(&layouts).unwrap())
+            .expect("Failed to write layout");
+
+        let mut manager = PanelManager::new();
+        manager
+            .load_layout(temp_file.path())
+            .expect("Failed to load layout");
+
+        let hierarchy = manager
+            .get_panel(&PanelId("hierarchy".to_string()))
+            .expect("Panel not found");
+        assert_eq!(hierarchy.title, "Scene Hierarchy");
+        assert!(manager.get_panel(&PanelId("history".to_string())).is_some());
+    }
 }
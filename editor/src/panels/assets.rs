@@ -135,12 +135,15 @@ fn scan_directory(path: &Path) -> Result<FileNode, std::io::Error> {
         let include = matches!(
             path.extension().and_then(|e| e.to_str()),
             Some("obj")
+                | Some("gltf")
+                | Some("glb")
                 | Some("rhai")
                 | Some("json")
                 | Some("wav")
                 | Some("mp3")
                 | Some("ogg")
                 | Some("flac")
+                | Some("prefab")
         );
 
         if include {
@@ -169,10 +172,25 @@ pub fn is_scene_file(path: &Path) -> bool {
     false
 }
 
+/// Check if a path refers to a model file that can be used as a mesh or imported as a hierarchy
+pub fn is_model_file(path: &str) -> bool {
+    matches!(
+        Path::new(path).extension().and_then(|e| e.to_str()),
+        Some("obj") | Some("gltf") | Some("glb")
+    )
+}
+
+/// Check if a path refers to a prefab file
+pub fn is_prefab_file(path: &str) -> bool {
+    Path::new(path).extension().and_then(|e| e.to_str())
+        == Some(engine::io::prefab::PREFAB_EXTENSION)
+}
+
 /// Check if a file is draggable
 fn is_draggable_file(path: &Path) -> bool {
     match path.extension().and_then(|e| e.to_str()) {
-        Some("obj") | Some("rhai") | Some("wav") | Some("mp3") | Some("ogg") | Some("flac") => true,
+        Some("obj") | Some("gltf") | Some("glb") | Some("rhai") | Some("wav") | Some("mp3")
+        | Some("ogg") | Some("flac") | Some("prefab") => true,
         Some("json") => is_scene_file(path), // Only drag scene JSONs
         _ => false,
     }
@@ -181,8 +199,9 @@ fn is_draggable_file(path: &Path) -> bool {
 /// Get the appropriate icon for a file type
 fn get_file_icon(path: &Path) -> &'static str {
     match path.extension().and_then(|e| e.to_str()) {
-        Some("obj") => "🗿",
+        Some("obj") | Some("gltf") | Some("glb") => "🗿",
         Some("rhai") => "📜",
+        Some("prefab") => "📦",
         Some("wav") | Some("mp3") | Some("ogg") | Some("flac") => "🔊",
         Some("json") => {
             if is_scene_file(path) {
@@ -337,7 +356,7 @@ pub fn render_assets_panel(
             ui.child_window("asset_tree").build(|| {
                 if state.file_tree.children.is_empty() {
                     ui.text("No assets found");
-                    ui.text("Place .obj, .rhai, .json, or audio files in:");
+                    ui.text("Place .obj, .gltf, .glb, .rhai, .json, or audio files in:");
                     ui.text(format!("{}", state.asset_root.display()));
                 } else {
                     // Render each top-level child
@@ -3,6 +3,7 @@
 //! Displays all entities in the scene in a tree structure,
 //! allowing selection and basic operations.
 
+use crate::history::{EditorCommand, ParentState};
 use crate::panel_state::{PanelId, PanelManager};
 use crate::shared_state::EditorSharedState;
 use engine::prelude::{Camera, GlobalTransform, Material, MeshId, Name, Parent, Transform, World};
@@ -98,7 +99,7 @@ pub fn render_hierarchy_panel(
         .resizable(true)
         .build(|| {
             // Access the world through shared state
-            if let Some((parent_map, root_entities, _other_entities, selected_entity)) = shared_state
+            if let Some((parent_map, root_entities, _other_entities, selected)) = shared_state
                 .with_world_read(|world| {
                     // Debug info for troubleshooting
                     let total_entities = world.query::<()>().iter().count();
@@ -155,19 +156,24 @@ pub fn render_hierarchy_panel(
                         .collect();
 
                     // Get current selection
-                    let selected_entity = shared_state.selected_entity();
+                    let selected = shared_state.selected_entities();
 
-                    (parent_map, root_entities, other_entities, selected_entity)
+                    (parent_map, root_entities, other_entities, selected)
                 })
             {
+                let selection = HierarchySelection {
+                    order: display_order(&root_entities, &parent_map),
+                    selected,
+                };
+
                 // Render the hierarchy tree
-                for root_entity in root_entities {
+                for &root_entity in &root_entities {
                     render_entity_tree(
                         ui,
                         shared_state,
                         root_entity,
                         &parent_map,
-                        selected_entity,
+                        &selection,
                         0,
                     );
                 }
@@ -198,44 +204,45 @@ pub fn render_hierarchy_panel(
                             ui.tooltip_text("Drop here to remove parent");
                         }
 
-                        // Accept drop to remove paren
+                        // Accept drop to remove parent from the dragged entities
                         if target.accept_payload_empty("ENTITY_PARENT", DragDropFlags::empty()).is_some() {
-                            shared_state.with_world_write(|world| {
-                                // First ensure hierarchy is up to date
-                                engine::core::entity::update_hierarchy_system(world);
-
-                                // Get the current world transform before removing paren
-                                let world_matrix = if let Ok(global_transform) = world.get::<GlobalTransform>(dragged) {
-                                    Some(global_transform.matrix)
-                                } else if let Ok(transform) = world.get::<Transform>(dragged) {
-                                    // If no GlobalTransform exists yet, compute it from Transform
-                                    Some(transform.to_matrix())
-                                } else {
-                                    None
-                                };
-
-                                // Remove parent componen
-                                let _ = world.inner_mut().remove_one::<Parent>(dragged);
-
-                                // Update the local transform to maintain world position
-                                if let Some(world_mat) = world_matrix {
-                                    if let Ok(transform) = world.query_one_mut::<&mut Transform>(dragged) {
-                                        let (scale, rotation, translation) = world_mat.to_scale_rotation_translation();
-                                        transform.position = translation;
-                                        transform.rotation = rotation;
-                                        transform.scale = scale;
-                                    }
-                                }
-
-                                // Update hierarchy immediately
-                                engine::core::entity::update_hierarchy_system(world);
-                            });
+                            let moved = shared_state
+                                .with_world_read(|world| {
+                                    entities_to_move(world, dragged_entities(dragged, &selection.selected), None)
+                                })
+                                .unwrap_or_default();
+                            reparent_recorded(shared_state, &moved, None);
 
                             // Clear drag state
                             state.dragged_entity = None;
                             state.drag_source_name.clear();
 
-                            debug!("Removed parent from entity {:?}", dragged);
+                            debug!("Removed parent from entities {:?}", moved);
+                        }
+                    } else if crate::panels::assets::AssetBrowserState::get_dragged_file()
+                        .is_some_and(|file| crate::panels::assets::is_model_file(&file))
+                    {
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text("Drop here to import model as hierarchy");
+                        }
+
+                        // Accept model drop to spawn one entity per node / group
+                        if target.accept_payload_empty("ASSET_FILE", DragDropFlags::empty()).is_some() {
+                            if let Some(file_path) = crate::panels::assets::AssetBrowserState::take_dragged_file() {
+                                import_model_hierarchy(shared_state, &file_path);
+                            }
+                        }
+                    } else if crate::panels::assets::AssetBrowserState::get_dragged_file()
+                        .is_some_and(|file| crate::panels::assets::is_prefab_file(&file))
+                    {
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text("Drop here to instantiate prefab");
+                        }
+
+                        if target.accept_payload_empty("ASSET_FILE", DragDropFlags::empty()).is_some() {
+                            if let Some(file_path) = crate::panels::assets::AssetBrowserState::take_dragged_file() {
+                                crate::scene_operations::instantiate_prefab(shared_state, &file_path);
+                            }
                         }
                     }
                 }
@@ -245,17 +252,18 @@ pub fn render_hierarchy_panel(
                 // Add Create Entity button
                 ui.separator();
                 if ui.button("Create New Entity") {
-                    shared_state.with_world_write(|world| {
-                        let new_entity = world.spawn((
+                    if let Some(new_entity) = shared_state.with_world_write(|world| {
+                        world.spawn((
                             Name::new("New Entity"),
                             Transform::default(),
                             GlobalTransform::default(),
-                        ));
-
+                        ))
+                    }) {
                         debug!(entity = ?new_entity, "Created new entity from hierarchy panel");
+                        shared_state.record_spawn("Create Entity", &[new_entity]);
                         shared_state.set_selected_entity(Some(new_entity));
                         shared_state.mark_scene_modified();
-                    });
+                    }
                 }
             } else {
                 ui.text("Failed to access world data");
@@ -271,7 +279,7 @@ fn render_entity_tree(
     shared_state: &EditorSharedState,
     entity: hecs::Entity,
     parent_map: &HashMap<hecs::Entity, Vec<hecs::Entity>>,
-    selected_entity: Option<hecs::Entity>,
+    selection: &HierarchySelection,
     depth: usize,
 ) {
     // Create indentation for tree structure
@@ -286,10 +294,11 @@ fn render_entity_tree(
     let entity_name = shared_state
         .with_world_read(|world| get_entity_name(world, entity))
         .unwrap_or_else(|| format!("Entity {entity:?}"));
+    let is_selected = selection.selected.contains(&entity);
 
     // Show tree node if has children
     if has_children {
-        let node_flags = if Some(entity) == selected_entity {
+        let node_flags = if is_selected {
             TreeNodeFlags::SELECTED | TreeNodeFlags::DEFAULT_OPEN
         } else {
             TreeNodeFlags::DEFAULT_OPEN
@@ -301,234 +310,21 @@ fn render_entity_tree(
 
         // Check if the node was clicked
         if ui.is_item_clicked() {
-            shared_state.set_selected_entity(Some(entity));
-            debug!("Selected entity: {:?}", entity);
-        }
-
-        // Add drag source
-        if ui
-            .drag_drop_source_config("ENTITY_PARENT")
-            .condition(Condition::Once)
-            .begin()
-            .is_some()
-        {
-            let state = get_hierarchy_drag_state();
-            state.dragged_entity = Some(entity);
-            state.drag_source_name = entity_name.clone();
-
-            // Visual feedback during drag
-            ui.text(format!("🔗 {entity_name}"));
+            select_clicked(ui, shared_state, entity, &selection.order);
         }
 
-        // Add drop target
-        if let Some(target) = ui.drag_drop_target() {
-            let state = get_hierarchy_drag_state();
-
-            if let Some(dragged) = state.dragged_entity {
-                // Visual feedback when hovering
-                let can_drop = dragged != entity
-                    && !shared_state
-                        .with_world_read(|world| is_ancestor_of(world, dragged, entity))
-                        .unwrap_or(false);
-
-                if ui.is_item_hovered() {
-                    let color = if can_drop {
-                        [0.0, 1.0, 0.0, 0.5] // Green for valid
-                    } else {
-                        [1.0, 0.0, 0.0, 0.5] // Red for invalid
-                    };
-
-                    ui.get_window_draw_list()
-                        .add_rect(ui.item_rect_min(), ui.item_rect_max(), color)
-                        .build();
-
-                    // Tooltip explaining why drop is invalid
-                    if !can_drop {
-                        if dragged == entity {
-                            ui.tooltip_text("Cannot parent entity to itself");
-                        } else {
-                            ui.tooltip_text("Cannot create circular dependency");
-                        }
-                    }
-                }
-
-                // Accept drop
-                if target
-                    .accept_payload_empty("ENTITY_PARENT", DragDropFlags::empty())
-                    .is_some()
-                    && can_drop
-                {
-                    // Perform the parenting
-                    shared_state.with_world_write(|world| {
-                        // First ensure hierarchy is up to date so GlobalTransforms exis
-                        engine::core::entity::update_hierarchy_system(world);
-
-                        // Get the current world transforms before parenting
-                        let child_world_matrix =
-                            if let Ok(global_transform) = world.get::<GlobalTransform>(dragged) {
-                                Some(global_transform.matrix)
-                            } else if let Ok(transform) = world.get::<Transform>(dragged) {
-                                // If no GlobalTransform exists yet, compute it from Transform
-                                Some(transform.to_matrix())
-                            } else {
-                                None
-                            };
-
-                        let parent_world_matrix =
-                            if let Ok(global_transform) = world.get::<GlobalTransform>(entity) {
-                                Some(global_transform.matrix)
-                            } else if let Ok(transform) = world.get::<Transform>(entity) {
-                                // If no GlobalTransform exists yet, compute it from Transform
-                                Some(transform.to_matrix())
-                            } else {
-                                None
-                            };
-
-                        // Store exact world position with f64 precision for cameras
-                        let original_world_pos = child_world_matrix.map(|m| {
-                            let (_, _, translation) = m.to_scale_rotation_translation();
-                            glam::DVec3::new(
-                                translation.x as f64,
-                                translation.y as f64,
-                                translation.z as f64,
-                            )
-                        });
-
-                        // Remove existing parent if any
-                        let _ = world.inner_mut().remove_one::<Parent>(dragged);
-                        // Add new paren
-                        let _ = world.insert_one(dragged, Parent(entity));
-
-                        // Check if this is a camera entity before transform adjustmen
-                        let is_camera = world.get::<Camera>(dragged).is_ok();
-
-                        // Adjust the child's local transform to maintain its world position
-                        if let (Some(child_world), Some(parent_world)) =
-                            (child_world_matrix, parent_world_matrix)
-                        {
-                            if let Ok(child_transform) =
-                                world.query_one_mut::<&mut Transform>(dragged)
-                            {
-                                // Calculate the local transform relative to the new paren
-                                // child_world = parent_world * child_local
-                                // child_local = parent_world^-1 * child_world
-                                let parent_inverse = parent_world.inverse();
-                                let new_local_matrix = parent_inverse * child_world;
-                                let (scale, rotation, translation) =
-                                    new_local_matrix.to_scale_rotation_translation();
-
-                                if is_camera {
-                                    let old_pos = child_transform.position;
-                                    trace!(
-                                        old_local_pos = ?old_pos,
-                                        new_local_pos = ?translation,
-                                        child_world_matrix = ?child_world,
-                                        parent_world_matrix = ?parent_world,
-                                        "Camera parenting transform calculation"
-                                    );
-                                }
-
-                                child_transform.position = translation;
-                                child_transform.rotation = rotation.normalize(); // Ensure rotation is normalized
-                                child_transform.scale = scale;
-                            }
-                        }
-
-                        // Update hierarchy immediately to ensure GlobalTransforms are correc
-                        engine::core::entity::update_hierarchy_system(world);
-
-                        // Verify the camera's world position after hierarchy update
-                        if is_camera {
-                            if let Some(original_pos) = original_world_pos {
-                                // Get the new world position and calculate drif
-                                let (new_world_pos, drift) =
-                                    if let Ok(new_global) = world.get::<GlobalTransform>(dragged) {
-                                        let pos = new_global.position();
-                                        let d = ((pos.x as f64 - original_pos.x).abs()
-                                            + (pos.y as f64 - original_pos.y).abs()
-                                            + (pos.z as f64 - original_pos.z).abs())
-                                            / 3.0;
-                                        (pos, d)
-                                    } else {
-                                        return; // Skip if we can't get the transform
-                                    };
-
-                                if drift > 0.0001 {
-                                    warn!(
-                                        entity = ?dragged,
-                                        drift = drift,
-                                        original_pos = ?original_pos,
-                                        new_pos = ?new_world_pos,
-                                        "Camera position drifted after parenting"
-                                    );
-
-                                    // If drift is significant, attempt to correct i
-                                    if drift > 0.001 {
-                                        if let Ok(child_transform) =
-                                            world.query_one_mut::<&mut Transform>(dragged)
-                                        {
-                                            // Recalculate with higher precision
-                                            let parent_world_f64 = parent_world_matrix.map(|m| {
-                                                glam::DMat4::from_cols(
-                                                    m.x_axis.as_dvec4(),
-                                                    m.y_axis.as_dvec4(),
-                                                    m.z_axis.as_dvec4(),
-                                                    m.w_axis.as_dvec4(),
-                                                )
-                                            });
-
-                                            if let Some(parent_f64) = parent_world_f64 {
-                                                let parent_inverse_f64 = parent_f64.inverse();
-                                                let child_local_pos_f64 = parent_inverse_f64
-                                                    .transform_point3(original_pos);
-                                                child_transform.position =
-                                                    child_local_pos_f64.as_vec3();
-
-                                                debug!(
-                                                    entity = ?dragged,
-                                                    corrected_pos = ?child_local_pos_f64,
-                                                    "Applied drift correction to camera"
-                                                );
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    trace!(
-                                        entity = ?dragged,
-                                        drift = drift,
-                                        "Camera parenting completed with minimal drift"
-                                    );
-                                }
-                            }
-                        }
-                    });
-
-                    // Clear drag state
-                    state.dragged_entity = None;
-                    state.drag_source_name.clear();
-
-                    debug!("Parented entity {:?} to {:?}", dragged, entity);
-                }
-            }
-        }
+        entity_drag_source(ui, entity, &entity_name);
+        entity_drop_target(ui, shared_state, entity, &selection.selected);
 
         if let Some(_token) = is_open {
             if let Some(children) = parent_map.get(&entity) {
                 for &child in children {
-                    render_entity_tree(
-                        ui,
-                        shared_state,
-                        child,
-                        parent_map,
-                        selected_entity,
-                        depth + 1,
-                    );
+                    render_entity_tree(ui, shared_state, child, parent_map, selection, depth + 1);
                 }
             }
         }
     } else {
         // Leaf node - just show selectable
-        let is_selected = Some(entity) == selected_entity;
         // Use entity ID in the selectable ID to ensure uniqueness
         let selectable_id = format!("{entity_name}##{entity:?}");
         if ui
@@ -536,223 +332,429 @@ fn render_entity_tree(
             .selected(is_selected)
             .build()
         {
-            shared_state.set_selected_entity(Some(entity));
-            debug!("Selected entity: {:?}", entity);
+            select_clicked(ui, shared_state, entity, &selection.order);
         }
 
-        // Add drag source for leaf nodes too
-        if ui
-            .drag_drop_source_config("ENTITY_PARENT")
-            .condition(Condition::Once)
-            .begin()
-            .is_some()
-        {
-            let state = get_hierarchy_drag_state();
-            state.dragged_entity = Some(entity);
-            state.drag_source_name = entity_name.clone();
+        entity_drag_source(ui, entity, &entity_name);
+        entity_drop_target(ui, shared_state, entity, &selection.selected);
+    }
 
-            // Visual feedback during drag
-            ui.text(format!("🔗 {entity_name}"));
+    // Unindent for next items at same level
+    if depth > 0 {
+        for _ in 0..depth {
+            ui.unindent();
         }
+    }
+}
 
-        // Add drop targett for leaf nodes
-        if let Some(target) = ui.drag_drop_target() {
-            let state = get_hierarchy_drag_state();
-
-            if let Some(dragged) = state.dragged_entity {
-                // Visual feedback when hovering
-                let can_drop = dragged != entity
-                    && !shared_state
-                        .with_world_read(|world| is_ancestor_of(world, dragged, entity))
-                        .unwrap_or(false);
-
-                if ui.is_item_hovered() {
-                    let color = if can_drop {
-                        [0.0, 1.0, 0.0, 0.5] // Green for valid
-                    } else {
-                        [1.0, 0.0, 0.0, 0.5] // Red for invalid
-                    };
-
-                    ui.get_window_draw_list()
-                        .add_rect(ui.item_rect_min(), ui.item_rect_max(), color)
-                        .build();
-
-                    // Tooltip explaining why drop is invalid
-                    if !can_drop {
-                        if dragged == entity {
-                            ui.tooltip_text("Cannot parent entity to itself");
-                        } else {
-                            ui.tooltip_text("Cannot create circular dependency");
-                        }
-                    }
+/// Selection state the hierarchy tree is drawn with
+struct HierarchySelection {
+    /// Selected entities
+    selected: Vec<hecs::Entity>,
+    /// All tree entities in display order, used for shift ranges
+    order: Vec<hecs::Entity>,
+}
+
+/// Entities of the tree in the order they are displayed
+fn display_order(
+    roots: &[hecs::Entity],
+    parent_map: &HashMap<hecs::Entity, Vec<hecs::Entity>>,
+) -> Vec<hecs::Entity> {
+    fn visit(
+        entity: hecs::Entity,
+        parent_map: &HashMap<hecs::Entity, Vec<hecs::Entity>>,
+        visited: &mut HashSet<hecs::Entity>,
+        order: &mut Vec<hecs::Entity>,
+    ) {
+        if !visited.insert(entity) {
+            return;
+        }
+        order.push(entity);
+        for &child in parent_map.get(&entity).into_iter().flatten() {
+            visit(child, parent_map, visited, order);
+        }
+    }
+
+    let mut order = Vec::new();
+    let mut visited = HashSet::new();
+    for &root in roots {
+        visit(root, parent_map, &mut visited, &mut order);
+    }
+    order
+}
+
+/// Update the selection for a click on an entity
+///
+/// Ctrl toggles the entity, Shift selects the range from the anchor and
+/// Ctrl+Shift adds that range to the selection.
+fn select_clicked(
+    ui: &imgui::Ui,
+    shared_state: &EditorSharedState,
+    entity: hecs::Entity,
+    order: &[hecs::Entity],
+) {
+    let io = ui.io();
+    if io.key_shift {
+        shared_state.select_range(order, entity, io.key_ctrl);
+    } else if io.key_ctrl {
+        shared_state.toggle_selection(entity);
+    } else {
+        shared_state.set_selected_entity(Some(entity));
+    }
+    debug!("Selected entity: {:?}", entity);
+}
+
+/// Make the last item a drag source for parenting `entity`
+fn entity_drag_source(ui: &imgui::Ui, entity: hecs::Entity, entity_name: &str) {
+    if ui
+        .drag_drop_source_config("ENTITY_PARENT")
+        .condition(Condition::Once)
+        .begin()
+        .is_some()
+    {
+        let state = get_hierarchy_drag_state();
+        state.dragged_entity = Some(entity);
+        state.drag_source_name = entity_name.to_string();
+
+        // Visual feedback during drag
+        ui.text(format!("🔗 {entity_name}"));
+    }
+}
+
+/// Make the last item a drop target that parents the dragged entities to `entity`
+fn entity_drop_target(
+    ui: &imgui::Ui,
+    shared_state: &EditorSharedState,
+    entity: hecs::Entity,
+    selected: &[hecs::Entity],
+) {
+    let Some(target) = ui.drag_drop_target() else {
+        return;
+    };
+    let state = get_hierarchy_drag_state();
+    let Some(dragged) = state.dragged_entity else {
+        return;
+    };
+
+    let moved = shared_state
+        .with_world_read(|world| {
+            entities_to_move(world, dragged_entities(dragged, selected), Some(entity))
+        })
+        .unwrap_or_default();
+    let can_drop = !moved.is_empty();
+
+    // Visual feedback when hovering
+    if ui.is_item_hovered() {
+        let color = if can_drop {
+            [0.0, 1.0, 0.0, 0.5] // Green for valid
+        } else {
+            [1.0, 0.0, 0.0, 0.5] // Red for invalid
+        };
+
+        ui.get_window_draw_list()
+            .add_rect(ui.item_rect_min(), ui.item_rect_max(), color)
+            .build();
+
+        // Tooltip explaining why drop is invalid
+        if !can_drop {
+            if dragged == entity {
+                ui.tooltip_text("Cannot parent entity to itself");
+            } else {
+                ui.tooltip_text("Cannot create circular dependency");
+            }
+        }
+    }
+
+    // Accept drop
+    if target
+        .accept_payload_empty("ENTITY_PARENT", DragDropFlags::empty())
+        .is_some()
+        && can_drop
+    {
+        reparent_recorded(shared_state, &moved, Some(entity));
+
+        // Clear drag state
+        state.dragged_entity = None;
+        state.drag_source_name.clear();
+
+        debug!("Parented entities {:?} to {:?}", moved, entity);
+    }
+}
+
+/// Entities a drag moves, the whole selection if the dragged entity is part of it
+fn dragged_entities(dragged: hecs::Entity, selected: &[hecs::Entity]) -> Vec<hecs::Entity> {
+    if selected.contains(&dragged) {
+        selected.to_vec()
+    } else {
+        vec![dragged]
+    }
+}
+
+/// Filter dragged entities down to the ones that can move to `parent`
+///
+/// Entities that are the new parent or one of its ancestors are skipped, as
+/// are entities whose ancestor moves along, so they stay in its subtree.
+fn entities_to_move(
+    world: &World,
+    dragged: Vec<hecs::Entity>,
+    parent: Option<hecs::Entity>,
+) -> Vec<hecs::Entity> {
+    let movable: Vec<_> = dragged
+        .into_iter()
+        .filter(|&entity| world.contains(entity))
+        .filter(|&entity| match parent {
+            Some(parent) => !is_ancestor_of(world, entity, parent),
+            None => world.get::<Parent>(entity).is_ok(),
+        })
+        .collect();
+
+    movable
+        .iter()
+        .copied()
+        .filter(|&entity| {
+            !movable
+                .iter()
+                .any(|&other| other != entity && is_ancestor_of(world, other, entity))
+        })
+        .collect()
+}
+
+/// Move entities under `parent`, or to the root for `None`, as one undo step
+fn reparent_recorded(
+    shared_state: &EditorSharedState,
+    entities: &[hecs::Entity],
+    parent: Option<hecs::Entity>,
+) {
+    let Some(commands) = shared_state.with_world_write(|world| {
+        entities
+            .iter()
+            .map(|&entity| {
+                let before = ParentState::capture(world, entity);
+                match parent {
+                    Some(parent) => parent_to(world, entity, parent),
+                    None => unparent(world, entity),
+                }
+                EditorCommand::Reparent {
+                    entity,
+                    before,
+                    after: ParentState::capture(world, entity),
                 }
+            })
+            .collect::<Vec<_>>()
+    }) else {
+        return;
+    };
+    if commands.is_empty() {
+        return;
+    }
 
-                // Accept drop
-                if target
-                    .accept_payload_empty("ENTITY_PARENT", DragDropFlags::empty())
-                    .is_some()
-                    && can_drop
-                {
-                    // Perform the parenting
-                    shared_state.with_world_write(|world| {
-                        // First ensure hierarchy is up to date so GlobalTransforms exis
-                        engine::core::entity::update_hierarchy_system(world);
-
-                        // Get the current world transforms before parenting
-                        let child_world_matrix =
-                            if let Ok(global_transform) = world.get::<GlobalTransform>(dragged) {
-                                Some(global_transform.matrix)
-                            } else if let Ok(transform) = world.get::<Transform>(dragged) {
-                                // If no GlobalTransform exists yet, compute it from Transform
-                                Some(transform.to_matrix())
-                            } else {
-                                None
-                            };
-
-                        let parent_world_matrix =
-                            if let Ok(global_transform) = world.get::<GlobalTransform>(entity) {
-                                Some(global_transform.matrix)
-                            } else if let Ok(transform) = world.get::<Transform>(entity) {
-                                // If no GlobalTransform exists yet, compute it from Transform
-                                Some(transform.to_matrix())
-                            } else {
-                                None
-                            };
-
-                        // Store exact world position with f64 precision for cameras
-                        let original_world_pos = child_world_matrix.map(|m| {
-                            let (_, _, translation) = m.to_scale_rotation_translation();
-                            glam::DVec3::new(
-                                translation.x as f64,
-                                translation.y as f64,
-                                translation.z as f64,
-                            )
-                        });
+    let label = if parent.is_some() {
+        format!("Reparent {} Entities", commands.len())
+    } else {
+        format!("Unparent {} Entities", commands.len())
+    };
+    shared_state.record_command(EditorCommand::batch(label, commands));
+    shared_state.mark_scene_modified();
+}
 
-                        // Remove existing parent if any
-                        let _ = world.inner_mut().remove_one::<Parent>(dragged);
-                        // Add new paren
-                        let _ = world.insert_one(dragged, Parent(entity));
-
-                        // Check if this is a camera entity before transform adjustmen
-                        let is_camera = world.get::<Camera>(dragged).is_ok();
-
-                        // Adjust the child's local transform to maintain its world position
-                        if let (Some(child_world), Some(parent_world)) =
-                            (child_world_matrix, parent_world_matrix)
-                        {
-                            if let Ok(child_transform) =
-                                world.query_one_mut::<&mut Transform>(dragged)
-                            {
-                                // Calculate the local transform relative to the new paren
-                                // child_world = parent_world * child_local
-                                // child_local = parent_world^-1 * child_world
-                                let parent_inverse = parent_world.inverse();
-                                let new_local_matrix = parent_inverse * child_world;
-                                let (scale, rotation, translation) =
-                                    new_local_matrix.to_scale_rotation_translation();
-
-                                if is_camera {
-                                    let old_pos = child_transform.position;
-                                    trace!(
-                                        old_local_pos = ?old_pos,
-                                        new_local_pos = ?translation,
-                                        child_world_matrix = ?child_world,
-                                        parent_world_matrix = ?parent_world,
-                                        "Camera parenting transform calculation"
-                                    );
-                                }
-
-                                child_transform.position = translation;
-                                child_transform.rotation = rotation.normalize(); // Ensure rotation is normalized
-                                child_transform.scale = scale;
-                            }
-                        }
+/// Remove the parent of an entity, keeping its world transform
+fn unparent(world: &mut World, entity: hecs::Entity) {
+    // First ensure hierarchy is up to date
+    engine::core::entity::update_hierarchy_system(world);
+
+    // Get the current world transform before removing paren
+    let world_matrix = if let Ok(global_transform) = world.get::<GlobalTransform>(entity) {
+        Some(global_transform.matrix)
+    } else if let Ok(transform) = world.get::<Transform>(entity) {
+        // If no GlobalTransform exists yet, compute it from Transform
+        Some(transform.to_matrix())
+    } else {
+        None
+    };
 
-                        // Update hierarchy immediately to ensure GlobalTransforms are correc
-                        engine::core::entity::update_hierarchy_system(world);
-
-                        // Verify the camera's world position after hierarchy update
-                        if is_camera {
-                            if let Some(original_pos) = original_world_pos {
-                                // Get the new world position and calculate drif
-                                let (new_world_pos, drift) =
-                                    if let Ok(new_global) = world.get::<GlobalTransform>(dragged) {
-                                        let pos = new_global.position();
-                                        let d = ((pos.x as f64 - original_pos.x).abs()
-                                            + (pos.y as f64 - original_pos.y).abs()
-                                            + (pos.z as f64 - original_pos.z).abs())
-                                            / 3.0;
-                                        (pos, d)
-                                    } else {
-                                        return; // Skip if we can't get the transform
-                                    };
-
-                                if drift > 0.0001 {
-                                    warn!(
-                                        entity = ?dragged,
-                                        drift = drift,
-                                        original_pos = ?original_pos,
-                                        new_pos = ?new_world_pos,
-                                        "Camera position drifted after parenting"
-                                    );
-
-                                    // If drift is significant, attempt to correct i
-                                    if drift > 0.001 {
-                                        if let Ok(child_transform) =
-                                            world.query_one_mut::<&mut Transform>(dragged)
-                                        {
-                                            // Recalculate with higher precision
-                                            let parent_world_f64 = parent_world_matrix.map(|m| {
-                                                glam::DMat4::from_cols(
-                                                    m.x_axis.as_dvec4(),
-                                                    m.y_axis.as_dvec4(),
-                                                    m.z_axis.as_dvec4(),
-                                                    m.w_axis.as_dvec4(),
-                                                )
-                                            });
-
-                                            if let Some(parent_f64) = parent_world_f64 {
-                                                let parent_inverse_f64 = parent_f64.inverse();
-                                                let child_local_pos_f64 = parent_inverse_f64
-                                                    .transform_point3(original_pos);
-                                                child_transform.position =
-                                                    child_local_pos_f64.as_vec3();
-
-                                                debug!(
-                                                    entity = ?dragged,
-                                                    corrected_pos = ?child_local_pos_f64,
-                                                    "Applied drift correction to camera"
-                                                );
-                                            }
-                                        }
-                                    }
-                                } else {
-                                    trace!(
-                                        entity = ?dragged,
-                                        drift = drift,
-                                        "Camera parenting completed with minimal drift"
-                                    );
-                                }
-                            }
-                        }
-                    });
+    // Remove parent componen
+    let _ = world.inner_mut().remove_one::<Parent>(entity);
+
+    // Update the local transform to maintain world position
+    if let Some(world_mat) = world_matrix {
+        if let Ok(transform) = world.query_one_mut::<&mut Transform>(entity) {
+            let (scale, rotation, translation) = world_mat.to_scale_rotation_translation();
+            transform.position = translation;
+            transform.rotation = rotation;
+            transform.scale = scale;
+        }
+    }
+
+    // Update hierarchy immediately
+    engine::core::entity::update_hierarchy_system(world);
+}
+
+/// Parent `child` to `parent`, keeping the child's world transform
+fn parent_to(world: &mut World, child: hecs::Entity, parent: hecs::Entity) {
+    // First ensure hierarchy is up to date so GlobalTransforms exis
+    engine::core::entity::update_hierarchy_system(world);
+
+    // Get the current world transforms before parenting
+    let child_world_matrix = if let Ok(global_transform) = world.get::<GlobalTransform>(child) {
+        Some(global_transform.matrix)
+    } else if let Ok(transform) = world.get::<Transform>(child) {
+        // If no GlobalTransform exists yet, compute it from Transform
+        Some(transform.to_matrix())
+    } else {
+        None
+    };
 
-                    // Clear drag state
-                    state.dragged_entity = None;
-                    state.drag_source_name.clear();
+    let parent_world_matrix = if let Ok(global_transform) = world.get::<GlobalTransform>(parent) {
+        Some(global_transform.matrix)
+    } else if let Ok(transform) = world.get::<Transform>(parent) {
+        // If no GlobalTransform exists yet, compute it from Transform
+        Some(transform.to_matrix())
+    } else {
+        None
+    };
+
+    // Store exact world position with f64 precision for cameras
+    let original_world_pos = child_world_matrix.map(|m| {
+        let (_, _, translation) = m.to_scale_rotation_translation();
+        glam::DVec3::new(
+            translation.x as f64,
+            translation.y as f64,
+            translation.z as f64,
+        )
+    });
+
+    // Remove existing parent if any
+    let _ = world.inner_mut().remove_one::<Parent>(child);
+    // Add new paren
+    let _ = world.insert_one(child, Parent(parent));
+
+    // Check if this is a camera entity before transform adjustmen
+    let is_camera = world.get::<Camera>(child).is_ok();
+
+    // Adjust the child's local transform to maintain its world position
+    if let (Some(child_world), Some(parent_world)) = (child_world_matrix, parent_world_matrix) {
+        if let Ok(child_transform) = world.query_one_mut::<&mut Transform>(child) {
+            // Calculate the local transform relative to the new paren
+            // child_world = parent_world * child_local
+            // child_local = parent_world^-1 * child_world
+            let parent_inverse = parent_world.inverse();
+            let new_local_matrix = parent_inverse * child_world;
+            let (scale, rotation, translation) = new_local_matrix.to_scale_rotation_translation();
+
+            if is_camera {
+                let old_pos = child_transform.position;
+                trace!(
+                    old_local_pos = ?old_pos,
+                    new_local_pos = ?translation,
+                    child_world_matrix = ?child_world,
+                    parent_world_matrix = ?parent_world,
+                    "Camera parenting transform calculation"
+                );
+            }
+
+            child_transform.position = translation;
+            child_transform.rotation = rotation.normalize(); // Ensure rotation is normalized
+            child_transform.scale = scale;
+        }
+    }
+
+    // Update hierarchy immediately to ensure GlobalTransforms are correc
+    engine::core::entity::update_hierarchy_system(world);
 
-                    debug!("Parented entity {:?} to {:?}", dragged, entity);
+    // Verify the camera's world position after hierarchy update
+    if is_camera {
+        if let Some(original_pos) = original_world_pos {
+            // Get the new world position and calculate drif
+            let (new_world_pos, drift) = if let Ok(new_global) = world.get::<GlobalTransform>(child)
+            {
+                let pos = new_global.position();
+                let d = ((pos.x as f64 - original_pos.x).abs()
+                    + (pos.y as f64 - original_pos.y).abs()
+                    + (pos.z as f64 - original_pos.z).abs())
+                    / 3.0;
+                (pos, d)
+            } else {
+                return; // Skip if we can't get the transform
+            };
+
+            if drift > 0.0001 {
+                warn!(
+                    entity = ?child,
+                    drift = drift,
+                    original_pos = ?original_pos,
+                    new_pos = ?new_world_pos,
+                    "Camera position drifted after parenting"
+                );
+
+                // If drift is significant, attempt to correct i
+                if drift > 0.001 {
+                    if let Ok(child_transform) = world.query_one_mut::<&mut Transform>(child) {
+                        // Recalculate with higher precision
+                        let parent_world_f64 = parent_world_matrix.map(|m| {
+                            glam::DMat4::from_cols(
+                                m.x_axis.as_dvec4(),
+                                m.y_axis.as_dvec4(),
+                                m.z_axis.as_dvec4(),
+                                m.w_axis.as_dvec4(),
+                            )
+                        });
+
+                        if let Some(parent_f64) = parent_world_f64 {
+                            let parent_inverse_f64 = parent_f64.inverse();
+                            let child_local_pos_f64 =
+                                parent_inverse_f64.transform_point3(original_pos);
+                            child_transform.position = child_local_pos_f64.as_vec3();
+
+                            debug!(
+                                entity = ?child,
+                                corrected_pos = ?child_local_pos_f64,
+                                "Applied drift correction to camera"
+                            );
+                        }
+                    }
                 }
+            } else {
+                trace!(
+                    entity = ?child,
+                    drift = drift,
+                    "Camera parenting completed with minimal drift"
+                );
             }
         }
     }
+}
 
-    // Unindent for next items at same level
-    if depth > 0 {
-        for _ in 0..depth {
-            ui.unindent();
+/// Import a model file from the asset browser as an entity hierarchy
+fn import_model_hierarchy(shared_state: &EditorSharedState, file_path: &str) {
+    if !crate::panels::assets::validate_asset_path(file_path) {
+        warn!("Invalid or unsafe asset path dropped: {}", file_path);
+        return;
+    }
+
+    let model_path = format!("game/assets/{file_path}");
+    match engine::graphics::load_scene_from_file(std::path::Path::new(&model_path)) {
+        Ok(scene) => {
+            let spawned = shared_state.with_world_write(|world| {
+                let existing: HashSet<hecs::Entity> =
+                    world.query::<()>().iter().map(|(e, _)| e).collect();
+                let root = scene.spawn(world);
+                let spawned: Vec<hecs::Entity> = world
+                    .query::<()>()
+                    .iter()
+                    .map(|(e, _)| e)
+                    .filter(|e| !existing.contains(e))
+                    .collect();
+                (root, spawned)
+            });
+
+            if let Some((root, spawned)) = spawned {
+                debug!(entity = ?root, model = %model_path, "Imported model hierarchy");
+                shared_state.record_spawn(format!("Import {file_path}"), &spawned);
+                shared_state.set_selected_entity(Some(root));
+                shared_state.mark_scene_modified();
+            }
         }
+        Err(e) => warn!("Failed to import model {}: {}", model_path, e),
     }
 }
 
@@ -818,4 +820,30 @@ mod tests {
         assert!(name.starts_with("Entity"));
         assert!(!name.contains("[No Transform]"));
     }
+
+    #[test]
+    fn test_display_order_and_move_filtering() {
+        let mut world = World::new();
+        let root = world.spawn((Name::new("Root"), Transform::default()));
+        let child = world.spawn((Name::new("Child"), Transform::default(), Parent(root)));
+        let grandchild =
+            world.spawn((Name::new("Grandchild"), Transform::default(), Parent(child)));
+        let other = world.spawn((Name::new("Other"), Transform::default()));
+
+        let parent_map = HashMap::from([(root, vec![child]), (child, vec![grandchild])]);
+        assert_eq!(
+            display_order(&[root, other], &parent_map),
+            vec![root, child, grandchild, other]
+        );
+
+        // The grandchild follows its selected parent, the root can't move below itself
+        let moved = entities_to_move(&world, vec![root, child, grandchild], Some(other));
+        assert_eq!(moved, vec![root]);
+        let moved = entities_to_move(&world, vec![root, grandchild, other], Some(child));
+        assert_eq!(moved, vec![grandchild, other]);
+
+        // Only parented entities move to the root
+        let moved = entities_to_move(&world, vec![grandchild, other], None);
+        assert_eq!(moved, vec![grandchild]);
+    }
 }
@@ -0,0 +1,111 @@
+//! Undo history panel
+//!
+//! Lists the recorded scene edits and lets the user step back and forth
+//! through them.
+
+use crate::panel_state::{PanelId, PanelManager};
+use crate::shared_state::EditorSharedState;
+use engine::profile_zone;
+use imgui::*;
+use tracing::debug;
+
+/// Render the undo history panel
+pub fn render_history_panel(
+    ui: &imgui::Ui,
+    shared_state: &EditorSharedState,
+    panel_manager: &mut PanelManager,
+    _window_size: (f32, f32),
+) {
+    profile_zone!("render_history_panel");
+
+    let panel_id = PanelId("history".to_string());
+
+    // Get panel info
+    let (panel_title, is_visible) = {
+        match panel_manager.get_panel(&panel_id) {
+            Some(panel) => (panel.title.clone(), panel.is_visible),
+            None => return,
+        }
+    };
+
+    if !is_visible {
+        return;
+    }
+
+    let window_name = format!("{}##{}", panel_title, panel_id.0);
+
+    ui.window(&window_name)
+        .size([280.0, 250.0], Condition::FirstUseEver)
+        .position([20.0, 470.0], Condition::FirstUseEver)
+        .resizable(true)
+        .build(|| {
+            let Some((applied, undone)) = shared_state.with_history(|history| {
+                let applied: Vec<String> = history
+                    .undo_commands()
+                    .iter()
+                    .map(|command| command.label())
+                    .collect();
+                let undone: Vec<String> = history
+                    .redo_commands()
+                    .map(|command| command.label())
+                    .collect();
+                (applied, undone)
+            }) else {
+                ui.text("Failed to access history");
+                return;
+            };
+
+            ui.disabled(applied.is_empty(), || {
+                if ui.button("Undo") {
+                    shared_state.undo();
+                }
+            });
+            ui.same_line();
+            ui.disabled(undone.is_empty(), || {
+                if ui.button("Redo") {
+                    shared_state.redo();
+                }
+            });
+            ui.separator();
+
+            // Number of commands that should stay applied after a click
+            let mut target = None;
+
+            if ui
+                .selectable_config("Scene opened")
+                .selected(applied.is_empty())
+                .build()
+            {
+                target = Some(0);
+            }
+
+            for (index, label) in applied.iter().enumerate() {
+                let is_current = index + 1 == applied.len();
+                if ui
+                    .selectable_config(format!("{label}##applied{index}"))
+                    .selected(is_current)
+                    .build()
+                {
+                    target = Some(index + 1);
+                }
+            }
+
+            // Undone commands are greyed out until they are redone
+            for (index, label) in undone.iter().enumerate() {
+                let _color = ui.push_style_color(StyleColor::Text, [0.5, 0.5, 0.5, 1.0]);
+                if ui.selectable(format!("{label}##undone{index}")) {
+                    target = Some(applied.len() + index + 1);
+                }
+            }
+
+            if let Some(target) = target {
+                debug!(from = applied.len(), to = target, "Jumping in history");
+                for _ in target..applied.len() {
+                    shared_state.undo();
+                }
+                for _ in applied.len()..target {
+                    shared_state.redo();
+                }
+            }
+        });
+}
@@ -5,7 +5,9 @@
 use crate::panel_state::{PanelId, PanelManager};
 use crate::shared_state::EditorSharedState;
 use engine::component_system::ComponentRegistryExt;
-use engine::prelude::{Camera, Material, MeshId, Name, Parent, ScriptProperties, Transform};
+use engine::prelude::{
+    Camera, Material, MeshId, Name, Parent, PrefabInstance, ScriptProperties, Transform,
+};
 use engine::profile_zone;
 use engine::scripting::property_types::PropertyValue;
 use engine::scripting::ScriptRef;
@@ -102,7 +104,7 @@ pub fn render_inspector_panel(
 
                                                 if has_script {
                                                     // Update existing script
-                                                    shared_state.with_world_write(|world| {
+                                                    shared_state.edit_component(entity, "ScriptRef", |world| {
                                                         if let Ok(mut script) = world.inner_mut().remove_one::<ScriptRef>(entity) {
                                                             script.name = name.to_string();
                                                             debug!(entity = ?entity, script = %name, "Updated script via inspector drop");
@@ -111,14 +113,14 @@ pub fn render_inspector_panel(
                                                     });
                                                 } else {
                                                     // Add new script component
-                                                    shared_state.with_world_write(|world| {
+                                                    shared_state.edit_component(entity, "ScriptRef", |world| {
                                                         let _ = world.insert_one(entity, ScriptRef::new(name));
                                                         debug!(entity = ?entity, script = %name, "Added script via inspector drop");
                                                     });
                                                 }
                                                 shared_state.mark_scene_modified();
                                             }
-                                        } else if file_path.ends_with(".obj") {
+                                        } else if crate::panels::assets::is_model_file(&file_path) {
                                             // Handle mesh drop
                                             let mesh_path = format!("game/assets/{file_path}");
 
@@ -129,7 +131,7 @@ pub fn render_inspector_panel(
 
                                             if has_mesh {
                                                 // Update existing mesh
-                                                shared_state.with_world_write(|world| {
+                                                shared_state.edit_component(entity, "MeshId", |world| {
                                                     if let Ok(mut mesh) = world.inner_mut().remove_one::<MeshId>(entity) {
                                                         mesh.0 = mesh_path.clone();
                                                         debug!(entity = ?entity, mesh = %mesh_path, "Updated mesh via inspector drop");
@@ -138,7 +140,7 @@ pub fn render_inspector_panel(
                                                 });
                                             } else {
                                                 // Add new mesh component
-                                                shared_state.with_world_write(|world| {
+                                                shared_state.edit_component(entity, "MeshId", |world| {
                                                     let _ = world.insert_one(entity, MeshId(mesh_path.clone()));
                                                     debug!(entity = ?entity, mesh = %mesh_path, "Added mesh via inspector drop");
                                                 });
@@ -155,7 +157,7 @@ pub fn render_inspector_panel(
 
                                             if has_audio_source {
                                                 // Update existing audio source
-                                                shared_state.with_world_write(|world| {
+                                                shared_state.edit_component(entity, "AudioSource", |world| {
                                                     if let Ok(mut audio_source) = world.inner_mut().remove_one::<engine::audio::AudioSource>(entity) {
                                                         audio_source.sound_path = audio_path.clone();
                                                         debug!(entity = ?entity, audio = %audio_path, "Updated audio source via inspector drop");
@@ -164,7 +166,7 @@ pub fn render_inspector_panel(
                                                 });
                                             } else {
                                                 // Add new audio source component
-                                                shared_state.with_world_write(|world| {
+                                                shared_state.edit_component(entity, "AudioSource", |world| {
                                                     let audio_source = engine::audio::AudioSource {
                                                         sound: None,
                                                         sound_path: audio_path.clone(),
@@ -196,15 +198,23 @@ pub fn render_inspector_panel(
                 .size(available_size)
                 .build(|| {
                     if let Some(entity) = shared_state.selected_entity() {
-                        ui.text(format!("Entity: {entity:?}"));
+                        // Other selected entities, edited along with the primary one
+                        let others: Vec<hecs::Entity> = shared_state
+                            .selected_entities()
+                            .into_iter()
+                            .filter(|&other| other != entity)
+                            .collect();
+                        if others.is_empty() {
+                            ui.text(format!("Entity: {entity:?}"));
+                        } else {
+                            ui.text(format!("{} entities selected", others.len() + 1));
+                            ui.text_disabled(format!("Showing {entity:?}, edits apply to all"));
+                        }
                         ui.separator();
 
-                // Get all component types on this entity
+                // Get the component types all selected entities have
                 let component_types = shared_state.with_world_read(|world| {
-                    world.inner().entity(entity)
-                        .ok()
-                        .map(|e| e.component_types().collect::<Vec<_>>())
-                        .unwrap_or_default()
+                    shared_component_types(world, entity, &others)
                 }).unwrap_or_default();
 
                 debug!(entity = ?entity, component_count = component_types.len(), "Entity components");
@@ -228,7 +238,8 @@ pub fn render_inspector_panel(
                     // Skip certain internal components
                     if matches!(metadata.name,
                         "GlobalTransform" | "GlobalWorldTransform" |
-                        "PreviousTransform" | "ParentData" | "CameraWorldPosition"
+                        "PreviousTransform" | "PreviousWorldTransform" | "ParentData" |
+                        "CameraWorldPosition"
                     ) {
                         continue;
                     }
@@ -237,72 +248,102 @@ pub fn render_inspector_panel(
                     match type_id {
                         t if t == TypeId::of::<Name>() => {
                             render_component_with_metadata::<Name>(
-                                ui, entity, "Name", shared_state, &registry
+                                ui, entity, &others, "Name", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<Transform>() => {
                             render_component_with_metadata::<Transform>(
-                                ui, entity, "Transform", shared_state, &registry
+                                ui, entity, &others, "Transform", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<Camera>() => {
                             render_component_with_metadata::<Camera>(
-                                ui, entity, "Camera", shared_state, &registry
+                                ui, entity, &others, "Camera", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<Material>() => {
                             render_component_with_metadata::<Material>(
-                                ui, entity, "Material", shared_state, &registry
+                                ui, entity, &others, "Material", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::physics::components::RigidBody>() => {
                             render_component_with_metadata::<engine::physics::components::RigidBody>(
-                                ui, entity, "RigidBody", shared_state, &registry
+                                ui, entity, &others, "RigidBody", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::physics::components::Collider>() => {
                             render_component_with_metadata::<engine::physics::components::Collider>(
-                                ui, entity, "Collider", shared_state, &registry
+                                ui, entity, &others, "Collider", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::physics::components::PhysicsVelocity>() => {
                             render_component_with_metadata::<engine::physics::components::PhysicsVelocity>(
-                                ui, entity, "PhysicsVelocity", shared_state, &registry
+                                ui, entity, &others, "PhysicsVelocity", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::physics::components::PhysicsMass>() => {
                             render_component_with_metadata::<engine::physics::components::PhysicsMass>(
-                                ui, entity, "PhysicsMass", shared_state, &registry
+                                ui, entity, &others, "PhysicsMass", shared_state, &registry
+                            );
+                        }
+                        t if t == TypeId::of::<engine::physics::CharacterController>() => {
+                            render_component_with_metadata::<engine::physics::CharacterController>(
+                                ui, entity, &others, "CharacterController", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::audio::AudioSource>() => {
                             render_component_with_metadata::<engine::audio::AudioSource>(
-                                ui, entity, "AudioSource", shared_state, &registry
+                                ui, entity, &others, "AudioSource", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::audio::AudioListener>() => {
                             render_component_with_metadata::<engine::audio::AudioListener>(
-                                ui, entity, "AudioListener", shared_state, &registry
+                                ui, entity, &others, "AudioListener", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::audio::AmbientSound>() => {
                             render_component_with_metadata::<engine::audio::AmbientSound>(
-                                ui, entity, "AmbientSound", shared_state, &registry
+                                ui, entity, &others, "AmbientSound", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::audio::AudioMaterial>() => {
                             render_component_with_metadata::<engine::audio::AudioMaterial>(
-                                ui, entity, "AudioMaterial", shared_state, &registry
+                                ui, entity, &others, "AudioMaterial", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::graphics::AABB>() => {
                             render_component_with_metadata::<engine::graphics::AABB>(
-                                ui, entity, "AABB", shared_state, &registry
+                                ui, entity, &others, "AABB", shared_state, &registry
                             );
                         }
                         t if t == TypeId::of::<engine::graphics::Visibility>() => {
                             render_component_with_metadata::<engine::graphics::Visibility>(
-                                ui, entity, "Visibility", shared_state, &registry
+                                ui, entity, &others, "Visibility", shared_state, &registry
+                            );
+                        }
+                        t if t == TypeId::of::<engine::graphics::DirectionalLight>() => {
+                            render_component_with_metadata::<engine::graphics::DirectionalLight>(
+                                ui, entity, &others, "DirectionalLight", shared_state, &registry
+                            );
+                        }
+                        t if t == TypeId::of::<engine::graphics::PointLight>() => {
+                            render_component_with_metadata::<engine::graphics::PointLight>(
+                                ui, entity, &others, "PointLight", shared_state, &registry
+                            );
+                        }
+                        t if t == TypeId::of::<engine::graphics::SpotLight>() => {
+                            render_component_with_metadata::<engine::graphics::SpotLight>(
+                                ui, entity, &others, "SpotLight", shared_state, &registry
+                            );
+                        }
+                        t if t == TypeId::of::<engine::graphics::PostProcessSettings>() => {
+                            render_component_with_metadata::<engine::graphics::PostProcessSettings>(
+                                ui, entity, &others, "PostProcessSettings", shared_state, &registry
+                            );
+                        }
+                        t if t == TypeId::of::<engine::graphics::Skybox>() => {
+                            render_component_with_metadata::<engine::graphics::Skybox>(
+                                ui, entity, &others, "Skybox", shared_state, &registry
                             );
                         }
                         _ => {
@@ -311,6 +352,8 @@ pub fn render_inspector_panel(
                     }
                 }
 
+                // Parent, mesh and script are only edited for a single entity
+                if others.is_empty() {
                 // Special handling for Parent component (read-only)
                 if let Some(parent_entity) = shared_state.with_world_read(|world| {
                     world.get::<Parent>(entity).map(|p| p.0).ok()
@@ -326,7 +369,7 @@ pub fn render_inspector_panel(
                     && ui.collapsing_header("Mesh", TreeNodeFlags::DEFAULT_OPEN) {
                         let mut remove_component = false;
 
-                        shared_state.with_world_write(|world| {
+                        shared_state.edit_component(entity, "MeshId", |world| {
                             if let Ok(mut mesh_id) = world.inner_mut().remove_one::<MeshId>(entity) {
                                 let mut mesh_name = mesh_id.0.clone();
                                 let input_changed = ui.input_text("Mesh ID", &mut mesh_name)
@@ -350,10 +393,10 @@ pub fn render_inspector_panel(
                                     if target.accept_payload_empty("ASSET_FILE", DragDropFlags::empty()).is_some() {
                                         // Get dragged file from asset browser state
                                         if let Some(file_path) = crate::panels::assets::AssetBrowserState::take_dragged_file() {
-                                            if file_path.ends_with(".obj") && crate::panels::assets::validate_asset_path(&file_path) {
+                                            if crate::panels::assets::is_model_file(&file_path) && crate::panels::assets::validate_asset_path(&file_path) {
                                                 mesh_name = format!("game/assets/{file_path}");
                                                 drop_accepted = true;
-                                                debug!(entity = ?entity, "Accepted .obj drop: {}", mesh_name);
+                                                debug!(entity = ?entity, "Accepted model drop: {}", mesh_name);
                                             } else {
                                                 warn!("Invalid or unsafe asset path dropped: {}", file_path);
                                             }
@@ -390,7 +433,7 @@ pub fn render_inspector_panel(
                     && ui.collapsing_header("Script", TreeNodeFlags::DEFAULT_OPEN) {
                         let mut remove_component = false;
 
-                        shared_state.with_world_write(|world| {
+                        shared_state.edit_component(entity, "ScriptRef", |world| {
                             if let Ok(mut script) = world.inner_mut().remove_one::<ScriptRef>(entity) {
                                 let mut script_name = script.name.clone();
                                 let input_changed = ui.input_text("Script Name", &mut script_name)
@@ -461,7 +504,7 @@ pub fn render_inspector_panel(
                             if has_props {
                                 ui.text("Script Properties:");
 
-                                shared_state.with_world_write(|world| {
+                                shared_state.edit_component(entity, "ScriptProperties", |world| {
                                     // Get the script name first before removing properties
                                     let script_name = world.get::<&ScriptRef>(entity)
                                         .map(|s| s.name.clone())
@@ -591,6 +634,11 @@ pub fn render_inspector_panel(
                         }
                     }
 
+                } else {
+                    ui.separator();
+                    ui.text_disabled("Select a single entity to edit its parent, mesh and script");
+                }
+
                 // Add component button
                 ui.separator();
                 let state = get_inspector_state();
@@ -627,12 +675,9 @@ pub fn render_inspector_panel(
                     let filter = state.component_filter.to_lowercase();
                     let mut component_added = false;
 
-                    // Get existing component types for this entity
+                    // Get component types every selected entity already has
                     let existing_types = shared_state.with_world_read(|world| {
-                        world.inner().entity(entity)
-                            .ok()
-                            .map(|e| e.component_types().collect::<Vec<_>>())
-                            .unwrap_or_default()
+                        shared_component_types(world, entity, &others)
                     }).unwrap_or_default();
 
                     // Get all registered components from the registry
@@ -646,7 +691,8 @@ pub fn render_inspector_panel(
                                 // Don't show internal components that shouldn't be manually added
                                 && !matches!(metadata.name,
                                     "GlobalTransform" | "GlobalWorldTransform" |
-                                    "PreviousTransform" | "Parent" | "ParentData" |
+                                    "PreviousTransform" | "PreviousWorldTransform" |
+                                    "Parent" | "ParentData" |
                                     "ScriptProperties" | "CameraWorldPosition"
                                 )
                         })
@@ -658,22 +704,28 @@ pub fn render_inspector_panel(
                     // Display each available component
                     for metadata in available_components {
                         if ui.selectable(metadata.name) {
-                            shared_state.with_world_write(|world| {
+                            let targets: Vec<_> = std::iter::once(entity).chain(others.iter().copied()).collect();
+                            shared_state.edit_components(&targets, metadata.name, |world, target| {
+                                // Entities of the selection that already have it keep their value
+                                if (metadata.serialize_component)(world, target).is_some() {
+                                    return;
+                                }
+
                                 // Use the add_default function from metadata
-                                match (metadata.add_default)(world, entity) {
+                                match (metadata.add_default)(world, target) {
                                     Ok(_) => {
-                                        debug!(entity = ?entity, component = metadata.name, "Added component via dynamic registry");
+                                        debug!(entity = ?target, component = metadata.name, "Added component via dynamic registry");
 
                                         // Special handling for Transform - also add GlobalTransform
                                         if metadata.name == "Transform" {
-                                            let _ = world.insert_one(entity, engine::prelude::GlobalTransform::default());
+                                            let _ = world.insert_one(target, engine::prelude::GlobalTransform::default());
                                         }
 
                                         shared_state.mark_scene_modified();
                                         component_added = true;
                                     }
                                     Err(e) => {
-                                        warn!(entity = ?entity, component = metadata.name, error = %e, "Failed to add component");
+                                        warn!(entity = ?target, component = metadata.name, error = %e, "Failed to add component");
                                     }
                                 }
                             });
@@ -689,37 +741,68 @@ pub fn render_inspector_panel(
                     }
                 }
 
+                // Prefab section
+                if others.is_empty() {
+                    render_prefab_section(ui, shared_state, entity);
+                }
+
                 // Entity controls section
                 ui.separator();
                 ui.text("Entity Actions:");
 
+                // Delete and duplicate act on the whole selection, primary last
+                let selection: Vec<_> = others.iter().copied().chain(std::iter::once(entity)).collect();
+
                 // Delete entity button
-                if ui.button("Delete Entity") {
-                    shared_state.with_world_write(|world| {
-                        if world.despawn(entity).is_ok() {
-                            debug!(entity = ?entity, "Deleted entity");
-                            shared_state.set_selected_entity(None);
-                            shared_state.mark_scene_modified();
-                        }
-                    });
+                let delete_label = if others.is_empty() {
+                    "Delete Entity".to_string()
+                } else {
+                    format!("Delete {} Entities##delete_entities", selection.len())
+                };
+                if ui.button(delete_label) {
+                    let deleted = shared_state.despawn_entities(&selection);
+                    if deleted > 0 {
+                        debug!(count = deleted, "Deleted entities");
+                        shared_state.set_selected_entity(None);
+                        shared_state.mark_scene_modified();
+                    }
                 }
 
                 ui.same_line();
 
                 // Duplicate entity button
-                if ui.button("Duplicate Entity") {
-                    shared_state.with_world_write(|world| {
-                        match duplicate_entity(world, entity, &registry) {
-                            Ok(new_entity) => {
-                                debug!(original = ?entity, new = ?new_entity, "Duplicated entity");
-                                shared_state.set_selected_entity(Some(new_entity));
-                                shared_state.mark_scene_modified();
+                let duplicate_label = if others.is_empty() {
+                    "Duplicate Entity".to_string()
+                } else {
+                    format!("Duplicate {} Entities##duplicate_entities", selection.len())
+                };
+                if ui.button(duplicate_label) {
+                    let mut copies = Vec::new();
+                    for &original in &selection {
+                        let result = shared_state.with_world_write(|world| {
+                            duplicate_entity(world, original, &registry)
+                        });
+                        match result {
+                            Some(Ok(new_entity)) => {
+                                debug!(original = ?original, new = ?new_entity, "Duplicated entity");
+                                copies.push(new_entity);
                             }
-                            Err(e) => {
-                                warn!(entity = ?entity, error = %e, "Failed to duplicate entity");
+                            Some(Err(e)) => {
+                                warn!(entity = ?original, error = %e, "Failed to duplicate entity");
                             }
+                            None => {}
                         }
-                    });
+                    }
+                    if !copies.is_empty() {
+                        let label = if copies.len() == 1 {
+                            "Duplicate Entity".to_string()
+                        } else {
+                            format!("Duplicate {} Entities", copies.len())
+                        };
+                        shared_state.record_spawn(label, &copies);
+                        shared_state.set_selection(copies);
+                        shared_state.mark_scene_modified();
+                    }
                 }
                     } else {
                         ui.text("No entity selected");
@@ -729,17 +812,18 @@ pub fn render_inspector_panel(
 
                         // Create new entity button
                         if ui.button("Create New Entity") {
-                            shared_state.with_world_write(|world| {
-                                let new_entity = world.spawn((
+                            if let Some(new_entity) = shared_state.with_world_write(|world| {
+                                world.spawn((
                                     Name::new("New Entity"),
                                     Transform::default(),
                                     engine::prelude::GlobalTransform::default(),
-                                ));
-
+                                ))
+                            }) {
                                 debug!(entity = ?new_entity, "Created new entity");
+                                shared_state.record_spawn("Create Entity", &[new_entity]);
                                 shared_state.set_selected_entity(Some(new_entity));
                                 shared_state.mark_scene_modified();
-                            });
+                            }
                         }
                     }
                 }); // End of inspector_content child window
@@ -747,18 +831,26 @@ pub fn render_inspector_panel(
 }
 
 /// Render a component using its metadata if available
+///
+/// With `others` the component is shown for `entity`, fields that differ on
+/// the other selected entities are marked as mixed and edits apply to all.
 fn render_component_with_metadata<
     T: 'static + Send + Sync + engine::component_system::field_access::FieldAccess,
 >(
     ui: &imgui::Ui,
     entity: hecs::Entity,
+    others: &[hecs::Entity],
     component_name: &str,
     shared_state: &EditorSharedState,
     registry: &engine::io::component_registry::ComponentRegistry,
 ) -> bool {
+    use engine::component_system::field_access::FieldValue;
     use std::any::TypeId;
 
     let mut component_modified = false;
+    let targets: Vec<_> = std::iter::once(entity)
+        .chain(others.iter().copied())
+        .collect();
 
     // Get component metadata
     let metadata = registry.get_metadata(TypeId::of::<T>());
@@ -783,29 +875,74 @@ fn render_component_with_metadata<
                     field_count = ui_metadata.fields.len(),
                     "Using metadata-based rendering"
                 );
+
+                let mixed = shared_state
+                    .with_world_read(|world| {
+                        let Ok(component) = world.get::<T>(entity) else {
+                            return Vec::new();
+                        };
+                        let others: Vec<_> = others
+                            .iter()
+                            .filter_map(|&other| world.get::<T>(other).ok())
+                            .collect();
+                        let others: Vec<&T> = others.iter().map(|other| &**other).collect();
+                        crate::ui_metadata_renderer::mixed_fields(&*component, &others, ui_metadata)
+                    })
+                    .unwrap_or_default();
+
+                // Edits of the primary entity, as (field, before, after)
+                let mut edits: Vec<(String, FieldValue, FieldValue)> = Vec::new();
+
                 // Use metadata-based rendering
-                shared_state.with_world_write(|world| {
+                shared_state.edit_components(&targets, metadata.name, |world, target| {
                     // We need to temporarily remove the component to get mutable access
-                    if let Ok(mut component) = world.inner_mut().remove_one::<T>(entity) {
-                        // Use the metadata renderer directly
-                        let modified = crate::ui_metadata_renderer::render_component_ui(
-                            ui,
-                            &mut component,
-                            ui_metadata,
-                            entity,
-                        );
-
-                        if modified {
-                            component_modified = true;
-                            shared_state.mark_scene_modified();
-                            debug!(
-                                component = component_name,
-                                "Component modified via metadata UI"
+                    if let Ok(mut component) = world.inner_mut().remove_one::<T>(target) {
+                        if target == entity {
+                            // Use the metadata renderer directly
+                            let before: Vec<_> = ui_metadata
+                                .fields
+                                .iter()
+                                .map(|field| component.get_field(&field.name))
+                                .collect();
+                            let modified = crate::ui_metadata_renderer::render_component_ui_mixed(
+                                ui,
+                                &mut component,
+                                ui_metadata,
+                                &mixed,
                             );
+                            for (field, before) in ui_metadata.fields.iter().zip(before) {
+                                if !modified.contains(&field.name) {
+                                    continue;
+                                }
+                                if let (Some(before), Some(after)) =
+                                    (before, component.get_field(&field.name))
+                                {
+                                    edits.push((field.name.clone(), before, after));
+                                }
+                            }
+
+                            if !modified.is_empty() {
+                                component_modified = true;
+                                shared_state.mark_scene_modified();
+                                debug!(
+                                    component = component_name,
+                                    "Component modified via metadata UI"
+                                );
+                            }
+                        } else {
+                            // Carry the primary entity's edits over
+                            for (field, before, after) in &edits {
+                                if let Some(current) = component.get_field(field) {
+                                    let value = crate::ui_metadata_renderer::apply_field_edit(
+                                        before, after, &current,
+                                    );
+                                    component.set_field(field, value);
+                                }
+                            }
                         }
 
                         // Always re-insert the component
-                        let _ = world.insert_one(entity, component);
+                        let _ = world.insert_one(target, component);
                     }
                 });
             } else {
@@ -820,10 +957,10 @@ fn render_component_with_metadata<
         // Remove component button
         ui.separator();
         if ui.small_button(format!("Remove##{component_name}")) {
-            shared_state.with_world_write(|world| {
-                world.inner_mut().remove_one::<T>(entity).ok();
+            shared_state.edit_components(&targets, component_name, |world, target| {
+                world.inner_mut().remove_one::<T>(target).ok();
                 remove_component = true;
-                debug!(entity = ?entity, component = component_name, "Removed component");
+                debug!(entity = ?target, component = component_name, "Removed component");
             });
         }
 
@@ -835,6 +972,29 @@ fn render_component_with_metadata<
     component_modified
 }
 
+/// Component types the primary entity shares with all other selected entities
+fn shared_component_types(
+    world: &engine::core::entity::World,
+    entity: hecs::Entity,
+    others: &[hecs::Entity],
+) -> Vec<std::any::TypeId> {
+    let component_types = |entity| {
+        world
+            .inner()
+            .entity(entity)
+            .ok()
+            .map(|e| e.component_types().collect::<Vec<_>>())
+            .unwrap_or_default()
+    };
+
+    let mut shared = component_types(entity);
+    for &other in others {
+        let other_types = component_types(other);
+        shared.retain(|type_id| other_types.contains(type_id));
+    }
+    shared
+}
+
 /// Duplicate an entity with all its components
 fn duplicate_entity(
     world: &mut engine::core::entity::World,
@@ -885,7 +1045,7 @@ fn duplicate_entity(
     if let Ok(material) = world.get::<engine::graphics::Material>(entity) {
         entity_components.insert(
             "Material".to_string(),
-            serde_json::to_value(*material).map_err(|e| e.to_string())?,
+            serde_json::to_value(&*material).map_err(|e| e.to_string())?,
         );
     }
 
@@ -926,6 +1086,13 @@ fn duplicate_entity(
         );
     }
 
+    if let Ok(controller) = world.get::<engine::physics::CharacterController>(entity) {
+        entity_components.insert(
+            "CharacterController".to_string(),
+            serde_json::to_value(&*controller).map_err(|e| e.to_string())?,
+        );
+    }
+
     // Scripting components
     if let Ok(script_ref) = world.get::<engine::scripting::ScriptRef>(entity) {
         entity_components.insert(
@@ -1017,6 +1184,14 @@ fn duplicate_entity(
                     .insert_one(new_entity, mass)
                     .map_err(|e| format!("Failed to insert PhysicsMass: {e:?}"))?;
             }
+            "CharacterController" => {
+                let controller: engine::physics::CharacterController =
+                    serde_json::from_value(value)
+                        .map_err(|e| format!("Failed to deserialize CharacterController: {e}"))?;
+                world
+                    .insert_one(new_entity, controller)
+                    .map_err(|e| format!("Failed to insert CharacterController: {e:?}"))?;
+            }
             "ScriptRef" => {
                 let script_ref: engine::scripting::ScriptRef = serde_json::from_value(value)
                     .map_err(|e| format!("Failed to deserialize ScriptRef: {e}"))?;
@@ -1046,3 +1221,51 @@ fn duplicate_entity(
 
     Ok(new_entity)
 }
+
+/// Show the prefab an entity belongs to, with apply and revert for instance roots
+fn render_prefab_section(ui: &imgui::Ui, shared_state: &EditorSharedState, entity: hecs::Entity) {
+    use engine::io::prefab;
+
+    let info = shared_state.with_prefabs(|world, registry, prefabs| {
+        if let Ok(instance) = world.get::<PrefabInstance>(entity) {
+            let source = instance.source.clone();
+            let overrides = prefabs
+                .load(&source)
+                .map(|prefab| prefab::instance_overrides(world, registry, prefab, entity).len())
+                .ok();
+            return (Some((source, overrides)), None);
+        }
+        let owner = prefab::owning_instance(world, entity).and_then(|root| {
+            world
+                .get::<PrefabInstance>(root)
+                .ok()
+                .map(|instance| instance.source.clone())
+        });
+        (None, owner)
+    });
+    let Some((instance, owner)) = info else {
+        return;
+    };
+
+    ui.separator();
+    if let Some((source, overrides)) = instance {
+        ui.text("Prefab:");
+        ui.same_line();
+        ui.text_colored([0.5, 0.8, 1.0, 1.0], &source);
+        match overrides {
+            Some(count) => ui.text(format!("{count} override(s)")),
+            None => ui.text_colored([1.0, 0.4, 0.4, 1.0], "Prefab file could not be loaded"),
+        }
+        if ui.button("Apply to Prefab") {
+            crate::scene_operations::apply_prefab_instance(shared_state, entity);
+        }
+        ui.same_line();
+        if ui.button("Revert Overrides") {
+            crate::scene_operations::revert_prefab_instance(shared_state, entity);
+        }
+    } else if let Some(source) = owner {
+        ui.text_colored([0.5, 0.8, 1.0, 1.0], format!("Part of prefab {source}"));
+    } else if ui.button("Create Prefab") {
+        crate::scene_operations::create_prefab(shared_state, entity);
+    }
+}
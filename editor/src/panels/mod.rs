@@ -1,14 +1,16 @@
 //! Editor UI panels
 //!
 //! This module contains all the individual UI panels that make up the editor,
-//! including the hierarchy, inspector, viewport, and asset browser.
+//! including the hierarchy, inspector, viewport, asset browser, and undo history.
 
 pub mod assets;
 pub mod hierarchy;
+pub mod history;
 pub mod inspector;
 pub mod viewport;
 
 pub use assets::render_assets_panel;
 pub use hierarchy::render_hierarchy_panel;
+pub use history::render_history_panel;
 pub use inspector::render_inspector_panel;
 pub use viewport::{render_viewport_panel, PerformanceMetrics};
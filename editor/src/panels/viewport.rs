@@ -2,9 +2,17 @@
 //!
 //! Displays the rendered game view within the editor.
 
+use crate::editor_camera::{self, EditorCamera, Navigation, ViewAxis};
+use crate::gizmo::{
+    GizmoHandles, GizmoInput, GizmoMode, GizmoSpace, GizmoState, ViewportProjection, AXIS_COLORS,
+    HIGHLIGHT_COLOR,
+};
 use crate::panel_state::{PanelId, PanelManager};
+use crate::picking::ViewportPicker;
+use crate::play_mode::PlayState;
 use crate::shared_state::EditorSharedState;
 use engine::profile_zone;
+use glam::{Vec2, Vec3};
 use imgui::*;
 use std::collections::VecDeque;
 use std::path::PathBuf;
@@ -17,6 +25,16 @@ pub enum ViewportAction {
     Resize(u32, u32),
     /// Request to load a scene file
     LoadScene(PathBuf),
+    /// Enter play mode or resume a paused game
+    Play,
+    /// Pause the running game
+    Pause,
+    /// Advance the paused game by one frame
+    Step,
+    /// Leave play mode and restore the scene
+    Stop,
+    /// Frame the selected entities with the editor camera
+    FocusSelection,
 }
 
 /// Performance metrics tracker
@@ -95,16 +113,219 @@ impl PerformanceMetrics {
     }
 }
 
+/// Render the play, pause, step and stop buttons
+fn render_play_controls(ui: &imgui::Ui, play_state: PlayState) -> Option<ViewportAction> {
+    let mut action = None;
+
+    if play_state == PlayState::Playing {
+        if ui.button("Pause") {
+            action = Some(ViewportAction::Pause);
+        }
+    } else if ui.button("Play") {
+        action = Some(ViewportAction::Play);
+    }
+
+    ui.same_line();
+    ui.disabled(play_state == PlayState::Editing, || {
+        if ui.button("Step") {
+            action = Some(ViewportAction::Step);
+        }
+        ui.same_line();
+        if ui.button("Stop") {
+            action = Some(ViewportAction::Stop);
+        }
+    });
+
+    action
+}
+
+/// Render the gizmo mode, space and snapping controls
+fn render_gizmo_toolbar(ui: &imgui::Ui, gizmo: &mut GizmoState) {
+    for (mode, label) in [
+        (GizmoMode::Translate, "Move (W)"),
+        (GizmoMode::Rotate, "Rotate (E)"),
+        (GizmoMode::Scale, "Scale (R)"),
+    ] {
+        ui.same_line();
+        if ui.radio_button_bool(label, gizmo.mode == mode) {
+            gizmo.mode = mode;
+        }
+    }
+
+    ui.same_line();
+    let space_label = match gizmo.space {
+        GizmoSpace::World => "World##gizmo_space",
+        GizmoSpace::Local => "Local##gizmo_space",
+    };
+    if ui.button(space_label) {
+        gizmo.space = gizmo.space.toggled();
+    }
+    if ui.is_item_hovered() {
+        ui.tooltip_text("Toggle between world and local axes");
+    }
+
+    ui.same_line();
+    ui.checkbox("Snap", &mut gizmo.snap.enabled);
+    if gizmo.snap.enabled {
+        let (increment, speed, format) = match gizmo.mode {
+            GizmoMode::Translate => (&mut gizmo.snap.translate, 0.05, "%.2f"),
+            GizmoMode::Rotate => (&mut gizmo.snap.rotate_degrees, 1.0, "%.0f deg"),
+            GizmoMode::Scale => (&mut gizmo.snap.scale, 0.01, "%.2f"),
+        };
+        ui.same_line();
+        ui.set_next_item_width(70.0);
+        Drag::new("##gizmo_snap")
+            .speed(speed)
+            .range(0.0, f32::MAX)
+            .display_format(format)
+            .build(ui, increment);
+    }
+}
+
+/// Render the editor camera's view and focus controls
+fn render_camera_toolbar(
+    ui: &imgui::Ui,
+    editor_camera: &mut EditorCamera,
+    play_state: PlayState,
+) -> Option<ViewportAction> {
+    let mut action = None;
+
+    // The game renders through its own camera while it runs
+    ui.disabled(play_state != PlayState::Editing, || {
+        ui.same_line();
+        ui.set_next_item_width(110.0);
+        if let Some(_token) = ui.begin_combo("##view_axis", editor_camera.view_axis.label()) {
+            for axis in ViewAxis::ALL {
+                if ui
+                    .selectable_config(axis.label())
+                    .selected(editor_camera.view_axis == axis)
+                    .build()
+                {
+                    editor_camera.set_view_axis(axis);
+                }
+            }
+        }
+
+        ui.same_line();
+        if ui.button("Focus (F)") {
+            action = Some(ViewportAction::FocusSelection);
+        }
+    });
+
+    action
+}
+
+/// Move the editor camera with the mouse and keyboard
+///
+/// Alt and the left mouse button orbit, the middle button pans, the right
+/// button looks around and flies with WASD, Q and E. The mouse wheel zooms,
+/// or changes the flying speed while flying. Returns true while the mouse is
+/// used for navigation, so it doesn't also select or drag the gizmo.
+fn navigate_camera(
+    ui: &imgui::Ui,
+    editor_camera: &mut EditorCamera,
+    hovered: bool,
+    viewport_height: f32,
+) -> bool {
+    let io = ui.io();
+
+    if editor_camera.navigation.is_none() && hovered {
+        editor_camera.navigation = if io.key_alt && ui.is_mouse_clicked(MouseButton::Left) {
+            Some(Navigation::Orbit)
+        } else if ui.is_mouse_clicked(MouseButton::Middle) {
+            Some(Navigation::Pan)
+        } else if ui.is_mouse_clicked(MouseButton::Right) {
+            Some(Navigation::Fly)
+        } else {
+            None
+        };
+    }
+
+    let delta = Vec2::from(io.mouse_delta);
+    match editor_camera.navigation {
+        Some(Navigation::Orbit) if ui.is_mouse_down(MouseButton::Left) => {
+            editor_camera.orbit(delta);
+        }
+        Some(Navigation::Pan) if ui.is_mouse_down(MouseButton::Middle) => {
+            editor_camera.pan(delta, viewport_height);
+        }
+        Some(Navigation::Fly) if ui.is_mouse_down(MouseButton::Right) => {
+            let axis = |positive: Key, negative: Key| {
+                ui.is_key_down(positive) as i32 as f32 - ui.is_key_down(negative) as i32 as f32
+            };
+            let movement = Vec3::new(
+                axis(Key::D, Key::A),
+                axis(Key::E, Key::Q),
+                axis(Key::S, Key::W),
+            );
+            editor_camera.adjust_fly_speed(io.mouse_wheel);
+            editor_camera.fly(delta, movement, io.delta_time, io.key_shift);
+        }
+        Some(_) => editor_camera.navigation = None,
+        None => {}
+    }
+
+    if editor_camera.navigation.is_none() && hovered && io.mouse_wheel != 0.0 {
+        editor_camera.zoom(io.mouse_wheel);
+    }
+
+    editor_camera.navigation.is_some() || (hovered && io.key_alt)
+}
+
+/// Draw the gizmo handles, clipped to the viewport image
+fn draw_gizmo(ui: &imgui::Ui, handles: &GizmoHandles, clip_min: [f32; 2], clip_max: [f32; 2]) {
+    let draw_list = ui.get_window_draw_list();
+    draw_list.with_clip_rect_intersect(clip_min, clip_max, || {
+        for (axis, points) in handles.axes.iter().enumerate() {
+            let Some(&tip) = points.last().filter(|_| points.len() >= 2) else {
+                continue;
+            };
+            let active = handles.active_axis == Some(axis);
+            let color = if active {
+                HIGHLIGHT_COLOR
+            } else {
+                AXIS_COLORS[axis]
+            };
+
+            draw_list
+                .add_polyline(points.clone(), color)
+                .thickness(if active { 4.0 } else { 2.5 })
+                .build();
+            match handles.mode {
+                GizmoMode::Translate => draw_list.add_circle(tip, 6.0, color).filled(true).build(),
+                GizmoMode::Scale => draw_list
+                    .add_rect(
+                        [tip[0] - 5.0, tip[1] - 5.0],
+                        [tip[0] + 5.0, tip[1] + 5.0],
+                        color,
+                    )
+                    .filled(true)
+                    .build(),
+                GizmoMode::Rotate => {}
+            }
+        }
+        draw_list
+            .add_circle(handles.origin, 4.0, [1.0, 1.0, 1.0, 1.0])
+            .filled(true)
+            .build();
+    });
+}
+
 /// Render the viewport panel with texture
 /// Returns a ViewportAction if an action is requested
+#[allow(clippy::too_many_arguments)]
 pub fn render_viewport_panel(
     ui: &imgui::Ui,
     texture_id: imgui::TextureId,
     render_target: &engine::graphics::render_target::RenderTarget,
-    _shared_state: &EditorSharedState,
+    shared_state: &EditorSharedState,
     panel_manager: &mut PanelManager,
     _window_size: (f32, f32),
     performance_metrics: &mut PerformanceMetrics,
+    play_state: PlayState,
+    gizmo: &mut GizmoState,
+    picker: &mut ViewportPicker,
+    editor_camera: &mut EditorCamera,
 ) -> Option<ViewportAction> {
     profile_zone!("render_viewport_panel");
 
@@ -130,6 +351,34 @@ pub fn render_viewport_panel(
         .position([100.0, 100.0], Condition::FirstUseEver)
         .resizable(true)
         .build(|| {
+        // Play controls above the game view
+        if let Some(action) = render_play_controls(ui, play_state) {
+            resize_needed = Some(action);
+        }
+        render_gizmo_toolbar(ui, gizmo);
+        if let Some(action) = render_camera_toolbar(ui, editor_camera, play_state) {
+            resize_needed = Some(action);
+        }
+
+        // W/E/R switch the gizmo mode and F frames the selection while the viewport
+        // has focus, unless WASD and QE are flying the camera
+        if ui.is_window_focused()
+            && !ui.io().want_text_input
+            && !gizmo.is_dragging()
+            && editor_camera.navigation.is_none()
+        {
+            if ui.is_key_pressed(Key::F) && play_state == PlayState::Editing {
+                resize_needed = Some(ViewportAction::FocusSelection);
+            }
+            if ui.is_key_pressed(Key::W) {
+                gizmo.mode = GizmoMode::Translate;
+            } else if ui.is_key_pressed(Key::E) {
+                gizmo.mode = GizmoMode::Rotate;
+            } else if ui.is_key_pressed(Key::R) {
+                gizmo.mode = GizmoMode::Scale;
+            }
+        }
+
         let available_size = ui.content_region_avail();
         tracing::debug!(
             "Rendering viewport panel: texture_id={:?}, available_size={:?}, render_target_size={:?}",
@@ -149,6 +398,82 @@ pub fn render_viewport_panel(
 
         // Display the game render target with proper aspect ratio
         imgui::Image::new(texture_id, available_size).build(ui);
+        let image_min = ui.item_rect_min();
+        let image_max = ui.item_rect_max();
+
+        // Outline the game view while the game is running
+        let indicator = match play_state {
+            PlayState::Editing => None,
+            PlayState::Playing => Some(([0.2, 0.8, 0.2, 1.0], "PLAYING")),
+            PlayState::Paused => Some(([1.0, 0.6, 0.0, 1.0], "PAUSED")),
+        };
+        if let Some((color, label)) = indicator {
+            let draw_list = ui.get_window_draw_list();
+            draw_list
+                .add_rect(image_min, image_max, color)
+                .thickness(3.0)
+                .build();
+            draw_list.add_text([image_min[0] + 8.0, image_min[1] + 6.0], color, label);
+        }
+
+        // Claim mouse input over the image so gizmo drags don't move the window
+        let mut viewport_hovered = false;
+        if available_size[0] > 0.0 && available_size[1] > 0.0 {
+            ui.set_cursor_screen_pos(image_min);
+            ui.invisible_button("##viewport_input", available_size);
+            viewport_hovered = ui.is_item_hovered();
+        }
+
+        // The editor camera only moves while editing, the game owns the view otherwise
+        let navigating = play_state == PlayState::Editing
+            && navigate_camera(ui, editor_camera, viewport_hovered, available_size[1]);
+
+        // Gizmo for the selected entity, seen through the camera the viewport renders with
+        let camera = shared_state
+            .with_world_read(|world| {
+                editor_camera::viewport_view(world, editor_camera, play_state)
+            })
+            .flatten();
+        if let Some(camera) = camera {
+            let projection = ViewportProjection::new(&camera, image_min, available_size);
+            let input = GizmoInput {
+                mouse: ui.io().mouse_pos,
+                hovered: viewport_hovered && !navigating,
+                clicked: ui.is_mouse_clicked(MouseButton::Left),
+                down: ui.is_mouse_down(MouseButton::Left),
+            };
+            if let Some(handles) = gizmo.update(shared_state, &projection, input) {
+                draw_gizmo(ui, &handles, image_min, image_max);
+            }
+        }
+
+        // Clicks the gizmo didn't take select entities, dragging selects with a box
+        let mouse = ui.io().mouse_pos;
+        if viewport_hovered
+            && !navigating
+            && ui.is_mouse_clicked(MouseButton::Left)
+            && !gizmo.is_dragging()
+        {
+            picker.press(mouse, image_min, available_size);
+        }
+        if picker.is_pressed() {
+            if ui.is_mouse_down(MouseButton::Left) {
+                if let Some((rect_min, rect_max)) = picker.drag_rect(mouse) {
+                    let draw_list = ui.get_window_draw_list();
+                    draw_list.with_clip_rect_intersect(image_min, image_max, || {
+                        draw_list
+                            .add_rect(rect_min, rect_max, [0.3, 0.6, 1.0, 0.2])
+                            .filled(true)
+                            .build();
+                        draw_list
+                            .add_rect(rect_min, rect_max, [0.3, 0.6, 1.0, 0.9])
+                            .build();
+                    });
+                }
+            } else {
+                picker.release(mouse, ui.io().key_ctrl);
+            }
+        }
 
         // Add drag-drop target for scene files
         if let Some(target) = ui.drag_drop_target() {
@@ -169,8 +494,10 @@ pub fn render_viewport_panel(
                     if crate::panels::assets::is_scene_file(&PathBuf::from(&file_path)) {
                         tracing::debug!("Accepted scene drop: {}", file_path);
                         resize_needed = Some(ViewportAction::LoadScene(PathBuf::from(format!("game/assets/{file_path}"))));
+                    } else if crate::panels::assets::is_prefab_file(&file_path) {
+                        crate::scene_operations::instantiate_prefab(shared_state, &file_path);
                     } else {
-                        tracing::warn!("Dropped file is not a scene: {}", file_path);
+                        tracing::warn!("Dropped file is not a scene or prefab: {}", file_path);
                     }
                 }
             }
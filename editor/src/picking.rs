@@ -0,0 +1,298 @@
+//! Viewport selection by clicking and box dragging
+//!
+//! The viewport panel turns mouse presses the gizmo didn't take into pick
+//! requests. They are resolved against the renderer when the viewport is
+//! rendered next, since only the renderer knows the mesh triangles, and
+//! then applied to the editor selection.
+
+use crate::gizmo::ViewportProjection;
+use crate::shared_state::EditorSharedState;
+use engine::core::camera::CameraView;
+use engine::core::entity::World;
+use engine::graphics::renderer::Renderer;
+use glam::Vec2;
+use tracing::{debug, warn};
+
+/// Distance in pixels the mouse has to move before a click becomes a box selection
+pub const BOX_SELECT_THRESHOLD: f32 = 4.0;
+
+/// How picked entities change the selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Select only the picked entities
+    Replace,
+    /// Flip the selection state of the picked entities
+    Toggle,
+    /// Add the picked entities to the selection
+    Add,
+}
+
+/// Selection change requested in the viewport
+///
+/// Positions are normalized across the viewport image, from (0, 0) at the
+/// top-left to (1, 1) at the bottom-right corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickRequest {
+    /// Select the entity under a point
+    Point {
+        position: [f32; 2],
+        mode: SelectionMode,
+    },
+    /// Select the entities centered inside a rectangle
+    Rect {
+        start: [f32; 2],
+        end: [f32; 2],
+        mode: SelectionMode,
+    },
+}
+
+/// A left button press over the viewport image
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    /// Press position in screen pixels
+    start: [f32; 2],
+    /// Top-left corner of the viewport image
+    image_min: [f32; 2],
+    /// Size of the viewport image
+    image_size: [f32; 2],
+}
+
+impl Press {
+    fn normalize(&self, position: [f32; 2]) -> [f32; 2] {
+        [
+            ((position[0] - self.image_min[0]) / self.image_size[0]).clamp(0.0, 1.0),
+            ((position[1] - self.image_min[1]) / self.image_size[1]).clamp(0.0, 1.0),
+        ]
+    }
+
+    fn is_drag(&self, position: [f32; 2]) -> bool {
+        Vec2::from(position).distance(Vec2::from(self.start)) > BOX_SELECT_THRESHOLD
+    }
+}
+
+/// Click and box drag tracking of the viewport
+#[derive(Debug, Default)]
+pub struct ViewportPicker {
+    /// Press in progress, if any
+    press: Option<Press>,
+    /// Request waiting for the next viewport render
+    pending: Option<PickRequest>,
+}
+
+impl ViewportPicker {
+    /// Create a picker with nothing pressed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a press is being tracked
+    pub fn is_pressed(&self) -> bool {
+        self.press.is_some()
+    }
+
+    /// Start tracking a press at a screen position inside the viewport image
+    pub fn press(&mut self, position: [f32; 2], image_min: [f32; 2], image_size: [f32; 2]) {
+        if image_size[0] <= 0.0 || image_size[1] <= 0.0 {
+            return;
+        }
+        self.press = Some(Press {
+            start: position,
+            image_min,
+            image_size,
+        });
+    }
+
+    /// Screen rectangle of the box selection being dragged, if the mouse
+    /// moved far enough from the press
+    pub fn drag_rect(&self, position: [f32; 2]) -> Option<([f32; 2], [f32; 2])> {
+        let press = self.press.filter(|press| press.is_drag(position))?;
+        let (a, b) = (Vec2::from(press.start), Vec2::from(position));
+        Some((a.min(b).into(), a.max(b).into()))
+    }
+
+    /// Finish the press when the button is released and queue its request
+    ///
+    /// Ctrl toggles a clicked entity and adds boxed entities to the
+    /// selection, otherwise the selection is replaced.
+    pub fn release(&mut self, position: [f32; 2], ctrl: bool) {
+        let Some(press) = self.press.take() else {
+            return;
+        };
+
+        let request = if press.is_drag(position) {
+            PickRequest::Rect {
+                start: press.normalize(press.start),
+                end: press.normalize(position),
+                mode: if ctrl {
+                    SelectionMode::Add
+                } else {
+                    SelectionMode::Replace
+                },
+            }
+        } else {
+            PickRequest::Point {
+                position: press.normalize(press.start),
+                mode: if ctrl {
+                    SelectionMode::Toggle
+                } else {
+                    SelectionMode::Replace
+                },
+            }
+        };
+        debug!(?request, "Queued viewport pick");
+        self.pending = Some(request);
+    }
+
+    /// Take the request waiting to be resolved
+    pub fn take_request(&mut self) -> Option<PickRequest> {
+        self.pending.take()
+    }
+}
+
+/// Resolve a pick request against the scene and update the selection
+///
+/// `camera` is the view the viewport was drawn with and `size` the size of
+/// its render target in pixels. With
+/// `gpu_picking` clicks are resolved with the renderer's entity ID pass,
+/// falling back to a ray cast if that fails. Box selection always uses the
+/// mesh bounds.
+pub fn resolve_pick(
+    request: PickRequest,
+    renderer: &mut Renderer,
+    world: &World,
+    shared_state: &EditorSharedState,
+    camera: &CameraView,
+    size: (u32, u32),
+    gpu_picking: bool,
+) {
+    // Normalized image coordinates are the screen space of this projection
+    let projection = ViewportProjection::new(camera, [0.0, 0.0], [1.0, 1.0]);
+
+    match request {
+        PickRequest::Point { position, mode } => {
+            let gpu_hit = if gpu_picking {
+                let pixel = (
+                    ((position[0] * size.0 as f32) as u32).min(size.0.saturating_sub(1)),
+                    ((position[1] * size.1 as f32) as u32).min(size.1.saturating_sub(1)),
+                );
+                renderer
+                    .pick_gpu(world, camera, size, pixel)
+                    .inspect_err(|e| warn!("GPU picking failed, using a ray cast: {e}"))
+                    .ok()
+            } else {
+                None
+            };
+            let hit = gpu_hit.unwrap_or_else(|| {
+                let (origin, direction) = projection.ray(position);
+                renderer
+                    .pick_ray(world, camera.world_position, origin, direction)
+                    .map(|hit| hit.entity)
+            });
+            debug!(?hit, "Viewport click pick");
+            apply_selection(shared_state, hit.into_iter().collect(), mode);
+        }
+        PickRequest::Rect { start, end, mode } => {
+            let to_ndc = |p: [f32; 2]| Vec2::new(p[0] * 2.0 - 1.0, 1.0 - p[1] * 2.0);
+            let entities = renderer.pick_rect(
+                world,
+                camera.world_position,
+                camera.view_projection(),
+                to_ndc(start),
+                to_ndc(end),
+            );
+            debug!(count = entities.len(), "Viewport box pick");
+            apply_selection(shared_state, entities, mode);
+        }
+    }
+}
+
+/// Change the selection by picked entities
+pub fn apply_selection(
+    shared_state: &EditorSharedState,
+    picked: Vec<hecs::Entity>,
+    mode: SelectionMode,
+) {
+    match mode {
+        SelectionMode::Replace => shared_state.set_selection(picked),
+        SelectionMode::Toggle => {
+            for entity in picked {
+                shared_state.toggle_selection(entity);
+            }
+        }
+        SelectionMode::Add => {
+            let mut selection = shared_state.selected_entities();
+            selection.retain(|entity| !picked.contains(entity));
+            selection.extend(picked);
+            shared_state.set_selection(selection);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::core::entity::Name;
+    use engine::io::component_registry::ComponentRegistry;
+
+    #[test]
+    fn test_click_and_box_requests() {
+        let mut picker = ViewportPicker::new();
+
+        // Small movement stays a click, at the press position
+        picker.press([110.0, 120.0], [100.0, 100.0], [200.0, 100.0]);
+        assert!(picker.drag_rect([112.0, 121.0]).is_none());
+        picker.release([112.0, 121.0], true);
+        assert_eq!(
+            picker.take_request(),
+            Some(PickRequest::Point {
+                position: [0.05, 0.2],
+                mode: SelectionMode::Toggle,
+            })
+        );
+        assert_eq!(picker.take_request(), None);
+
+        // Dragging further turns it into a box, clamped to the image
+        picker.press([200.0, 150.0], [100.0, 100.0], [200.0, 100.0]);
+        assert_eq!(
+            picker.drag_rect([150.0, 250.0]),
+            Some(([150.0, 150.0], [200.0, 250.0]))
+        );
+        picker.release([150.0, 250.0], false);
+        assert!(!picker.is_pressed());
+        assert_eq!(
+            picker.take_request(),
+            Some(PickRequest::Rect {
+                start: [0.5, 0.5],
+                end: [0.25, 1.0],
+                mode: SelectionMode::Replace,
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_selection_modes() {
+        let shared_state = EditorSharedState::new(World::new(), ComponentRegistry::new());
+        let [a, b, c] = shared_state
+            .with_world_write(|world| {
+                [
+                    world.spawn((Name::new("A"),)),
+                    world.spawn((Name::new("B"),)),
+                    world.spawn((Name::new("C"),)),
+                ]
+            })
+            .unwrap();
+
+        apply_selection(&shared_state, vec![a], SelectionMode::Replace);
+        apply_selection(&shared_state, vec![b, c], SelectionMode::Add);
+        assert_eq!(shared_state.selected_entities(), vec![a, b, c]);
+
+        apply_selection(&shared_state, vec![b], SelectionMode::Toggle);
+        assert_eq!(shared_state.selected_entities(), vec![a, c]);
+
+        // Clicking empty space clears, ctrl-clicking it keeps the selection
+        apply_selection(&shared_state, Vec::new(), SelectionMode::Toggle);
+        assert_eq!(shared_state.selected_entities(), vec![a, c]);
+        apply_selection(&shared_state, Vec::new(), SelectionMode::Replace);
+        assert_eq!(shared_state.selected_entity(), None);
+    }
+}
@@ -0,0 +1,317 @@
+//! Play mode for testing gameplay inside the editor
+//!
+//! Entering play mode snapshots the world. Scripts and physics only run while
+//! playing, and stopping restores the snapshot so a test run never leaves
+//! changes in the authored scene.
+
+use crate::history::CommandHistory;
+use crate::shared_state::EditorSharedState;
+use engine::core::entity::update_hierarchy_system;
+use engine::io::Scene;
+use tracing::{error, info};
+
+/// Time step used when advancing a paused game by a single frame
+pub const STEP_DELTA_TIME: f32 = 1.0 / 60.0;
+
+/// Whether the game is running inside the editor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayState {
+    /// Editing the authored scene, gameplay systems do not run
+    #[default]
+    Editing,
+    /// Gameplay systems run every frame
+    Playing,
+    /// Gameplay is frozen until resumed or stepped
+    Paused,
+}
+
+/// Editor state captured when play mode starts
+struct PlaySnapshot {
+    /// Serialized world
+    scene: Scene,
+    /// Entity handle of each serialized entity, in scene order
+    entities: Vec<hecs::Entity>,
+    /// Undo history, so edits made while playing are discarded too
+    history: CommandHistory,
+    /// Whether the scene had unsaved changes
+    scene_modified: bool,
+}
+
+/// Play, pause and stop controls for the editor world
+#[derive(Default)]
+pub struct PlayMode {
+    /// Current play state
+    state: PlayState,
+    /// Snapshot restored on stop
+    snapshot: Option<PlaySnapshot>,
+    /// Whether a paused game should advance by one frame
+    step_requested: bool,
+    /// Whether runtime state from the last session still needs dropping
+    reset_requested: bool,
+}
+
+impl PlayMode {
+    /// Create a play mode in the editing state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the current play state
+    pub fn state(&self) -> PlayState {
+        self.state
+    }
+
+    /// Check whether the authored scene is being edited
+    pub fn is_editing(&self) -> bool {
+        self.state == PlayState::Editing
+    }
+
+    /// Start playing from the current world, or resume a paused game
+    pub fn play(&mut self, shared_state: &EditorSharedState, scene_modified: bool) {
+        match self.state {
+            PlayState::Playing => {}
+            PlayState::Paused => {
+                info!("Resumed play mode");
+                self.state = PlayState::Playing;
+            }
+            PlayState::Editing => {
+                let registry = &shared_state.component_registry;
+                let Some((scene, entities)) = shared_state.with_world_read(|world| {
+                    let entities: Vec<_> = world
+                        .query::<()>()
+                        .iter()
+                        .map(|(entity, ())| entity)
+                        .collect();
+                    (Scene::from_world_with_registry(world, registry), entities)
+                }) else {
+                    error!("Failed to snapshot the world, not entering play mode");
+                    return;
+                };
+
+                shared_state.end_command_coalescing();
+                let history = shared_state
+                    .with_history(|history| history.clone())
+                    .unwrap_or_default();
+
+                info!(entity_count = entities.len(), "Entered play mode");
+                self.snapshot = Some(PlaySnapshot {
+                    scene,
+                    entities,
+                    history,
+                    scene_modified,
+                });
+                self.state = PlayState::Playing;
+            }
+        }
+    }
+
+    /// Freeze a running game
+    pub fn pause(&mut self) {
+        if self.state == PlayState::Playing {
+            info!("Paused play mode");
+            self.state = PlayState::Paused;
+        }
+    }
+
+    /// Pause the game and advance it by a single frame
+    pub fn step(&mut self) {
+        if self.state != PlayState::Editing {
+            self.state = PlayState::Paused;
+            self.step_requested = true;
+        }
+    }
+
+    /// Stop playing and restore the world from the snapshot
+    ///
+    /// Returns whether the scene had unsaved changes when play started, or
+    /// None if the editor was not playing.
+    pub fn stop(&mut self, shared_state: &EditorSharedState) -> Option<bool> {
+        let snapshot = self.snapshot.take()?;
+        self.leave_play_mode();
+
+        let registry = &shared_state.component_registry;
+        let result = shared_state.with_world_write(|world| {
+            world.inner_mut().clear();
+            let result = snapshot
+                .scene
+                .restore_with_registry(world, registry, &snapshot.entities);
+            update_hierarchy_system(world);
+            result
+        });
+        match result {
+            Some(Ok(_)) => info!("Stopped play mode, scene restored"),
+            Some(Err(e)) => error!(error = %e, "Failed to restore scene after play mode"),
+            None => error!("Failed to access world to restore scene"),
+        }
+
+        if let Ok(mut history) = shared_state.history.lock() {
+            *history = snapshot.history;
+        }
+
+        // Entities spawned while playing are gone again
+        shared_state.prune_selection();
+
+        Some(snapshot.scene_modified)
+    }
+
+    /// Leave play mode without restoring the snapshot
+    ///
+    /// Used when a different scene replaces the world while playing.
+    pub fn discard(&mut self) {
+        if self.snapshot.take().is_some() {
+            info!("Discarded play mode snapshot");
+            self.leave_play_mode();
+        }
+    }
+
+    /// Get the time step to run gameplay systems with this frame
+    ///
+    /// Returns None while editing or paused. A pending single-frame step is
+    /// consumed and runs with [`STEP_DELTA_TIME`].
+    pub fn update_delta(&mut self, delta_time: f32) -> Option<f32> {
+        match self.state {
+            PlayState::Playing => Some(delta_time),
+            PlayState::Paused if self.step_requested => {
+                self.step_requested = false;
+                Some(STEP_DELTA_TIME)
+            }
+            PlayState::Paused | PlayState::Editing => None,
+        }
+    }
+
+    /// Check whether runtime state such as physics bodies and started
+    /// scripts should be dropped because a play session ended
+    ///
+    /// The request is cleared by this call.
+    pub fn take_reset_request(&mut self) -> bool {
+        std::mem::take(&mut self.reset_requested)
+    }
+
+    fn leave_play_mode(&mut self) {
+        self.state = PlayState::Editing;
+        self.step_requested = false;
+        self.reset_requested = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::core::entity::{Name, Transform, World};
+    use engine::io::component_registry::ComponentRegistry;
+    use glam::Vec3;
+
+    fn shared_state() -> EditorSharedState {
+        EditorSharedState::new(World::new(), ComponentRegistry::with_default_components())
+    }
+
+    #[test]
+    fn test_stop_restores_world() {
+        let shared_state = shared_state();
+        let entity = shared_state
+            .with_world_write(|world| {
+                world.spawn((Name::new("Player"), Transform::from_position(Vec3::X)))
+            })
+            .unwrap();
+        shared_state.set_selected_entity(Some(entity));
+
+        let mut play_mode = PlayMode::new();
+        play_mode.play(&shared_state, false);
+        assert_eq!(play_mode.state(), PlayState::Playing);
+
+        // Gameplay moves the player and spawns a projectile
+        let projectile = shared_state
+            .with_world_write(|world| {
+                world
+                    .query_one_mut::<&mut Transform>(entity)
+                    .unwrap()
+                    .position = Vec3::Y;
+                world.spawn((Name::new("Projectile"),))
+            })
+            .unwrap();
+        shared_state.set_selected_entity(Some(projectile));
+
+        assert_eq!(play_mode.stop(&shared_state), Some(false));
+        assert!(play_mode.is_editing());
+        assert!(play_mode.take_reset_request());
+
+        shared_state.with_world_read(|world| {
+            assert!(!world.contains(projectile));
+            // The original handle stays valid
+            assert_eq!(world.get::<Transform>(entity).unwrap().position, Vec3::X);
+            assert_eq!(world.get::<Name>(entity).unwrap().0, "Player");
+        });
+        assert_eq!(shared_state.selected_entity(), None);
+    }
+
+    #[test]
+    fn test_stop_prunes_multi_selection() {
+        let shared_state = shared_state();
+        let player = shared_state
+            .with_world_write(|world| world.spawn((Name::new("Player"),)))
+            .unwrap();
+
+        let mut play_mode = PlayMode::new();
+        play_mode.play(&shared_state, false);
+
+        let projectile = shared_state
+            .with_world_write(|world| world.spawn((Name::new("Projectile"),)))
+            .unwrap();
+        shared_state.set_selection(vec![projectile, player]);
+        assert_eq!(shared_state.selected_entity(), Some(player));
+
+        play_mode.stop(&shared_state);
+        assert_eq!(shared_state.selected_entities(), vec![player]);
+        assert_eq!(shared_state.selected_entity(), Some(player));
+    }
+
+    #[test]
+    fn test_step_runs_one_frame() {
+        let shared_state = shared_state();
+        let mut play_mode = PlayMode::new();
+
+        // Nothing runs while editing
+        play_mode.step();
+        assert_eq!(play_mode.update_delta(0.1), None);
+
+        play_mode.play(&shared_state, false);
+        assert_eq!(play_mode.update_delta(0.1), Some(0.1));
+
+        play_mode.pause();
+        assert_eq!(play_mode.update_delta(0.1), None);
+
+        play_mode.step();
+        assert_eq!(play_mode.update_delta(0.1), Some(STEP_DELTA_TIME));
+        assert_eq!(play_mode.update_delta(0.1), None);
+        assert_eq!(play_mode.state(), PlayState::Paused);
+
+        play_mode.play(&shared_state, false);
+        assert_eq!(play_mode.state(), PlayState::Playing);
+    }
+
+    #[test]
+    fn test_stop_discards_edits_made_while_playing() {
+        let shared_state = shared_state();
+        let mut play_mode = PlayMode::new();
+        play_mode.play(&shared_state, true);
+
+        let entity = shared_state
+            .with_world_write(|world| world.spawn((Name::new("Temporary"),)))
+            .unwrap();
+        shared_state.record_spawn("Create Entity", &[entity]);
+        assert!(shared_state
+            .with_history(|history| history.can_undo())
+            .unwrap());
+
+        assert_eq!(play_mode.stop(&shared_state), Some(true));
+        assert!(!shared_state
+            .with_world_read(|world| world.contains(entity))
+            .unwrap());
+        assert!(!shared_state
+            .with_history(|history| history.can_undo())
+            .unwrap());
+
+        // Stopping again is a no-op
+        assert_eq!(play_mode.stop(&shared_state), None);
+    }
+}
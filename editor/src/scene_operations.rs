@@ -3,12 +3,15 @@
 //! This module provides the actual implementation of scene operations
 //! that are triggered from the editor UI.
 
+use crate::shared_state::EditorSharedState;
 use engine::core::entity::World;
 use engine::graphics::renderer::Renderer;
+use engine::io::prefab;
 use engine::prelude::*;
 use engine::scripting::lifecycle_tracker::get_tracker;
+use std::collections::HashSet;
 use std::path::Path;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Create a default scene with camera and basic lighting
 pub fn create_default_scene(world: &mut World, renderer: &mut Renderer) {
@@ -66,6 +69,18 @@ pub fn save_scene_to_file(world: &World, path: &Path) -> Result<(), Box<dyn std:
     world.save_scene(path)
 }
 
+/// Save the editor world, recording the overrides of prefab instances first
+pub fn save_scene(
+    shared_state: &EditorSharedState,
+    path: &Path,
+) -> Option<Result<(), Box<dyn std::error::Error>>> {
+    shared_state.with_prefabs(|world, registry, prefabs| {
+        // Instances are saved expanded, with their overrides on the root
+        prefab::update_overrides(world, registry, prefabs);
+        save_scene_to_file(world, path)
+    })
+}
+
 /// Load a scene file into the world
 pub fn load_scene_from_file(
     world: &mut World,
@@ -79,3 +94,146 @@ pub fn load_scene_from_file(
 
     Ok(())
 }
+
+/// Load a scene file into the editor world and rebuild its prefab instances
+///
+/// Prefabs are read from disk again, they may have changed since the last load.
+pub fn load_scene(
+    shared_state: &EditorSharedState,
+    renderer: &mut Renderer,
+    path: &Path,
+) -> Option<Result<(), Box<dyn std::error::Error>>> {
+    shared_state.clear_prefabs();
+    shared_state.with_prefabs(|world, registry, prefabs| {
+        let result = load_scene_from_file(world, renderer, path);
+        if result.is_ok() {
+            world.refresh_prefab_instances(registry, prefabs);
+        }
+        result
+    })
+}
+
+/// Spawn an instance of a prefab dropped from the asset browser
+pub fn instantiate_prefab(shared_state: &EditorSharedState, file_path: &str) {
+    if !crate::panels::assets::validate_asset_path(file_path) {
+        warn!("Invalid or unsafe prefab path dropped: {}", file_path);
+        return;
+    }
+
+    let source = format!("game/assets/{file_path}");
+    let spawned = shared_state.with_prefabs(|world, registry, prefabs| {
+        let existing: HashSet<hecs::Entity> = world.query::<()>().iter().map(|(e, _)| e).collect();
+        let root = prefab::instantiate(world, registry, prefabs, &source)?;
+        let spawned: Vec<hecs::Entity> = world
+            .query::<()>()
+            .iter()
+            .map(|(e, _)| e)
+            .filter(|e| !existing.contains(e))
+            .collect();
+        Ok::<_, SceneError>((root, spawned))
+    });
+
+    match spawned {
+        Some(Ok((root, spawned))) => {
+            shared_state.record_spawn(format!("Instantiate {file_path}"), &spawned);
+            shared_state.set_selected_entity(Some(root));
+            shared_state.mark_scene_modified();
+        }
+        Some(Err(e)) => warn!("Failed to instantiate prefab {}: {}", source, e),
+        None => {}
+    }
+}
+
+/// Save an entity and its children as a prefab in `game/assets/prefabs`
+///
+/// The entity becomes the first instance of the new prefab.
+pub fn create_prefab(shared_state: &EditorSharedState, entity: hecs::Entity) {
+    let name = shared_state
+        .with_world_read(|world| world.get::<Name>(entity).map(|name| name.0.clone()).ok())
+        .flatten()
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| "Prefab".to_string());
+    let file_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let directory = Path::new("game/assets/prefabs");
+    if let Err(e) = std::fs::create_dir_all(directory) {
+        warn!("Failed to create prefab directory: {}", e);
+        return;
+    }
+    let source = format!(
+        "game/assets/prefabs/{file_name}.{}",
+        prefab::PREFAB_EXTENSION
+    );
+
+    let result = shared_state.with_prefabs(|world, registry, prefabs| {
+        if prefab::owning_instance(world, entity).is_some() {
+            return Err(SceneError::ComponentError(
+                "entity is already part of a prefab instance".to_string(),
+            ));
+        }
+        prefab::create_prefab(world, registry, prefabs, &[entity], &source)
+    });
+
+    match result {
+        Some(Ok(root)) => {
+            // The entities were restructured in place
+            shared_state.clear_history();
+            shared_state.set_selected_entity(Some(root));
+            shared_state.mark_scene_modified();
+        }
+        Some(Err(e)) => warn!("Failed to create prefab {}: {}", source, e),
+        None => {}
+    }
+}
+
+/// Write a prefab instance back to its prefab file
+pub fn apply_prefab_instance(shared_state: &EditorSharedState, root: hecs::Entity) {
+    let result = shared_state.with_prefabs(|world, registry, prefabs| {
+        prefab::apply_instance(world, registry, prefabs, root)
+    });
+    finish_prefab_edit(shared_state, result, "apply");
+}
+
+/// Drop the overrides of a prefab instance
+pub fn revert_prefab_instance(shared_state: &EditorSharedState, root: hecs::Entity) {
+    let result = shared_state.with_prefabs(|world, registry, prefabs| {
+        prefab::revert_instance(world, registry, prefabs, root)
+    });
+    finish_prefab_edit(shared_state, result, "revert");
+}
+
+fn finish_prefab_edit(
+    shared_state: &EditorSharedState,
+    result: Option<Result<(), SceneError>>,
+    action: &str,
+) {
+    match result {
+        Some(Ok(())) => {
+            // Refreshing instances can spawn and despawn members of any instance
+            shared_state.clear_history();
+            let selected = shared_state.selected_entities();
+            let remaining: Vec<_> = shared_state
+                .with_world_read(|world| {
+                    selected
+                        .iter()
+                        .copied()
+                        .filter(|entity| world.contains(*entity))
+                        .collect()
+                })
+                .unwrap_or_default();
+            shared_state.set_selection(remaining);
+            shared_state.mark_scene_modified();
+        }
+        Some(Err(e)) => warn!("Failed to {} prefab instance: {}", action, e),
+        None => {}
+    }
+}
@@ -14,6 +14,10 @@ pub struct EditorSettings {
     #[serde(default)]
     pub audio: AudioSettings,
 
+    /// Viewport interaction settings
+    #[serde(default)]
+    pub viewport: ViewportSettings,
+
     /// Settings version for future migration support
     #[serde(default)]
     pub version: u32,
@@ -28,10 +32,19 @@ pub struct AudioSettings {
     pub master_volume: f32,
 }
 
+/// Viewport interaction settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ViewportSettings {
+    /// Pick clicked entities with an entity ID render pass instead of ray casts
+    #[serde(default)]
+    pub gpu_picking: bool,
+}
+
 impl Default for EditorSettings {
     fn default() -> Self {
         Self {
             audio: AudioSettings::default(),
+            viewport: ViewportSettings::default(),
             version: 1,
         }
     }
@@ -111,6 +124,7 @@ mod tests {
         assert_eq!(settings.version, 1);
         assert_eq!(settings.audio.master_volume, 1.0);
         assert!(settings.audio.output_device.is_none());
+        assert!(!settings.viewport.gpu_picking);
     }
 
     #[test]
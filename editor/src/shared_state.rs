@@ -3,16 +3,22 @@
 //! This module provides thread-safe shared state that can be accessed
 //! from both the main editor window and detached panel windows.
 
+use crate::history::{component_value, CommandHistory, EditorCommand, EntitySnapshot};
 use engine::core::entity::World;
 use engine::io::component_registry::ComponentRegistry;
+use engine::io::PrefabLibrary;
 use std::sync::{Arc, Mutex};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 /// Shared editor state that needs to be synchronized between windows
 #[derive(Debug, Default)]
 pub struct SharedEditorState {
-    /// Currently selected entity in the hierarchy
+    /// Primary selected entity, the one the gizmo and inspector work on
     pub selected_entity: Option<hecs::Entity>,
+    /// All selected entities, the primary one last
+    pub selected_entities: Vec<hecs::Entity>,
+    /// Entity shift ranges start from, the last one selected without shift
+    pub selection_anchor: Option<hecs::Entity>,
     /// Whether the scene has been modified since last save
     pub scene_modified: bool,
     /// Current scene file path
@@ -25,15 +31,70 @@ impl SharedEditorState {
         Self::default()
     }
 
-    /// Set the selected entity
+    /// Set the selected entity, replacing any multi-selection
     pub fn set_selected_entity(&mut self, entity: Option<hecs::Entity>) {
-        if self.selected_entity != entity {
+        self.set_selection(entity.into_iter().collect());
+    }
+
+    /// Replace the selection, the last entity becomes the primary one
+    pub fn set_selection(&mut self, mut entities: Vec<hecs::Entity>) {
+        // Keep the last occurrence of duplicates so the primary stays last
+        let mut seen = std::collections::HashSet::new();
+        entities.reverse();
+        entities.retain(|entity| seen.insert(*entity));
+        entities.reverse();
+
+        let primary = entities.last().copied();
+        if self.selected_entity != primary {
             debug!(
                 "Selected entity changed: {:?} -> {:?}",
-                self.selected_entity, entity
+                self.selected_entity, primary
             );
-            self.selected_entity = entity;
         }
+        self.selected_entity = primary;
+        self.selection_anchor = primary;
+        self.selected_entities = entities;
+    }
+
+    /// Select the entities between the anchor and `target` in display order
+    ///
+    /// With `additive` the range is added to the current selection. The
+    /// anchor stays in place so further shift clicks resize the range, and
+    /// `target` becomes the primary entity.
+    pub fn select_range(&mut self, order: &[hecs::Entity], target: hecs::Entity, additive: bool) {
+        let anchor = self.selection_anchor;
+        let target_index = order.iter().position(|entity| *entity == target);
+        let anchor_index =
+            anchor.and_then(|anchor| order.iter().position(|entity| *entity == anchor));
+
+        let range = match (anchor_index, target_index) {
+            (Some(a), Some(t)) if a <= t => order[a..=t].to_vec(),
+            (Some(a), Some(t)) => order[t..=a].iter().rev().copied().collect(),
+            _ => vec![target],
+        };
+
+        let mut entities = if additive {
+            self.selected_entities.clone()
+        } else {
+            Vec::new()
+        };
+        entities.retain(|entity| !range.contains(entity));
+        entities.extend(range);
+        self.set_selection(entities);
+        if anchor_index.is_some() {
+            self.selection_anchor = anchor;
+        }
+    }
+
+    /// Add an entity to the selection or remove it if already selected
+    pub fn toggle_selection(&mut self, entity: hecs::Entity) {
+        let mut entities = self.selected_entities.clone();
+        if let Some(index) = entities.iter().position(|selected| *selected == entity) {
+            entities.remove(index);
+        } else {
+            entities.push(entity);
+        }
+        self.set_selection(entities);
     }
 
     /// Mark the scene as modified
@@ -173,6 +234,10 @@ pub struct EditorSharedState {
     pub world: SharedWorldHandle,
     /// Component registry for reflection and serialization
     pub component_registry: Arc<ComponentRegistry>,
+    /// Undo/redo history of scene edits
+    pub history: Arc<Mutex<CommandHistory>>,
+    /// Prefabs the instances in the scene were built from
+    pub prefabs: Arc<Mutex<PrefabLibrary>>,
 }
 
 impl EditorSharedState {
@@ -182,6 +247,8 @@ impl EditorSharedState {
             editor_state: create_shared_state(),
             world: create_shared_world(world),
             component_registry: Arc::new(component_registry),
+            history: Arc::new(Mutex::new(CommandHistory::new())),
+            prefabs: Arc::new(Mutex::new(PrefabLibrary::new())),
         }
     }
 
@@ -195,6 +262,41 @@ impl EditorSharedState {
         update_selected_entity(&self.editor_state, entity);
     }
 
+    /// Get all selected entities, the primary one last
+    pub fn selected_entities(&self) -> Vec<hecs::Entity> {
+        match self.editor_state.lock() {
+            Ok(state) => state.selected_entities.clone(),
+            Err(e) => {
+                warn!("Failed to lock shared state for reading selection: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Replace the selection, the last entity becomes the primary one
+    pub fn set_selection(&self, entities: Vec<hecs::Entity>) {
+        match self.editor_state.lock() {
+            Ok(mut state) => state.set_selection(entities),
+            Err(e) => warn!("Failed to lock shared state for selection: {}", e),
+        }
+    }
+
+    /// Add an entity to the selection or remove it if already selected
+    pub fn toggle_selection(&self, entity: hecs::Entity) {
+        match self.editor_state.lock() {
+            Ok(mut state) => state.toggle_selection(entity),
+            Err(e) => warn!("Failed to lock shared state for selection: {}", e),
+        }
+    }
+
+    /// Select the entities between the anchor and `target` in display order
+    pub fn select_range(&self, order: &[hecs::Entity], target: hecs::Entity, additive: bool) {
+        match self.editor_state.lock() {
+            Ok(mut state) => state.select_range(order, target, additive),
+            Err(e) => warn!("Failed to lock shared state for selection: {}", e),
+        }
+    }
+
     /// Mark the scene as modified safely
     pub fn mark_scene_modified(&self) {
         mark_scene_modified(&self.editor_state);
@@ -220,6 +322,215 @@ impl EditorSharedState {
     {
         with_world_write(&self.world, f)
     }
+
+    /// Perform a mutable operation on the world that needs the prefab library
+    pub fn with_prefabs<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut World, &ComponentRegistry, &mut PrefabLibrary) -> R,
+    {
+        let mut prefabs = match self.prefabs.lock() {
+            Ok(prefabs) => prefabs,
+            Err(e) => {
+                warn!("Failed to lock prefab library: {}", e);
+                return None;
+            }
+        };
+        self.with_world_write(|world| f(world, &self.component_registry, &mut prefabs))
+    }
+
+    /// Record an edit that has already been applied to the world
+    pub fn record_command(&self, command: EditorCommand) {
+        match self.history.lock() {
+            Ok(mut history) => history.push(command),
+            Err(e) => warn!("Failed to lock history for recording: {}", e),
+        }
+    }
+
+    /// Apply an edit to one registered component and record it for undo
+    ///
+    /// The component is captured before and after `f` runs, so the edit may
+    /// add, change or remove it.
+    pub fn edit_component<F, R>(&self, entity: hecs::Entity, component: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut World) -> R,
+    {
+        let registry = &self.component_registry;
+        let (result, command) = self.with_world_write(|world| {
+            let before = component_value(world, registry, entity, component);
+            let result = f(world);
+            let after = component_value(world, registry, entity, component);
+            let command = EditorCommand::set_component(entity, component, before, after);
+            (result, command)
+        })?;
+        self.record_command(command);
+        Some(result)
+    }
+
+    /// Apply the same edit to one component of several entities
+    ///
+    /// `f` runs once per entity and all changes are recorded as a single
+    /// undo step, which keeps coalescing while a multi-selection is dragged.
+    pub fn edit_components<F>(&self, entities: &[hecs::Entity], component: &str, mut f: F)
+    where
+        F: FnMut(&mut World, hecs::Entity),
+    {
+        let registry = &self.component_registry;
+        let commands = self.with_world_write(|world| {
+            entities
+                .iter()
+                .map(|&entity| {
+                    let before = component_value(world, registry, entity, component);
+                    f(world, entity);
+                    let after = component_value(world, registry, entity, component);
+                    EditorCommand::set_component(entity, component, before, after)
+                })
+                .collect::<Vec<_>>()
+        });
+        let Some(commands) = commands.filter(|commands| !commands.is_empty()) else {
+            return;
+        };
+        let label = format!("Edit {component} ({} Entities)", commands.len());
+        self.record_command(EditorCommand::batch(label, commands));
+    }
+
+    /// Record newly spawned entities so they can be removed again by undo
+    pub fn record_spawn(&self, label: impl Into<String>, entities: &[hecs::Entity]) {
+        let snapshots = self
+            .with_world_read(|world| {
+                entities
+                    .iter()
+                    .filter_map(|&entity| {
+                        EntitySnapshot::capture(world, &self.component_registry, entity)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.record_command(EditorCommand::Spawn {
+            label: label.into(),
+            snapshots,
+        });
+    }
+
+    /// Despawn an entity, recording it so undo can bring it back
+    ///
+    /// Returns true if the entity existed.
+    pub fn despawn_entity(&self, entity: hecs::Entity) -> bool {
+        self.despawn_entities(&[entity]) > 0
+    }
+
+    /// Despawn several entities as one undo step
+    ///
+    /// Returns the number of entities that existed.
+    pub fn despawn_entities(&self, entities: &[hecs::Entity]) -> usize {
+        let snapshots: Vec<_> = self
+            .with_world_write(|world| {
+                // Capture everything first so parent links between the
+                // entities are restored by undo
+                let snapshots: Vec<_> = entities
+                    .iter()
+                    .filter_map(|&entity| {
+                        EntitySnapshot::capture(world, &self.component_registry, entity)
+                    })
+                    .collect();
+                for snapshot in &snapshots {
+                    let _ = world.despawn(snapshot.entity);
+                }
+                snapshots
+            })
+            .unwrap_or_default();
+
+        let count = snapshots.len();
+        let label = match snapshots.as_slice() {
+            [] => return 0,
+            [snapshot] => match snapshot.name() {
+                Some(name) => format!("Delete {name}"),
+                None => "Delete Entity".to_string(),
+            },
+            _ => format!("Delete {count} Entities"),
+        };
+        self.record_command(EditorCommand::Despawn { label, snapshots });
+        count
+    }
+
+    /// Stop merging component edits into the newest history entry
+    pub fn end_command_coalescing(&self) {
+        if let Ok(mut history) = self.history.lock() {
+            history.end_coalescing();
+        }
+    }
+
+    /// Perform a read-only operation on the command history
+    pub fn with_history<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&CommandHistory) -> R,
+    {
+        match self.history.lock() {
+            Ok(history) => Some(f(&history)),
+            Err(e) => {
+                warn!("Failed to lock history for reading: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Undo the newest edit, returning its label
+    pub fn undo(&self) -> Option<String> {
+        let mut history = self.history.lock().ok()?;
+        let label = self
+            .with_world_write(|world| history.undo(world, &self.component_registry))
+            .flatten()?;
+        self.after_history_change();
+        info!(command = %label, "Undo");
+        Some(label)
+    }
+
+    /// Redo the most recently undone edit, returning its label
+    pub fn redo(&self) -> Option<String> {
+        let mut history = self.history.lock().ok()?;
+        let label = self
+            .with_world_write(|world| history.redo(world, &self.component_registry))
+            .flatten()?;
+        self.after_history_change();
+        info!(command = %label, "Redo");
+        Some(label)
+    }
+
+    /// Forget all edits, e.g. after a different scene was loaded
+    pub fn clear_history(&self) {
+        if let Ok(mut history) = self.history.lock() {
+            history.clear();
+        }
+    }
+
+    /// Forget the loaded prefabs so they are read from disk again
+    pub fn clear_prefabs(&self) {
+        if let Ok(mut prefabs) = self.prefabs.lock() {
+            prefabs.clear();
+        }
+    }
+
+    /// Remove entities that no longer exist from the selection
+    pub fn prune_selection(&self) {
+        let selected = self.selected_entities();
+        let remaining: Vec<_> = self
+            .with_world_read(|world| {
+                selected
+                    .iter()
+                    .copied()
+                    .filter(|entity| world.contains(*entity))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if remaining.len() != selected.len() {
+            self.set_selection(remaining);
+        }
+    }
+
+    /// Keep the selection valid and flag the scene after undo or redo
+    fn after_history_change(&self) {
+        self.prune_selection();
+        self.mark_scene_modified();
+    }
 }
 
 #[cfg(test)]
@@ -288,6 +599,98 @@ mod tests {
         assert_eq!(shared_state.selected_entity(), None);
     }
 
+    #[test]
+    fn test_multi_selection() {
+        let world = World::new();
+        let registry = engine::io::component_registry::ComponentRegistry::new();
+        let shared_state = EditorSharedState::new(world, registry);
+        let (a, b) = shared_state
+            .with_world_write(|world| {
+                (
+                    world.spawn((Name::new("A"),)),
+                    world.spawn((Name::new("B"),)),
+                )
+            })
+            .unwrap();
+
+        shared_state.set_selection(vec![a, b, a]);
+        assert_eq!(shared_state.selected_entities(), vec![b, a]);
+        assert_eq!(shared_state.selected_entity(), Some(a));
+
+        // Toggling removes a selected entity and promotes the previous one
+        shared_state.toggle_selection(a);
+        assert_eq!(shared_state.selected_entities(), vec![b]);
+        assert_eq!(shared_state.selected_entity(), Some(b));
+
+        shared_state.toggle_selection(a);
+        assert_eq!(shared_state.selected_entity(), Some(a));
+
+        // Single selection replaces the set
+        shared_state.set_selected_entity(Some(b));
+        assert_eq!(shared_state.selected_entities(), vec![b]);
+        shared_state.set_selected_entity(None);
+        assert!(shared_state.selected_entities().is_empty());
+    }
+
+    #[test]
+    fn test_select_range() {
+        let mut state = SharedEditorState::new();
+        let mut world = World::new();
+        let order: Vec<_> = (0..5)
+            .map(|i| world.spawn((Name::new(format!("E{i}")),)))
+            .collect();
+
+        state.set_selected_entity(Some(order[1]));
+        state.select_range(&order, order[3], false);
+        assert_eq!(state.selected_entities, order[1..=3].to_vec());
+        assert_eq!(state.selected_entity, Some(order[3]));
+
+        // The anchor stays, so shift clicking again resizes the range
+        state.select_range(&order, order[0], false);
+        assert_eq!(state.selected_entities, vec![order[1], order[0]]);
+
+        // Ctrl+Shift adds the range to the selection
+        state.set_selected_entity(Some(order[4]));
+        state.toggle_selection(order[0]);
+        state.select_range(&order, order[1], true);
+        assert_eq!(state.selected_entities, vec![order[4], order[0], order[1]]);
+        assert_eq!(state.selected_entity, Some(order[1]));
+    }
+
+    #[test]
+    fn test_batch_edit_and_delete() {
+        let world = World::new();
+        let registry = engine::io::component_registry::ComponentRegistry::with_default_components();
+        let shared_state = EditorSharedState::new(world, registry);
+        let (a, b) = shared_state
+            .with_world_write(|world| {
+                (
+                    world.spawn((Name::new("A"), Transform::default())),
+                    world.spawn((Name::new("B"), Transform::default())),
+                )
+            })
+            .unwrap();
+
+        shared_state.edit_components(&[a, b], "Transform", |world, entity| {
+            if let Ok(transform) = world.query_one_mut::<&mut Transform>(entity) {
+                transform.scale = glam::Vec3::splat(2.0);
+            }
+        });
+        assert_eq!(shared_state.despawn_entities(&[a, b]), 2);
+
+        assert_eq!(shared_state.undo().as_deref(), Some("Delete 2 Entities"));
+        assert_eq!(
+            shared_state.undo().as_deref(),
+            Some("Edit Transform (2 Entities)")
+        );
+        let scales = shared_state
+            .with_world_read(|world| {
+                [a, b].map(|entity| world.get::<Transform>(entity).unwrap().scale)
+            })
+            .unwrap();
+        assert_eq!(scales, [glam::Vec3::ONE; 2]);
+    }
+
     #[test]
     fn test_scene_modification_tracking() {
         let world = World::new();
@@ -301,4 +704,34 @@ mod tests {
         shared_state.mark_scene_modified();
         assert!(shared_state.is_scene_modified());
     }
+
+    #[test]
+    fn test_undo_spawn_clears_selection() {
+        let world = World::new();
+        let registry = engine::io::component_registry::ComponentRegistry::with_default_components();
+        let shared_state = EditorSharedState::new(world, registry);
+
+        let entity = shared_state
+            .with_world_write(|world| world.spawn((Name::new("New Entity"), Transform::default())))
+            .unwrap();
+        let snapshot = shared_state
+            .with_world_read(|world| {
+                EntitySnapshot::capture(world, &shared_state.component_registry, entity)
+            })
+            .flatten()
+            .unwrap();
+        shared_state.record_command(EditorCommand::Spawn {
+            label: "Create Entity".to_string(),
+            snapshots: vec![snapshot],
+        });
+        shared_state.set_selected_entity(Some(entity));
+
+        assert_eq!(shared_state.undo().as_deref(), Some("Create Entity"));
+        assert_eq!(shared_state.selected_entity(), None);
+        assert!(shared_state.is_scene_modified());
+
+        assert_eq!(shared_state.redo().as_deref(), Some("Create Entity"));
+        let count = shared_state.with_world_read(|world| world.query::<&Name>().iter().count());
+        assert_eq!(count.unwrap(), 1);
+    }
 }
@@ -21,10 +21,28 @@ pub fn render_component_ui<T>(
 where
     T: FieldAccess + ?Sized,
 {
-    let mut modified = false;
+    !render_component_ui_mixed(ui, component, metadata, &[]).is_empty()
+}
+
+/// Render a component's UI for a multi-selection
+///
+/// Fields named in `mixed` differ between the selected entities and are
+/// marked as such, while the widgets show this component's values.
+/// Returns the names of the modified fields.
+pub fn render_component_ui_mixed<T>(
+    ui: &Ui,
+    component: &mut T,
+    metadata: &ComponentUIMetadata,
+    mixed: &[String],
+) -> Vec<String>
+where
+    T: FieldAccess + ?Sized,
+{
+    let mut modified = Vec::new();
 
     debug!(
         field_count = metadata.fields.len(),
+        mixed_count = mixed.len(),
         "Rendering component UI from metadata"
     );
 
@@ -39,12 +57,64 @@ where
             "Rendering field"
         );
 
-        modified |= render_field_ui(ui, component, field);
+        if mixed.contains(&field.name) {
+            ui.text_colored([1.0, 0.8, 0.3, 1.0], "(mixed)");
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Values differ between the selected entities");
+            }
+            ui.same_line();
+        }
+
+        if render_field_ui(ui, component, field) {
+            modified.push(field.name.clone());
+        }
     }
 
     modified
 }
 
+/// Visible fields whose values differ between `component` and any of `others`
+pub fn mixed_fields<T>(component: &T, others: &[&T], metadata: &ComponentUIMetadata) -> Vec<String>
+where
+    T: FieldAccess + ?Sized,
+{
+    metadata
+        .fields
+        .iter()
+        .filter(|field| !field.hidden)
+        .filter(|field| {
+            let value = component.get_field(&field.name);
+            others
+                .iter()
+                .any(|other| other.get_field(&field.name) != value)
+        })
+        .map(|field| field.name.clone())
+        .collect()
+}
+
+/// Carry an edit of one entity's field over to another entity's value
+///
+/// Vectors only take over the axes that were edited, so dragging X of a
+/// multi-selection keeps each entity's Y and Z. Other values are replaced.
+pub fn apply_field_edit(
+    before: &FieldValue,
+    after: &FieldValue,
+    target: &FieldValue,
+) -> FieldValue {
+    match (before, after, target) {
+        (FieldValue::Vec3(before), FieldValue::Vec3(after), FieldValue::Vec3(target)) => {
+            let mut value = *target;
+            for axis in 0..3 {
+                if before[axis] != after[axis] {
+                    value[axis] = after[axis];
+                }
+            }
+            FieldValue::Vec3(value)
+        }
+        _ => after.clone(),
+    }
+}
+
 /// Render a single field's UI based on its metadata
 fn render_field_ui<T>(ui: &Ui, component: &mut T, field: &UIFieldMetadata) -> bool
 where
@@ -383,3 +453,29 @@ where
 
     modified
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_field_edit_keeps_unedited_axes() {
+        let before = FieldValue::Vec3(Vec3::new(1.0, 2.0, 3.0));
+        let after = FieldValue::Vec3(Vec3::new(5.0, 2.0, 3.0));
+        let target = FieldValue::Vec3(Vec3::new(0.0, -1.0, 7.0));
+        assert_eq!(
+            apply_field_edit(&before, &after, &target),
+            FieldValue::Vec3(Vec3::new(5.0, -1.0, 7.0))
+        );
+
+        // Scalars are simply replaced
+        assert_eq!(
+            apply_field_edit(
+                &FieldValue::Float(1.0),
+                &FieldValue::Float(2.0),
+                &FieldValue::Float(9.0)
+            ),
+            FieldValue::Float(2.0)
+        );
+    }
+}
@@ -9,7 +9,7 @@ use crate::core::coordinates::LargeWorldConfig;
 use crate::core::entity::{update_hierarchy_system, World};
 use crate::graphics::{RenderContext, Renderer};
 use crate::input::InputState;
-use crate::physics::world::PhysicsWorld;
+use crate::physics::{world::PhysicsWorld, CollisionLayers, FixedTimestep};
 use crate::profiling::profile_zone;
 use crate::scripting::ScriptEngine;
 use crate::windowing::WindowManager;
@@ -38,6 +38,8 @@ pub struct EngineConfig {
     pub log_filter: Option<String>,
     /// Large world coordinate system configuration
     pub large_world: LargeWorldConfig,
+    /// Rate and maximum substeps of the physics simulation
+    pub physics_timestep: FixedTimestep,
 }
 
 impl Default for EngineConfig {
@@ -49,6 +51,7 @@ impl Default for EngineConfig {
             enable_scripting: true,
             log_filter: None,
             large_world: LargeWorldConfig::default(),
+            physics_timestep: FixedTimestep::default(),
         }
     }
 }
@@ -80,7 +83,6 @@ pub struct EngineApp {
     config: EngineConfig,
     instance: Option<Arc<wgpu::Instance>>,
     last_time: std::time::Instant,
-    // physics_accumulator: PhysicsAccumulator,
     focus_tracker: HashMap<WindowId, bool>,
     last_focused_window: Option<WindowId>,
     initialized: bool,
@@ -226,7 +228,11 @@ impl EngineApp {
         };
 
         // Initialize physics world
-        let physics_world = PhysicsWorld::new();
+        let mut physics_world = PhysicsWorld::new();
+        physics_world.set_timestep(self.config.physics_timestep);
+        physics_world.set_collision_layers(CollisionLayers::load_or_default(
+            self.config.asset_config.collision_layers_path(),
+        ));
 
         // Initialize audio engine
         #[cfg(feature = "audio")]
@@ -265,36 +271,51 @@ impl EngineApp {
             self.input_state.clear_frame_data();
         }
 
+        let mut world = std::mem::take(&mut self.world);
+        self.update_world(&mut world, delta_time);
+        self.world = world;
+    }
+
+    /// Run the per-frame gameplay systems against the given world
+    ///
+    /// This is what [`EngineApp::update`] runs on the engine's own world. The
+    /// editor keeps its world behind a lock and calls this directly while in
+    /// play mode. Per-frame input data is not cleared here.
+    pub fn update_world(&mut self, world: &mut World, delta_time: f32) {
+        profile_zone!("EngineApp::update_world");
+
         // Execute scripts
         if let Some(script_engine) = &mut self.script_engine {
             profile_zone!("Script system update");
             let script_input_state = self.input_state.to_script_input_state();
 
             // Initialize script properties for new scripts
-            crate::scripting::script_initialization_system(&mut self.world, script_engine);
-
-            // Execute scripts
-            crate::scripting::script_execution_system(
-                &mut self.world,
-                script_engine,
-                &script_input_state,
-                delta_time,
-            );
+            crate::scripting::script_initialization_system(world, script_engine);
+
+            // Execute scripts, letting their spatial queries see the physics world
+            crate::physics::queries::lend_to_scripts(&mut self.physics_world, || {
+                crate::scripting::script_execution_system(
+                    world,
+                    script_engine,
+                    &script_input_state,
+                    delta_time,
+                )
+            });
 
             // Process mesh uploads from scripts
             if let Some(renderer) = &mut self.renderer {
-                crate::scripting::process_script_mesh_uploads(script_engine, renderer);
+                crate::scripting::process_script_mesh_uploads(
+                    script_engine,
+                    renderer,
+                    self.physics_world.as_mut(),
+                );
             }
         }
 
         // Update physics simulation
         if let Some(physics_world) = &mut self.physics_world {
             profile_zone!("Physics update");
-            crate::physics::system::physics_update_system(
-                &mut self.world,
-                physics_world,
-                delta_time,
-            );
+            crate::physics::system::physics_update_system(world, physics_world, delta_time);
         }
 
         // Update audio system
@@ -302,7 +323,7 @@ impl EngineApp {
         if let Some(audio_engine) = &mut self.audio_engine {
             profile_zone!("Audio update");
             audio_update_system(
-                &mut self.world,
+                world,
                 audio_engine,
                 &mut self.audio_system_state,
                 delta_time,
@@ -310,7 +331,12 @@ impl EngineApp {
         }
 
         // Update transform hierarchy to maintain GlobalTransform
-        update_hierarchy_system(&mut self.world);
+        update_hierarchy_system(world);
+
+        // Render dynamic bodies between their last two physics states
+        if let Some(physics_world) = &self.physics_world {
+            crate::physics::system::interpolate_physics_transforms(world, physics_world);
+        }
     }
 
     fn handle_resize(&mut self, window_id: WindowId, new_size: winit::dpi::PhysicalSize<u32>) {
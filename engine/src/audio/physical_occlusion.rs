@@ -4,8 +4,9 @@
 //! and frequency-dependent attenuation for realistic sound propagation.
 
 use crate::audio::components::AudioMaterial;
-use crate::audio::raycast::{audio_raycast, AudioRay};
+use crate::audio::raycast::{audio_raycast_on_layers, AudioRay};
 use crate::core::entity::{Entity, World};
+use crate::physics::CollisionLayers;
 use glam::Vec3;
 use std::f32::consts::PI;
 use tracing::{debug, trace};
@@ -21,6 +22,8 @@ pub struct OcclusionConfig {
     pub frequency_dependent: bool,
     /// Frequency bands for analysis (Hz)
     pub frequency_bands: Vec<f32>,
+    /// Collision layers whose colliders block sound, as a bit mask
+    pub occlusion_mask: u32,
 }
 
 impl Default for OcclusionConfig {
@@ -30,6 +33,7 @@ impl Default for OcclusionConfig {
             max_diffraction_angle: PI / 4.0,
             frequency_dependent: true,
             frequency_bands: vec![125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0],
+            occlusion_mask: CollisionLayers::ALL,
         }
     }
 }
@@ -85,6 +89,7 @@ pub fn calculate_physical_occlusion(
         world,
         source_entity,
         config.num_rays,
+        config.occlusion_mask,
     );
 
     // Check for direct path
@@ -105,6 +110,7 @@ pub fn calculate_physical_occlusion(
             world,
             source_entity,
             config.max_diffraction_angle,
+            config.occlusion_mask,
         )
     } else {
         vec![]
@@ -132,6 +138,7 @@ fn cast_occlusion_rays(
     world: &World,
     source_entity: Entity,
     num_rays: usize,
+    occlusion_mask: u32,
 ) -> (f32, Vec<AudioMaterial>) {
     let mut total_occlusion = 0.0;
     let mut hit_materials = Vec::new();
@@ -144,11 +151,17 @@ fn cast_occlusion_rays(
         direction: base_direction,
     };
 
-    if let Some(hit) = audio_raycast(world, central_ray, distance, Some(source_entity)) {
+    if let Some(hit) = audio_raycast_on_layers(
+        world,
+        central_ray,
+        distance,
+        Some(source_entity),
+        occlusion_mask,
+    ) {
         if hit.distance < distance - 0.1 {
             let material = world
-                .get::<&AudioMaterial>(hit.entity)
-                .map(|m| **m)
+                .get::<AudioMaterial>(hit.entity)
+                .map(|m| *m)
                 .unwrap_or_else(|_| AudioMaterial::default());
 
             total_occlusion += 1.0 - material.transmission;
@@ -178,11 +191,17 @@ fn cast_occlusion_rays(
                 direction: offset_dir,
             };
 
-            if let Some(hit) = audio_raycast(world, ray, distance * 1.1, Some(source_entity)) {
+            if let Some(hit) = audio_raycast_on_layers(
+                world,
+                ray,
+                distance * 1.1,
+                Some(source_entity),
+                occlusion_mask,
+            ) {
                 if hit.distance < distance {
                     let material = world
-                        .get::<&AudioMaterial>(hit.entity)
-                        .map(|m| **m)
+                        .get::<AudioMaterial>(hit.entity)
+                        .map(|m| *m)
                         .unwrap_or_else(|_| AudioMaterial::default());
 
                     total_occlusion += 1.0 - material.transmission;
@@ -236,6 +255,7 @@ fn find_diffraction_paths(
     world: &World,
     source_entity: Entity,
     max_angle: f32,
+    occlusion_mask: u32,
 ) -> Vec<DiffractionPath> {
     let mut paths = Vec::new();
     let direct_dir = (source_pos - listener_pos).normalize();
@@ -264,7 +284,8 @@ fn find_diffraction_paths(
         };
 
         let edge_distance = (edge_point - listener_pos).length();
-        let hit_to_edge = audio_raycast(world, ray_to_edge, edge_distance, None);
+        let hit_to_edge =
+            audio_raycast_on_layers(world, ray_to_edge, edge_distance, None, occlusion_mask);
 
         // Check if we can reach source from edge point
         let from_edge = (source_pos - edge_point).normalize();
@@ -274,8 +295,13 @@ fn find_diffraction_paths(
         };
 
         let source_distance = (source_pos - edge_point).length();
-        let hit_from_edge =
-            audio_raycast(world, ray_from_edge, source_distance, Some(source_entity));
+        let hit_from_edge = audio_raycast_on_layers(
+            world,
+            ray_from_edge,
+            source_distance,
+            Some(source_entity),
+            occlusion_mask,
+        );
 
         // If both paths are clear, we have a diffraction path
         if hit_to_edge.is_none() && hit_from_edge.is_none() {
@@ -4,6 +4,7 @@
 
 use crate::core::entity::{Entity, Transform, World};
 use crate::graphics::culling::AABB;
+use crate::physics::{Collider, CollisionLayers};
 use glam::Vec3;
 
 /// Ray structure for audio raycasting
@@ -106,16 +107,38 @@ pub fn audio_raycast(
     ray: AudioRay,
     max_distance: f32,
     exclude: Option<Entity>,
+) -> Option<AudioRayHit> {
+    audio_raycast_on_layers(world, ray, max_distance, exclude, CollisionLayers::ALL)
+}
+
+/// Perform audio raycast against entities on the given collision layers
+///
+/// Entities with a [`Collider`] are only hit when one of its layers is in
+/// `layer_mask`. Entities without a collider are always hit.
+pub fn audio_raycast_on_layers(
+    world: &World,
+    ray: AudioRay,
+    max_distance: f32,
+    exclude: Option<Entity>,
+    layer_mask: u32,
 ) -> Option<AudioRayHit> {
     let mut closest_hit: Option<(Entity, f32, AABB)> = None;
 
     // Query all entities with both AABB and Transform
-    for (entity, (aabb, transform)) in world.query::<(&AABB, &Transform)>().iter() {
+    for (entity, (aabb, transform, collider)) in world
+        .query::<(&AABB, &Transform, Option<&Collider>)>()
+        .iter()
+    {
         // Skip excluded entity
         if Some(entity) == exclude {
             continue;
         }
 
+        // Skip colliders on layers that do not block sound
+        if collider.is_some_and(|collider| collider.membership & layer_mask == 0) {
+            continue;
+        }
+
         // Transform AABB to world space
         let world_aabb = transform_aabb(aabb, transform);
 
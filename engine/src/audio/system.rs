@@ -17,6 +17,9 @@ pub struct AudioSystemState {
     listener_velocity_trackers: HashMap<u64, VelocityTracker>,
     /// Currently playing ambient sounds
     playing_ambient: HashMap<u64, crate::audio::AudioHandle>,
+    /// Settings for occlusion of spatial sounds, including which collision
+    /// layers block sound
+    pub occlusion_config: OcclusionConfig,
 }
 
 impl Default for AudioSystemState {
@@ -24,6 +27,7 @@ impl Default for AudioSystemState {
         Self {
             listener_velocity_trackers: HashMap::new(),
             playing_ambient: HashMap::new(),
+            occlusion_config: OcclusionConfig::default(),
         }
     }
 }
@@ -68,7 +72,12 @@ pub fn audio_update_system(
     );
 
     // Process audio sources
-    process_audio_sources(world, audio_engine, &listener_state);
+    process_audio_sources(
+        world,
+        audio_engine,
+        &listener_state,
+        &state.occlusion_config,
+    );
 
     // Process ambient sounds
     process_ambient_sounds(world, audio_engine, state, &listener_state);
@@ -79,6 +88,7 @@ fn process_audio_sources(
     world: &mut World,
     audio_engine: &mut AudioEngine,
     listener_state: &crate::audio::listener::ListenerState,
+    occlusion_config: &OcclusionConfig,
 ) {
     // First pass: collect entities that need processing
     let entities_to_process: Vec<hecs::Entity> = world
@@ -213,13 +223,12 @@ fn process_audio_sources(
                     // Always update spatial parameters for active handles
                     if spatial {
                         // Use physical occlusion for more realistic sound
-                        let occlusion_config = OcclusionConfig::default();
                         let occlusion_result = calculate_physical_occlusion(
                             listener_state.position,
                             transform.position,
                             world,
                             entity,
-                            &occlusion_config,
+                            occlusion_config,
                         );
 
                         // Apply spatial parameters
@@ -0,0 +1,421 @@
+//! Headless scene renderer
+//!
+//! Loads scene files, simulates them for a fixed number of frames and writes
+//! screenshots to disk. Used for scene thumbnails and for reproducible
+//! captures of bug reports.
+//!
+//! ```text
+//! render_scene [OPTIONS] <SCENE|DIR>...
+//! ```
+//!
+//! Directories are expanded to the `.json` scenes they contain. Every run uses
+//! a fixed timestep and no input, so the same scene always renders the same.
+
+use engine::config::AssetConfig;
+use engine::core::camera::Camera;
+use engine::core::entity::{hierarchy, update_hierarchy_system, Entity, Name, World};
+use engine::graphics::{RenderContext, Renderer};
+use engine::io::component_registry::ComponentRegistry;
+use engine::io::PrefabLibrary;
+use engine::physics::world::PhysicsWorld;
+use engine::scripting::{self, ScriptEngine, ScriptInputState};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+const USAGE: &str = "\
+Usage: render_scene [OPTIONS] <SCENE|DIR>...
+
+Options:
+  -o, --output <PATH>     Output directory, or a .png file for a single capture [default: screenshots]
+  -f, --frames <N>        Frames to simulate before the capture [default: 60]
+  -i, --interval <N>      Also capture every N frames while simulating
+  -c, --camera <NAME>     Render from the camera entity with this name
+      --width <PIXELS>    Image width [default: 640]
+      --height <PIXELS>   Image height [default: 360]
+      --timestep <SECS>   Fixed frame time [default: 0.016666668]
+      --assets <DIR>      Asset root for scripts [default: game/assets]
+      --no-scripts        Do not run scripts
+      --no-physics        Do not run physics
+  -h, --help              Print this help";
+
+/// Command line options
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    scenes: Vec<PathBuf>,
+    output: PathBuf,
+    frames: u32,
+    interval: Option<u32>,
+    camera: Option<String>,
+    width: u32,
+    height: u32,
+    timestep: f32,
+    asset_root: PathBuf,
+    scripts: bool,
+    physics: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            scenes: Vec::new(),
+            output: PathBuf::from("screenshots"),
+            frames: 60,
+            interval: None,
+            camera: None,
+            width: 640,
+            height: 360,
+            timestep: 1.0 / 60.0,
+            asset_root: PathBuf::from("game/assets"),
+            scripts: true,
+            physics: true,
+        }
+    }
+}
+
+impl Options {
+    /// Parse options from command line arguments, without the program name
+    ///
+    /// Returns `Ok(None)` when help was requested.
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {name}"))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => options.output = PathBuf::from(value(&arg)?),
+                "-f" | "--frames" => options.frames = parse_number(&arg, &value(&arg)?)?,
+                "-i" | "--interval" => {
+                    let interval: u32 = parse_number(&arg, &value(&arg)?)?;
+                    if interval == 0 {
+                        return Err("--interval must be at least 1".to_string());
+                    }
+                    options.interval = Some(interval);
+                }
+                "-c" | "--camera" => options.camera = Some(value(&arg)?),
+                "--width" => options.width = parse_number(&arg, &value(&arg)?)?,
+                "--height" => options.height = parse_number(&arg, &value(&arg)?)?,
+                "--timestep" => options.timestep = parse_number(&arg, &value(&arg)?)?,
+                "--assets" => options.asset_root = PathBuf::from(value(&arg)?),
+                "--no-scripts" => options.scripts = false,
+                "--no-physics" => options.physics = false,
+                _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+                _ => options.scenes.push(PathBuf::from(&arg)),
+            }
+        }
+
+        if options.scenes.is_empty() {
+            return Err("No scene given".to_string());
+        }
+        if options.width == 0 || options.height == 0 {
+            return Err("Image size must not be zero".to_string());
+        }
+        if options.timestep <= 0.0 {
+            return Err("--timestep must be positive".to_string());
+        }
+
+        Ok(Some(options))
+    }
+
+    /// Check if the output names a single image file
+    fn output_is_file(&self) -> bool {
+        self.output
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+    }
+
+    /// Path of the capture of `scene` taken after `frame` frames
+    ///
+    /// The final capture is named after the scene, interval captures get the
+    /// frame number appended.
+    fn capture_path(&self, scene: &Path, frame: Option<u32>) -> PathBuf {
+        if self.output_is_file() && frame.is_none() {
+            return self.output.clone();
+        }
+
+        let directory = if self.output_is_file() {
+            self.output.parent().unwrap_or(Path::new("")).to_path_buf()
+        } else {
+            self.output.clone()
+        };
+        let stem = scene
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "scene".to_string());
+
+        match frame {
+            Some(frame) => directory.join(format!("{stem}_frame{frame:04}.png")),
+            None => directory.join(format!("{stem}.png")),
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {name}: {value}"))
+}
+
+/// Expand directories to the scene files they contain, sorted by name
+fn collect_scenes(paths: &[PathBuf]) -> Result<Vec<PathBuf>, String> {
+    let mut scenes = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let entries = std::fs::read_dir(path)
+                .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+            let mut found: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "json")
+                })
+                .collect();
+            found.sort();
+            scenes.extend(found);
+        } else {
+            scenes.push(path.clone());
+        }
+    }
+    Ok(scenes)
+}
+
+/// Find the camera entity with the given name
+fn find_named_camera(world: &World, name: &str) -> Option<Entity> {
+    world
+        .query::<(&Camera, &Name)>()
+        .iter()
+        .find(|(_, (_, entity_name))| entity_name.0 == name)
+        .map(|(entity, _)| entity)
+}
+
+/// Systems advanced every simulated frame
+struct Simulation {
+    script_engine: Option<ScriptEngine>,
+    physics_world: Option<PhysicsWorld>,
+    input: ScriptInputState,
+    timestep: f32,
+}
+
+impl Simulation {
+    fn new(options: &Options) -> Self {
+        let script_engine = options.scripts.then(|| {
+            let asset_config = AssetConfig::new(
+                options.asset_root.clone(),
+                "scripts".to_string(),
+                "scenes".to_string(),
+            );
+            let mut engine = ScriptEngine::with_config(asset_config);
+            scripting::system::initialize_script_engine(&mut engine);
+            engine
+        });
+
+        Self {
+            script_engine,
+            physics_world: options.physics.then(PhysicsWorld::new),
+            input: ScriptInputState::new(),
+            timestep: options.timestep,
+        }
+    }
+
+    /// Advance the world by one fixed frame, in the same order as the engine loop
+    fn step(&mut self, world: &mut World, renderer: &mut Renderer) {
+        hierarchy::advance_frame();
+
+        if let Some(script_engine) = &mut self.script_engine {
+            scripting::script_initialization_system(world, script_engine);
+            engine::physics::queries::lend_to_scripts(&mut self.physics_world, || {
+                scripting::script_execution_system(world, script_engine, &self.input, self.timestep)
+            });
+            scripting::process_script_mesh_uploads(
+                script_engine,
+                renderer,
+                self.physics_world.as_mut(),
+            );
+        }
+
+        if let Some(physics_world) = &mut self.physics_world {
+            engine::physics::system::physics_update_system(world, physics_world, self.timestep);
+        }
+
+        update_hierarchy_system(world);
+
+        if let Some(physics_world) = &self.physics_world {
+            engine::physics::system::interpolate_physics_transforms(world, physics_world);
+        }
+    }
+}
+
+/// Simulate one scene and write its captures, returning the written paths
+fn render_scene(
+    renderer: &mut Renderer,
+    registry: &ComponentRegistry,
+    prefabs: &mut PrefabLibrary,
+    scene_path: &Path,
+    options: &Options,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut world = World::new();
+    world.load_scene(scene_path)?;
+    world.refresh_prefab_instances(registry, prefabs);
+
+    let camera = match &options.camera {
+        Some(name) => Some(
+            find_named_camera(&world, name)
+                .ok_or_else(|| format!("No camera named \"{name}\" in scene"))?,
+        ),
+        None => None,
+    };
+
+    let mut simulation = Simulation::new(options);
+    let mut written = Vec::new();
+    let mut capture = |renderer: &mut Renderer, world: &World, frame: Option<u32>| {
+        let image = renderer.render_to_image(world, camera, options.width, options.height)?;
+        let path = options.capture_path(scene_path, frame);
+        image.save_png(&path)?;
+        written.push(path);
+        Ok::<_, Box<dyn std::error::Error>>(())
+    };
+
+    for frame in 1..=options.frames {
+        simulation.step(&mut world, renderer);
+        if options
+            .interval
+            .is_some_and(|interval| frame.is_multiple_of(interval) && frame != options.frames)
+        {
+            capture(renderer, &world, Some(frame))?;
+        }
+    }
+    capture(renderer, &world, None)?;
+
+    Ok(written)
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("✗ {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    engine::init_logging();
+
+    let scenes = match collect_scenes(&options.scenes) {
+        Ok(scenes) => scenes,
+        Err(e) => {
+            eprintln!("✗ {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if options.output_is_file() && (scenes.len() > 1 || options.interval.is_some()) {
+        eprintln!("✗ A .png output only fits a single capture, use an output directory");
+        return ExitCode::FAILURE;
+    }
+
+    let context = match pollster::block_on(RenderContext::new_headless()) {
+        Ok(context) => context,
+        Err(e) => {
+            eprintln!("✗ Failed to create render context: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "Rendering with {} ({:?})",
+        context.adapter_info.name, context.adapter_info.backend
+    );
+    let mut renderer = Renderer::new(Arc::new(context));
+    let registry = ComponentRegistry::with_default_components();
+    let mut prefabs = PrefabLibrary::new();
+
+    let mut failures = 0;
+    for scene in &scenes {
+        match render_scene(&mut renderer, &registry, &mut prefabs, scene, &options) {
+            Ok(written) => {
+                println!("✓ {}", scene.display());
+                for path in written {
+                    println!("  {}", path.display());
+                }
+            }
+            Err(e) => {
+                eprintln!("✗ {}: {e}", scene.display());
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        eprintln!("{failures} of {} scenes failed", scenes.len());
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Option<Options>, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse(&[
+            "scene.json",
+            "-f",
+            "120",
+            "--camera",
+            "Overview",
+            "--width",
+            "256",
+            "--no-physics",
+        ])
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(options.scenes, vec![PathBuf::from("scene.json")]);
+        assert_eq!(options.frames, 120);
+        assert_eq!(options.camera.as_deref(), Some("Overview"));
+        assert_eq!((options.width, options.height), (256, 360));
+        assert!(options.scripts);
+        assert!(!options.physics);
+
+        assert_eq!(parse(&["--help"]).unwrap(), None);
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["scene.json", "--frames"]).is_err());
+        assert!(parse(&["scene.json", "--interval", "0"]).is_err());
+        assert!(parse(&["scene.json", "--bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_capture_paths() {
+        let scene = Path::new("game/assets/scenes/demo_scene.json");
+
+        let options = parse(&["x", "-o", "thumbs"]).unwrap().unwrap();
+        assert_eq!(
+            options.capture_path(scene, None),
+            PathBuf::from("thumbs/demo_scene.png")
+        );
+        assert_eq!(
+            options.capture_path(scene, Some(30)),
+            PathBuf::from("thumbs/demo_scene_frame0030.png")
+        );
+
+        let options = parse(&["x", "-o", "out/bug.png"]).unwrap().unwrap();
+        assert_eq!(
+            options.capture_path(scene, None),
+            PathBuf::from("out/bug.png")
+        );
+    }
+}
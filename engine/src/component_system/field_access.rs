@@ -6,7 +6,7 @@
 use glam::{Quat, Vec3};
 
 /// A value that can be displayed and edited in the UI
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FieldValue {
     Float(f32),
     Int(i32),
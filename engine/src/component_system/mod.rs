@@ -56,6 +56,27 @@ pub type RemoveComponentFn = Arc<
 pub type GetComponentFn =
     Arc<dyn Fn(&crate::core::entity::World, hecs::Entity) -> Option<Box<dyn Any>> + Send + Sync>;
 
+/// Type alias for serializing a component straight from an entity
+pub type SerializeComponentFn = Arc<
+    dyn Fn(
+            &crate::core::entity::World,
+            hecs::Entity,
+        ) -> Option<Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>>>
+        + Send
+        + Sync,
+>;
+
+/// Type alias for deserializing a component from JSON and inserting it on an entity
+pub type InsertComponentFn = Arc<
+    dyn Fn(
+            &mut crate::core::entity::World,
+            hecs::Entity,
+            &serde_json::Value,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+        + Send
+        + Sync,
+>;
+
 /// Trait for components that can be automatically registered and managed
 pub trait Component: Any + Send + Sync + 'static {
     /// Get the name of this component type
@@ -131,6 +152,14 @@ pub struct ComponentMetadata {
 
     /// Function to get component as dyn Any
     pub get_component: GetComponentFn,
+
+    /// Function to serialize this component of an entity to JSON
+    /// Returns None if the entity doesn't have the component
+    pub serialize_component: SerializeComponentFn,
+
+    /// Function to insert this component on an entity from JSON,
+    /// replacing any existing value
+    pub insert_component: InsertComponentFn,
 }
 
 impl ComponentMetadata {
@@ -179,6 +208,20 @@ impl ComponentMetadata {
                     None
                 }
             }),
+            serialize_component: Arc::new(|world, entity| {
+                let component = world.get::<T>(entity).ok()?;
+                Some(
+                    serde_json::to_value(&*component)
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>),
+                )
+            }),
+            insert_component: Arc::new(|world, entity, value| {
+                let component: T = serde_json::from_value(value.clone())
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+                world
+                    .insert_one(entity, component)
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+            }),
         }
     }
 
@@ -111,6 +111,32 @@ fn test_add_default_component() {
     assert_eq!(component.name, "");
 }
 
+#[test]
+fn test_serialize_and_insert_component() {
+    use crate::core::entity::World;
+
+    let mut world = World::default();
+    let source = world.spawn((TestComponent {
+        value: 7,
+        name: "source".to_string(),
+    },));
+    let target = world.spawn(());
+
+    let metadata = ComponentMetadata::new::<TestComponent>("TestComponent");
+
+    // Missing components serialize to None
+    assert!((metadata.serialize_component)(&world, target).is_none());
+
+    let value = (metadata.serialize_component)(&world, source)
+        .unwrap()
+        .unwrap();
+    (metadata.insert_component)(&mut world, target, &value).unwrap();
+
+    let component = world.get::<TestComponent>(target).unwrap();
+    assert_eq!(component.value, 7);
+    assert_eq!(component.name, "source");
+}
+
 #[test]
 fn test_registry_iteration() {
     let mut registry = ComponentRegistry::new();
@@ -58,6 +58,12 @@ impl AssetConfig {
         path
     }
 
+    /// Get the path of the project's collision layer names
+    pub fn collision_layers_path(&self) -> PathBuf {
+        self.asset_root
+            .join(crate::physics::CollisionLayers::FILE_NAME)
+    }
+
     /// Check if the asset directories exist
     pub fn validate(&self) -> Result<(), std::io::Error> {
         let scripts_path = self.asset_root.join(&self.scripts_dir);
@@ -4,9 +4,9 @@
 //! orthographic projections, and view matrix calculation from transforms.
 
 use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
-use crate::core::entity::{components::GlobalWorldTransform, GlobalTransform};
+use crate::core::entity::{components::GlobalWorldTransform, GlobalTransform, World};
 use crate::io::component_registry::ComponentRegistry;
-use glam::{DVec3, Mat4};
+use glam::{DVec3, Mat4, Quat, Vec3};
 use serde::{Deserialize, Serialize};
 
 /// Camera component that defines projection parameters for rendering
@@ -147,6 +147,49 @@ impl Camera {
         self.projection_matrix() * Self::view_matrix(camera_transform)
     }
 
+    /// Calculate the view matrix for camera-relative rendering
+    ///
+    /// Objects are offset by the camera position before they reach the GPU,
+    /// so the view only has to undo the camera's rotation.
+    pub fn view_matrix_relative(camera_rotation: Quat) -> Mat4 {
+        Mat4::from_quat(camera_rotation.inverse())
+    }
+
+    /// Calculate the view-projection matrix for camera-relative rendering
+    pub fn view_projection_matrix_relative(&self, camera_rotation: Quat) -> Mat4 {
+        self.projection_matrix() * Self::view_matrix_relative(camera_rotation)
+    }
+
+    /// Corners of a slice of the view frustum in camera-relative space
+    ///
+    /// `near` and `far` are view depths. The first four corners lie on the near
+    /// plane, the last four on the far plane.
+    pub fn frustum_slice_corners(&self, camera_rotation: Quat, near: f32, far: f32) -> [Vec3; 8] {
+        let half_extents = |depth: f32| match self.projection_mode {
+            ProjectionMode::Perspective => {
+                let half_height = depth * (self.fov_y_radians * 0.5).tan();
+                (half_height * self.aspect_ratio, half_height)
+            }
+            ProjectionMode::Orthographic { height } => {
+                let half_height = height * 0.5;
+                (half_height * self.aspect_ratio, half_height)
+            }
+        };
+
+        let mut corners = [Vec3::ZERO; 8];
+        for (plane, depth) in [near, far].into_iter().enumerate() {
+            let (half_width, half_height) = half_extents(depth);
+            for (i, (x, y)) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .into_iter()
+                .enumerate()
+            {
+                let local = Vec3::new(x * half_width, y * half_height, -depth);
+                corners[plane * 4 + i] = camera_rotation * local;
+            }
+        }
+        corners
+    }
+
     /// Update the aspect ratio (useful when window resizes)
     pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
         self.aspect_ratio = aspect_ratio;
@@ -220,6 +263,141 @@ impl Camera {
     }
 }
 
+/// A camera resolved to its placement in the world
+///
+/// The scene is rendered camera-relative, so the view only needs the camera
+/// rotation while object positions are offset by `world_position`.
+#[derive(Debug, Clone, Copy)]
+pub struct CameraView {
+    /// Camera entity, None for a free view such as the editor camera
+    pub entity: Option<hecs::Entity>,
+    /// Projection parameters
+    pub camera: Camera,
+    /// High precision camera position
+    pub world_position: DVec3,
+    /// Camera orientation in world space
+    pub rotation: Quat,
+}
+
+impl CameraView {
+    /// Create a free view that doesn't belong to any entity
+    ///
+    /// `world_position` is in the same large-world space as
+    /// [`CameraWorldPosition`].
+    pub fn new(camera: Camera, world_position: DVec3, rotation: Quat) -> Self {
+        Self {
+            entity: None,
+            camera,
+            world_position,
+            rotation,
+        }
+    }
+
+    /// Find the camera to render with
+    ///
+    /// With `camera_entity` set only that entity is considered, otherwise the
+    /// first camera in the world is used. Cameras with a `GlobalWorldTransform`
+    /// (large world cameras) take precedence over regular cameras.
+    pub fn resolve(world: &World, camera_entity: Option<hecs::Entity>) -> Option<Self> {
+        let from_world_transform =
+            |entity: hecs::Entity,
+             camera: &Camera,
+             world_transform: &GlobalWorldTransform,
+             world_pos: Option<&CameraWorldPosition>| {
+                // Use world position if available, otherwise derive from transform
+                let world_position = world_pos
+                    .map(|pos| pos.position)
+                    .unwrap_or_else(|| world_transform.position());
+
+                let (_, rotation, _) = world_transform.matrix.to_scale_rotation_translation();
+
+                Self {
+                    entity: Some(entity),
+                    camera: *camera,
+                    world_position,
+                    rotation: rotation.as_quat(),
+                }
+            };
+
+        let from_transform =
+            |entity: hecs::Entity,
+             camera: &Camera,
+             transform: &GlobalTransform,
+             world_pos: Option<&CameraWorldPosition>| {
+                // Use CameraWorldPosition if available for exact position, otherwise extract
+                // from transform (less precise for parented cameras)
+                let world_position = world_pos
+                    .map(|pos| pos.position)
+                    .unwrap_or_else(|| transform.position().as_dvec3());
+
+                let (_, rotation, _) = transform.matrix.to_scale_rotation_translation();
+
+                Self {
+                    entity: Some(entity),
+                    camera: *camera,
+                    world_position,
+                    rotation,
+                }
+            };
+
+        match camera_entity {
+            Some(entity) => {
+                if let Ok(mut query) = world.query_one::<(
+                    &Camera,
+                    &GlobalWorldTransform,
+                    Option<&CameraWorldPosition>,
+                )>(entity)
+                {
+                    if let Some((camera, world_transform, world_pos)) = query.get() {
+                        return Some(from_world_transform(
+                            entity,
+                            camera,
+                            world_transform,
+                            world_pos,
+                        ));
+                    }
+                }
+
+                let mut query = world
+                    .query_one::<(&Camera, &GlobalTransform, Option<&CameraWorldPosition>)>(entity)
+                    .ok()?;
+                query.get().map(|(camera, transform, world_pos)| {
+                    from_transform(entity, camera, transform, world_pos)
+                })
+            }
+            None => {
+                // First try to find a camera with WorldTransform (large world camera)
+                let mut world_camera_query =
+                    world.query::<(&Camera, &GlobalWorldTransform, Option<&CameraWorldPosition>)>();
+                if let Some((entity, (camera, world_transform, world_pos))) =
+                    world_camera_query.iter().next()
+                {
+                    return Some(from_world_transform(
+                        entity,
+                        camera,
+                        world_transform,
+                        world_pos,
+                    ));
+                }
+
+                // Fall back to regular camera with GlobalTransform
+                let mut regular_camera_query =
+                    world.query::<(&Camera, &GlobalTransform, Option<&CameraWorldPosition>)>();
+                regular_camera_query.iter().next().map(
+                    |(entity, (camera, transform, world_pos))| {
+                        from_transform(entity, camera, transform, world_pos)
+                    },
+                )
+            }
+        }
+    }
+
+    /// View-projection matrix for camera-relative rendering
+    pub fn view_projection(&self) -> Mat4 {
+        self.camera.view_projection_matrix_relative(self.rotation)
+    }
+}
+
 /// Camera world position component for large world coordinate tracking
 ///
 /// This component tracks the camera's position in high-precision world coordinates.
@@ -303,6 +481,36 @@ mod tests {
         assert_eq!(view.w_axis.z, -5.0);
     }
 
+    #[test]
+    fn test_view_matrix_relative() {
+        let camera = Camera::default();
+        // Turned to look along -X
+        let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+
+        // The camera sits at the origin of camera-relative space, so a point in
+        // front of it projects to the center of the screen
+        let view_proj = camera.view_projection_matrix_relative(rotation);
+        let clip = view_proj * Vec3::new(-10.0, 0.0, 0.0).extend(1.0);
+        let ndc = clip.truncate() / clip.w;
+        assert!(ndc.x.abs() < 1e-5 && ndc.y.abs() < 1e-5);
+        assert!(ndc.z > 0.0 && ndc.z < 1.0);
+    }
+
+    #[test]
+    fn test_frustum_slice_corners() {
+        let camera = Camera::perspective(90.0, 1.0, 0.1, 100.0);
+        let corners = camera.frustum_slice_corners(Quat::IDENTITY, 1.0, 10.0);
+
+        for corner in &corners[..4] {
+            assert!((corner.z + 1.0).abs() < 1e-5);
+            assert!((corner.x.abs() - 1.0).abs() < 1e-5);
+        }
+        for corner in &corners[4..] {
+            assert!((corner.z + 10.0).abs() < 1e-5);
+            assert!((corner.y.abs() - 10.0).abs() < 1e-4);
+        }
+    }
+
     #[test]
     fn test_camera_default() {
         let camera = Camera::default();
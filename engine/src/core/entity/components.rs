@@ -139,6 +139,49 @@ impl PreviousTransform {
     }
 }
 
+/// Previous transform component for entities using WorldTransform
+/// This is the high-precision counterpart of [`PreviousTransform`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, engine_derive::Component)]
+#[component(name = "PreviousWorldTransform")]
+pub struct PreviousWorldTransform {
+    /// Position from the previous frame using 64-bit precision
+    pub position: DVec3,
+    /// Rotation from the previous frame
+    pub rotation: Quat,
+    /// Scale from the previous frame
+    pub scale: Vec3,
+}
+
+impl Default for PreviousWorldTransform {
+    fn default() -> Self {
+        Self {
+            position: DVec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+        }
+    }
+}
+
+impl PreviousWorldTransform {
+    /// Create from a WorldTransform component
+    pub fn from_world_transform(world_transform: &WorldTransform) -> Self {
+        Self {
+            position: world_transform.position,
+            rotation: world_transform.rotation,
+            scale: world_transform.scale,
+        }
+    }
+
+    /// Convert to transformation matrix
+    pub fn to_matrix(&self) -> DMat4 {
+        DMat4::from_scale_rotation_translation(
+            self.scale.as_dvec3(),
+            self.rotation.as_dquat(),
+            self.position,
+        )
+    }
+}
+
 /// Global transform component representing the world-space transformation matrix
 #[derive(
     Debug, Clone, Copy, Serialize, Deserialize, engine_derive::Component, engine_derive::EditorUI,
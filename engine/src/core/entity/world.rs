@@ -231,6 +231,23 @@ impl World {
         Ok(mapper)
     }
 
+    /// Rebuild prefab instances from their prefab files
+    ///
+    /// Scenes store instances fully expanded, so calling this after loading a
+    /// scene picks up changes made to the prefabs since the scene was saved
+    /// while keeping the overrides. Prefabs are read through `library`, and
+    /// `registry` has to know every component the prefabs use.
+    pub fn refresh_prefab_instances(
+        &mut self,
+        registry: &crate::io::component_registry::ComponentRegistry,
+        library: &mut crate::io::PrefabLibrary,
+    ) {
+        let refreshed = crate::io::prefab::refresh_instances(self, registry, library);
+        if refreshed > 0 {
+            info!(count = refreshed, "Refreshed prefab instances");
+        }
+    }
+
     /// Assign default meshes to entities that have transforms but no MeshId
     ///
     /// This method automatically assigns default meshes and materials to entities
@@ -0,0 +1,269 @@
+//! Frame capture for headless rendering
+//!
+//! The renderer draws into an offscreen [`RenderTarget`](super::RenderTarget),
+//! copies the color texture into a mappable buffer and hands the pixels back
+//! as a [`CapturedImage`]. Captured images can be saved as PNG and compared
+//! against reference images, which is what the golden-image tests use.
+
+use std::path::Path;
+
+/// Color format used when capturing into a fresh renderer
+pub const CAPTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Errors that can occur while capturing or storing a frame
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError {
+    #[error("No camera found to render from")]
+    NoCamera,
+
+    #[error("Invalid capture size {0}x{1}")]
+    InvalidSize(u32, u32),
+
+    #[error("GPU poll error: {0}")]
+    Poll(#[from] wgpu::PollError),
+
+    #[error("Readback buffer mapping failed: {0}")]
+    BufferMap(#[from] wgpu::BufferAsyncError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+/// Check if frames in this format can be read back as 8-bit RGBA
+pub(crate) fn is_capture_format(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Rgba8Unorm
+            | wgpu::TextureFormat::Bgra8UnormSrgb
+            | wgpu::TextureFormat::Bgra8Unorm
+    )
+}
+
+/// Bytes per row of a readback buffer, padded to the copy alignment
+pub(crate) fn padded_bytes_per_row(width: u32) -> u32 {
+    let unpadded = width * 4;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// A captured frame as tightly packed 8-bit RGBA, top row first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Result of comparing two images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageDiff {
+    /// Whether both images have the same dimensions
+    pub size_matches: bool,
+    /// Number of pixels with a channel difference above the tolerance
+    pub mismatched_pixels: usize,
+    /// Largest difference of any channel
+    pub max_difference: u8,
+}
+
+impl ImageDiff {
+    /// Check if the images match within the tolerance used for the comparison
+    pub fn is_match(&self) -> bool {
+        self.size_matches && self.mismatched_pixels == 0
+    }
+}
+
+impl CapturedImage {
+    /// Build an image from buffer rows padded to the copy alignment
+    ///
+    /// BGRA data is swizzled to RGBA so captures look the same whatever
+    /// format the renderer draws in.
+    pub(crate) fn from_padded_rows(
+        width: u32,
+        height: u32,
+        data: &[u8],
+        bytes_per_row: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let row_len = width as usize * 4;
+        let mut pixels = Vec::with_capacity(row_len * height as usize);
+        for row in data.chunks(bytes_per_row as usize).take(height as usize) {
+            pixels.extend_from_slice(&row[..row_len]);
+        }
+
+        if matches!(
+            format,
+            wgpu::TextureFormat::Bgra8UnormSrgb | wgpu::TextureFormat::Bgra8Unorm
+        ) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Get the RGBA value of a pixel
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[offset..offset + 4]);
+        pixel
+    }
+
+    /// Save the image as PNG, creating parent directories as needed
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), CaptureError> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        image::save_buffer(
+            path,
+            &self.pixels,
+            self.width,
+            self.height,
+            image::ExtendedColorType::Rgba8,
+        )?;
+        Ok(())
+    }
+
+    /// Load an image file as RGBA
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self, CaptureError> {
+        let image = image::open(path)?.to_rgba8();
+        let (width, height) = image.dimensions();
+        Ok(Self {
+            width,
+            height,
+            pixels: image.into_raw(),
+        })
+    }
+
+    /// Compare against another image
+    ///
+    /// A pixel counts as mismatched when any channel differs by more than
+    /// `tolerance`, which absorbs small rasterization differences between
+    /// GPUs and drivers.
+    pub fn compare(&self, other: &CapturedImage, tolerance: u8) -> ImageDiff {
+        if self.width != other.width || self.height != other.height {
+            return ImageDiff {
+                size_matches: false,
+                mismatched_pixels: (self.width * self.height).max(other.width * other.height)
+                    as usize,
+                max_difference: u8::MAX,
+            };
+        }
+
+        let mut mismatched_pixels = 0;
+        let mut max_difference = 0;
+        for (a, b) in self
+            .pixels
+            .chunks_exact(4)
+            .zip(other.pixels.chunks_exact(4))
+        {
+            let difference = a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or(0);
+            max_difference = max_difference.max(difference);
+            if difference > tolerance {
+                mismatched_pixels += 1;
+            }
+        }
+
+        ImageDiff {
+            size_matches: true,
+            mismatched_pixels,
+            max_difference,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, rgba: [u8; 4]) -> CapturedImage {
+        CapturedImage {
+            width,
+            height,
+            pixels: rgba.repeat((width * height) as usize),
+        }
+    }
+
+    #[test]
+    fn test_padded_bytes_per_row() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+        assert_eq!(padded_bytes_per_row(128), 512);
+    }
+
+    #[test]
+    fn test_from_padded_rows() {
+        let bytes_per_row = padded_bytes_per_row(2);
+        let mut data = vec![0u8; (bytes_per_row * 2) as usize];
+        data[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let second_row = bytes_per_row as usize;
+        data[second_row..second_row + 8].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+
+        let image = CapturedImage::from_padded_rows(
+            2,
+            2,
+            &data,
+            bytes_per_row,
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+        );
+        assert_eq!(image.pixels.len(), 16);
+        assert_eq!(image.pixel(1, 0), [5, 6, 7, 8]);
+        assert_eq!(image.pixel(0, 1), [9, 10, 11, 12]);
+
+        // BGRA captures come back as RGBA
+        let image = CapturedImage::from_padded_rows(
+            2,
+            2,
+            &data,
+            bytes_per_row,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+        );
+        assert_eq!(image.pixel(0, 0), [3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = solid(4, 4, [100, 100, 100, 255]);
+        assert!(a.compare(&a, 0).is_match());
+
+        let mut b = a.clone();
+        b.pixels[0] = 103;
+        let diff = a.compare(&b, 2);
+        assert_eq!(diff.mismatched_pixels, 1);
+        assert_eq!(diff.max_difference, 3);
+        assert!(a.compare(&b, 3).is_match());
+
+        let diff = a.compare(&solid(2, 2, [100, 100, 100, 255]), 255);
+        assert!(!diff.size_matches);
+        assert!(!diff.is_match());
+    }
+
+    #[test]
+    fn test_png_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/capture.png");
+
+        let mut image = solid(3, 2, [10, 20, 30, 255]);
+        image.pixels[4..8].copy_from_slice(&[200, 150, 100, 255]);
+        image.save_png(&path).unwrap();
+
+        let loaded = CapturedImage::load_png(&path).unwrap();
+        assert_eq!(loaded, image);
+    }
+}
@@ -45,6 +45,50 @@ impl RenderContext {
             })
             .await?;
 
+        Self::from_adapter(instance, adapter).await
+    }
+
+    /// Create a render context without any window or surface
+    ///
+    /// Used for offscreen rendering such as screenshots and image tests.
+    /// Backends can be restricted with the `WGPU_BACKEND` environment
+    /// variable. When no hardware adapter is available this falls back to a
+    /// software adapter, so it also works on machines without a GPU.
+    pub async fn new_headless() -> Result<Self, Box<dyn std::error::Error>> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+
+        let adapter = match instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+        {
+            Ok(adapter) => adapter,
+            Err(e) => {
+                info!(
+                    "No hardware adapter available ({}), trying fallback adapter",
+                    e
+                );
+                instance
+                    .request_adapter(&wgpu::RequestAdapterOptions {
+                        power_preference: wgpu::PowerPreference::LowPower,
+                        compatible_surface: None,
+                        force_fallback_adapter: true,
+                    })
+                    .await?
+            }
+        };
+
+        Self::from_adapter(instance, adapter).await
+    }
+
+    /// Request the device and queue from a selected adapter
+    async fn from_adapter(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let adapter_info = adapter.get_info();
         info!(
             gpu_name = %adapter_info.name,
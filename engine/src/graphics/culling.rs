@@ -75,6 +75,22 @@ impl AABB {
 
         Self::from_points(&transformed_corners)
     }
+
+    /// Distance along a ray to where it enters the AABB
+    ///
+    /// Uses the slab test. Rays starting inside the box hit at distance 0.
+    /// Returns None when the ray misses or the box is behind the origin.
+    pub fn ray_intersection(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let inverse = direction.recip();
+        let t1 = (self.min - origin) * inverse;
+        let t2 = (self.max - origin) * inverse;
+
+        // NaN from 0 * inf on a slab boundary is ignored by min/max
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element();
+
+        (near <= far).then_some(near)
+    }
 }
 
 impl Default for AABB {
@@ -235,6 +251,27 @@ mod tests {
         assert_eq!(aabb.max, Vec3::ONE);
     }
 
+    #[test]
+    fn test_aabb_ray_intersection() {
+        let aabb = AABB::new(Vec3::splat(-1.0), Vec3::ONE);
+
+        let hit = aabb.ray_intersection(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+        assert_eq!(hit, Some(4.0));
+
+        // Axis-parallel ray next to the box
+        assert_eq!(
+            aabb.ray_intersection(Vec3::new(2.0, 0.0, 5.0), Vec3::NEG_Z),
+            None
+        );
+        // Box behind the ray
+        assert_eq!(
+            aabb.ray_intersection(Vec3::new(0.0, 0.0, 5.0), Vec3::Z),
+            None
+        );
+        // Ray starting inside
+        assert_eq!(aabb.ray_intersection(Vec3::ZERO, Vec3::X), Some(0.0));
+    }
+
     #[test]
     fn test_visibility_update() {
         let mut visibility = Visibility::new();
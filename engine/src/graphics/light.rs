@@ -0,0 +1,508 @@
+//! Light components and GPU light data
+//!
+//! Lights are regular components placed on entities with a transform. A light
+//! shines along the entity's local -Z axis, the same direction a camera looks.
+//! Each frame the renderer gathers up to [`MAX_LIGHTS`] lights into a storage
+//! buffer that the PBR shader loops over.
+//!
+//! Directional and spot lights can cast shadows. Their shadow settings live on
+//! the light component; the shadow maps themselves are planned and rendered by
+//! the [`shadow`](super::shadow) module.
+
+use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
+use crate::core::entity::{components::GlobalWorldTransform, GlobalTransform, World};
+use crate::io::component_registry::ComponentRegistry;
+use bytemuck::{Pod, Zeroable};
+use glam::{DVec3, Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of lights uploaded to the GPU per frame
+pub const MAX_LIGHTS: usize = 64;
+
+/// Light type ids, must match the constants in `pbr.wgsl`
+pub const LIGHT_TYPE_DIRECTIONAL: u32 = 0;
+pub const LIGHT_TYPE_POINT: u32 = 1;
+pub const LIGHT_TYPE_SPOT: u32 = 2;
+
+/// Direction of the light used when a world contains no light components
+const FALLBACK_LIGHT_DIRECTION: Vec3 = Vec3::new(0.5, -1.0, -0.3);
+
+/// Infinitely distant light such as the sun
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    engine_derive::Component,
+    engine_derive::EditorUI,
+)]
+#[component(name = "DirectionalLight")]
+#[serde(default)]
+pub struct DirectionalLight {
+    /// Light color (linear RGB)
+    #[ui(tooltip = "Light color")]
+    pub color: [f32; 3],
+
+    /// Light intensity multiplier
+    #[ui(range = 0.0..100.0, speed = 0.05, tooltip = "Light intensity")]
+    pub intensity: f32,
+
+    /// Whether the light renders cascaded shadow maps
+    #[ui(tooltip = "Cast shadows")]
+    pub cast_shadows: bool,
+
+    /// Distance from the camera covered by the shadow cascades
+    #[ui(range = 1.0..1000.0, speed = 0.5, tooltip = "Shadow distance in meters")]
+    pub shadow_distance: f32,
+
+    /// Depth offset applied when comparing against the shadow map
+    #[ui(range = 0.0..0.05, speed = 0.0001, tooltip = "Shadow depth bias")]
+    pub shadow_bias: f32,
+
+    /// Offset along the surface normal applied before shadow lookups
+    #[ui(range = 0.0..1.0, speed = 0.001, tooltip = "Shadow normal bias in meters")]
+    pub shadow_normal_bias: f32,
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            intensity: 3.0,
+            cast_shadows: true,
+            shadow_distance: 50.0,
+            shadow_bias: 0.001,
+            shadow_normal_bias: 0.02,
+        }
+    }
+}
+
+/// Light radiating in all directions from a point
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    engine_derive::Component,
+    engine_derive::EditorUI,
+)]
+#[component(name = "PointLight")]
+#[serde(default)]
+pub struct PointLight {
+    /// Light color (linear RGB)
+    #[ui(tooltip = "Light color")]
+    pub color: [f32; 3],
+
+    /// Light intensity multiplier
+    #[ui(range = 0.0..1000.0, speed = 0.1, tooltip = "Light intensity")]
+    pub intensity: f32,
+
+    /// Distance at which the light fades out completely
+    #[ui(range = 0.01..1000.0, speed = 0.1, tooltip = "Light range in meters")]
+    pub range: f32,
+}
+
+impl Default for PointLight {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            intensity: 10.0,
+            range: 10.0,
+        }
+    }
+}
+
+/// Cone shaped light pointing along the entity's -Z axis
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    engine_derive::Component,
+    engine_derive::EditorUI,
+)]
+#[component(name = "SpotLight")]
+#[serde(default)]
+pub struct SpotLight {
+    /// Light color (linear RGB)
+    #[ui(tooltip = "Light color")]
+    pub color: [f32; 3],
+
+    /// Light intensity multiplier
+    #[ui(range = 0.0..1000.0, speed = 0.1, tooltip = "Light intensity")]
+    pub intensity: f32,
+
+    /// Distance at which the light fades out completely
+    #[ui(range = 0.01..1000.0, speed = 0.1, tooltip = "Light range in meters")]
+    pub range: f32,
+
+    /// Half angle in degrees where the cone starts to fade
+    #[ui(range = 0.0..90.0, speed = 0.5, tooltip = "Inner cone angle in degrees")]
+    pub inner_angle: f32,
+
+    /// Half angle in degrees where the cone has faded out
+    #[ui(range = 0.0..90.0, speed = 0.5, tooltip = "Outer cone angle in degrees")]
+    pub outer_angle: f32,
+
+    /// Whether the light renders a shadow map
+    #[ui(tooltip = "Cast shadows")]
+    pub cast_shadows: bool,
+
+    /// Depth offset applied when comparing against the shadow map
+    #[ui(range = 0.0..0.05, speed = 0.0001, tooltip = "Shadow depth bias")]
+    pub shadow_bias: f32,
+
+    /// Offset along the surface normal applied before shadow lookups
+    #[ui(range = 0.0..1.0, speed = 0.001, tooltip = "Shadow normal bias in meters")]
+    pub shadow_normal_bias: f32,
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 1.0, 1.0],
+            intensity: 10.0,
+            range: 10.0,
+            inner_angle: 20.0,
+            outer_angle: 30.0,
+            cast_shadows: true,
+            shadow_bias: 0.0005,
+            shadow_normal_bias: 0.02,
+        }
+    }
+}
+
+/// A light as seen by the shader
+///
+/// Matches the `Light` struct in `pbr.wgsl`. Positions are camera-relative,
+/// like the model matrices of rendered objects.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct GpuLight {
+    /// Light position (point and spot lights)
+    pub position: [f32; 3],
+    /// One of the `LIGHT_TYPE_*` constants
+    pub light_type: u32,
+    /// Direction the light travels (directional and spot lights)
+    pub direction: [f32; 3],
+    /// Distance at which the light fades out
+    pub range: f32,
+    /// Light color (linear RGB)
+    pub color: [f32; 3],
+    /// Light intensity multiplier
+    pub intensity: f32,
+    /// Cosine of the spot inner cone angle
+    pub cos_inner: f32,
+    /// Cosine of the spot outer cone angle
+    pub cos_outer: f32,
+    /// First shadow map layer of the light, -1 when it casts no shadows
+    pub shadow_index: i32,
+    /// Depth offset applied when comparing against the shadow map
+    pub shadow_bias: f32,
+    /// Far view distance of each shadow cascade (directional lights)
+    pub cascade_splits: [f32; 4],
+    /// Offset along the surface normal applied before shadow lookups
+    pub shadow_normal_bias: f32,
+    /// Number of shadow map layers used by the light
+    pub cascade_count: u32,
+    /// Padding to 16 byte alignment
+    pub _padding: [f32; 2],
+}
+
+impl GpuLight {
+    /// Create GPU data for a directional light travelling along `direction`
+    pub fn directional(light: &DirectionalLight, direction: Vec3) -> Self {
+        Self {
+            light_type: LIGHT_TYPE_DIRECTIONAL,
+            direction: direction.normalize_or_zero().to_array(),
+            color: light.color,
+            intensity: light.intensity,
+            shadow_bias: light.shadow_bias,
+            shadow_normal_bias: light.shadow_normal_bias,
+            ..Self::unshadowed()
+        }
+    }
+
+    /// Create GPU data for a point light at `position`
+    pub fn point(light: &PointLight, position: Vec3) -> Self {
+        Self {
+            position: position.to_array(),
+            light_type: LIGHT_TYPE_POINT,
+            range: light.range.max(0.01),
+            color: light.color,
+            intensity: light.intensity,
+            ..Self::unshadowed()
+        }
+    }
+
+    /// Create GPU data for a spot light at `position` pointing along `direction`
+    pub fn spot(light: &SpotLight, position: Vec3, direction: Vec3) -> Self {
+        let outer = light.outer_angle.clamp(0.0, 90.0);
+        let inner = light.inner_angle.clamp(0.0, outer);
+        Self {
+            position: position.to_array(),
+            light_type: LIGHT_TYPE_SPOT,
+            direction: direction.normalize_or_zero().to_array(),
+            range: light.range.max(0.01),
+            color: light.color,
+            intensity: light.intensity,
+            cos_inner: inner.to_radians().cos(),
+            cos_outer: outer.to_radians().cos(),
+            shadow_bias: light.shadow_bias,
+            shadow_normal_bias: light.shadow_normal_bias,
+            ..Self::unshadowed()
+        }
+    }
+
+    /// Zeroed light data without a shadow map
+    fn unshadowed() -> Self {
+        Self {
+            shadow_index: -1,
+            ..Self::zeroed()
+        }
+    }
+
+    /// Whether a shadow map layer was assigned to the light
+    pub fn has_shadow(&self) -> bool {
+        self.shadow_index >= 0
+    }
+
+    /// Light used when a world contains no light components
+    pub fn fallback() -> Self {
+        Self::directional(&DirectionalLight::default(), FALLBACK_LIGHT_DIRECTION)
+    }
+}
+
+/// Header of the light storage buffer, followed by the light array
+///
+/// Matches the first fields of the `LightBuffer` struct in `pbr.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct LightHeader {
+    /// Number of valid lights in the array
+    pub count: u32,
+    /// Padding to 16 byte alignment
+    pub _padding: [u32; 3],
+    /// Ambient light color (RGB, w unused)
+    pub ambient: [f32; 4],
+}
+
+impl LightHeader {
+    /// Create a header for `count` lights with the given ambient color
+    pub fn new(count: usize, ambient: Vec3) -> Self {
+        Self {
+            count: count.min(MAX_LIGHTS) as u32,
+            _padding: [0; 3],
+            ambient: ambient.extend(0.0).to_array(),
+        }
+    }
+}
+
+/// Size in bytes of the light storage buffer
+pub const LIGHT_BUFFER_SIZE: u64 =
+    (std::mem::size_of::<LightHeader>() + MAX_LIGHTS * std::mem::size_of::<GpuLight>()) as u64;
+
+/// Direction a light with the given transform shines in (its local -Z axis)
+fn light_direction(matrix: &Mat4) -> Vec3 {
+    matrix.transform_vector3(Vec3::NEG_Z)
+}
+
+/// A light that asked for a shadow map this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowCaster {
+    /// Index of the light in [`FrameLights::lights`]
+    pub light_index: usize,
+    /// Distance from the camera covered by the shadow (directional lights)
+    pub distance: f32,
+}
+
+/// Lights gathered for a frame
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameLights {
+    /// Lights in upload order
+    pub lights: Vec<GpuLight>,
+    /// Lights with shadows enabled, in the same order as `lights`
+    pub shadow_casters: Vec<ShadowCaster>,
+}
+
+/// Gather all lights in the world, at most [`MAX_LIGHTS`]
+///
+/// Positions are made relative to `camera_world_position`. Lights with a
+/// `GlobalWorldTransform` use it for precision, otherwise `GlobalTransform` is
+/// used. Directional lights come first so they are never dropped in favor of
+/// local lights. When the world has no lights a single fallback directional
+/// light is returned so unlit scenes stay visible.
+pub fn collect_lights(world: &World, camera_world_position: DVec3) -> FrameLights {
+    let transform_of = |entity: hecs::Entity| -> Option<(Mat4, Vec3)> {
+        if let Ok(world_transform) = world.get::<GlobalWorldTransform>(entity) {
+            let relative = world_transform.to_camera_relative(camera_world_position);
+            return Some((relative.matrix, relative.position()));
+        }
+        world.get::<GlobalTransform>(entity).ok().map(|transform| {
+            let relative = transform.position().as_dvec3() - camera_world_position;
+            (transform.matrix, relative.as_vec3())
+        })
+    };
+
+    let mut lights = Vec::new();
+    let mut shadow_casters = Vec::new();
+
+    for (entity, light) in world.query::<&DirectionalLight>().iter() {
+        if let Some((matrix, _)) = transform_of(entity) {
+            if light.cast_shadows {
+                shadow_casters.push(ShadowCaster {
+                    light_index: lights.len(),
+                    distance: light.shadow_distance.max(1.0),
+                });
+            }
+            lights.push(GpuLight::directional(light, light_direction(&matrix)));
+        }
+    }
+
+    for (entity, light) in world.query::<&PointLight>().iter() {
+        if let Some((_, position)) = transform_of(entity) {
+            lights.push(GpuLight::point(light, position));
+        }
+    }
+
+    for (entity, light) in world.query::<&SpotLight>().iter() {
+        if let Some((matrix, position)) = transform_of(entity) {
+            if light.cast_shadows {
+                shadow_casters.push(ShadowCaster {
+                    light_index: lights.len(),
+                    distance: light.range.max(0.01),
+                });
+            }
+            lights.push(GpuLight::spot(light, position, light_direction(&matrix)));
+        }
+    }
+
+    if lights.is_empty() {
+        lights.push(GpuLight::fallback());
+    } else if lights.len() > MAX_LIGHTS {
+        tracing::warn!(
+            count = lights.len(),
+            max = MAX_LIGHTS,
+            "Too many lights in scene, ignoring the rest"
+        );
+        lights.truncate(MAX_LIGHTS);
+        shadow_casters.retain(|caster| caster.light_index < MAX_LIGHTS);
+    }
+
+    FrameLights {
+        lights,
+        shadow_casters,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    #[test]
+    fn test_gpu_light_layout() {
+        // Must match the WGSL struct: 6 x vec4
+        assert_eq!(std::mem::size_of::<GpuLight>(), 96);
+        assert_eq!(std::mem::size_of::<LightHeader>(), 32);
+    }
+
+    #[test]
+    fn test_spot_light_cone() {
+        let light = SpotLight {
+            inner_angle: 60.0,
+            outer_angle: 45.0,
+            ..Default::default()
+        };
+        let gpu = GpuLight::spot(&light, Vec3::ZERO, Vec3::NEG_Y);
+
+        // Inner angle is clamped to the outer angle
+        assert_eq!(gpu.cos_inner, gpu.cos_outer);
+        assert!((gpu.cos_outer - 45f32.to_radians().cos()).abs() < 1e-6);
+        assert_eq!(gpu.light_type, LIGHT_TYPE_SPOT);
+    }
+
+    #[test]
+    fn test_collect_lights_fallback() {
+        let world = World::new();
+        let frame = collect_lights(&world, DVec3::ZERO);
+        assert_eq!(frame.lights, vec![GpuLight::fallback()]);
+        // The fallback light never casts shadows
+        assert!(frame.shadow_casters.is_empty());
+        assert!(!frame.lights[0].has_shadow());
+    }
+
+    #[test]
+    fn test_collect_lights_camera_relative() {
+        let mut world = World::new();
+
+        // Rotated to point straight down
+        let rotation = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+        world.spawn((
+            DirectionalLight::default(),
+            GlobalTransform::from_matrix(Mat4::from_quat(rotation)),
+        ));
+        world.spawn((
+            PointLight::default(),
+            GlobalTransform::from_matrix(Mat4::from_translation(Vec3::new(10.0, 2.0, 0.0))),
+        ));
+        // Lights without a transform are ignored
+        world.spawn((SpotLight::default(),));
+
+        let frame = collect_lights(&world, DVec3::new(10.0, 0.0, 0.0));
+        let lights = &frame.lights;
+        assert_eq!(lights.len(), 2);
+
+        let directional = lights[0];
+        assert_eq!(directional.light_type, LIGHT_TYPE_DIRECTIONAL);
+        assert!((Vec3::from(directional.direction) - Vec3::NEG_Y).length() < 1e-5);
+
+        let point = lights[1];
+        assert_eq!(point.light_type, LIGHT_TYPE_POINT);
+        assert!((Vec3::from(point.position) - Vec3::new(0.0, 2.0, 0.0)).length() < 1e-5);
+
+        // Only the directional light casts shadows, point lights never do
+        assert_eq!(
+            frame.shadow_casters,
+            vec![ShadowCaster {
+                light_index: 0,
+                distance: DirectionalLight::default().shadow_distance,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_shadow_toggle() {
+        let mut world = World::new();
+        world.spawn((
+            SpotLight {
+                cast_shadows: false,
+                ..Default::default()
+            },
+            GlobalTransform::default(),
+        ));
+
+        let frame = collect_lights(&world, DVec3::ZERO);
+        assert_eq!(frame.lights.len(), 1);
+        assert!(frame.shadow_casters.is_empty());
+        assert_eq!(frame.lights[0].shadow_index, -1);
+    }
+
+    #[test]
+    fn test_light_deserialize_defaults() {
+        let light: PointLight = serde_json::from_str(r#"{"range": 5.0}"#).unwrap();
+        assert_eq!(light.range, 5.0);
+        assert_eq!(light.intensity, PointLight::default().intensity);
+
+        // Lights saved before shadows existed pick up the shadow defaults
+        let light: SpotLight = serde_json::from_str(r#"{"range": 5.0}"#).unwrap();
+        assert!(light.cast_shadows);
+        assert_eq!(light.shadow_bias, SpotLight::default().shadow_bias);
+    }
+}
@@ -1,8 +1,9 @@
 //! Material component for mesh rendering
 //!
-//! Provides material properties for rendering, currently supporting
-//! basic color. Future versions will support textures and more
-//! advanced material properties.
+//! Materials follow the glTF metallic-roughness model: a base color,
+//! metallic and roughness factors, an emissive color and optional albedo,
+//! normal and ORM (occlusion / roughness / metallic) textures referenced by
+//! asset path. Texture values are multiplied with the factors.
 
 use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
 use crate::io::component_registry::ComponentRegistry;
@@ -11,19 +12,59 @@ use serde::{Deserialize, Serialize};
 
 /// Material component defining surface properties
 #[derive(
-    Debug, Clone, Copy, Serialize, Deserialize, engine_derive::Component, engine_derive::EditorUI,
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    engine_derive::Component,
+    engine_derive::EditorUI,
 )]
 #[component(name = "Material")]
+#[serde(default)]
 pub struct Material {
     /// Base color of the material (RGBA)
     #[ui(color_mode = "rgba", tooltip = "Base color of the material")]
     pub color: [f32; 4],
+
+    /// Metalness (0 = dielectric, 1 = metal)
+    #[ui(range = 0.0..1.0, speed = 0.01, tooltip = "Metalness (0 = dielectric, 1 = metal)")]
+    pub metallic: f32,
+
+    /// Perceptual roughness (0 = mirror, 1 = fully rough)
+    #[ui(range = 0.0..1.0, speed = 0.01, tooltip = "Surface roughness")]
+    pub roughness: f32,
+
+    /// Emitted light color (linear RGB, may exceed 1.0)
+    #[ui(tooltip = "Emitted light color")]
+    pub emissive: [f32; 3],
+
+    /// Albedo texture asset path (sRGB), multiplied with the base color
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[ui(tooltip = "Albedo texture asset path")]
+    pub albedo_texture: String,
+
+    /// Tangent space normal map asset path
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[ui(tooltip = "Normal map asset path")]
+    pub normal_texture: String,
+
+    /// Occlusion (R), roughness (G) and metallic (B) texture asset path
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[ui(tooltip = "Occlusion/roughness/metallic texture asset path")]
+    pub orm_texture: String,
 }
 
 impl Default for Material {
     fn default() -> Self {
         Self {
             color: [1.0, 1.0, 1.0, 1.0], // White by default
+            metallic: 0.0,
+            roughness: 0.5,
+            emissive: [0.0, 0.0, 0.0],
+            albedo_texture: String::new(),
+            normal_texture: String::new(),
+            orm_texture: String::new(),
         }
     }
 }
@@ -31,20 +72,78 @@ impl Default for Material {
 impl Material {
     /// Create a new material with the given color
     pub fn new(color: [f32; 4]) -> Self {
-        Self { color }
+        Self {
+            color,
+            ..Default::default()
+        }
     }
 
     /// Create a material from RGB values (alpha = 1.0)
     pub fn from_rgb(r: f32, g: f32, b: f32) -> Self {
-        Self {
-            color: [r, g, b, 1.0],
-        }
+        Self::new([r, g, b, 1.0])
     }
 
     /// Create a material from RGBA values
     pub fn from_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
-        Self {
-            color: [r, g, b, a],
+        Self::new([r, g, b, a])
+    }
+
+    /// Set the metallic and roughness factors
+    pub fn with_metallic_roughness(mut self, metallic: f32, roughness: f32) -> Self {
+        self.metallic = metallic.clamp(0.0, 1.0);
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set the emissive color
+    pub fn with_emissive(mut self, emissive: [f32; 3]) -> Self {
+        self.emissive = emissive;
+        self
+    }
+
+    /// Set the albedo texture path
+    pub fn with_albedo_texture(mut self, path: impl Into<String>) -> Self {
+        self.albedo_texture = path.into();
+        self
+    }
+
+    /// Set the normal map path
+    pub fn with_normal_texture(mut self, path: impl Into<String>) -> Self {
+        self.normal_texture = path.into();
+        self
+    }
+
+    /// Set the occlusion/roughness/metallic texture path
+    pub fn with_orm_texture(mut self, path: impl Into<String>) -> Self {
+        self.orm_texture = path.into();
+        self
+    }
+
+    /// Check if the material references any texture
+    pub fn has_textures(&self) -> bool {
+        !self.albedo_texture.is_empty()
+            || !self.normal_texture.is_empty()
+            || !self.orm_texture.is_empty()
+    }
+
+    /// Key identifying materials that can share GPU resources
+    ///
+    /// The base color is not part of the key, it is passed per instance so
+    /// entities that only differ in color are drawn together.
+    pub fn cache_key(&self) -> MaterialKey {
+        MaterialKey {
+            factors: [
+                self.metallic.to_bits(),
+                self.roughness.to_bits(),
+                self.emissive[0].to_bits(),
+                self.emissive[1].to_bits(),
+                self.emissive[2].to_bits(),
+            ],
+            textures: [
+                self.albedo_texture.clone(),
+                self.normal_texture.clone(),
+                self.orm_texture.clone(),
+            ],
         }
     }
 
@@ -79,28 +178,49 @@ impl Material {
     }
 }
 
+/// Hashable identity of a material's GPU-relevant values
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaterialKey {
+    factors: [u32; 5],
+    textures: [String; 3],
+}
+
 /// Material data for GPU uniform buffer
 ///
-/// This struct is aligned for GPU uniform buffer requirements
+/// This struct matches the MaterialUniform struct in the PBR shader and is
+/// aligned for GPU uniform buffer requirements. The base color is not part of
+/// it, it is passed per instance along with the model matrix.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 pub struct MaterialUniform {
-    /// Base color of the material (RGBA)
-    pub color: [f32; 4],
+    /// Emissive color (RGB, w unused)
+    pub emissive: [f32; 4],
+    /// Metallic factor
+    pub metallic: f32,
+    /// Roughness factor
+    pub roughness: f32,
+    /// Padding to 16 byte alignment
+    pub _padding: [f32; 2],
 }
 
 impl From<Material> for MaterialUniform {
     fn from(material: Material) -> Self {
-        Self {
-            color: material.color,
-        }
+        Self::from(&material)
     }
 }
 
 impl From<&Material> for MaterialUniform {
     fn from(material: &Material) -> Self {
         Self {
-            color: material.color,
+            emissive: [
+                material.emissive[0],
+                material.emissive[1],
+                material.emissive[2],
+                0.0,
+            ],
+            metallic: material.metallic,
+            roughness: material.roughness,
+            _padding: [0.0; 2],
         }
     }
 }
@@ -113,6 +233,35 @@ mod tests {
     fn test_material_default() {
         let mat = Material::default();
         assert_eq!(mat.color, [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(mat.metallic, 0.0);
+        assert_eq!(mat.roughness, 0.5);
+        assert!(!mat.has_textures());
+    }
+
+    #[test]
+    fn test_material_deserialize_color_only() {
+        // Scenes saved before PBR materials only contain a color
+        let mat: Material = serde_json::from_str(r#"{"color": [0.2, 0.3, 0.4, 1.0]}"#).unwrap();
+        assert_eq!(mat.color, [0.2, 0.3, 0.4, 1.0]);
+        assert_eq!(mat.roughness, 0.5);
+        assert!(mat.albedo_texture.is_empty());
+
+        // Empty texture paths are not written out
+        let json = serde_json::to_string(&mat).unwrap();
+        assert!(!json.contains("albedo_texture"));
+    }
+
+    #[test]
+    fn test_material_cache_key() {
+        let a = Material::red().with_albedo_texture("textures/brick.png");
+        let b = Material::red().with_albedo_texture("textures/brick.png");
+        let c = Material::red().with_metallic_roughness(1.0, 0.2);
+        assert_eq!(a.cache_key(), b.cache_key());
+        assert_ne!(a.cache_key(), c.cache_key());
+
+        // Color is per instance, so it doesn't split materials
+        let d = Material::blue().with_albedo_texture("textures/brick.png");
+        assert_eq!(a.cache_key(), d.cache_key());
     }
 
     #[test]
@@ -139,15 +288,19 @@ mod tests {
 
     #[test]
     fn test_material_uniform_conversion() {
-        let mat = Material::from_rgba(0.1, 0.2, 0.3, 0.4);
-        let uniform: MaterialUniform = mat.into();
-        assert_eq!(uniform.color, mat.color);
+        let mat = Material::from_rgba(0.1, 0.2, 0.3, 0.4)
+            .with_metallic_roughness(0.7, 0.3)
+            .with_emissive([1.0, 2.0, 3.0]);
+        let uniform: MaterialUniform = (&mat).into();
+        assert_eq!(uniform.emissive, [1.0, 2.0, 3.0, 0.0]);
+        assert_eq!(uniform.metallic, 0.7);
+        assert_eq!(uniform.roughness, 0.3);
     }
 
     #[test]
     fn test_material_uniform_size() {
         use std::mem;
         // Ensure MaterialUniform is the expected size for GPU
-        assert_eq!(mem::size_of::<MaterialUniform>(), 16); // 4 floats * 4 bytes
+        assert_eq!(mem::size_of::<MaterialUniform>(), 32); // 8 floats * 4 bytes
     }
 }
@@ -4,6 +4,7 @@
 //! for common 3D shapes like cubes, spheres, and planes.
 
 use bytemuck::{Pod, Zeroable};
+use glam::Mat4;
 
 /// Vertex data structure for GPU rendering
 ///
@@ -59,6 +60,48 @@ impl Vertex {
     }
 }
 
+/// Per-instance data for instanced drawing
+///
+/// Bound as a second vertex buffer stepped per instance, following the mesh
+/// vertex attributes at shader locations 3 to 7.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct InstanceData {
+    /// Camera-relative model matrix (columns)
+    pub model: [[f32; 4]; 4],
+    /// Instance color (RGBA)
+    pub color: [f32; 4],
+}
+
+impl InstanceData {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        // Model matrix columns
+        3 => Float32x4,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        // Color
+        7 => Float32x4,
+    ];
+
+    /// Create instance data from a model matrix and color
+    pub fn new(model: Mat4, color: [f32; 4]) -> Self {
+        Self {
+            model: model.to_cols_array_2d(),
+            color,
+        }
+    }
+
+    /// Get the instance attribute layout for wgpu
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
 /// Mesh component containing vertex and index data
 #[derive(Debug, Clone)]
 pub struct Mesh {
@@ -264,6 +307,16 @@ mod tests {
         assert_eq!(mem::size_of::<Vertex>(), 32); // 8 floats * 4 bytes
     }
 
+    #[test]
+    fn test_instance_data_layout() {
+        // Matrix plus color, matching the five Float32x4 attributes
+        assert_eq!(std::mem::size_of::<InstanceData>(), 80);
+        let attributes = InstanceData::desc().attributes;
+        assert_eq!(attributes.len(), 5);
+        assert_eq!(attributes[0].shader_location, 3);
+        assert_eq!(attributes[4].offset, 64);
+    }
+
     #[test]
     fn test_mesh_cube_vertices() {
         let cube = Mesh::cube(1.0);
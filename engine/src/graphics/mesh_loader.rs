@@ -1,11 +1,22 @@
 //! Mesh file loading utilities
 //!
-//! Supports loading meshes from various file formats like OBJ and FBX.
+//! Supports loading meshes from OBJ and glTF 2.0 (`.gltf` / `.glb`) files.
+//!
+//! Single meshes are loaded with [`load_mesh_from_file`]. Files containing
+//! several meshes can address an individual sub-mesh by appending `#<index>`
+//! to the path (e.g. `models/robot.glb#3`), which is also the form used for
+//! the [`MeshId`]s of entities spawned from an [`ImportedScene`]. Caches keyed
+//! by mesh id should use [`load_meshes_from_file`], which imports such a file
+//! once and returns all of its sub-meshes.
 
+use crate::core::entity::{GlobalTransform, Name, Parent, Transform, World};
+use crate::graphics::material::Material;
 use crate::graphics::mesh::{Mesh, Vertex};
-use glam::Vec3;
+use crate::graphics::renderer::MeshId;
+use glam::{Quat, Vec3};
+use std::collections::HashSet;
 use std::path::Path;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// Errors that can occur during mesh loading
 #[derive(Debug, thiserror::Error)]
@@ -19,43 +30,547 @@ pub enum MeshLoadError {
     #[error("Unsupported file format: {0}")]
     UnsupportedFormat(String),
 
+    #[error("glTF loading error: {0}")]
+    Gltf(#[from] gltf::Error),
+
     #[error("No mesh data found in file")]
     NoMeshData,
+
+    #[error("Sub-mesh {0} not found in file")]
+    SubMeshNotFound(usize),
 }
 
-/// Load a mesh from a file
-pub fn load_mesh_from_file(path: &Path) -> Result<Mesh, MeshLoadError> {
-    let extension = path
-        .extension()
+/// File extensions that [`load_mesh_from_file`] understands
+const SUPPORTED_EXTENSIONS: &[&str] = &["obj", "gltf", "glb"];
+
+/// Check whether a mesh id refers to a file rather than a library mesh
+pub fn is_mesh_file_reference(mesh_id: &str) -> bool {
+    if mesh_id.contains('/') || mesh_id.contains('\\') {
+        return true;
+    }
+
+    let (path, _) = split_sub_mesh_reference(mesh_id);
+    file_extension(Path::new(path))
+        .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.as_str()))
+        .unwrap_or(false)
+}
+
+/// Split a `path#index` reference into the file path and optional sub-mesh index
+fn split_sub_mesh_reference(reference: &str) -> (&str, Option<usize>) {
+    match reference.rsplit_once('#') {
+        Some((path, index)) => match index.parse::<usize>() {
+            Ok(index) => (path, Some(index)),
+            Err(_) => (reference, None),
+        },
+        None => (reference, None),
+    }
+}
+
+fn file_extension(path: &Path) -> Option<String> {
+    path.extension()
         .and_then(|ext| ext.to_str())
         .map(|s| s.to_lowercase())
-        .unwrap_or_default();
+}
+
+/// Load a mesh from a file
+///
+/// The path may carry a `#<index>` suffix to select a sub-mesh of a file
+/// containing several meshes. Without a suffix the first mesh is loaded.
+pub fn load_mesh_from_file(path: &Path) -> Result<Mesh, MeshLoadError> {
+    let reference = path.to_string_lossy();
+    let (file, sub_mesh) = split_sub_mesh_reference(&reference);
+    let file = Path::new(file);
+    let extension = file_extension(file).unwrap_or_default();
 
     match extension.as_str() {
-        "obj" => load_obj(path),
+        "obj" => load_obj(file, sub_mesh.unwrap_or(0)),
+        "gltf" | "glb" => {
+            let index = sub_mesh.unwrap_or(0);
+            load_gltf_scene(file)?
+                .meshes
+                .into_iter()
+                .nth(index)
+                .map(|imported| imported.mesh)
+                .ok_or(MeshLoadError::SubMeshNotFound(index))
+        }
         ext => Err(MeshLoadError::UnsupportedFormat(ext.to_string())),
     }
 }
 
-/// Load a mesh from an OBJ file
-fn load_obj(path: &Path) -> Result<Mesh, MeshLoadError> {
+/// Load every mesh of the file a mesh reference points to
+///
+/// Returns `(mesh id, mesh)` pairs for all sub-meshes in `path#index` form,
+/// plus the plain file path for the first mesh and the reference itself if it
+/// is spelled differently (e.g. `path#01`). Fails like
+/// [`load_mesh_from_file`] if the referenced sub-mesh does not exist.
+pub fn load_meshes_from_file(path: &Path) -> Result<Vec<(String, Mesh)>, MeshLoadError> {
+    let reference = path.to_string_lossy();
+    let (file, sub_mesh) = split_sub_mesh_reference(&reference);
+    let meshes = load_scene_from_file(Path::new(file))?.meshes;
+
+    let index = sub_mesh.unwrap_or(0);
+    if index >= meshes.len() {
+        return Err(MeshLoadError::SubMeshNotFound(index));
+    }
+
+    let mut result = Vec::with_capacity(meshes.len() + 2);
+    result.push((file.to_string(), meshes[0].mesh.clone()));
+    if reference != file && reference != format!("{file}#{index}") {
+        result.push((reference.to_string(), meshes[index].mesh.clone()));
+    }
+    result.extend(
+        meshes
+            .into_iter()
+            .map(|imported| (imported.id, imported.mesh)),
+    );
+    Ok(result)
+}
+
+/// A mesh extracted from a multi-mesh file together with its material
+#[derive(Debug, Clone)]
+pub struct ImportedMesh {
+    /// Mesh id in `path#index` form, resolvable by the renderer
+    pub id: String,
+    /// Human readable name
+    pub name: String,
+    /// Vertex and index data
+    pub mesh: Mesh,
+    /// Material assigned to the mesh
+    pub material: Material,
+}
+
+/// A node of an imported scene graph
+#[derive(Debug, Clone)]
+pub struct ImportedNode {
+    /// Node name
+    pub name: String,
+    /// Local transform relative to the parent node
+    pub transform: Transform,
+    /// Indices into [`ImportedScene::meshes`] drawn by this node
+    pub meshes: Vec<usize>,
+    /// Indices into [`ImportedScene::nodes`] of the child nodes
+    pub children: Vec<usize>,
+}
+
+/// Scene graph loaded from a model file, ready to be spawned into a [`World`]
+#[derive(Debug, Clone, Default)]
+pub struct ImportedScene {
+    /// Name of the scene, usually the file stem
+    pub name: String,
+    /// All meshes referenced by the nodes
+    pub meshes: Vec<ImportedMesh>,
+    /// All nodes of the scene graph
+    pub nodes: Vec<ImportedNode>,
+    /// Indices of the top level nodes
+    pub roots: Vec<usize>,
+}
+
+impl ImportedScene {
+    /// Spawn the scene graph into the world
+    ///
+    /// A root entity named after the scene is created and every node becomes a
+    /// child entity with `Name`, `Transform` and `Parent`. Nodes drawing a single
+    /// mesh get `MeshId` and `Material` directly; nodes with several meshes get
+    /// one child entity per mesh. Nodes reached a second time, e.g. through a
+    /// cycle in a malformed file, are skipped. Returns the root entity.
+    pub fn spawn(&self, world: &mut World) -> hecs::Entity {
+        let root = world.spawn((
+            Name::new(self.name.clone()),
+            Transform::default(),
+            GlobalTransform::default(),
+        ));
+
+        let mut visited = HashSet::new();
+        for &node_index in &self.roots {
+            self.spawn_node(world, node_index, root, &mut visited);
+        }
+
+        debug!(
+            scene = %self.name,
+            nodes = self.nodes.len(),
+            meshes = self.meshes.len(),
+            "Spawned imported scene"
+        );
+
+        root
+    }
+
+    fn spawn_node(
+        &self,
+        world: &mut World,
+        node_index: usize,
+        parent: hecs::Entity,
+        visited: &mut HashSet<usize>,
+    ) {
+        let Some(node) = self.nodes.get(node_index) else {
+            warn!(node_index, "Imported scene references missing node");
+            return;
+        };
+        if !visited.insert(node_index) {
+            warn!(
+                node_index,
+                "Imported scene references node more than once, skipping"
+            );
+            return;
+        }
+
+        let entity = world.spawn((
+            Name::new(node.name.clone()),
+            node.transform,
+            GlobalTransform::default(),
+            Parent(parent),
+        ));
+
+        match node.meshes.as_slice() {
+            [] => {}
+            [mesh_index] => {
+                if let Some(imported) = self.meshes.get(*mesh_index) {
+                    let _ = world.insert_one(entity, MeshId(imported.id.clone()));
+                    let _ = world.insert_one(entity, imported.material.clone());
+                }
+            }
+            mesh_indices => {
+                for &mesh_index in mesh_indices {
+                    if let Some(imported) = self.meshes.get(mesh_index) {
+                        world.spawn((
+                            Name::new(imported.name.clone()),
+                            Transform::default(),
+                            GlobalTransform::default(),
+                            Parent(entity),
+                            MeshId(imported.id.clone()),
+                            imported.material.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        for &child in &node.children {
+            self.spawn_node(world, child, entity, visited);
+        }
+    }
+}
+
+/// Load a model file as a scene graph
+pub fn load_scene_from_file(path: &Path) -> Result<ImportedScene, MeshLoadError> {
+    match file_extension(path).unwrap_or_default().as_str() {
+        "obj" => load_obj_scene(path),
+        "gltf" | "glb" => load_gltf_scene(path),
+        ext => Err(MeshLoadError::UnsupportedFormat(ext.to_string())),
+    }
+}
+
+/// Load a glTF 2.0 file (`.gltf` with embedded or external buffers, or `.glb`)
+///
+/// Every primitive becomes one [`ImportedMesh`]; the node graph of the default
+/// scene (or the first scene) is preserved.
+fn load_gltf_scene(path: &Path) -> Result<ImportedScene, MeshLoadError> {
+    info!("Loading glTF file: {:?}", path);
+
+    let (document, buffers, _images) = gltf::import(path)?;
+
+    let mut scene = ImportedScene {
+        name: path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("glTF Scene")
+            .to_string(),
+        ..Default::default()
+    };
+
+    // Flatten mesh primitives, remembering which flat indices belong to each glTF mesh
+    let mut mesh_primitives: Vec<Vec<usize>> = Vec::new();
+    for gltf_mesh in document.meshes() {
+        let mesh_name = gltf_mesh
+            .name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Mesh {}", gltf_mesh.index()));
+        let mut indices = Vec::new();
+
+        for primitive in gltf_mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                warn!(
+                    mesh = %mesh_name,
+                    mode = ?primitive.mode(),
+                    "Skipping non-triangle glTF primitive"
+                );
+                continue;
+            }
+
+            let reader = primitive
+                .reader(|buffer| buffers.get(buffer.index()).map(|data| data.0.as_slice()));
+            let Some(positions) = reader.read_positions() else {
+                warn!(mesh = %mesh_name, "Skipping glTF primitive without positions");
+                continue;
+            };
+            let positions: Vec<[f32; 3]> = positions.collect();
+            let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(|n| n.collect());
+            let uvs: Vec<[f32; 2]> = reader
+                .read_tex_coords(0)
+                .map(|t| t.into_f32().collect())
+                .unwrap_or_default();
+
+            let mut vertices: Vec<Vertex> = positions
+                .iter()
+                .enumerate()
+                .map(|(i, position)| Vertex {
+                    position: *position,
+                    normal: normals
+                        .as_ref()
+                        .and_then(|n| n.get(i).copied())
+                        .unwrap_or([0.0, 1.0, 0.0]),
+                    uv: uvs.get(i).copied().unwrap_or([0.0, 0.0]),
+                })
+                .collect();
+
+            let mesh_indices: Vec<u32> = match reader.read_indices() {
+                Some(read) => read.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+
+            if normals.is_none() {
+                calculate_normals(&mut vertices, &mesh_indices);
+            }
+
+            let material = gltf_material_to_material(&primitive.material(), path);
+
+            let flat_index = scene.meshes.len();
+            let name = if gltf_mesh.primitives().len() > 1 {
+                format!("{mesh_name}.{}", primitive.index())
+            } else {
+                mesh_name.clone()
+            };
+
+            debug!(
+                "Loaded glTF primitive '{}' with {} vertices and {} indices",
+                name,
+                vertices.len(),
+                mesh_indices.len()
+            );
+
+            scene.meshes.push(ImportedMesh {
+                id: format!("{}#{}", path.display(), flat_index),
+                name,
+                mesh: Mesh {
+                    vertices,
+                    indices: mesh_indices,
+                },
+                material,
+            });
+            indices.push(flat_index);
+        }
+
+        mesh_primitives.push(indices);
+    }
+
+    if scene.meshes.is_empty() {
+        return Err(MeshLoadError::NoMeshData);
+    }
+
+    scene.nodes = document
+        .nodes()
+        .map(|node| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            ImportedNode {
+                name: node
+                    .name()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("Node {}", node.index())),
+                transform: Transform {
+                    position: Vec3::from(translation),
+                    rotation: Quat::from_array(rotation),
+                    scale: Vec3::from(scale),
+                },
+                meshes: node
+                    .mesh()
+                    .and_then(|mesh| mesh_primitives.get(mesh.index()).cloned())
+                    .unwrap_or_default(),
+                children: node.children().map(|child| child.index()).collect(),
+            }
+        })
+        .collect();
+
+    scene.roots = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(gltf_scene) => gltf_scene.nodes().map(|node| node.index()).collect(),
+        None => {
+            // No scene defined: treat every node that is nobody's child as a root
+            let mut is_child = vec![false; scene.nodes.len()];
+            for node in &scene.nodes {
+                for &child in &node.children {
+                    if let Some(flag) = is_child.get_mut(child) {
+                        *flag = true;
+                    }
+                }
+            }
+            (0..scene.nodes.len()).filter(|&i| !is_child[i]).collect()
+        }
+    };
+
+    Ok(scene)
+}
+
+/// Load a single model from an OBJ file
+///
+/// Index 0 is the first model, which is what a plain `.obj` path resolves to.
+fn load_obj(path: &Path, index: usize) -> Result<Mesh, MeshLoadError> {
     info!("Loading OBJ file: {:?}", path);
 
-    let (models, _materials) = tobj::load_obj(
-        path,
-        &tobj::LoadOptions {
-            triangulate: true,
-            single_index: true,
-            ..Default::default()
-        },
-    )?;
+    let (models, _materials) = tobj::load_obj(path, &obj_load_options())?;
 
     if models.is_empty() {
         return Err(MeshLoadError::NoMeshData);
     }
 
-    // For now, just load the first model
-    let model = &models[0];
+    let model = models
+        .get(index)
+        .ok_or(MeshLoadError::SubMeshNotFound(index))?;
+
+    Ok(obj_model_to_mesh(model))
+}
+
+fn obj_load_options() -> tobj::LoadOptions {
+    tobj::LoadOptions {
+        triangulate: true,
+        single_index: true,
+        ..Default::default()
+    }
+}
+
+/// Map a glTF material to the engine material
+///
+/// Factors are copied as-is. Textures are only referenced when they are
+/// external files; embedded images are skipped with a warning. The
+/// metallic-roughness texture is used as the ORM texture since both store
+/// roughness in green and metallic in blue.
+fn gltf_material_to_material(material: &gltf::Material, path: &Path) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+
+    let mut result = Material::new(pbr.base_color_factor())
+        .with_metallic_roughness(pbr.metallic_factor(), pbr.roughness_factor())
+        .with_emissive(material.emissive_factor());
+
+    if let Some(info) = pbr.base_color_texture() {
+        result.albedo_texture = gltf_texture_path(&info.texture(), path).unwrap_or_default();
+    }
+    if let Some(normal) = material.normal_texture() {
+        result.normal_texture = gltf_texture_path(&normal.texture(), path).unwrap_or_default();
+    }
+    if let Some(info) = pbr.metallic_roughness_texture() {
+        result.orm_texture = gltf_texture_path(&info.texture(), path).unwrap_or_default();
+    }
+
+    result
+}
+
+/// Resolve the file path of a glTF texture relative to the glTF file
+fn gltf_texture_path(texture: &gltf::Texture, path: &Path) -> Option<String> {
+    match texture.source().source() {
+        gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+            Some(resolve_relative(path, uri))
+        }
+        _ => {
+            warn!(
+                texture = texture.index(),
+                "Embedded glTF textures are not supported, using default texture"
+            );
+            None
+        }
+    }
+}
+
+/// Resolve a path referenced from a model file relative to that file's directory
+fn resolve_relative(model_path: &Path, reference: &str) -> String {
+    model_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(reference)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Load every model/group of an OBJ file as a sub-mesh, with MTL materials
+///
+/// Each group becomes a top level node so the file spawns as a parent entity
+/// with one child per group. MTL diffuse color and dissolve map to the
+/// material color and alpha; groups without a material are white.
+fn load_obj_scene(path: &Path) -> Result<ImportedScene, MeshLoadError> {
+    info!("Loading OBJ scene: {:?}", path);
+
+    let (models, materials) = tobj::load_obj(path, &obj_load_options())?;
+
+    if models.is_empty() {
+        return Err(MeshLoadError::NoMeshData);
+    }
+
+    let materials = materials.unwrap_or_else(|e| {
+        warn!("Failed to load MTL for {:?}: {}", path, e);
+        Vec::new()
+    });
+
+    let mut scene = ImportedScene {
+        name: path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("OBJ Scene")
+            .to_string(),
+        ..Default::default()
+    };
+
+    for (index, model) in models.iter().enumerate() {
+        let material = model
+            .mesh
+            .material_id
+            .and_then(|id| materials.get(id))
+            .map(|material| obj_material_to_material(material, path))
+            .unwrap_or_default();
+
+        let name = if model.name.is_empty() {
+            format!("Group {index}")
+        } else {
+            model.name.clone()
+        };
+
+        scene.meshes.push(ImportedMesh {
+            id: format!("{}#{}", path.display(), index),
+            name: name.clone(),
+            mesh: obj_model_to_mesh(model),
+            material,
+        });
+        scene.nodes.push(ImportedNode {
+            name,
+            transform: Transform::default(),
+            meshes: vec![index],
+            children: Vec::new(),
+        });
+        scene.roots.push(index);
+    }
+
+    Ok(scene)
+}
+
+/// Map an MTL material to the engine material
+///
+/// Diffuse color and dissolve become color and alpha; diffuse and bump
+/// (`map_Bump`, used as a normal map) textures are resolved relative to the OBJ file.
+fn obj_material_to_material(material: &tobj::Material, path: &Path) -> Material {
+    let [r, g, b] = material.diffuse.unwrap_or([1.0, 1.0, 1.0]);
+    let mut result = Material::from_rgba(r, g, b, material.dissolve.unwrap_or(1.0));
+
+    if let Some(texture) = &material.diffuse_texture {
+        result.albedo_texture = resolve_relative(path, texture);
+    }
+    if let Some(texture) = &material.normal_texture {
+        result.normal_texture = resolve_relative(path, texture);
+    }
+
+    result
+}
+
+/// Convert a tobj model to our mesh format
+fn obj_model_to_mesh(model: &tobj::Model) -> Mesh {
     let mesh = &model.mesh;
 
     debug!(
@@ -109,10 +624,10 @@ fn load_obj(path: &Path) -> Result<Mesh, MeshLoadError> {
         calculate_normals(&mut vertices, &mesh.indices);
     }
 
-    Ok(Mesh {
+    Mesh {
         vertices,
         indices: mesh.indices.clone(),
-    })
+    }
 }
 
 /// Calculate normals for vertices based on face geometry
@@ -168,4 +683,207 @@ mod tests {
         let result = load_mesh_from_file(Path::new("test.fbx"));
         assert!(matches!(result, Err(MeshLoadError::UnsupportedFormat(_))));
     }
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/gltf")
+            .join(name)
+    }
+
+    fn find_by_name(world: &World, name: &str) -> hecs::Entity {
+        world
+            .query::<&Name>()
+            .iter()
+            .find(|(_, n)| n.0 == name)
+            .map(|(entity, _)| entity)
+            .unwrap_or_else(|| panic!("entity '{name}' not found"))
+    }
+
+    fn parent_of(world: &World, entity: hecs::Entity) -> Option<hecs::Entity> {
+        world.get::<Parent>(entity).ok().map(|parent| parent.0)
+    }
+
+    fn assert_fixture_hierarchy(file: &str) {
+        let scene = load_scene_from_file(&fixture(file)).expect("fixture should load");
+
+        // One primitive in TriangleMesh, two in TwoPartMesh
+        assert_eq!(scene.meshes.len(), 3);
+        assert_eq!(scene.nodes.len(), 5);
+        assert_eq!(scene.roots, vec![0]);
+
+        let triangle = &scene.meshes[0].mesh;
+        assert_eq!(triangle.vertices.len(), 3);
+        assert_eq!(triangle.indices, vec![0, 1, 2]);
+        assert_eq!(triangle.vertices[1].position, [1.0, 0.0, 0.0]);
+        assert_eq!(triangle.vertices[2].uv, [0.0, 1.0]);
+        assert_eq!(triangle.vertices[0].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(scene.meshes[0].material.color, [1.0, 0.0, 0.0, 1.0]);
+        // glTF defaults: fully metallic and rough, no textures
+        assert_eq!(scene.meshes[0].material.metallic, 1.0);
+        assert_eq!(scene.meshes[0].material.roughness, 1.0);
+        assert!(!scene.meshes[0].material.has_textures());
+
+        // Second primitive has no indices or normals
+        let generated = &scene.meshes[2];
+        assert_eq!(generated.mesh.indices, vec![0, 1, 2]);
+        assert!((Vec3::from(generated.mesh.vertices[0].normal).z - 1.0).abs() < 1e-5);
+        assert_eq!(generated.material.color, [0.0, 0.0, 1.0, 0.5]);
+
+        let mut world = World::new();
+        let root = scene.spawn(&mut world);
+
+        let root_node = find_by_name(&world, "Root");
+        assert_eq!(parent_of(&world, root_node), Some(root));
+        assert_eq!(
+            world.get::<Transform>(root_node).unwrap().position,
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+
+        let triangle_node = find_by_name(&world, "Triangle");
+        assert_eq!(parent_of(&world, triangle_node), Some(root_node));
+        assert_eq!(
+            world.get::<Transform>(triangle_node).unwrap().scale,
+            Vec3::splat(2.0)
+        );
+        let mesh_id = world.get::<MeshId>(triangle_node).unwrap().0.clone();
+        assert!(mesh_id.ends_with("#0"));
+        assert!(is_mesh_file_reference(&mesh_id));
+
+        let leaf = find_by_name(&world, "Leaf");
+        let pivot = find_by_name(&world, "Pivot");
+        assert_eq!(parent_of(&world, leaf), Some(pivot));
+        assert_eq!(parent_of(&world, pivot), Some(root_node));
+
+        // Multi-primitive nodes get one child per primitive
+        let two_part = find_by_name(&world, "TwoPart");
+        assert!(world.get::<MeshId>(two_part).is_err());
+        let parts = world
+            .query::<(&Parent, &MeshId)>()
+            .iter()
+            .filter(|(_, (parent, _))| parent.0 == two_part)
+            .count();
+        assert_eq!(parts, 2);
+    }
+
+    #[test]
+    fn test_gltf_external_buffer() {
+        assert_fixture_hierarchy("triangle_hierarchy_external.gltf");
+    }
+
+    #[test]
+    fn test_gltf_embedded_buffer() {
+        assert_fixture_hierarchy("triangle_hierarchy_embedded.gltf");
+    }
+
+    #[test]
+    fn test_glb() {
+        assert_fixture_hierarchy("triangle_hierarchy.glb");
+    }
+
+    #[test]
+    fn test_gltf_sub_mesh_reference() {
+        let path = format!("{}#2", fixture("triangle_hierarchy.glb").display());
+        let mesh = load_mesh_from_file(Path::new(&path)).unwrap();
+        assert_eq!(mesh.vertices.len(), 3);
+
+        let path = format!("{}#7", fixture("triangle_hierarchy.glb").display());
+        let result = load_mesh_from_file(Path::new(&path));
+        assert!(matches!(result, Err(MeshLoadError::SubMeshNotFound(7))));
+    }
+
+    #[test]
+    fn test_load_all_sub_meshes_at_once() {
+        let file = fixture("triangle_hierarchy.glb").display().to_string();
+        let meshes = load_meshes_from_file(Path::new(&format!("{file}#1"))).unwrap();
+
+        let ids: Vec<&str> = meshes.iter().map(|(id, _)| id.as_str()).collect();
+        let expected = [
+            file.clone(),
+            format!("{file}#0"),
+            format!("{file}#1"),
+            format!("{file}#2"),
+        ];
+        assert_eq!(ids, expected);
+        assert_eq!(meshes[0].1.indices, meshes[1].1.indices);
+
+        let result = load_meshes_from_file(Path::new(&format!("{file}#7")));
+        assert!(matches!(result, Err(MeshLoadError::SubMeshNotFound(7))));
+    }
+
+    #[test]
+    fn test_obj_groups_with_materials() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/obj/two_parts.obj");
+        let scene = load_scene_from_file(&path).expect("fixture should load");
+
+        assert_eq!(scene.name, "two_parts");
+        assert_eq!(scene.meshes.len(), 2);
+        assert_eq!(scene.roots, vec![0, 1]);
+
+        let body = &scene.meshes[0];
+        assert_eq!(body.name, "Body");
+        assert_eq!(body.material.color, [0.8, 0.1, 0.1, 1.0]);
+        // Texture maps resolve relative to the OBJ file
+        let fixture_dir = path.parent().unwrap();
+        assert_eq!(
+            Path::new(&body.material.albedo_texture),
+            fixture_dir.join("textures/paint_albedo.png")
+        );
+        assert_eq!(
+            Path::new(&body.material.normal_texture),
+            fixture_dir.join("textures/paint_normal.png")
+        );
+        // Quad is triangulated into two triangles
+        assert_eq!(body.mesh.indices.len(), 6);
+
+        let visor = &scene.meshes[1];
+        assert_eq!(visor.name, "Visor");
+        assert_eq!(visor.material.color, [0.1, 0.2, 0.9, 0.25]);
+        assert!(!visor.material.has_textures());
+        assert_eq!(visor.mesh.indices.len(), 3);
+
+        let mut world = World::new();
+        let root = scene.spawn(&mut world);
+        for name in ["Body", "Visor"] {
+            let entity = find_by_name(&world, name);
+            assert_eq!(parent_of(&world, entity), Some(root));
+            assert!(world.get::<MeshId>(entity).is_ok());
+        }
+
+        // Sub-mesh references resolve to the matching group
+        let visor_mesh = load_mesh_from_file(Path::new(&visor.id)).unwrap();
+        assert_eq!(visor_mesh.indices.len(), 3);
+    }
+
+    #[test]
+    fn test_spawn_skips_node_cycles() {
+        let node = |name: &str, children: Vec<usize>| ImportedNode {
+            name: name.to_string(),
+            transform: Transform::default(),
+            meshes: Vec::new(),
+            children,
+        };
+        let scene = ImportedScene {
+            name: "cyclic".to_string(),
+            meshes: Vec::new(),
+            nodes: vec![node("A", vec![1]), node("B", vec![0, 1])],
+            roots: vec![0],
+        };
+
+        let mut world = World::new();
+        let root = scene.spawn(&mut world);
+
+        let a = find_by_name(&world, "A");
+        let b = find_by_name(&world, "B");
+        assert_eq!(parent_of(&world, a), Some(root));
+        assert_eq!(parent_of(&world, b), Some(a));
+        assert_eq!(world.query::<&Name>().iter().count(), 3);
+    }
+
+    #[test]
+    fn test_is_mesh_file_reference() {
+        assert!(is_mesh_file_reference("suzanne.obj"));
+        assert!(is_mesh_file_reference("robot.glb#2"));
+        assert!(is_mesh_file_reference("assets/models/tree"));
+        assert!(!is_mesh_file_reference("cube"));
+    }
 }
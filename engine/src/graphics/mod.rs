@@ -4,34 +4,51 @@
 //! render pipelines, and the main renderer.
 
 pub mod asset_manager;
+pub mod capture;
 pub mod context;
 pub mod culling;
 pub mod frustum_culling_system;
+pub mod light;
 pub mod material;
 pub mod mesh;
 pub mod mesh_library;
 pub mod mesh_loader;
+pub mod picking;
 pub mod pipeline;
+pub mod post_process;
 pub mod render_target;
 pub mod renderer;
 pub mod safe_scissor;
+pub mod shadow;
+pub mod skybox;
+pub mod texture;
 pub mod uniform;
 
 // Re-export commonly used types
 pub use asset_manager::{AssetManager, AssetValidationReport, AssetValidationSummary};
+pub use capture::{CaptureError, CapturedImage, ImageDiff};
 pub use context::RenderContext;
 pub use culling::{Frustum, Visibility, AABB};
 pub use frustum_culling_system::{
     frustum_culling_system, frustum_culling_system_with_aabb_components, initialize_mesh_aabbs,
 };
-pub use material::{Material, MaterialUniform};
-pub use mesh::{Mesh, Vertex};
+pub use light::{DirectionalLight, GpuLight, PointLight, SpotLight, MAX_LIGHTS};
+pub use material::{Material, MaterialKey, MaterialUniform};
+pub use mesh::{InstanceData, Mesh, Vertex};
 pub use mesh_library::MeshLibrary;
-pub use mesh_loader::{load_mesh_from_file, MeshLoadError};
+pub use mesh_loader::{
+    load_mesh_from_file, load_meshes_from_file, load_scene_from_file, ImportedMesh, ImportedNode,
+    ImportedScene, MeshLoadError,
+};
+pub use picking::{ray_triangle_intersection, PickHit, PickMesh};
 pub use pipeline::{DepthTexture, RenderPipeline};
+pub use post_process::{PostProcessSettings, PostProcessor, Tonemapping, HDR_FORMAT};
 pub use render_target::RenderTarget;
 pub use renderer::{MeshId, Renderer};
 pub use safe_scissor::{
     safe_set_scissor_rect, viewport_to_render_target_scissor, RenderTargetInfo,
 };
+pub use shadow::{ShadowMaps, CASCADE_COUNT, MAX_SHADOW_MAPS, SHADOW_MAP_SIZE};
+pub use skybox::{Environment, Skybox, SkyboxError};
+pub use texture::{Texture, TextureCache, TextureError, TextureKind};
 pub use uniform::{CameraUniform, ObjectUniform, UniformBuffer};
@@ -0,0 +1,395 @@
+//! Entity picking for the editor viewport
+//!
+//! Rays through a viewport pixel are tested against the bounding box of
+//! each mesh first and then against its triangles, which the renderer keeps
+//! on the CPU next to the GPU buffers. The optional ID pass renders pick IDs
+//! into an integer target instead, so the result matches the rasterized
+//! image exactly at the cost of a GPU round trip.
+
+use crate::graphics::{
+    capture::{self, CaptureError},
+    culling::AABB,
+    mesh::Mesh,
+    pipeline::{DepthTexture, RenderPipeline},
+    uniform::{CameraUniform, UniformBuffer},
+};
+use glam::{Mat4, Vec2, Vec3};
+
+/// Format of the entity ID target
+pub const PICK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+/// Pick IDs travel through the f32 instance color, which is exact up to 2^24
+const MAX_PICK_ID: usize = 1 << 24;
+
+/// Entity hit by a pick ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickHit {
+    /// The entity that was hit
+    pub entity: hecs::Entity,
+    /// Distance from the ray origin to the hit point
+    pub distance: f32,
+}
+
+/// Distance along a ray to a triangle, using the Möller–Trumbore test
+///
+/// Triangles are hit from both sides. The distance is in units of
+/// `direction`, so it is only a length when the direction is normalized.
+pub fn ray_triangle_intersection(
+    origin: Vec3,
+    direction: Vec3,
+    triangle: [Vec3; 3],
+) -> Option<f32> {
+    let [a, b, c] = triangle;
+    let edge1 = b - a;
+    let edge2 = c - a;
+
+    let p = direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < f32::EPSILON {
+        // Ray parallel to the triangle plane
+        return None;
+    }
+    let inverse = 1.0 / determinant;
+
+    let to_origin = origin - a;
+    let u = to_origin.dot(p) * inverse;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = to_origin.cross(edge1);
+    let v = direction.dot(q) * inverse;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let distance = edge2.dot(q) * inverse;
+    (distance >= 0.0).then_some(distance)
+}
+
+/// Triangles of a mesh kept on the CPU for ray picking
+#[derive(Debug, Clone)]
+pub struct PickMesh {
+    positions: Vec<Vec3>,
+    indices: Vec<u32>,
+    aabb: AABB,
+}
+
+impl PickMesh {
+    /// Copy the vertex positions and indices of a mesh
+    pub fn from_mesh(mesh: &Mesh) -> Self {
+        Self {
+            positions: mesh
+                .vertices
+                .iter()
+                .map(|vertex| Vec3::from_array(vertex.position))
+                .collect(),
+            indices: mesh.indices.clone(),
+            aabb: mesh.calculate_aabb(),
+        }
+    }
+
+    /// Bounding box of the mesh in model space
+    pub fn aabb(&self) -> AABB {
+        self.aabb
+    }
+
+    /// Distance to the closest triangle hit by a model space ray
+    ///
+    /// Hits further than `max_distance` are ignored, which lets the bounding
+    /// box reject the mesh early when something closer was already hit.
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<f32> {
+        let entry = self.aabb.ray_intersection(origin, direction)?;
+        if entry > max_distance {
+            return None;
+        }
+
+        self.indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let corner = |i: usize| self.positions.get(triangle[i] as usize).copied();
+                let triangle = [corner(0)?, corner(1)?, corner(2)?];
+                ray_triangle_intersection(origin, direction, triangle)
+            })
+            .filter(|distance| *distance <= max_distance)
+            .min_by(|a, b| a.total_cmp(b))
+    }
+}
+
+/// Check if a camera-relative point projects into a rectangle in NDC
+pub(crate) fn projects_into_rect(view_proj: Mat4, point: Vec3, min: Vec2, max: Vec2) -> bool {
+    let clip = view_proj * point.extend(1.0);
+    if clip.w <= 1e-6 {
+        return false;
+    }
+    let ndc = clip.truncate().truncate() / clip.w;
+    ndc.cmpge(min).all() && ndc.cmple(max).all()
+}
+
+/// Encode the index of a render item as the instance color of the ID pass
+///
+/// IDs start at 1 so that the cleared target reads as "nothing".
+pub(crate) fn encode_pick_id(index: usize) -> [f32; 4] {
+    [(index + 1).min(MAX_PICK_ID) as f32, 0.0, 0.0, 0.0]
+}
+
+/// Decode a value read from the ID target back into a render item index
+pub(crate) fn decode_pick_id(id: u32) -> Option<usize> {
+    (id as usize).checked_sub(1)
+}
+
+/// Offscreen targets of the ID pass
+struct IdTargets {
+    size: (u32, u32),
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    depth: DepthTexture,
+}
+
+impl IdTargets {
+    fn new(device: &wgpu::Device, size: (u32, u32)) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Pick ID Texture"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: PICK_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            size,
+            texture,
+            view,
+            depth: DepthTexture::new(device, size.0, size.1),
+        }
+    }
+}
+
+/// GPU resources of the entity ID pass, created on the first GPU pick
+pub(crate) struct IdPass {
+    pipeline: RenderPipeline,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    /// Holds the single picked pixel, one padded row
+    readback_buffer: wgpu::Buffer,
+    targets: Option<IdTargets>,
+}
+
+impl IdPass {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let pipeline = RenderPipeline::new_pick(device);
+        let camera_buffer =
+            CameraUniform::default().create_buffer(device, Some("Pick Camera Buffer"));
+        let camera_bind_group = pipeline.create_camera_bind_group(device, &camera_buffer);
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Readback Buffer"),
+            size: capture::padded_bytes_per_row(1) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            camera_buffer,
+            camera_bind_group,
+            readback_buffer,
+            targets: None,
+        }
+    }
+
+    /// Upload the camera and make sure the targets match the frame size
+    pub(crate) fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_proj: Mat4,
+        size: (u32, u32),
+    ) {
+        CameraUniform::new(view_proj).update_buffer(queue, &self.camera_buffer);
+        if self
+            .targets
+            .as_ref()
+            .is_none_or(|targets| targets.size != size)
+        {
+            self.targets = Some(IdTargets::new(device, size));
+        }
+    }
+
+    /// Begin the ID pass, scissored to the picked pixel
+    ///
+    /// Must be called after [`prepare`](Self::prepare).
+    pub(crate) fn begin<'a>(
+        &self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        pixel: (u32, u32),
+    ) -> wgpu::RenderPass<'a> {
+        let targets = self.targets.as_ref().expect("ID pass not prepared");
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Pick Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &targets.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &targets.depth.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        // Only the picked pixel is ever read back
+        pass.set_scissor_rect(pixel.0, pixel.1, 1, 1);
+        pass.set_pipeline(&self.pipeline.pipeline);
+        pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        pass
+    }
+
+    /// Copy the picked pixel into the readback buffer
+    pub(crate) fn copy_pixel(&self, encoder: &mut wgpu::CommandEncoder, pixel: (u32, u32)) {
+        let targets = self.targets.as_ref().expect("ID pass not prepared");
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &targets.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: pixel.0,
+                    y: pixel.1,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(capture::padded_bytes_per_row(1)),
+                    rows_per_image: Some(1),
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    /// Wait for the submitted pass and read the picked ID
+    pub(crate) fn read_pixel(&self, device: &wgpu::Device) -> Result<u32, CaptureError> {
+        let slice = self.readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::PollType::Wait)?;
+        receiver
+            .recv()
+            .map_err(|_| CaptureError::BufferMap(wgpu::BufferAsyncError))??;
+
+        let id = {
+            let data = slice.get_mapped_range();
+            u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+        };
+        self.readback_buffer.unmap();
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIANGLE: [Vec3; 3] = [
+        Vec3::new(-1.0, -1.0, 0.0),
+        Vec3::new(1.0, -1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    ];
+
+    #[test]
+    fn test_ray_triangle_intersection() {
+        let hit = ray_triangle_intersection(Vec3::new(0.0, 0.0, 3.0), Vec3::NEG_Z, TRIANGLE);
+        assert!((hit.unwrap() - 3.0).abs() < 1e-6);
+
+        // Hit from behind
+        let hit = ray_triangle_intersection(Vec3::new(0.0, 0.0, -2.0), Vec3::Z, TRIANGLE);
+        assert!((hit.unwrap() - 2.0).abs() < 1e-6);
+
+        // Outside the triangle, pointing away and parallel to it
+        assert_eq!(
+            ray_triangle_intersection(Vec3::new(0.9, 0.9, 3.0), Vec3::NEG_Z, TRIANGLE),
+            None
+        );
+        assert_eq!(
+            ray_triangle_intersection(Vec3::new(0.0, 0.0, 3.0), Vec3::Z, TRIANGLE),
+            None
+        );
+        assert_eq!(
+            ray_triangle_intersection(Vec3::new(0.0, 0.0, 3.0), Vec3::X, TRIANGLE),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pick_mesh_refines_aabb_hits() {
+        let mesh = PickMesh::from_mesh(&Mesh::cube(2.0));
+
+        let hit = mesh.raycast(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z, f32::INFINITY);
+        assert!((hit.unwrap() - 4.0).abs() < 1e-5);
+
+        // Something closer was already hit
+        assert_eq!(
+            mesh.raycast(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z, 3.0),
+            None
+        );
+
+        // A plane passes the AABB test along its surface but has no triangle there
+        let plane = PickMesh::from_mesh(&Mesh::plane(2.0, 2.0));
+        assert_eq!(
+            plane.raycast(Vec3::new(-5.0, 0.0, 0.0), Vec3::X, f32::INFINITY),
+            None
+        );
+        let hit = plane.raycast(Vec3::new(0.5, 2.0, 0.5), Vec3::NEG_Y, f32::INFINITY);
+        assert!((hit.unwrap() - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_projects_into_rect() {
+        let view_proj = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+        let min = Vec2::new(-0.5, -0.5);
+        let max = Vec2::new(0.5, 0.5);
+
+        let inside = |point| projects_into_rect(view_proj, point, min, max);
+
+        assert!(inside(Vec3::new(0.0, 0.0, -5.0)));
+        assert!(!inside(Vec3::new(4.0, 0.0, -5.0)));
+        // Behind the camera
+        assert!(!inside(Vec3::new(0.0, 0.0, 5.0)));
+    }
+
+    #[test]
+    fn test_pick_id_round_trip() {
+        assert_eq!(decode_pick_id(0), None);
+        for index in [0, 1, 41, 1 << 20] {
+            let id = encode_pick_id(index)[0] as u32;
+            assert_eq!(decode_pick_id(id), Some(index));
+        }
+    }
+}
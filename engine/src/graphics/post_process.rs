@@ -0,0 +1,736 @@
+//! Post-processing of the HDR scene color
+//!
+//! The scene is rendered into an HDR [`RenderTarget`] and then resolved to
+//! the output by a chain of fullscreen passes:
+//!
+//! 1. Bloom: the exposed color above a threshold is downsampled into a chain
+//!    of half resolution levels and blurred back up.
+//! 2. Composite: exposure, bloom, tonemapping and gamma.
+//! 3. FXAA on the display color.
+//!
+//! Each pass can be toggled through the [`PostProcessSettings`] component on
+//! the camera. Cameras without the component get [`PostProcessSettings::passthrough`],
+//! which shows the scene color as it was rendered.
+
+use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
+use crate::graphics::{
+    render_target::RenderTarget,
+    texture::{Texture, TextureKind},
+};
+use crate::io::component_registry::ComponentRegistry;
+use crate::shaders::POST_PROCESS_SHADER;
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+/// Format of the HDR scene color target
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Format of the display color fed to FXAA, stores sRGB encoded values
+const LDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Number of bloom levels, each half the size of the previous one
+const BLOOM_LEVELS: usize = 5;
+
+/// Operator mapping HDR color to the displayable range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Tonemapping {
+    /// Clamp to [0, 1]
+    None,
+    /// Luminance based Reinhard
+    Reinhard,
+    /// ACES filmic curve
+    #[default]
+    Aces,
+}
+
+impl Tonemapping {
+    /// Operator id, must match the TONEMAPPING_* constants in `post_process.wgsl`
+    fn shader_id(self) -> u32 {
+        match self {
+            Tonemapping::None => 0,
+            Tonemapping::Reinhard => 1,
+            Tonemapping::Aces => 2,
+        }
+    }
+}
+
+/// Post-processing settings of a camera
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    engine_derive::Component,
+    engine_derive::EditorUI,
+)]
+#[component(name = "PostProcessSettings")]
+#[serde(default)]
+pub struct PostProcessSettings {
+    /// Apply the settings, otherwise the scene color is shown unchanged
+    #[ui(tooltip = "Enable post-processing")]
+    pub enabled: bool,
+
+    /// Linear multiplier applied to the scene color
+    #[ui(range = 0.0..16.0, speed = 0.01, tooltip = "Exposure multiplier")]
+    pub exposure: f32,
+
+    /// Tonemapping operator
+    #[ui(tooltip = "Tonemapping operator")]
+    pub tonemapping: Tonemapping,
+
+    /// Display gamma, 2.2 matches the sRGB output
+    #[ui(range = 1.0..3.0, speed = 0.01, tooltip = "Display gamma")]
+    pub gamma: f32,
+
+    /// Whether bright areas bleed light into their surroundings
+    #[ui(tooltip = "Enable bloom")]
+    pub bloom: bool,
+
+    /// Exposed brightness above which colors contribute to bloom
+    #[ui(range = 0.0..10.0, speed = 0.01, tooltip = "Bloom brightness threshold")]
+    pub bloom_threshold: f32,
+
+    /// Strength of the bloom added to the scene
+    #[ui(range = 0.0..1.0, speed = 0.005, tooltip = "Bloom intensity")]
+    pub bloom_intensity: f32,
+
+    /// Whether edges are smoothed with FXAA
+    #[ui(tooltip = "Enable FXAA anti-aliasing")]
+    pub fxaa: bool,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            exposure: 1.0,
+            tonemapping: Tonemapping::Aces,
+            gamma: 2.2,
+            bloom: true,
+            bloom_threshold: 1.0,
+            bloom_intensity: 0.04,
+            fxaa: true,
+        }
+    }
+}
+
+impl PostProcessSettings {
+    /// Settings that show the scene color unchanged
+    pub fn passthrough() -> Self {
+        Self {
+            enabled: false,
+            ..Default::default()
+        }
+    }
+
+    /// Check if the bloom passes run
+    pub fn bloom_active(&self) -> bool {
+        self.enabled && self.bloom && self.bloom_intensity > 0.0
+    }
+
+    /// Check if the FXAA pass runs
+    pub fn fxaa_active(&self) -> bool {
+        self.enabled && self.fxaa
+    }
+}
+
+/// Settings uniform shared by all post-processing passes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct PostProcessUniform {
+    pub exposure: f32,
+    pub gamma: f32,
+    pub tonemapping: u32,
+    pub bloom_intensity: f32,
+    pub bloom_threshold: f32,
+    pub bloom_knee: f32,
+    pub _padding: [f32; 2],
+}
+
+impl PostProcessUniform {
+    /// Create the uniform for a camera's settings
+    pub fn new(settings: &PostProcessSettings) -> Self {
+        if !settings.enabled {
+            return Self {
+                exposure: 1.0,
+                gamma: 2.2,
+                tonemapping: Tonemapping::None.shader_id(),
+                bloom_intensity: 0.0,
+                bloom_threshold: 0.0,
+                bloom_knee: 0.0,
+                _padding: [0.0; 2],
+            };
+        }
+
+        let bloom_threshold = settings.bloom_threshold.max(0.0);
+        Self {
+            exposure: settings.exposure.max(0.0),
+            gamma: settings.gamma.max(0.01),
+            tonemapping: settings.tonemapping.shader_id(),
+            bloom_intensity: if settings.bloom_active() {
+                settings.bloom_intensity
+            } else {
+                0.0
+            },
+            bloom_threshold,
+            bloom_knee: bloom_threshold * 0.5,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+/// Size of every bloom level for an output size
+fn bloom_level_sizes(width: u32, height: u32) -> Vec<(u32, u32)> {
+    (1..=BLOOM_LEVELS as u32)
+        .map(|level| ((width >> level).max(1), (height >> level).max(1)))
+        .collect()
+}
+
+/// Pipelines writing to an output format
+struct OutputPipelines {
+    composite: wgpu::RenderPipeline,
+    fxaa: wgpu::RenderPipeline,
+}
+
+/// Size dependent textures and their bind groups
+struct PostProcessTargets {
+    size: (u32, u32),
+    /// HDR scene color and depth
+    hdr: RenderTarget,
+    /// Display color before FXAA
+    ldr_view: wgpu::TextureView,
+    bloom_views: Vec<wgpu::TextureView>,
+    /// Reads the HDR color
+    hdr_bind_group: wgpu::BindGroup,
+    /// Reads each bloom level
+    bloom_bind_groups: Vec<wgpu::BindGroup>,
+    /// Reads the HDR color and the blurred bloom
+    composite_bind_group: wgpu::BindGroup,
+    /// Reads the display color
+    ldr_bind_group: wgpu::BindGroup,
+}
+
+/// Post-processing passes and the intermediate targets they render through
+pub struct PostProcessor {
+    settings_buffer: wgpu::Buffer,
+    settings_bind_group: wgpu::BindGroup,
+    texture_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    shader: wgpu::ShaderModule,
+    prefilter_pipeline: wgpu::RenderPipeline,
+    downsample_pipeline: wgpu::RenderPipeline,
+    upsample_pipeline: wgpu::RenderPipeline,
+    /// Composite and FXAA pipelines, created per output format on first use
+    output_pipelines: HashMap<wgpu::TextureFormat, OutputPipelines>,
+    /// Stand-in bloom texture when bloom is off
+    black_texture: Texture,
+    targets: Option<PostProcessTargets>,
+}
+
+impl PostProcessor {
+    /// Create the post-processing pipelines
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        info!("Creating post-processing pipelines");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Post Process Shader"),
+            source: wgpu::ShaderSource::Wgsl(POST_PROCESS_SHADER.into()),
+        });
+
+        let settings_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process Settings Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let texture_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        };
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Post Process Texture Bind Group Layout"),
+            entries: &[texture_entry(0), texture_entry(1)],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Process Pipeline Layout"),
+            bind_group_layouts: &[&settings_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let settings_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Process Settings Buffer"),
+            size: std::mem::size_of::<PostProcessUniform>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Post Process Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let settings_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Process Settings Bind Group"),
+            layout: &settings_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: settings_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let prefilter_pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            "fs_bloom_prefilter",
+            HDR_FORMAT,
+            None,
+            false,
+        );
+        let downsample_pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            "fs_bloom_downsample",
+            HDR_FORMAT,
+            None,
+            false,
+        );
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let upsample_pipeline = Self::create_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            "fs_bloom_upsample",
+            HDR_FORMAT,
+            Some(wgpu::BlendState {
+                color: additive,
+                alpha: additive,
+            }),
+            false,
+        );
+
+        let black_texture = Texture::solid(
+            device,
+            queue,
+            [0, 0, 0, 255],
+            TextureKind::Data,
+            Some("Post Process Black Texture"),
+        );
+
+        Self {
+            settings_buffer,
+            settings_bind_group,
+            texture_layout,
+            pipeline_layout,
+            shader,
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            output_pipelines: HashMap::new(),
+            black_texture,
+            targets: None,
+        }
+    }
+
+    /// Create a fullscreen pipeline for one fragment entry point
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        entry_point: &str,
+        format: wgpu::TextureFormat,
+        blend: Option<wgpu::BlendState>,
+        output_srgb: bool,
+    ) -> wgpu::RenderPipeline {
+        let constants = [("OUTPUT_SRGB", if output_srgb { 1.0 } else { 0.0 })];
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_fullscreen"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: Some(entry_point),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &constants,
+                    ..Default::default()
+                },
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Create the composite and FXAA pipelines for an output format
+    fn ensure_output_pipelines(&mut self, device: &wgpu::Device, format: wgpu::TextureFormat) {
+        if self.output_pipelines.contains_key(&format) {
+            return;
+        }
+
+        let srgb = format.is_srgb();
+        let pipelines = OutputPipelines {
+            composite: Self::create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &self.shader,
+                "fs_composite",
+                format,
+                None,
+                srgb,
+            ),
+            fxaa: Self::create_pipeline(
+                device,
+                &self.pipeline_layout,
+                &self.shader,
+                "fs_fxaa",
+                format,
+                None,
+                srgb,
+            ),
+        };
+        self.output_pipelines.insert(format, pipelines);
+    }
+
+    /// Bind a source texture, with an optional bloom texture for the composite pass
+    fn texture_bind_group(
+        &self,
+        device: &wgpu::Device,
+        source: &wgpu::TextureView,
+        bloom: Option<&wgpu::TextureView>,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Post Process Texture Bind Group"),
+            layout: &self.texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        bloom.unwrap_or(&self.black_texture.view),
+                    ),
+                },
+            ],
+        })
+    }
+
+    /// Make sure the intermediate targets match the output size
+    ///
+    /// Returns the HDR color and depth views the scene is rendered into.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::TextureView, wgpu::TextureView) {
+        let width = width.max(1);
+        let height = height.max(1);
+
+        if self
+            .targets
+            .as_ref()
+            .is_none_or(|targets| targets.size != (width, height))
+        {
+            self.targets = Some(self.create_targets(device, width, height));
+        }
+
+        let hdr = &self.targets.as_ref().unwrap().hdr;
+        (hdr.view.clone(), hdr.depth_view.clone())
+    }
+
+    fn create_targets(&self, device: &wgpu::Device, width: u32, height: u32) -> PostProcessTargets {
+        let hdr = RenderTarget::new(device, width, height, HDR_FORMAT);
+
+        let create_texture = |label, width, height, format| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        let ldr_view = create_texture("Post Process LDR Texture", width, height, LDR_FORMAT);
+        let bloom_views: Vec<wgpu::TextureView> = bloom_level_sizes(width, height)
+            .into_iter()
+            .map(|(width, height)| create_texture("Bloom Texture", width, height, HDR_FORMAT))
+            .collect();
+
+        PostProcessTargets {
+            size: (width, height),
+            hdr_bind_group: self.texture_bind_group(device, &hdr.view, None),
+            bloom_bind_groups: bloom_views
+                .iter()
+                .map(|view| self.texture_bind_group(device, view, None))
+                .collect(),
+            composite_bind_group: self.texture_bind_group(device, &hdr.view, Some(&bloom_views[0])),
+            ldr_bind_group: self.texture_bind_group(device, &ldr_view, None),
+            hdr,
+            ldr_view,
+            bloom_views,
+        }
+    }
+
+    /// Record one fullscreen pass
+    fn fullscreen_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        label: &str,
+        pipeline: &wgpu::RenderPipeline,
+        source: &wgpu::BindGroup,
+        target: &wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &self.settings_bind_group, &[]);
+        render_pass.set_bind_group(1, source, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Resolve the HDR scene color into `output_view`
+    ///
+    /// Must be called after [`prepare`](Self::prepare) with the size of the
+    /// output view.
+    pub fn run(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        settings: &PostProcessSettings,
+        output_view: &wgpu::TextureView,
+        output_format: wgpu::TextureFormat,
+    ) {
+        let uniform = PostProcessUniform::new(settings);
+        queue.write_buffer(&self.settings_buffer, 0, bytemuck::bytes_of(&uniform));
+
+        let fxaa = settings.fxaa_active();
+        self.ensure_output_pipelines(device, output_format);
+        if fxaa {
+            self.ensure_output_pipelines(device, LDR_FORMAT);
+        }
+
+        let Some(targets) = &self.targets else {
+            return;
+        };
+        let clear = wgpu::LoadOp::Clear(wgpu::Color::BLACK);
+
+        let bloom = settings.bloom_active();
+        if bloom {
+            self.fullscreen_pass(
+                encoder,
+                "Bloom Prefilter Pass",
+                &self.prefilter_pipeline,
+                &targets.hdr_bind_group,
+                &targets.bloom_views[0],
+                clear,
+            );
+            for level in 1..targets.bloom_views.len() {
+                self.fullscreen_pass(
+                    encoder,
+                    "Bloom Downsample Pass",
+                    &self.downsample_pipeline,
+                    &targets.bloom_bind_groups[level - 1],
+                    &targets.bloom_views[level],
+                    clear,
+                );
+            }
+            for level in (1..targets.bloom_views.len()).rev() {
+                self.fullscreen_pass(
+                    encoder,
+                    "Bloom Upsample Pass",
+                    &self.upsample_pipeline,
+                    &targets.bloom_bind_groups[level],
+                    &targets.bloom_views[level - 1],
+                    wgpu::LoadOp::Load,
+                );
+            }
+        }
+
+        let composite_source = if bloom {
+            &targets.composite_bind_group
+        } else {
+            &targets.hdr_bind_group
+        };
+        let output_pipelines = &self.output_pipelines[&output_format];
+
+        if fxaa {
+            self.fullscreen_pass(
+                encoder,
+                "Composite Pass",
+                &self.output_pipelines[&LDR_FORMAT].composite,
+                composite_source,
+                &targets.ldr_view,
+                clear,
+            );
+            self.fullscreen_pass(
+                encoder,
+                "FXAA Pass",
+                &output_pipelines.fxaa,
+                &targets.ldr_bind_group,
+                output_view,
+                clear,
+            );
+        } else {
+            self.fullscreen_pass(
+                encoder,
+                "Composite Pass",
+                &output_pipelines.composite,
+                composite_source,
+                output_view,
+                clear,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_process_uniform_layout() {
+        assert_eq!(std::mem::size_of::<PostProcessUniform>(), 32);
+    }
+
+    #[test]
+    fn test_passthrough_uniform() {
+        let uniform = PostProcessUniform::new(&PostProcessSettings::passthrough());
+        assert_eq!(uniform.exposure, 1.0);
+        assert_eq!(uniform.gamma, 2.2);
+        assert_eq!(uniform.tonemapping, Tonemapping::None.shader_id());
+        assert_eq!(uniform.bloom_intensity, 0.0);
+
+        let passthrough = PostProcessSettings::passthrough();
+        assert!(!passthrough.bloom_active());
+        assert!(!passthrough.fxaa_active());
+    }
+
+    #[test]
+    fn test_settings_uniform() {
+        let settings = PostProcessSettings {
+            exposure: 2.0,
+            tonemapping: Tonemapping::Reinhard,
+            bloom_threshold: 0.8,
+            ..Default::default()
+        };
+        let uniform = PostProcessUniform::new(&settings);
+        assert_eq!(uniform.exposure, 2.0);
+        assert_eq!(uniform.tonemapping, 1);
+        assert_eq!(uniform.bloom_intensity, settings.bloom_intensity);
+        assert!((uniform.bloom_knee - 0.4).abs() < 1e-6);
+
+        // Disabled bloom contributes nothing to the composite
+        let uniform = PostProcessUniform::new(&PostProcessSettings {
+            bloom: false,
+            ..settings
+        });
+        assert_eq!(uniform.bloom_intensity, 0.0);
+    }
+
+    #[test]
+    fn test_bloom_level_sizes() {
+        let sizes = bloom_level_sizes(1280, 720);
+        assert_eq!(sizes.len(), BLOOM_LEVELS);
+        assert_eq!(sizes[0], (640, 360));
+        assert_eq!(sizes[4], (40, 22));
+
+        // Tiny outputs never produce empty levels
+        assert!(bloom_level_sizes(3, 1)
+            .iter()
+            .all(|&(w, h)| w >= 1 && h >= 1));
+    }
+
+    #[test]
+    fn test_settings_deserialize_defaults() {
+        let settings: PostProcessSettings =
+            serde_json::from_str(r#"{"exposure": 0.5, "tonemapping": "Reinhard"}"#).unwrap();
+        assert_eq!(settings.exposure, 0.5);
+        assert_eq!(settings.tonemapping, Tonemapping::Reinhard);
+        assert!(settings.bloom);
+        assert!(settings.fxaa);
+    }
+}
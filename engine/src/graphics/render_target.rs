@@ -1,7 +1,8 @@
 //! Render target abstraction for off-screen rendering
 //!
 //! This module provides the RenderTarget struct which allows rendering to a texture
-//! instead of directly to the window surface. This is used by the editor for viewport rendering.
+//! instead of directly to the window surface. This is used by the editor for viewport rendering
+//! and for headless captures, which copy the color texture back to the CPU.
 
 /// A render target that can be used for off-screen rendering
 #[derive(Debug)]
@@ -39,7 +40,9 @@ impl RenderTarget {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
@@ -90,7 +93,9 @@ impl RenderTarget {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: self.format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
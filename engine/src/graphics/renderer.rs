@@ -4,22 +4,31 @@
 //! render pipelines, GPU resources, and the rendering of entities.
 
 use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
-use crate::core::camera::{Camera, CameraWorldPosition};
+use crate::core::camera::{Camera, CameraView};
 use crate::core::entity::{components::GlobalWorldTransform, GlobalTransform, World};
 use crate::graphics::{
+    capture::{self, CaptureError, CapturedImage},
     context::RenderContext,
+    light::{self, GpuLight, LightHeader},
     material::Material,
-    mesh::Mesh,
+    material::{MaterialKey, MaterialUniform},
+    mesh::{InstanceData, Mesh},
     mesh_library::MeshLibrary,
-    pipeline::{DepthTexture, RenderPipeline},
+    picking::{self, IdPass, PickHit, PickMesh},
+    pipeline::RenderPipeline,
+    post_process::{PostProcessSettings, PostProcessor, HDR_FORMAT},
     render_target::RenderTarget,
+    shadow::{self, ShadowCamera, ShadowMaps},
+    skybox::{Environment, Skybox},
+    texture::{TextureCache, TextureKind},
     uniform::{CameraUniform, ObjectUniform, UniformBuffer},
     Visibility,
 };
 use crate::io::component_registry::ComponentRegistry;
-use glam::{DVec3, Mat4, Vec3};
+use glam::{DVec3, Mat4, Quat, Vec2, Vec3};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 use wgpu::util::DeviceExt;
@@ -32,38 +41,219 @@ struct MeshGpuData {
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     num_indices: u32,
-    aabb: crate::graphics::culling::AABB,
+    /// Triangles and bounds kept on the CPU for picking and culling
+    pick_mesh: PickMesh,
+}
+
+/// GPU resources for a material
+struct MaterialGpuData {
+    /// Material uniform buffer, kept alive for the bind group
+    _buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    /// Frame the material was last drawn in, used for eviction
+    last_used_frame: u64,
+}
+
+/// Number of frames an unused material bind group stays cached
+const MATERIAL_EVICTION_FRAMES: u64 = 300;
+
+/// Number of instances the instance buffer holds before it has to grow
+const INITIAL_INSTANCE_CAPACITY: usize = 1024;
+
+/// Camera resolved for the current frame
+///
+/// The scene is rendered camera-relative, so the camera itself sits at the
+/// origin and `view_proj` only contains its rotation and projection.
+struct FrameCamera {
+    view_proj: Mat4,
+    /// High precision camera position used for camera-relative rendering
+    world_position: DVec3,
+    /// Projection parameters, used to fit shadow cascades
+    camera: Camera,
+    /// Camera orientation in world space
+    rotation: Quat,
+    /// Post-processing applied to the frame
+    post_process: PostProcessSettings,
+}
+
+/// Color target a frame is resolved into
+struct FrameOutput<'a> {
+    view: &'a wgpu::TextureView,
+    format: wgpu::TextureFormat,
+    size: (u32, u32),
+}
+
+impl FrameCamera {
+    fn shadow_camera(&self) -> ShadowCamera {
+        ShadowCamera {
+            camera: self.camera,
+            rotation: self.rotation,
+            world_position: self.world_position,
+        }
+    }
+}
+
+/// An entity collected for drawing
+struct RenderItem {
+    entity: hecs::Entity,
+    mesh_id: MeshId,
+    material: Material,
+    material_key: MaterialKey,
+    /// Camera-relative model matrix
+    model: Mat4,
+    /// False for entities culled from the camera view, which only cast shadows
+    visible: bool,
+}
+
+impl RenderItem {
+    fn new(
+        entity: hecs::Entity,
+        mesh_id: &MeshId,
+        material: &Material,
+        model: Mat4,
+        visibility: Option<&Visibility>,
+    ) -> Self {
+        Self {
+            entity,
+            mesh_id: mesh_id.clone(),
+            material: material.clone(),
+            material_key: material.cache_key(),
+            model,
+            visible: visibility.is_none_or(|vis| vis.is_visible),
+        }
+    }
+}
+
+/// Instances drawn with a single instanced call
+struct DrawBatch {
+    mesh_id: MeshId,
+    /// Material shared by the batch, ignored by shadow batches
+    material: Material,
+    material_key: MaterialKey,
+    /// Range of the batch in the frame's instance buffer
+    instances: Range<u32>,
+}
+
+/// Instance data and draw batches for one frame
+#[derive(Default)]
+struct FrameBatches {
+    /// Instance data of all batches, uploaded to the instance buffer
+    instances: Vec<InstanceData>,
+    /// Visible entities grouped by mesh and material
+    scene: Vec<DrawBatch>,
+    /// All entities grouped by mesh, for the shadow passes
+    shadow: Vec<DrawBatch>,
+}
+
+impl FrameBatches {
+    /// Group render items into batches
+    ///
+    /// Batches keep the order in which their first entity was collected.
+    /// Shadow batches are only built when `with_shadows` is set.
+    fn build(items: &[RenderItem], with_shadows: bool) -> Self {
+        let mut batches = Self::default();
+
+        let visible = items.iter().filter(|item| item.visible);
+        batches.scene = Self::group(&mut batches.instances, visible, |item| {
+            (item.mesh_id.clone(), Some(item.material_key.clone()))
+        });
+
+        if with_shadows {
+            batches.shadow = Self::group(&mut batches.instances, items.iter(), |item| {
+                (item.mesh_id.clone(), None)
+            });
+        }
+
+        batches
+    }
+
+    /// Append the instances of `items` grouped by `key`, returning the batches
+    fn group<'a>(
+        instances: &mut Vec<InstanceData>,
+        items: impl Iterator<Item = &'a RenderItem>,
+        key: impl Fn(&RenderItem) -> (MeshId, Option<MaterialKey>),
+    ) -> Vec<DrawBatch> {
+        let mut groups: Vec<(&RenderItem, Vec<InstanceData>)> = Vec::new();
+        let mut lookup = HashMap::new();
+
+        for item in items {
+            let instance = InstanceData::new(item.model, item.material.color);
+            let index = *lookup.entry(key(item)).or_insert_with(|| {
+                groups.push((item, Vec::new()));
+                groups.len() - 1
+            });
+            groups[index].1.push(instance);
+        }
+
+        groups
+            .into_iter()
+            .map(|(first, group)| {
+                let start = instances.len() as u32;
+                instances.extend(group);
+                DrawBatch {
+                    mesh_id: first.mesh_id.clone(),
+                    material: first.material.clone(),
+                    material_key: first.material_key.clone(),
+                    instances: start..instances.len() as u32,
+                }
+            })
+            .collect()
+    }
 }
 
 /// Main renderer that manages all rendering operations
 pub struct Renderer {
     /// Render context with device and queue
     context: Arc<RenderContext>,
-    /// Basic 3D render pipeline
-    basic_pipeline: RenderPipeline,
+    /// PBR render pipeline for meshes
+    pbr_pipeline: RenderPipeline,
     /// Outline render pipeline for selection
     outline_pipeline: RenderPipeline,
     /// Debug lines render pipeline
     debug_pipeline: RenderPipeline,
-    /// Depth texture for depth testing
-    depth_texture: DepthTexture,
+    /// Depth-only pipeline for shadow maps
+    shadow_pipeline: RenderPipeline,
+    /// Entity ID pass for GPU picking, created on first use
+    id_pass: Option<IdPass>,
+    /// Shadow map array shared by all shadow casting lights
+    shadow_maps: ShadowMaps,
+    /// Number of shadow map layers in use this frame
+    shadow_layer_count: usize,
+    /// HDR scene target and the passes resolving it to the output
+    post_processor: PostProcessor,
+    /// Skybox pass and environment lighting resources
+    environment: Environment,
     /// Camera uniform buffer
     camera_uniform_buffer: wgpu::Buffer,
-    /// Camera bind group
+    /// Light storage buffer (header followed by the light array)
+    light_buffer: wgpu::Buffer,
+    /// Ambient light color applied to all surfaces
+    ambient_light: Vec3,
+    /// Camera and light bind group for the PBR pipeline
     camera_bind_group: wgpu::BindGroup,
     /// Outline camera bind group
     outline_camera_bind_group: wgpu::BindGroup,
     /// Debug camera bind group
     debug_camera_bind_group: wgpu::BindGroup,
+    /// Per-instance vertex buffer shared by all batches of a frame
+    instance_buffer: wgpu::Buffer,
+    /// Number of instances the instance buffer can hold
+    instance_capacity: usize,
     /// Debug line vertex buffer
     debug_line_buffer: Option<wgpu::Buffer>,
     /// Number of debug line vertices
     debug_line_count: u32,
     /// Cached mesh GPU data
     mesh_cache: HashMap<String, MeshGpuData>,
+    /// Textures referenced by materials
+    texture_cache: TextureCache,
+    /// Cached material bind groups
+    material_cache: HashMap<MaterialKey, MaterialGpuData>,
+    /// Number of scene passes recorded, used for material eviction
+    frame_index: u64,
     /// Mesh library for default meshes and fallbacks
     mesh_library: MeshLibrary,
-    /// Current surface format
+    /// Format of the surface frames are presented to
     surface_format: wgpu::TextureFormat,
 }
 
@@ -75,40 +265,74 @@ impl Renderer {
         // Default format - will be updated when rendering to a surface
         let surface_format = wgpu::TextureFormat::Bgra8UnormSrgb;
 
-        // Create render pipelines
-        let basic_pipeline = RenderPipeline::new_basic_3d(&context.device, surface_format);
-        let outline_pipeline = RenderPipeline::new_outline(&context.device, surface_format);
-        let debug_pipeline = RenderPipeline::new_debug_lines(&context.device, surface_format);
+        // Scene pipelines render into the HDR target, post-processing writes the output
+        let pbr_pipeline = RenderPipeline::new_pbr(&context.device, HDR_FORMAT);
+        let outline_pipeline = RenderPipeline::new_outline(&context.device, HDR_FORMAT);
+        let debug_pipeline = RenderPipeline::new_debug_lines(&context.device, HDR_FORMAT);
+        let shadow_pipeline = RenderPipeline::new_shadow(&context.device);
+        let shadow_maps = ShadowMaps::new(&context.device, &shadow_pipeline);
 
-        // Create depth texture with default size
-        let depth_texture = DepthTexture::new(&context.device, 1280, 720);
+        // Create the HDR target with a default size
+        let mut post_processor = PostProcessor::new(&context.device, &context.queue);
+        post_processor.prepare(&context.device, 1280, 720);
 
         // Create camera uniform buffer
         let camera_uniform = CameraUniform::default();
         let camera_uniform_buffer =
             camera_uniform.create_buffer(&context.device, Some("Camera Uniform Buffer"));
 
+        // Create light storage buffer, filled every frame
+        let light_buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light Storage Buffer"),
+            size: light::LIGHT_BUFFER_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let environment = Environment::new(&context.device, &context.queue);
+
         // Create camera bind groups
-        let camera_bind_group =
-            basic_pipeline.create_camera_bind_group(&context.device, &camera_uniform_buffer);
+        let camera_bind_group = pbr_pipeline.create_camera_light_bind_group(
+            &context.device,
+            &camera_uniform_buffer,
+            &light_buffer,
+            &shadow_maps,
+            &environment,
+        );
         let outline_camera_bind_group =
             outline_pipeline.create_camera_bind_group(&context.device, &camera_uniform_buffer);
         let debug_camera_bind_group =
             debug_pipeline.create_camera_bind_group(&context.device, &camera_uniform_buffer);
 
+        let texture_cache = TextureCache::new(&context.device, &context.queue);
+        let instance_buffer =
+            Self::create_instance_buffer(&context.device, INITIAL_INSTANCE_CAPACITY);
+
         Self {
             context,
-            basic_pipeline,
+            pbr_pipeline,
             outline_pipeline,
             debug_pipeline,
-            depth_texture,
+            shadow_pipeline,
+            id_pass: None,
+            shadow_maps,
+            shadow_layer_count: 0,
+            post_processor,
+            environment,
             camera_uniform_buffer,
+            light_buffer,
+            ambient_light: Vec3::splat(0.3),
             camera_bind_group,
             outline_camera_bind_group,
             debug_camera_bind_group,
+            instance_buffer,
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
             debug_line_buffer: None,
             debug_line_count: 0,
             mesh_cache: HashMap::new(),
+            texture_cache,
+            material_cache: HashMap::new(),
+            frame_index: 0,
             mesh_library: MeshLibrary::new(),
             surface_format,
         }
@@ -117,31 +341,18 @@ impl Renderer {
     /// Resize the renderer when the window size changes
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            // Recreate the depth texture with the new size
-            self.depth_texture =
-                DepthTexture::new(&self.context.device, new_size.width, new_size.height);
+            // Recreate the HDR target with the new size
+            self.post_processor
+                .prepare(&self.context.device, new_size.width, new_size.height);
         }
     }
 
     /// Update the surface format if it changes
+    ///
+    /// Scene pipelines always render to the HDR target, only the final
+    /// post-processing pass depends on the output format.
     pub fn update_surface_format(&mut self, format: wgpu::TextureFormat) {
-        if self.surface_format != format {
-            self.surface_format = format;
-            // Recreate pipelines with new format
-            self.basic_pipeline = RenderPipeline::new_basic_3d(&self.context.device, format);
-            self.outline_pipeline = RenderPipeline::new_outline(&self.context.device, format);
-            self.debug_pipeline = RenderPipeline::new_debug_lines(&self.context.device, format);
-            // Recreate camera bind groups
-            self.camera_bind_group = self
-                .basic_pipeline
-                .create_camera_bind_group(&self.context.device, &self.camera_uniform_buffer);
-            self.outline_camera_bind_group = self
-                .outline_pipeline
-                .create_camera_bind_group(&self.context.device, &self.camera_uniform_buffer);
-            self.debug_camera_bind_group = self
-                .debug_pipeline
-                .create_camera_bind_group(&self.context.device, &self.camera_uniform_buffer);
-        }
+        self.surface_format = format;
     }
 
     /// Upload a mesh to the GPU and return its ID
@@ -168,7 +379,7 @@ impl Renderer {
             vertex_buffer,
             index_buffer,
             num_indices: mesh.indices.len() as u32,
-            aabb: mesh.calculate_aabb(),
+            pick_mesh: PickMesh::from_mesh(mesh),
         };
 
         let mesh_id = MeshId(name.to_string());
@@ -178,22 +389,36 @@ impl Renderer {
         mesh_id
     }
 
+    /// Upload all meshes of an imported scene so they don't have to be re-read from disk
+    pub fn upload_imported_scene(&mut self, scene: &crate::graphics::mesh_loader::ImportedScene) {
+        for imported in &scene.meshes {
+            self.upload_mesh(&imported.mesh, &imported.id);
+        }
+    }
+
     /// Get the AABB for a mesh by its ID
     pub fn get_mesh_aabb(&self, mesh_id: &MeshId) -> Option<crate::graphics::culling::AABB> {
-        self.mesh_cache.get(&mesh_id.0).map(|data| data.aabb)
+        self.mesh_cache
+            .get(&mesh_id.0)
+            .map(|data| data.pick_mesh.aabb())
     }
 
     /// Get or create a mesh from the library
     fn get_or_create_mesh(&mut self, mesh_id: &MeshId) -> Result<(), String> {
         if !self.mesh_cache.contains_key(&mesh_id.0) {
             // First, check if it's a file path
-            if mesh_id.0.contains('/') || mesh_id.0.contains('\\') || mesh_id.0.ends_with(".obj") {
-                // Try to load from file
+            if crate::graphics::mesh_loader::is_mesh_file_reference(&mesh_id.0) {
+                // Try to load from file, uploading its other sub-meshes too so
+                // multi-mesh files are only imported once
                 let path = std::path::Path::new(&mesh_id.0);
-                match crate::graphics::mesh_loader::load_mesh_from_file(path) {
-                    Ok(mesh) => {
-                        info!("Loaded mesh from file: {}", mesh_id.0);
-                        self.upload_mesh(&mesh, &mesh_id.0);
+                match crate::graphics::mesh_loader::load_meshes_from_file(path) {
+                    Ok(meshes) => {
+                        info!("Loaded {} meshes from file: {}", meshes.len(), mesh_id.0);
+                        for (id, mesh) in &meshes {
+                            if !self.mesh_cache.contains_key(id) {
+                                self.upload_mesh(mesh, id);
+                            }
+                        }
                     }
                     Err(e) => {
                         error!("Failed to load mesh from file {}: {}", mesh_id.0, e);
@@ -243,250 +468,40 @@ impl Renderer {
         self.update_surface_format(self.context.get_preferred_format(surface));
 
         // Find the active camera and determine camera world position
-        let mut camera_view_proj = None;
-        let mut camera_world_position = DVec3::ZERO;
-
-        {
+        let camera = {
             profile_zone!("Update camera uniforms");
+            self.update_camera(world, None)
+        };
+        let camera_world_position = camera
+            .as_ref()
+            .map(|camera| camera.world_position)
+            .unwrap_or(DVec3::ZERO);
 
-            // First try to find a camera with WorldTransform (large world camera)
-            let mut world_camera_query =
-                world.query::<(&Camera, &GlobalWorldTransform, Option<&CameraWorldPosition>)>();
-            if let Some((_, (camera, world_transform, world_pos))) =
-                world_camera_query.iter().next()
-            {
-                // Use world position if available, otherwise derive from transform
-                camera_world_position = if let Some(pos) = world_pos {
-                    pos.position
-                } else {
-                    world_transform.position()
-                };
-
-                // Calculate view-projection using camera-relative coordinates
-                let view_proj =
-                    camera.view_projection_matrix_world(world_transform, camera_world_position);
-                camera_view_proj = Some(view_proj);
-            } else {
-                // Fall back to regular camera with GlobalTransform
-                let mut regular_camera_query =
-                    world.query::<(&Camera, &GlobalTransform, Option<&CameraWorldPosition>)>();
-                if let Some((_, (camera, transform, cam_world_pos))) =
-                    regular_camera_query.iter().next()
-                {
-                    let view_proj = camera.view_projection_matrix(transform);
-                    camera_view_proj = Some(view_proj);
-
-                    // Use CameraWorldPosition if available for exact position, otherwise extract from transform
-                    camera_world_position = if let Some(world_pos) = cam_world_pos {
-                        world_pos.position
-                    } else {
-                        // Fallback to extracting from transform (less precise for parented cameras)
-                        DVec3::new(
-                            transform.position().x as f64,
-                            transform.position().y as f64,
-                            transform.position().z as f64,
-                        )
-                    };
-                }
-            }
-
-            if let Some(view_proj) = camera_view_proj {
-                // Update camera uniform
-                let camera_uniform = CameraUniform::new(view_proj);
-                camera_uniform.update_buffer(&self.context.queue, &self.camera_uniform_buffer);
-            }
-        } // End of camera uniform update zone
+        self.update_lights(world, camera.as_ref());
+        self.update_environment(world, camera.as_ref());
 
         // Perform frustum culling if we have a valid camera
         // Note: We need a mutable reference to world for culling, but we only have immutable
         // For now, we'll add visibility checks during entity collection
+        let items = Self::collect_render_items(world, camera_world_position);
 
         // Create command encoder
         let mut encoder = self.context.create_command_encoder(Some("Render Encoder"));
 
         {
             profile_zone!("Main render pass");
-
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            self.draw_frame(
+                &mut encoder,
+                "Render Pass",
+                FrameOutput {
                     view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_texture.view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            // Query and render all entities with mesh, material, and transform
-            // Handle both regular and world transforms
-            let mut entities_to_render = Vec::new();
-
-            {
-                profile_zone!("Collect entities");
-
-                // Collect entities with regular GlobalTransform
-                let mut regular_query =
-                    world.query::<(&MeshId, &Material, &GlobalTransform, Option<&Visibility>)>();
-                for (entity, (mesh_id, material, transform, visibility)) in regular_query.iter() {
-                    // Skip culled entities
-                    if let Some(vis) = visibility {
-                        if !vis.is_visible {
-                            continue;
-                        }
-                    }
-                    // Get object position in f64 for precision
-                    let object_pos = transform.position();
-                    let object_pos_f64 = DVec3::new(
-                        object_pos.x as f64,
-                        object_pos.y as f64,
-                        object_pos.z as f64,
-                    );
-
-                    // Calculate camera-relative position with f64 precision
-                    let relative_pos_f64 = object_pos_f64 - camera_world_position;
-
-                    // Convert back to f32 for rendering
-                    let camera_relative_pos = Vec3::new(
-                        relative_pos_f64.x as f32,
-                        relative_pos_f64.y as f32,
-                        relative_pos_f64.z as f32,
-                    );
-
-                    // Decompose to get rotation and scale (these don't need adjustment)
-                    let (scale, rotation, _) = transform.matrix.to_scale_rotation_translation();
-
-                    // Reconstruct matrix with camera-relative position
-                    let camera_relative_matrix =
-                        Mat4::from_scale_rotation_translation(scale, rotation, camera_relative_pos);
-
-                    entities_to_render.push((
-                        entity,
-                        mesh_id.clone(),
-                        *material,
-                        camera_relative_matrix,
-                    ));
-                }
-
-                // Collect entities with WorldTransform and convert to camera-relative
-                let mut world_query = world.query::<(
-                    &MeshId,
-                    &Material,
-                    &GlobalWorldTransform,
-                    Option<&Visibility>,
-                )>();
-                for (entity, (mesh_id, material, world_transform, visibility)) in world_query.iter()
-                {
-                    // Skip culled entities
-                    if let Some(vis) = visibility {
-                        if !vis.is_visible {
-                            continue;
-                        }
-                    }
-                    // Convert world transform to camera-relative transform for rendering
-                    let camera_relative_transform =
-                        world_transform.to_camera_relative(camera_world_position);
-                    entities_to_render.push((
-                        entity,
-                        mesh_id.clone(),
-                        *material,
-                        camera_relative_transform.matrix,
-                    ));
-                }
-            } // End of entity collection
-
-            // First pass: Render outline for selected entity
-            if let Some(selected) = selected_entity {
-                // Find the selected entity in our render list
-                if let Some((_, mesh_id, _, transform_matrix)) = entities_to_render
-                    .iter()
-                    .find(|(e, _, _, _)| *e == selected)
-                {
-                    // Ensure mesh is loaded
-                    let _ = self.get_or_create_mesh(mesh_id);
-                    let mesh_data = &self.mesh_cache[&mesh_id.0];
-
-                    // Use outline pipeline
-                    render_pass.set_pipeline(&self.outline_pipeline.pipeline);
-                    render_pass.set_bind_group(0, &self.outline_camera_bind_group, &[]);
-
-                    // Create outline uniform with bright color
-                    let outline_color = [0.0, 1.0, 1.0, 1.0]; // Bright cyan outline for better visibility
-                    let outline_uniform = ObjectUniform::new(*transform_matrix, outline_color);
-                    let outline_buffer = outline_uniform
-                        .create_buffer(&self.context.device, Some("Outline Uniform"));
-                    let outline_bind_group = self
-                        .outline_pipeline
-                        .create_object_bind_group(&self.context.device, &outline_buffer);
-
-                    render_pass.set_bind_group(1, &outline_bind_group, &[]);
-                    render_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(
-                        mesh_data.index_buffer.slice(..),
-                        wgpu::IndexFormat::Uint32,
-                    );
-                    render_pass.draw_indexed(0..mesh_data.num_indices, 0, 0..1);
-                }
-            }
-
-            // Always ensure basic pipeline is set for normal rendering
-            render_pass.set_pipeline(&self.basic_pipeline.pipeline);
-            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
-
-            // Second pass: Render all entities normally
-            {
-                profile_zone!("Draw calls");
-
-                for (entity, mesh_id, material, transform_matrix) in entities_to_render {
-                    debug!(entity = ?entity, "Rendering entity");
-
-                    // Ensure mesh is loaded (may borrow self mutably)
-                    let _ = self.get_or_create_mesh(&mesh_id);
-
-                    // Now access mesh data (no mut borrow)
-                    let mesh_data = &self.mesh_cache[&mesh_id.0];
-
-                    // Create object uniform
-                    let object_uniform = ObjectUniform::new(transform_matrix, material.color);
-                    let object_buffer =
-                        object_uniform.create_buffer(&self.context.device, Some("Object Uniform"));
-                    let object_bind_group = self
-                        .basic_pipeline
-                        .create_object_bind_group(&self.context.device, &object_buffer);
-
-                    // Set object bind group
-                    render_pass.set_bind_group(1, &object_bind_group, &[]);
-
-                    // Set vertex and index buffers
-                    render_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(
-                        mesh_data.index_buffer.slice(..),
-                        wgpu::IndexFormat::Uint32,
-                    );
-
-                    // Draw
-                    render_pass.draw_indexed(0..mesh_data.num_indices, 0, 0..1);
-                }
-            } // End of draw calls
-
-            // Render debug lines after all entities
-            self.render_debug_lines(&mut render_pass);
+                    format: self.surface_format,
+                    size: (output.texture.width(), output.texture.height()),
+                },
+                &items,
+                selected_entity.as_slice(),
+                &Self::post_process_settings(camera.as_ref()),
+            );
         }
 
         // Submit command buffer
@@ -516,242 +531,32 @@ impl Renderer {
         self.update_surface_format(self.context.get_preferred_format(surface));
 
         // Get the specified camera and its transform, and determine camera world position
-        #[allow(unused_assignments)]
-        let mut camera_world_position = DVec3::ZERO;
-
-        // First try to get camera with WorldTransform (large world camera)
-        let world_camera_data = world
-            .query_one::<(&Camera, &GlobalWorldTransform, Option<&CameraWorldPosition>)>(
-                camera_entity,
-            );
-        if let Ok(mut query_one) = world_camera_data {
-            if let Some((camera, world_transform, world_pos)) = query_one.get() {
-                // Use world position if available, otherwise derive from transform
-                camera_world_position = if let Some(pos) = world_pos {
-                    pos.position
-                } else {
-                    world_transform.position()
-                };
-
-                // Calculate view-projection using camera-relative coordinates
-                let view_proj =
-                    camera.view_projection_matrix_world(world_transform, camera_world_position);
-                let camera_uniform = CameraUniform::new(view_proj);
-                camera_uniform.update_buffer(&self.context.queue, &self.camera_uniform_buffer);
-            } else {
-                debug!("Camera entity missing required world transform components, trying regular transform");
+        let Some(camera) = self.update_camera(world, Some(camera_entity)) else {
+            debug!("Camera entity missing required components, skipping render");
+            return Ok(());
+        };
 
-                // Fall back to regular camera with GlobalTransform
-                let camera_data = world
-                    .query_one::<(&Camera, &GlobalTransform, Option<&CameraWorldPosition>)>(
-                        camera_entity,
-                    );
-                if let Ok(mut query_one) = camera_data {
-                    if let Some((camera, camera_transform, cam_world_pos)) = query_one.get() {
-                        // Update camera uniform
-                        let view_proj = camera.view_projection_matrix(camera_transform);
-                        let camera_uniform = CameraUniform::new(view_proj);
-                        camera_uniform
-                            .update_buffer(&self.context.queue, &self.camera_uniform_buffer);
-
-                        // Use CameraWorldPosition if available for exact position, otherwise extract from transform
-                        camera_world_position = if let Some(world_pos) = cam_world_pos {
-                            world_pos.position
-                        } else {
-                            // Fallback to extracting from transform (less precise for parented cameras)
-                            DVec3::new(
-                                camera_transform.position().x as f64,
-                                camera_transform.position().y as f64,
-                                camera_transform.position().z as f64,
-                            )
-                        };
-                    } else {
-                        debug!("Camera entity missing required components, skipping render");
-                        return Ok(());
-                    }
-                } else {
-                    debug!("Camera entity not found, skipping render");
-                    return Ok(());
-                }
-            }
-        } else {
-            // Fall back to regular camera with GlobalTransform
-            let camera_data = world
-                .query_one::<(&Camera, &GlobalTransform, Option<&CameraWorldPosition>)>(
-                    camera_entity,
-                );
-            if let Ok(mut query_one) = camera_data {
-                if let Some((camera, camera_transform, cam_world_pos)) = query_one.get() {
-                    // Update camera uniform
-                    let view_proj = camera.view_projection_matrix(camera_transform);
-                    let camera_uniform = CameraUniform::new(view_proj);
-                    camera_uniform.update_buffer(&self.context.queue, &self.camera_uniform_buffer);
-
-                    // Use CameraWorldPosition if available for exact position, otherwise extract from transform
-                    camera_world_position = if let Some(world_pos) = cam_world_pos {
-                        world_pos.position
-                    } else {
-                        // Fallback to extracting from transform (less precise for parented cameras)
-                        DVec3::new(
-                            camera_transform.position().x as f64,
-                            camera_transform.position().y as f64,
-                            camera_transform.position().z as f64,
-                        )
-                    };
-                } else {
-                    debug!("Camera entity missing required components, skipping render");
-                    return Ok(());
-                }
-            } else {
-                debug!("Camera entity not found, skipping render");
-                return Ok(());
-            }
-        }
+        self.update_lights(world, Some(&camera));
+        self.update_environment(world, Some(&camera));
+        let items = Self::collect_render_items(world, camera.world_position);
 
         // Create command encoder
         let mut encoder = self
             .context
             .create_command_encoder(Some("Render World Encoder"));
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render World Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &self.depth_texture.view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            // Query and render all entities with mesh, material, and transform
-            // Handle both regular and world transforms
-            let mut entities_to_render = Vec::new();
-
-            {
-                profile_zone!("Collect entities");
-
-                // Collect entities with regular GlobalTransform
-                let mut regular_query =
-                    world.query::<(&MeshId, &Material, &GlobalTransform, Option<&Visibility>)>();
-                for (entity, (mesh_id, material, transform, visibility)) in regular_query.iter() {
-                    // Skip culled entities
-                    if let Some(vis) = visibility {
-                        if !vis.is_visible {
-                            continue;
-                        }
-                    }
-                    // Get object position in f64 for precision
-                    let object_pos = transform.position();
-                    let object_pos_f64 = DVec3::new(
-                        object_pos.x as f64,
-                        object_pos.y as f64,
-                        object_pos.z as f64,
-                    );
-
-                    // Calculate camera-relative position with f64 precision
-                    let relative_pos_f64 = object_pos_f64 - camera_world_position;
-
-                    // Convert back to f32 for rendering
-                    let camera_relative_pos = Vec3::new(
-                        relative_pos_f64.x as f32,
-                        relative_pos_f64.y as f32,
-                        relative_pos_f64.z as f32,
-                    );
-
-                    // Decompose to get rotation and scale (these don't need adjustment)
-                    let (scale, rotation, _) = transform.matrix.to_scale_rotation_translation();
-
-                    // Reconstruct matrix with camera-relative position
-                    let camera_relative_matrix =
-                        Mat4::from_scale_rotation_translation(scale, rotation, camera_relative_pos);
-
-                    entities_to_render.push((
-                        entity,
-                        mesh_id.clone(),
-                        *material,
-                        camera_relative_matrix,
-                    ));
-                }
-
-                // Collect entities with WorldTransform and convert to camera-relative
-                let mut world_query = world.query::<(
-                    &MeshId,
-                    &Material,
-                    &GlobalWorldTransform,
-                    Option<&Visibility>,
-                )>();
-                for (entity, (mesh_id, material, world_transform, visibility)) in world_query.iter()
-                {
-                    // Skip culled entities
-                    if let Some(vis) = visibility {
-                        if !vis.is_visible {
-                            continue;
-                        }
-                    }
-                    // Convert world transform to camera-relative transform for rendering
-                    let camera_relative_transform =
-                        world_transform.to_camera_relative(camera_world_position);
-                    entities_to_render.push((
-                        entity,
-                        mesh_id.clone(),
-                        *material,
-                        camera_relative_transform.matrix,
-                    ));
-                }
-
-                for (entity, mesh_id, material, transform_matrix) in entities_to_render {
-                    debug!(entity = ?entity, mesh_id = %mesh_id.0, "Rendering entity from world");
-
-                    // Ensure mesh is loaded (may borrow self mutably)
-                    let _ = self.get_or_create_mesh(&mesh_id);
-
-                    // Now access mesh data (no mut borrow)
-                    let mesh_data = &self.mesh_cache[&mesh_id.0];
-
-                    // Create object uniform
-                    let object_uniform = ObjectUniform::new(transform_matrix, material.color);
-                    let object_buffer =
-                        object_uniform.create_buffer(&self.context.device, Some("Object Uniform"));
-                    let object_bind_group = self
-                        .basic_pipeline
-                        .create_object_bind_group(&self.context.device, &object_buffer);
-
-                    // Set object bind group
-                    render_pass.set_bind_group(1, &object_bind_group, &[]);
-
-                    // Set vertex and index buffers
-                    render_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(
-                        mesh_data.index_buffer.slice(..),
-                        wgpu::IndexFormat::Uint32,
-                    );
-
-                    // Draw
-                    render_pass.draw_indexed(0..mesh_data.num_indices, 0, 0..1);
-                }
-            } // End of draw calls
-
-            // Render debug lines after all entities
-            self.render_debug_lines(&mut render_pass);
-        }
+        self.draw_frame(
+            &mut encoder,
+            "Render World Pass",
+            FrameOutput {
+                view: &view,
+                format: self.surface_format,
+                size: (output.texture.width(), output.texture.height()),
+            },
+            &items,
+            &[],
+            &camera.post_process,
+        );
 
         // Submit command buffer
         self.context.submit(std::iter::once(encoder.finish()));
@@ -769,252 +574,803 @@ impl Renderer {
         world: &World,
         render_target: &RenderTarget,
     ) -> Result<(), wgpu::SurfaceError> {
-        self.render_to_target_with_selection(world, render_target, None)
+        self.render_to_target_with_selection(world, render_target, &[])
     }
 
-    /// Render to a specific render target with optional selection highlighting
+    /// Render to a specific render target, outlining the selected entities
     pub fn render_to_target_with_selection(
         &mut self,
         world: &World,
         render_target: &RenderTarget,
-        selected_entity: Option<hecs::Entity>,
+        selected: &[hecs::Entity],
     ) -> Result<(), wgpu::SurfaceError> {
         // Find the active camera and determine camera world position
-        let mut camera_world_position = DVec3::ZERO;
-
-        // First try to find a camera with WorldTransform (large world camera)
-        let mut world_camera_query =
-            world.query::<(&Camera, &GlobalWorldTransform, Option<&CameraWorldPosition>)>();
-        if let Some((_, (camera, world_transform, world_pos))) = world_camera_query.iter().next() {
-            // Use world position if available, otherwise derive from transform
-            camera_world_position = if let Some(pos) = world_pos {
-                pos.position
-            } else {
-                world_transform.position()
-            };
+        let camera = Self::resolve_camera(world, None);
+        self.render_frame_to_target(world, render_target, camera, selected);
+        Ok(())
+    }
 
-            // Calculate view-projection using camera-relative coordinates
-            let view_proj =
-                camera.view_projection_matrix_world(world_transform, camera_world_position);
-            let camera_uniform = CameraUniform::new(view_proj);
-            camera_uniform.update_buffer(&self.context.queue, &self.camera_uniform_buffer);
-        } else {
-            // Fall back to regular camera with GlobalTransform
-            let mut regular_camera_query =
-                world.query::<(&Camera, &GlobalTransform, Option<&CameraWorldPosition>)>();
-            if let Some((_, (camera, transform, cam_world_pos))) =
-                regular_camera_query.iter().next()
-            {
-                let view_proj = camera.view_projection_matrix(transform);
-                let camera_uniform = CameraUniform::new(view_proj);
-                camera_uniform.update_buffer(&self.context.queue, &self.camera_uniform_buffer);
-
-                // Use CameraWorldPosition if available for exact position, otherwise extract from transform
-                camera_world_position = if let Some(world_pos) = cam_world_pos {
-                    world_pos.position
-                } else {
-                    // Fallback to extracting from transform (less precise for parented cameras)
-                    DVec3::new(
-                        transform.position().x as f64,
-                        transform.position().y as f64,
-                        transform.position().z as f64,
-                    )
-                };
-            }
+    /// Render to a render target through a view that needn't be a scene camera
+    ///
+    /// The editor uses this to look at the scene through its own camera. A
+    /// free view previews the post-processing of the scene camera.
+    pub fn render_view_to_target(
+        &mut self,
+        world: &World,
+        render_target: &RenderTarget,
+        view: &CameraView,
+        selected: &[hecs::Entity],
+    ) -> Result<(), wgpu::SurfaceError> {
+        let camera = Self::frame_camera(world, view);
+        self.render_frame_to_target(world, render_target, Some(camera), selected);
+        Ok(())
+    }
+
+    /// Draw one frame into a render target
+    fn render_frame_to_target(
+        &mut self,
+        world: &World,
+        render_target: &RenderTarget,
+        camera: Option<FrameCamera>,
+        selected: &[hecs::Entity],
+    ) {
+        if let Some(camera) = &camera {
+            self.upload_camera(camera);
         }
+        let camera_world_position = camera
+            .as_ref()
+            .map(|camera| camera.world_position)
+            .unwrap_or(DVec3::ZERO);
+
+        self.update_lights(world, camera.as_ref());
+        self.update_environment(world, camera.as_ref());
+        let items = Self::collect_render_items(world, camera_world_position);
 
         // Create command encoder
         let mut encoder = self
             .context
             .create_command_encoder(Some("Render to Target Encoder"));
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render to Target Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &render_target.view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &render_target.depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
+        self.draw_frame(
+            &mut encoder,
+            "Render to Target Pass",
+            FrameOutput {
+                view: &render_target.view,
+                format: render_target.format,
+                size: render_target.size,
+            },
+            &items,
+            selected,
+            &Self::post_process_settings(camera.as_ref()),
+        );
+
+        // Submit command buffer
+        self.context.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Render a world into an image on the CPU
+    ///
+    /// Renders from `camera_entity`, or the first camera in the world when
+    /// `None`, with the camera aspect ratio matched to the image size. This
+    /// needs no window, so together with [`RenderContext::new_headless`] it
+    /// is used for screenshots and golden-image tests. The call blocks until
+    /// the GPU has finished the frame.
+    pub fn render_to_image(
+        &mut self,
+        world: &World,
+        camera_entity: Option<hecs::Entity>,
+        width: u32,
+        height: u32,
+    ) -> Result<CapturedImage, CaptureError> {
+        profile_zone!("Renderer::render_to_image");
+
+        if width == 0 || height == 0 {
+            return Err(CaptureError::InvalidSize(width, height));
+        }
 
-            // Query and render all entities with mesh, material, and transform
-            // Handle both regular and world transforms
-            let mut entities_to_render = Vec::new();
+        // Keep the surface format if it can be read back
+        if !capture::is_capture_format(self.surface_format) {
+            self.update_surface_format(capture::CAPTURE_FORMAT);
+        }
+        let format = self.surface_format;
+
+        let mut camera =
+            Self::resolve_camera(world, camera_entity).ok_or(CaptureError::NoCamera)?;
+        camera.camera.set_aspect_ratio(width as f32 / height as f32);
+        camera.view_proj = camera
+            .camera
+            .view_projection_matrix_relative(camera.rotation);
+        self.upload_camera(&camera);
+
+        self.update_lights(world, Some(&camera));
+        self.update_environment(world, Some(&camera));
+        let items = Self::collect_render_items(world, camera.world_position);
+
+        let device = self.context.device.clone();
+        let render_target = RenderTarget::new(&device, width, height, format);
+        let bytes_per_row = capture::padded_bytes_per_row(width);
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Readback Buffer"),
+            size: bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.context.create_command_encoder(Some("Capture Encoder"));
+        self.draw_frame(
+            &mut encoder,
+            "Capture Pass",
+            FrameOutput {
+                view: &render_target.view,
+                format,
+                size: (width, height),
+            },
+            &items,
+            &[],
+            &camera.post_process,
+        );
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &render_target.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.context.submit(std::iter::once(encoder.finish()));
+
+        // Wait for the copy and map the buffer
+        let slice = readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::PollType::Wait)?;
+        receiver
+            .recv()
+            .map_err(|_| CaptureError::BufferMap(wgpu::BufferAsyncError))??;
+
+        let image = {
+            let data = slice.get_mapped_range();
+            CapturedImage::from_padded_rows(width, height, &data, bytes_per_row, format)
+        };
+        readback_buffer.unmap();
+
+        Ok(image)
+    }
+
+    /// Find the closest entity hit by a camera-relative ray
+    ///
+    /// Each entity is tested against its mesh bounds first, and only meshes
+    /// whose box is hit are tested triangle by triangle. With a normalized
+    /// `direction` the hit distance is in world units. Entities culled from
+    /// the view can't be picked.
+    pub fn pick_ray(
+        &mut self,
+        world: &World,
+        camera_world_position: DVec3,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Option<PickHit> {
+        profile_zone!("Renderer::pick_ray");
+
+        let items = Self::collect_render_items(world, camera_world_position);
+        let mut closest: Option<PickHit> = None;
+
+        for item in items.iter().filter(|item| item.visible) {
+            // Zero scale collapses the mesh, there is nothing to hit
+            if item.model.determinant().abs() < f32::EPSILON {
+                continue;
+            }
+            let _ = self.get_or_create_mesh(&item.mesh_id);
+            let Some(mesh_data) = self.mesh_cache.get(&item.mesh_id.0) else {
+                continue;
+            };
+
+            // The ray parameter survives the move to model space, so distances stay comparable
+            let inverse = item.model.inverse();
+            let local_origin = inverse.transform_point3(origin);
+            let local_direction = inverse.transform_vector3(direction);
+            let max_distance = closest.map_or(f32::INFINITY, |hit| hit.distance);
 
+            if let Some(distance) =
+                mesh_data
+                    .pick_mesh
+                    .raycast(local_origin, local_direction, max_distance)
             {
-                profile_zone!("Collect entities");
-
-                // Collect entities with regular GlobalTransform
-                let mut regular_query =
-                    world.query::<(&MeshId, &Material, &GlobalTransform, Option<&Visibility>)>();
-                for (entity, (mesh_id, material, transform, visibility)) in regular_query.iter() {
-                    // Skip culled entities
-                    if let Some(vis) = visibility {
-                        if !vis.is_visible {
-                            continue;
-                        }
-                    }
-                    // Get object position in f64 for precision
-                    let object_pos = transform.position();
-                    let object_pos_f64 = DVec3::new(
-                        object_pos.x as f64,
-                        object_pos.y as f64,
-                        object_pos.z as f64,
-                    );
+                closest = Some(PickHit {
+                    entity: item.entity,
+                    distance,
+                });
+            }
+        }
 
-                    // Calculate camera-relative position with f64 precision
-                    let relative_pos_f64 = object_pos_f64 - camera_world_position;
+        closest
+    }
 
-                    // Convert back to f32 for rendering
-                    let camera_relative_pos = Vec3::new(
-                        relative_pos_f64.x as f32,
-                        relative_pos_f64.y as f32,
-                        relative_pos_f64.z as f32,
-                    );
+    /// Find the entities whose mesh bounds are centered inside a rectangle
+    ///
+    /// `view_proj` is the camera-relative view-projection the viewport is
+    /// drawn with, and the corners are in normalized device coordinates in
+    /// any order. Used for box selection.
+    pub fn pick_rect(
+        &mut self,
+        world: &World,
+        camera_world_position: DVec3,
+        view_proj: Mat4,
+        corner_a: Vec2,
+        corner_b: Vec2,
+    ) -> Vec<hecs::Entity> {
+        profile_zone!("Renderer::pick_rect");
+
+        let (min, max) = (corner_a.min(corner_b), corner_a.max(corner_b));
+        let items = Self::collect_render_items(world, camera_world_position);
+        let mut entities = Vec::new();
+
+        for item in items.iter().filter(|item| item.visible) {
+            let _ = self.get_or_create_mesh(&item.mesh_id);
+            let Some(mesh_data) = self.mesh_cache.get(&item.mesh_id.0) else {
+                continue;
+            };
 
-                    // Decompose to get rotation and scale (these don't need adjustment)
-                    let (scale, rotation, _) = transform.matrix.to_scale_rotation_translation();
+            let center = item
+                .model
+                .transform_point3(mesh_data.pick_mesh.aabb().center());
+            if picking::projects_into_rect(view_proj, center, min, max) {
+                entities.push(item.entity);
+            }
+        }
 
-                    // Reconstruct matrix with camera-relative position
-                    let camera_relative_matrix =
-                        Mat4::from_scale_rotation_translation(scale, rotation, camera_relative_pos);
+        entities
+    }
 
-                    entities_to_render.push((
-                        entity,
-                        mesh_id.clone(),
-                        *material,
-                        camera_relative_matrix,
-                    ));
-                }
+    /// Read the entity under a pixel from an entity ID pass
+    ///
+    /// Renders the visible entities as seen by `view` into an ID target of
+    /// `size` pixels, stretched like a render target drawn with the same
+    /// camera, scissored to `pixel`, and reads the ID back. Unlike
+    /// [`pick_ray`](Self::pick_ray) this matches the rasterized image
+    /// exactly, but it blocks until the GPU has finished, so it is meant for
+    /// single clicks rather than every frame.
+    pub fn pick_gpu(
+        &mut self,
+        world: &World,
+        view: &CameraView,
+        size: (u32, u32),
+        pixel: (u32, u32),
+    ) -> Result<Option<hecs::Entity>, CaptureError> {
+        profile_zone!("Renderer::pick_gpu");
+
+        if size.0 == 0 || size.1 == 0 {
+            return Err(CaptureError::InvalidSize(size.0, size.1));
+        }
+        if pixel.0 >= size.0 || pixel.1 >= size.1 {
+            return Ok(None);
+        }
 
-                // Collect entities with WorldTransform and convert to camera-relative
-                let mut world_query = world.query::<(
-                    &MeshId,
-                    &Material,
-                    &GlobalWorldTransform,
-                    Option<&Visibility>,
-                )>();
-                for (entity, (mesh_id, material, world_transform, visibility)) in world_query.iter()
-                {
-                    // Skip culled entities
-                    if let Some(vis) = visibility {
-                        if !vis.is_visible {
-                            continue;
-                        }
-                    }
-                    // Convert world transform to camera-relative transform for rendering
-                    let camera_relative_transform =
-                        world_transform.to_camera_relative(camera_world_position);
-                    entities_to_render.push((
-                        entity,
-                        mesh_id.clone(),
-                        *material,
-                        camera_relative_transform.matrix,
-                    ));
-                }
-            } // End of entity collection
-
-            // First pass: Render outline for selected entity
-            if let Some(selected) = selected_entity {
-                // Find the selected entity in our render list
-                if let Some((_, mesh_id, _, transform_matrix)) = entities_to_render
-                    .iter()
-                    .find(|(e, _, _, _)| *e == selected)
-                {
-                    // Ensure mesh is loaded
-                    let _ = self.get_or_create_mesh(mesh_id);
-                    let mesh_data = &self.mesh_cache[&mesh_id.0];
-
-                    // Use outline pipeline
-                    render_pass.set_pipeline(&self.outline_pipeline.pipeline);
-                    render_pass.set_bind_group(0, &self.outline_camera_bind_group, &[]);
-
-                    // Create outline uniform with bright color
-                    let outline_color = [0.0, 1.0, 1.0, 1.0]; // Bright cyan outline for better visibility
-                    let outline_uniform = ObjectUniform::new(*transform_matrix, outline_color);
-                    let outline_buffer = outline_uniform
-                        .create_buffer(&self.context.device, Some("Outline Uniform"));
-                    let outline_bind_group = self
-                        .outline_pipeline
-                        .create_object_bind_group(&self.context.device, &outline_buffer);
-
-                    render_pass.set_bind_group(1, &outline_bind_group, &[]);
-                    render_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(
+        let items: Vec<RenderItem> = Self::collect_render_items(world, view.world_position)
+            .into_iter()
+            .filter(|item| item.visible)
+            .collect();
+        for item in &items {
+            let _ = self.get_or_create_mesh(&item.mesh_id);
+        }
+        let instances: Vec<InstanceData> = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| InstanceData::new(item.model, picking::encode_pick_id(index)))
+            .collect();
+
+        let view_proj = view.view_projection();
+
+        let device = self.context.device.clone();
+        let id_pass = self.id_pass.get_or_insert_with(|| IdPass::new(&device));
+        id_pass.prepare(&device, &self.context.queue, view_proj, size);
+
+        let instance_buffer = (!instances.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Pick Instance Buffer"),
+                contents: bytemuck::cast_slice(&instances),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+
+        let mut encoder = self.context.create_command_encoder(Some("Pick Encoder"));
+        {
+            let mut pick_pass = id_pass.begin(&mut encoder, pixel);
+            if let Some(instance_buffer) = &instance_buffer {
+                pick_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+                for (index, item) in items.iter().enumerate() {
+                    let mesh_data = &self.mesh_cache[&item.mesh_id.0];
+                    let instance = index as u32;
+                    pick_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
+                    pick_pass.set_index_buffer(
                         mesh_data.index_buffer.slice(..),
                         wgpu::IndexFormat::Uint32,
                     );
-                    render_pass.draw_indexed(0..mesh_data.num_indices, 0, 0..1);
+                    pick_pass.draw_indexed(0..mesh_data.num_indices, 0, instance..instance + 1);
                 }
             }
+        }
+        id_pass.copy_pixel(&mut encoder, pixel);
+        self.context.submit(std::iter::once(encoder.finish()));
 
-            // Always ensure basic pipeline is set for normal rendering
-            render_pass.set_pipeline(&self.basic_pipeline.pipeline);
-            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        let id = id_pass.read_pixel(&device)?;
+        Ok(picking::decode_pick_id(id)
+            .and_then(|index| items.get(index))
+            .map(|item| item.entity))
+    }
 
-            // Second pass: Render all entities normally
-            {
-                profile_zone!("Draw calls");
+    /// Find the camera to render with
+    ///
+    /// See [`CameraView::resolve`] for how the camera is picked. The view
+    /// only uses the camera rotation since objects are already camera-relative.
+    fn resolve_camera(world: &World, camera_entity: Option<hecs::Entity>) -> Option<FrameCamera> {
+        let view = CameraView::resolve(world, camera_entity)?;
+        Some(Self::frame_camera(world, &view))
+    }
 
-                for (entity, mesh_id, material, transform_matrix) in entities_to_render {
-                    debug!(entity = ?entity, "Rendering entity to target");
+    /// Frame camera for a resolved view
+    ///
+    /// Free views borrow the post-processing settings of the scene camera.
+    fn frame_camera(world: &World, view: &CameraView) -> FrameCamera {
+        let settings_entity = view
+            .entity
+            .or_else(|| CameraView::resolve(world, None).and_then(|scene| scene.entity));
+
+        // Cameras without settings show the scene color unchanged
+        let post_process = settings_entity
+            .and_then(|entity| world.get::<PostProcessSettings>(entity).ok())
+            .map(|settings| *settings)
+            .unwrap_or_else(PostProcessSettings::passthrough);
+
+        FrameCamera {
+            view_proj: view.view_projection(),
+            world_position: view.world_position,
+            camera: view.camera,
+            rotation: view.rotation,
+            post_process,
+        }
+    }
 
-                    // Ensure mesh is loaded (may borrow self mutably)
-                    let _ = self.get_or_create_mesh(&mesh_id);
+    /// Resolve the camera and upload its uniform
+    fn update_camera(
+        &self,
+        world: &World,
+        camera_entity: Option<hecs::Entity>,
+    ) -> Option<FrameCamera> {
+        let camera = Self::resolve_camera(world, camera_entity)?;
+        self.upload_camera(&camera);
+        Some(camera)
+    }
 
-                    // Now access mesh data (no mut borrow)
-                    let mesh_data = &self.mesh_cache[&mesh_id.0];
+    /// Post-processing settings of a frame, passthrough without a camera
+    fn post_process_settings(camera: Option<&FrameCamera>) -> PostProcessSettings {
+        camera
+            .map(|camera| camera.post_process)
+            .unwrap_or_else(PostProcessSettings::passthrough)
+    }
 
-                    // Create object uniform
-                    let object_uniform = ObjectUniform::new(transform_matrix, material.color);
-                    let object_buffer =
-                        object_uniform.create_buffer(&self.context.device, Some("Object Uniform"));
-                    let object_bind_group = self
-                        .basic_pipeline
-                        .create_object_bind_group(&self.context.device, &object_buffer);
+    /// Upload the camera uniform for a resolved camera
+    fn upload_camera(&self, camera: &FrameCamera) {
+        // The camera is the origin of camera-relative space
+        let camera_uniform = CameraUniform::new(camera.view_proj).with_position(Vec3::ZERO);
+        camera_uniform.update_buffer(&self.context.queue, &self.camera_uniform_buffer);
+    }
 
-                    // Set object bind group
-                    render_pass.set_bind_group(1, &object_bind_group, &[]);
+    /// Gather the scene lights and upload them to the light storage buffer
+    ///
+    /// Also plans the shadow maps for this frame. Without a camera there is
+    /// nothing to fit cascades to, so lights render unshadowed.
+    fn update_lights(&mut self, world: &World, camera: Option<&FrameCamera>) {
+        profile_zone!("Update lights");
+
+        let camera_world_position = camera
+            .map(|camera| camera.world_position)
+            .unwrap_or(DVec3::ZERO);
+        let mut frame_lights = light::collect_lights(world, camera_world_position);
+
+        let shadow_matrices = match camera {
+            Some(camera) => shadow::plan_shadows(&mut frame_lights, &camera.shadow_camera()),
+            None => Vec::new(),
+        };
+        self.shadow_maps
+            .update(&self.context.queue, &shadow_matrices);
+        self.shadow_layer_count = shadow_matrices.len();
+
+        let lights: &[GpuLight] = &frame_lights.lights;
+        let header = LightHeader::new(lights.len(), self.ambient_light);
+
+        let queue = &self.context.queue;
+        queue.write_buffer(&self.light_buffer, 0, bytemuck::bytes_of(&header));
+        queue.write_buffer(
+            &self.light_buffer,
+            std::mem::size_of::<LightHeader>() as u64,
+            bytemuck::cast_slice(lights),
+        );
+    }
 
-                    // Set vertex and index buffers
-                    render_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(
-                        mesh_data.index_buffer.slice(..),
-                        wgpu::IndexFormat::Uint32,
-                    );
+    /// Load the scene skybox and upload the sky and environment uniforms
+    ///
+    /// The PBR camera bind group holds the environment cubemap, so it is
+    /// rebuilt when the skybox image changes.
+    fn update_environment(&mut self, world: &World, camera: Option<&FrameCamera>) {
+        profile_zone!("Update environment");
+
+        let mut query = world.query::<&Skybox>();
+        let skybox = query.iter().next().map(|(_, skybox)| skybox);
+        let changed = self.environment.update(
+            &self.context.device,
+            &self.context.queue,
+            skybox,
+            camera.map(|camera| (&camera.camera, camera.rotation)),
+        );
+
+        if changed {
+            self.camera_bind_group = self.pbr_pipeline.create_camera_light_bind_group(
+                &self.context.device,
+                &self.camera_uniform_buffer,
+                &self.light_buffer,
+                &self.shadow_maps,
+                &self.environment,
+            );
+        }
+    }
 
-                    // Draw
-                    render_pass.draw_indexed(0..mesh_data.num_indices, 0, 0..1);
-                }
-            } // End of draw calls
+    /// Set the ambient light color applied to all surfaces
+    ///
+    /// Scenes with a skybox using environment lighting ignore it.
+    pub fn set_ambient_light(&mut self, color: Vec3) {
+        self.ambient_light = color;
+    }
+
+    /// Get the ambient light color
+    pub fn ambient_light(&self) -> Vec3 {
+        self.ambient_light
+    }
 
-            // Render debug lines after all entities
-            self.render_debug_lines(&mut render_pass);
+    /// Collect all entities with mesh, material, and transform
+    ///
+    /// Handles both regular and world transforms, converting every model
+    /// matrix to camera-relative coordinates. Culled entities are kept, marked
+    /// as not visible, since they can still cast shadows into the view.
+    fn collect_render_items(world: &World, camera_world_position: DVec3) -> Vec<RenderItem> {
+        profile_zone!("Collect entities");
+
+        let mut items = Vec::new();
+
+        // Collect entities with regular GlobalTransform
+        let mut regular_query =
+            world.query::<(&MeshId, &Material, &GlobalTransform, Option<&Visibility>)>();
+        for (entity, (mesh_id, material, transform, visibility)) in regular_query.iter() {
+            // Calculate camera-relative position with f64 precision
+            let relative_pos_f64 = transform.position().as_dvec3() - camera_world_position;
+            let camera_relative_pos = relative_pos_f64.as_vec3();
+
+            // Decompose to get rotation and scale (these don't need adjustment)
+            let (scale, rotation, _) = transform.matrix.to_scale_rotation_translation();
+
+            // Reconstruct matrix with camera-relative position
+            let model = Mat4::from_scale_rotation_translation(scale, rotation, camera_relative_pos);
+
+            items.push(RenderItem::new(
+                entity, mesh_id, material, model, visibility,
+            ));
         }
 
-        // Submit command buffer
-        self.context.submit(std::iter::once(encoder.finish()));
+        // Collect entities with WorldTransform and convert to camera-relative
+        let mut world_query = world.query::<(
+            &MeshId,
+            &Material,
+            &GlobalWorldTransform,
+            Option<&Visibility>,
+        )>();
+        for (entity, (mesh_id, material, world_transform, visibility)) in world_query.iter() {
+            let camera_relative_transform =
+                world_transform.to_camera_relative(camera_world_position);
+            items.push(RenderItem::new(
+                entity,
+                mesh_id,
+                material,
+                camera_relative_transform.matrix,
+                visibility,
+            ));
+        }
 
-        Ok(())
+        items
+    }
+
+    /// Create a vertex buffer for `capacity` instances
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (capacity * std::mem::size_of::<InstanceData>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Upload the instance data of a frame, growing the buffer if needed
+    fn upload_instances(&mut self, instances: &[InstanceData]) {
+        if instances.is_empty() {
+            return;
+        }
+
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().next_power_of_two();
+            self.instance_buffer =
+                Self::create_instance_buffer(&self.context.device, self.instance_capacity);
+            debug!(capacity = self.instance_capacity, "Resized instance buffer");
+        }
+
+        self.context
+            .queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+    }
+
+    /// Make sure a material has an up to date bind group in the cache
+    fn ensure_material(&mut self, material: &Material, key: &MaterialKey) {
+        if let Some(data) = self.material_cache.get_mut(key) {
+            data.last_used_frame = self.frame_index;
+            return;
+        }
+
+        let device = &self.context.device;
+        let queue = &self.context.queue;
+
+        let albedo = self.texture_cache.get_or_load(
+            device,
+            queue,
+            &material.albedo_texture,
+            TextureKind::Color,
+        );
+        let normal = self.texture_cache.get_or_load(
+            device,
+            queue,
+            &material.normal_texture,
+            TextureKind::Normal,
+        );
+        let orm =
+            self.texture_cache
+                .get_or_load(device, queue, &material.orm_texture, TextureKind::Data);
+
+        let buffer =
+            MaterialUniform::from(material).create_buffer(device, Some("Material Uniform"));
+        let bind_group = self.pbr_pipeline.create_material_bind_group(
+            device,
+            &buffer,
+            &albedo.view,
+            &normal.view,
+            &orm.view,
+            self.texture_cache.sampler(),
+        );
+
+        debug!(
+            materials = self.material_cache.len() + 1,
+            "Created material bind group"
+        );
+
+        self.material_cache.insert(
+            key.clone(),
+            MaterialGpuData {
+                _buffer: buffer,
+                bind_group,
+                last_used_frame: self.frame_index,
+            },
+        );
+    }
+
+    /// Drop material bind groups that haven't been used for a while
+    fn evict_unused_materials(&mut self) {
+        let frame_index = self.frame_index;
+        self.material_cache
+            .retain(|_, data| frame_index - data.last_used_frame <= MATERIAL_EVICTION_FRAMES);
+    }
+
+    /// Record one depth pass per shadow map layer in use
+    ///
+    /// Shadow batches include entities culled from the camera view, since
+    /// they can still cast shadows into it.
+    fn draw_shadows(&self, encoder: &mut wgpu::CommandEncoder, batches: &[DrawBatch]) {
+        if self.shadow_layer_count == 0 || batches.is_empty() {
+            return;
+        }
+        profile_zone!("Shadow passes");
+
+        for layer in 0..self.shadow_layer_count {
+            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: self.shadow_maps.layer_view(layer),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            shadow_pass.set_pipeline(&self.shadow_pipeline.pipeline);
+            shadow_pass.set_bind_group(0, self.shadow_maps.layer_bind_group(layer), &[]);
+            shadow_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+            for batch in batches {
+                let mesh_data = &self.mesh_cache[&batch.mesh_id.0];
+                shadow_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
+                shadow_pass
+                    .set_index_buffer(mesh_data.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                shadow_pass.draw_indexed(0..mesh_data.num_indices, 0, batch.instances.clone());
+            }
+        }
+    }
+
+    /// Draw the scene into the HDR target and post-process it into the output
+    fn draw_frame(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        label: &str,
+        output: FrameOutput<'_>,
+        items: &[RenderItem],
+        selected: &[hecs::Entity],
+        post_process: &PostProcessSettings,
+    ) {
+        let device = self.context.device.clone();
+        let (color_view, depth_view) =
+            self.post_processor
+                .prepare(&device, output.size.0, output.size.1);
+
+        self.draw_scene(encoder, label, &color_view, &depth_view, items, selected);
+
+        profile_zone!("Post-processing");
+        self.post_processor.run(
+            &device,
+            &self.context.queue,
+            encoder,
+            post_process,
+            output.view,
+            output.format,
+        );
+    }
+
+    /// Record the scene passes: shadow maps, then sky, selection outlines,
+    /// entities, and debug lines
+    fn draw_scene(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        label: &str,
+        color_view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+        items: &[RenderItem],
+        selected: &[hecs::Entity],
+    ) {
+        self.frame_index += 1;
+
+        // Upload meshes, materials and instances before the passes start recording
+        for item in items {
+            let _ = self.get_or_create_mesh(&item.mesh_id);
+        }
+        let batches = {
+            profile_zone!("Build batches");
+            FrameBatches::build(items, self.shadow_layer_count > 0)
+        };
+        for batch in &batches.scene {
+            self.ensure_material(&batch.material, &batch.material_key);
+        }
+        self.upload_instances(&batches.instances);
+
+        self.draw_shadows(encoder, &batches.shadow);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.1,
+                        g: 0.2,
+                        b: 0.3,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        // The sky goes first and leaves depth untouched, everything else covers it
+        self.environment.draw(&mut render_pass);
+
+        // First pass: Render outlines for the selected entities
+        if !selected.is_empty() {
+            // Use outline pipeline
+            render_pass.set_pipeline(&self.outline_pipeline.pipeline);
+            render_pass.set_bind_group(0, &self.outline_camera_bind_group, &[]);
+
+            for item in items
+                .iter()
+                .filter(|item| item.visible && selected.contains(&item.entity))
+            {
+                let mesh_data = &self.mesh_cache[&item.mesh_id.0];
+
+                // Create outline uniform with bright color
+                let outline_color = [0.0, 1.0, 1.0, 1.0]; // Bright cyan outline for better visibility
+                let outline_uniform = ObjectUniform::new(item.model, outline_color);
+                let outline_buffer =
+                    outline_uniform.create_buffer(&self.context.device, Some("Outline Uniform"));
+                let outline_bind_group = self
+                    .outline_pipeline
+                    .create_object_bind_group(&self.context.device, &outline_buffer);
+
+                render_pass.set_bind_group(1, &outline_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(mesh_data.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..mesh_data.num_indices, 0, 0..1);
+            }
+        }
+
+        // Always ensure the PBR pipeline is set for normal rendering
+        render_pass.set_pipeline(&self.pbr_pipeline.pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+        // Second pass: Render all visible entities, one instanced draw per batch
+        {
+            profile_zone!("Draw calls");
+
+            for batch in &batches.scene {
+                debug!(
+                    mesh = %batch.mesh_id.0,
+                    instances = batch.instances.len(),
+                    "Rendering batch"
+                );
+
+                let mesh_data = &self.mesh_cache[&batch.mesh_id.0];
+                let material_data = &self.material_cache[&batch.material_key];
+
+                render_pass.set_bind_group(1, &material_data.bind_group, &[]);
+
+                // Set vertex and index buffers
+                render_pass.set_vertex_buffer(0, mesh_data.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(mesh_data.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+                // Draw
+                render_pass.draw_indexed(0..mesh_data.num_indices, 0, batch.instances.clone());
+            }
+        } // End of draw calls
+
+        // Render debug lines after all entities
+        self.render_debug_lines(&mut render_pass);
+        drop(render_pass);
+
+        self.evict_unused_materials();
     }
 
     /// Get a list of all available mesh names
@@ -1079,59 +1435,18 @@ impl Renderer {
     pub fn prepare_render(&self, world: &mut World) {
         profile_zone!("Renderer::prepare_render");
 
-        // Find the active camera and determine camera world position
-        let mut camera_view_proj = None;
-        let mut camera_world_position = DVec3::ZERO;
-
-        // First try to find a camera with WorldTransform (large world camera)
-        {
-            let mut world_camera_query =
-                world.query::<(&Camera, &GlobalWorldTransform, Option<&CameraWorldPosition>)>();
-            if let Some((_, (camera, world_transform, world_pos))) =
-                world_camera_query.iter().next()
-            {
-                // Use world position if available, otherwise derive from transform
-                camera_world_position = if let Some(pos) = world_pos {
-                    pos.position
-                } else {
-                    world_transform.position()
-                };
-
-                // Calculate view-projection using camera-relative coordinates
-                let view_proj =
-                    camera.view_projection_matrix_world(world_transform, camera_world_position);
-                camera_view_proj = Some(view_proj);
-            }
-        }
-
-        // Fall back to regular camera with GlobalTransform if needed
-        if camera_view_proj.is_none() {
-            let mut regular_camera_query =
-                world.query::<(&Camera, &GlobalTransform, Option<&CameraWorldPosition>)>();
-            if let Some((_, (camera, transform, cam_world_pos))) =
-                regular_camera_query.iter().next()
-            {
-                let view_proj = camera.view_projection_matrix(transform);
-                camera_view_proj = Some(view_proj);
-
-                // Use CameraWorldPosition if available for exact position
-                camera_world_position = if let Some(world_pos) = cam_world_pos {
-                    world_pos.position
-                } else {
-                    // Extract position from transform matrix as f64
-                    let pos = transform.position();
-                    DVec3::new(pos.x as f64, pos.y as f64, pos.z as f64)
-                };
-            }
-        }
-
         // Perform frustum culling if we have a valid camera
-        if let Some(view_proj) = camera_view_proj {
+        if let Some(camera) = Self::resolve_camera(world, None) {
             // Initialize AABBs for entities that need them
             crate::graphics::initialize_mesh_aabbs(world, self);
 
             // Perform frustum culling
-            crate::graphics::frustum_culling_system(world, view_proj, camera_world_position, self);
+            crate::graphics::frustum_culling_system(
+                world,
+                camera.view_proj,
+                camera.world_position,
+                self,
+            );
         }
     }
 }
@@ -1168,4 +1483,79 @@ mod tests {
         let id = MeshId("cube".to_string());
         assert_eq!(id.0, "cube");
     }
+
+    #[test]
+    fn test_frame_batches() {
+        let mut world = World::new();
+        let cube = MeshId("cube".to_string());
+        let sphere = MeshId("sphere".to_string());
+        let culled = Visibility {
+            is_visible: false,
+            ..Default::default()
+        };
+
+        let items = vec![
+            RenderItem::new(
+                world.spawn(()),
+                &cube,
+                &Material::red(),
+                Mat4::IDENTITY,
+                None,
+            ),
+            RenderItem::new(
+                world.spawn(()),
+                &sphere,
+                &Material::red(),
+                Mat4::IDENTITY,
+                None,
+            ),
+            // Different color, same material otherwise: batched with the first cube
+            RenderItem::new(
+                world.spawn(()),
+                &cube,
+                &Material::blue(),
+                Mat4::IDENTITY,
+                None,
+            ),
+            RenderItem::new(
+                world.spawn(()),
+                &cube,
+                &Material::red().with_metallic_roughness(1.0, 0.1),
+                Mat4::IDENTITY,
+                None,
+            ),
+            // Culled entities only cast shadows
+            RenderItem::new(
+                world.spawn(()),
+                &cube,
+                &Material::red(),
+                Mat4::IDENTITY,
+                Some(&culled),
+            ),
+        ];
+
+        let batches = FrameBatches::build(&items, true);
+
+        let scene: Vec<_> = batches
+            .scene
+            .iter()
+            .map(|batch| (batch.mesh_id.0.as_str(), batch.instances.clone()))
+            .collect();
+        assert_eq!(
+            scene,
+            vec![("cube", 0..2), ("sphere", 2..3), ("cube", 3..4)]
+        );
+        assert_eq!(batches.instances[1].color, Material::blue().color);
+
+        // Shadow batches group every entity by mesh only
+        let shadow: Vec<_> = batches
+            .shadow
+            .iter()
+            .map(|batch| (batch.mesh_id.0.as_str(), batch.instances.len()))
+            .collect();
+        assert_eq!(shadow, vec![("cube", 4), ("sphere", 1)]);
+        assert_eq!(batches.instances.len(), 9);
+
+        assert!(FrameBatches::build(&items, false).shadow.is_empty());
+    }
 }
@@ -0,0 +1,421 @@
+//! Shadow maps for directional and spot lights
+//!
+//! All shadow maps share one depth texture array. Each frame the lights that
+//! cast shadows are assigned layers: a directional light takes one layer per
+//! cascade, a spot light takes a single layer. Light matrices are built in
+//! camera-relative space, the space the scene itself is rendered in, so
+//! shadows keep full precision far away from the world origin.
+
+use crate::core::camera::Camera;
+use crate::graphics::{
+    light::{FrameLights, GpuLight, LIGHT_TYPE_DIRECTIONAL, LIGHT_TYPE_SPOT},
+    pipeline::RenderPipeline,
+    uniform::{CameraUniform, UniformBuffer},
+};
+use glam::{DVec3, Mat4, Quat, Vec3};
+use tracing::warn;
+
+/// Width and height of every shadow map layer in texels
+pub const SHADOW_MAP_SIZE: u32 = 2048;
+
+/// Number of layers in the shadow map array
+pub const MAX_SHADOW_MAPS: usize = 8;
+
+/// Number of cascades rendered for each directional light (at most 4)
+pub const CASCADE_COUNT: usize = 3;
+
+/// Blend between logarithmic (1.0) and uniform (0.0) cascade splits
+const CASCADE_SPLIT_LAMBDA: f32 = 0.75;
+
+/// Extra depth behind each cascade so casters outside the view still shadow it
+const SHADOW_CASTER_MARGIN: f32 = 100.0;
+
+/// Near plane of spot light shadow frustums
+const SPOT_SHADOW_NEAR: f32 = 0.05;
+
+/// Widest spot light shadow frustum, a perspective projection can't reach 180 degrees
+const MAX_SPOT_SHADOW_FOV: f32 = 170.0;
+
+/// The camera shadows are fitted to
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowCamera {
+    /// Projection parameters
+    pub camera: Camera,
+    /// Camera orientation in world space
+    pub rotation: Quat,
+    /// High precision camera position, the origin of camera-relative space
+    pub world_position: DVec3,
+}
+
+/// Far view distance of each cascade between `near` and `far`
+///
+/// Uses the practical split scheme, a blend of logarithmic and uniform splits.
+/// Entries past `count` are set to `far`.
+pub fn cascade_splits(near: f32, far: f32, count: usize) -> [f32; 4] {
+    let count = count.clamp(1, 4);
+    let near = near.max(0.01);
+    let far = far.max(near + 0.01);
+
+    let mut splits = [far; 4];
+    for (i, split) in splits.iter_mut().enumerate().take(count) {
+        let t = (i + 1) as f32 / count as f32;
+        let logarithmic = near * (far / near).powf(t);
+        let uniform = near + (far - near) * t;
+        *split = CASCADE_SPLIT_LAMBDA * logarithmic + (1.0 - CASCADE_SPLIT_LAMBDA) * uniform;
+    }
+    splits
+}
+
+/// Up vector for a light view, avoiding one parallel to the light direction
+fn light_up(direction: Vec3) -> Vec3 {
+    if direction.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    }
+}
+
+/// Orthographic light matrix covering a slice of the camera frustum
+///
+/// `corners` are camera-relative. The projection is a bounding square of the
+/// slice so its size doesn't change as the camera turns, and it is snapped to
+/// whole shadow texels in world space so shadow edges don't shimmer while the
+/// camera moves.
+pub fn directional_cascade_matrix(
+    direction: Vec3,
+    corners: &[Vec3; 8],
+    camera_world_position: DVec3,
+) -> Mat4 {
+    let direction = direction.normalize_or(Vec3::NEG_Y);
+    let up = light_up(direction);
+
+    let center = corners.iter().copied().sum::<Vec3>() / corners.len() as f32;
+    let radius = corners
+        .iter()
+        .map(|corner| corner.distance(center))
+        .fold(0.0, f32::max);
+    // Round up so the projection size only changes in coarse steps
+    let radius = ((radius * 16.0).ceil() / 16.0).max(0.01);
+
+    // Snap in light space, computed with world coordinates in f64
+    let texel_size = f64::from(2.0 * radius / SHADOW_MAP_SIZE as f32);
+    let light_rotation = Mat4::look_to_rh(Vec3::ZERO, direction, up).as_dmat4();
+    let mut light_space_center =
+        light_rotation.transform_point3(camera_world_position + center.as_dvec3());
+    light_space_center.x = (light_space_center.x / texel_size).floor() * texel_size;
+    light_space_center.y = (light_space_center.y / texel_size).floor() * texel_size;
+    let center = (light_rotation
+        .inverse()
+        .transform_point3(light_space_center)
+        - camera_world_position)
+        .as_vec3();
+
+    let eye = center - direction * (radius + SHADOW_CASTER_MARGIN);
+    let view = Mat4::look_to_rh(eye, direction, up);
+    let projection = Mat4::orthographic_rh(
+        -radius,
+        radius,
+        -radius,
+        radius,
+        0.0,
+        2.0 * radius + SHADOW_CASTER_MARGIN,
+    );
+    projection * view
+}
+
+/// Perspective light matrix covering the cone of a spot light
+pub fn spot_shadow_matrix(light: &GpuLight) -> Mat4 {
+    let position = Vec3::from(light.position);
+    let direction = Vec3::from(light.direction).normalize_or(Vec3::NEG_Z);
+    let fov = (2.0 * light.cos_outer.clamp(-1.0, 1.0).acos())
+        .clamp(0.01, MAX_SPOT_SHADOW_FOV.to_radians());
+    let near = SPOT_SHADOW_NEAR.min(light.range * 0.5);
+
+    let view = Mat4::look_to_rh(position, direction, light_up(direction));
+    let projection = Mat4::perspective_rh(fov, 1.0, near, light.range);
+    projection * view
+}
+
+/// Assign shadow map layers to the lights that cast shadows
+///
+/// Fills in the shadow fields of the lights in `frame` and returns the light
+/// matrix of every used layer. Lights that don't fit in [`MAX_SHADOW_MAPS`]
+/// layers render without shadows.
+pub fn plan_shadows(frame: &mut FrameLights, camera: &ShadowCamera) -> Vec<Mat4> {
+    let mut layers = Vec::new();
+
+    for caster in &frame.shadow_casters {
+        let Some(light) = frame.lights.get_mut(caster.light_index) else {
+            continue;
+        };
+
+        let needed = match light.light_type {
+            LIGHT_TYPE_DIRECTIONAL => CASCADE_COUNT,
+            LIGHT_TYPE_SPOT => 1,
+            _ => continue,
+        };
+        if layers.len() + needed > MAX_SHADOW_MAPS {
+            warn!(
+                max = MAX_SHADOW_MAPS,
+                "Out of shadow map layers, light renders without shadows"
+            );
+            continue;
+        }
+
+        light.shadow_index = layers.len() as i32;
+        light.cascade_count = needed as u32;
+
+        if light.light_type == LIGHT_TYPE_DIRECTIONAL {
+            let near = camera.camera.z_near;
+            let far = caster.distance.min(camera.camera.z_far);
+            light.cascade_splits = cascade_splits(near, far, CASCADE_COUNT);
+
+            // Each cascade covers the frustum from the near plane to its split so
+            // the shader can pick cascades by plain distance from the camera
+            for &split in &light.cascade_splits[..CASCADE_COUNT] {
+                let corners = camera
+                    .camera
+                    .frustum_slice_corners(camera.rotation, near, split);
+                layers.push(directional_cascade_matrix(
+                    Vec3::from(light.direction),
+                    &corners,
+                    camera.world_position,
+                ));
+            }
+        } else {
+            light.cascade_splits = [light.range; 4];
+            layers.push(spot_shadow_matrix(light));
+        }
+    }
+
+    layers
+}
+
+/// GPU resources for the shadow map array
+pub struct ShadowMaps {
+    /// Depth texture with [`MAX_SHADOW_MAPS`] layers
+    _texture: wgpu::Texture,
+    /// View of all layers, sampled by the PBR shader
+    pub array_view: wgpu::TextureView,
+    /// Comparison sampler used for filtered shadow lookups
+    pub sampler: wgpu::Sampler,
+    /// Light matrix of every layer, read by the PBR shader
+    pub matrix_buffer: wgpu::Buffer,
+    /// One render target view per layer
+    layer_views: Vec<wgpu::TextureView>,
+    /// Light matrix uniform of every layer for the depth passes
+    layer_buffers: Vec<wgpu::Buffer>,
+    /// Bind groups of the layer uniforms
+    layer_bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl ShadowMaps {
+    /// Create the shadow map array for a shadow pipeline
+    pub fn new(device: &wgpu::Device, shadow_pipeline: &RenderPipeline) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map Array"),
+            size: wgpu::Extent3d {
+                width: SHADOW_MAP_SIZE,
+                height: SHADOW_MAP_SIZE,
+                depth_or_array_layers: MAX_SHADOW_MAPS as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Depth32Float,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let array_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Shadow Map Array View"),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let layer_views = (0..MAX_SHADOW_MAPS as u32)
+            .map(|layer| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("Shadow Map Layer View"),
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+
+        let matrix_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Matrix Buffer"),
+            size: (MAX_SHADOW_MAPS * std::mem::size_of::<Mat4>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layer_buffers: Vec<wgpu::Buffer> = (0..MAX_SHADOW_MAPS)
+            .map(|_| CameraUniform::default().create_buffer(device, Some("Shadow Layer Uniform")))
+            .collect();
+        let layer_bind_groups = layer_buffers
+            .iter()
+            .map(|buffer| shadow_pipeline.create_camera_bind_group(device, buffer))
+            .collect();
+
+        Self {
+            _texture: texture,
+            array_view,
+            sampler,
+            matrix_buffer,
+            layer_views,
+            layer_buffers,
+            layer_bind_groups,
+        }
+    }
+
+    /// Upload the light matrices returned by [`plan_shadows`]
+    pub fn update(&self, queue: &wgpu::Queue, matrices: &[Mat4]) {
+        let matrices = &matrices[..matrices.len().min(MAX_SHADOW_MAPS)];
+        if matrices.is_empty() {
+            return;
+        }
+
+        queue.write_buffer(&self.matrix_buffer, 0, bytemuck::cast_slice(matrices));
+        for (matrix, buffer) in matrices.iter().zip(&self.layer_buffers) {
+            CameraUniform::new(*matrix).update_buffer(queue, buffer);
+        }
+    }
+
+    /// Render target view of a layer
+    pub fn layer_view(&self, layer: usize) -> &wgpu::TextureView {
+        &self.layer_views[layer]
+    }
+
+    /// Light matrix bind group of a layer for the shadow pipeline
+    pub fn layer_bind_group(&self, layer: usize) -> &wgpu::BindGroup {
+        &self.layer_bind_groups[layer]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::light::{DirectionalLight, ShadowCaster, SpotLight};
+
+    fn test_camera() -> ShadowCamera {
+        ShadowCamera {
+            camera: Camera::perspective(60.0, 16.0 / 9.0, 0.1, 1000.0),
+            rotation: Quat::IDENTITY,
+            world_position: DVec3::ZERO,
+        }
+    }
+
+    fn project(matrix: Mat4, point: Vec3) -> Vec3 {
+        let clip = matrix * point.extend(1.0);
+        clip.truncate() / clip.w
+    }
+
+    fn in_shadow_map(ndc: Vec3) -> bool {
+        ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && (0.0..=1.0).contains(&ndc.z)
+    }
+
+    #[test]
+    fn test_cascade_splits() {
+        let splits = cascade_splits(0.1, 50.0, 3);
+
+        assert!(splits[0] > 0.1);
+        assert!(splits[0] < splits[1] && splits[1] < splits[2]);
+        assert!((splits[2] - 50.0).abs() < 1e-3);
+        // Unused entries cover the whole shadow distance
+        assert_eq!(splits[3], 50.0);
+    }
+
+    #[test]
+    fn test_cascade_covers_frustum_slice() {
+        let camera = test_camera();
+        let direction = Vec3::new(0.3, -1.0, 0.2).normalize();
+
+        // Far from the origin, where f32 world positions would lose precision
+        let world_position = DVec3::new(1.0e7, 0.0, -3.0e6);
+        let corners = camera
+            .camera
+            .frustum_slice_corners(camera.rotation, 0.1, 20.0);
+        let matrix = directional_cascade_matrix(direction, &corners, world_position);
+
+        for corner in corners {
+            assert!(in_shadow_map(project(matrix, corner)), "{corner:?}");
+        }
+    }
+
+    #[test]
+    fn test_spot_shadow_matrix() {
+        let light = SpotLight::default();
+        let gpu = GpuLight::spot(&light, Vec3::new(0.0, 5.0, 0.0), Vec3::NEG_Y);
+        let matrix = spot_shadow_matrix(&gpu);
+
+        // A point on the axis is centered, one behind the light is not covered
+        let below = project(matrix, Vec3::new(0.0, 1.0, 0.0));
+        assert!(below.x.abs() < 1e-5 && below.y.abs() < 1e-5);
+        assert!(in_shadow_map(below));
+        let behind = matrix * Vec3::new(0.0, 6.0, 0.0).extend(1.0);
+        assert!(behind.w < 0.0);
+    }
+
+    #[test]
+    fn test_plan_shadows_assigns_layers() {
+        let sun = GpuLight::directional(&DirectionalLight::default(), Vec3::NEG_Y);
+        let spot = GpuLight::spot(&SpotLight::default(), Vec3::Y, Vec3::NEG_Y);
+
+        let mut frame = FrameLights {
+            lights: vec![sun, spot],
+            shadow_casters: vec![
+                ShadowCaster {
+                    light_index: 0,
+                    distance: 50.0,
+                },
+                ShadowCaster {
+                    light_index: 1,
+                    distance: spot.range,
+                },
+            ],
+        };
+
+        let layers = plan_shadows(&mut frame, &test_camera());
+        assert_eq!(layers.len(), CASCADE_COUNT + 1);
+        assert_eq!(frame.lights[0].shadow_index, 0);
+        assert_eq!(frame.lights[0].cascade_count, CASCADE_COUNT as u32);
+        assert_eq!(frame.lights[1].shadow_index, CASCADE_COUNT as i32);
+        assert_eq!(frame.lights[1].cascade_count, 1);
+    }
+
+    #[test]
+    fn test_plan_shadows_runs_out_of_layers() {
+        let sun = GpuLight::directional(&DirectionalLight::default(), Vec3::NEG_Y);
+        let count = MAX_SHADOW_MAPS / CASCADE_COUNT + 1;
+
+        let mut frame = FrameLights {
+            lights: vec![sun; count],
+            shadow_casters: (0..count)
+                .map(|light_index| ShadowCaster {
+                    light_index,
+                    distance: 50.0,
+                })
+                .collect(),
+        };
+
+        let layers = plan_shadows(&mut frame, &test_camera());
+        assert!(layers.len() <= MAX_SHADOW_MAPS);
+        assert!(frame.lights[0].has_shadow());
+        assert!(!frame.lights[count - 1].has_shadow());
+    }
+}
@@ -0,0 +1,978 @@
+//! Skybox and image based environment lighting
+//!
+//! A [`Skybox`] component anywhere in the world sets the sky of the scene.
+//! Its image, an equirectangular panorama or six cubemap faces, is converted
+//! into an HDR cubemap with a mip chain when first used:
+//!
+//! - The sky pass draws the cubemap behind all geometry. It rebuilds view
+//!   directions from the camera rotation and field of view, so it works the
+//!   same with logarithmic depth and galaxy-scale near/far ratios.
+//! - The PBR shader can use it as the ambient light source: diffuse light
+//!   comes from spherical harmonics projected on the CPU, specular light from
+//!   the cubemap mip matching the surface roughness.
+
+use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
+use crate::core::camera::{Camera, ProjectionMode};
+use crate::graphics::post_process::HDR_FORMAT;
+use crate::io::component_registry::ComponentRegistry;
+use crate::shaders::SKYBOX_SHADER;
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::path::Path;
+use tracing::{error, info};
+
+/// Format of the environment cubemap
+pub const ENVIRONMENT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Largest cubemap face size created from an equirectangular image
+const MAX_FACE_SIZE: u32 = 1024;
+
+/// Largest mip used to project the diffuse spherical harmonics
+const SH_FACE_SIZE: u32 = 64;
+
+/// Placeholder in cubemap paths replaced by the face names
+pub const FACE_PLACEHOLDER: &str = "{face}";
+
+/// Cubemap face names in layer order (+X, -X, +Y, -Y, +Z, -Z)
+pub const FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Errors that can occur while loading a skybox image
+#[derive(Debug, thiserror::Error)]
+pub enum SkyboxError {
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("Cubemap face {0} is {1}x{2}, faces must be square and of equal size")]
+    FaceSize(String, u32, u32),
+}
+
+/// Sky of the scene, also usable as its ambient light
+///
+/// `path` is either an equirectangular panorama (`.hdr`, `.png`, `.jpg`),
+/// whose center faces -Z, or a cubemap given as a path containing `{face}`,
+/// which is replaced by `px`, `nx`, `py`, `ny`, `pz` and `nz` to find the six
+/// face images. Only the first skybox in the world is used.
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    engine_derive::Component,
+    engine_derive::EditorUI,
+)]
+#[component(name = "Skybox")]
+#[serde(default)]
+pub struct Skybox {
+    /// Equirectangular image, or cubemap faces with `{face}` in the path
+    #[ui(tooltip = "Equirectangular image, or cubemap faces with {face} in the path")]
+    pub path: String,
+
+    /// Brightness multiplier of the sky
+    #[ui(range = 0.0..16.0, speed = 0.01, tooltip = "Sky brightness")]
+    pub intensity: f32,
+
+    /// Rotation around the world Y axis in degrees
+    #[ui(range = -180.0..180.0, speed = 0.5, tooltip = "Rotation around Y in degrees")]
+    pub rotation: f32,
+
+    /// Light the scene with the sky instead of the flat ambient color
+    #[ui(tooltip = "Use the sky as ambient and specular light source")]
+    pub environment_lighting: bool,
+
+    /// Brightness multiplier of the environment lighting
+    #[ui(range = 0.0..16.0, speed = 0.01, tooltip = "Environment lighting strength")]
+    pub environment_intensity: f32,
+}
+
+impl Default for Skybox {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            intensity: 1.0,
+            rotation: 0.0,
+            environment_lighting: true,
+            environment_intensity: 1.0,
+        }
+    }
+}
+
+impl Skybox {
+    /// Create a skybox from an image path
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Check if the path names six cubemap faces
+    pub fn is_cubemap(&self) -> bool {
+        self.path.contains(FACE_PLACEHOLDER)
+    }
+
+    /// Paths of the six cubemap faces, in layer order
+    pub fn face_paths(&self) -> [String; 6] {
+        FACE_NAMES.map(|face| self.path.replace(FACE_PLACEHOLDER, face))
+    }
+
+    /// Cosine and sine of the rotation
+    fn rotation_cos_sin(&self) -> [f32; 2] {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        [cos, sin]
+    }
+}
+
+/// Linear HDR image on the CPU
+struct HdrImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+}
+
+impl HdrImage {
+    /// Load an image as linear color
+    ///
+    /// Float images (Radiance `.hdr`) are already linear, 8 and 16-bit images
+    /// are decoded from sRGB.
+    fn load(path: &Path) -> Result<Self, SkyboxError> {
+        let image = image::open(path)?;
+        let linear = matches!(
+            image,
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_)
+        );
+
+        let rgb = image.to_rgb32f();
+        let (width, height) = rgb.dimensions();
+        let pixels = rgb
+            .pixels()
+            .map(|pixel| {
+                let color = Vec3::from(pixel.0);
+                if linear {
+                    color
+                } else {
+                    srgb_to_linear(color)
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec3 {
+        let x = x.rem_euclid(self.width as i64);
+        let y = y.clamp(0, self.height as i64 - 1);
+        self.pixels[(y * self.width as i64 + x) as usize]
+    }
+
+    /// Bilinear sample, wrapping horizontally
+    fn sample(&self, u: f32, v: f32) -> Vec3 {
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0).lerp(self.texel(x0 + 1, y0), tx);
+        let bottom = self.texel(x0, y0 + 1).lerp(self.texel(x0 + 1, y0 + 1), tx);
+        top.lerp(bottom, ty)
+    }
+}
+
+fn srgb_to_linear(color: Vec3) -> Vec3 {
+    let channel = |c: f32| {
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    Vec3::new(channel(color.x), channel(color.y), channel(color.z))
+}
+
+/// Texel center of a cube face in [-1, 1]
+fn face_coordinate(texel: u32, size: u32) -> f32 {
+    2.0 * (texel as f32 + 0.5) / size as f32 - 1.0
+}
+
+/// Direction through a point of a cube face, not normalized
+///
+/// `s` and `t` run from -1 to 1, left to right and top to bottom, following
+/// the cubemap convention of wgpu.
+fn cube_direction(face: usize, s: f32, t: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    }
+}
+
+/// Texture coordinates of a direction in an equirectangular image
+///
+/// The image center faces -Z, +X is a quarter turn to the right.
+fn equirectangular_uv(direction: Vec3) -> (f32, f32) {
+    let direction = direction.normalize();
+    let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+/// Six square faces of linear HDR color
+#[derive(Debug, Clone, PartialEq)]
+struct CubeFaces {
+    size: u32,
+    faces: [Vec<Vec3>; 6],
+}
+
+impl CubeFaces {
+    /// Load the skybox image of a component
+    fn load(skybox: &Skybox) -> Result<Self, SkyboxError> {
+        if !skybox.is_cubemap() {
+            let image = HdrImage::load(Path::new(&skybox.path))?;
+            let size = (image.width / 4)
+                .next_power_of_two()
+                .clamp(1, MAX_FACE_SIZE);
+            return Ok(Self::from_equirectangular(&image, size));
+        }
+
+        let mut size = None;
+        let mut faces: [Vec<Vec3>; 6] = Default::default();
+        for (face, path) in faces.iter_mut().zip(skybox.face_paths()) {
+            let image = HdrImage::load(Path::new(&path))?;
+            if image.width != image.height || size.is_some_and(|size| size != image.width) {
+                return Err(SkyboxError::FaceSize(path, image.width, image.height));
+            }
+            size = Some(image.width);
+            *face = image.pixels;
+        }
+
+        Ok(Self {
+            size: size.unwrap_or(1),
+            faces,
+        })
+    }
+
+    /// Resample an equirectangular image into faces of `size` texels
+    fn from_equirectangular(image: &HdrImage, size: u32) -> Self {
+        let faces = std::array::from_fn(|face| {
+            (0..size)
+                .flat_map(|y| (0..size).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    let direction =
+                        cube_direction(face, face_coordinate(x, size), face_coordinate(y, size));
+                    let (u, v) = equirectangular_uv(direction);
+                    image.sample(u, v)
+                })
+                .collect()
+        });
+        Self { size, faces }
+    }
+
+    /// Half size faces, each texel the average of four
+    fn downsample(&self) -> Self {
+        let size = (self.size / 2).max(1);
+        let faces = std::array::from_fn(|face| {
+            let source = &self.faces[face];
+            let texel = |x: u32, y: u32| {
+                let x = x.min(self.size - 1);
+                let y = y.min(self.size - 1);
+                source[(y * self.size + x) as usize]
+            };
+            (0..size)
+                .flat_map(|y| (0..size).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    (texel(2 * x, 2 * y)
+                        + texel(2 * x + 1, 2 * y)
+                        + texel(2 * x, 2 * y + 1)
+                        + texel(2 * x + 1, 2 * y + 1))
+                        * 0.25
+                })
+                .collect()
+        });
+        Self { size, faces }
+    }
+
+    /// Full mip chain down to 1x1, starting with these faces
+    fn mip_chain(self) -> Vec<Self> {
+        let mut mips = vec![self];
+        while mips.last().unwrap().size > 1 {
+            let next = mips.last().unwrap().downsample();
+            mips.push(next);
+        }
+        mips
+    }
+}
+
+/// Real spherical harmonics basis up to band 2
+fn sh_basis(direction: Vec3) -> [f32; 9] {
+    let Vec3 { x, y, z } = direction;
+    [
+        0.282095,
+        0.488603 * y,
+        0.488603 * z,
+        0.488603 * x,
+        1.092548 * x * y,
+        1.092548 * y * z,
+        0.315392 * (3.0 * z * z - 1.0),
+        1.092548 * x * z,
+        0.546274 * (x * x - y * y),
+    ]
+}
+
+/// Project the faces on spherical harmonics convolved with a cosine lobe
+///
+/// Evaluating the basis of a normal with these coefficients gives the
+/// irradiance divided by pi, the factor the albedo is multiplied with.
+fn irradiance_sh(cube: &CubeFaces) -> [Vec3; 9] {
+    let mut coefficients = [Vec3::ZERO; 9];
+    let mut total_weight = 0.0;
+
+    for (face, texels) in cube.faces.iter().enumerate() {
+        for y in 0..cube.size {
+            for x in 0..cube.size {
+                let direction = cube_direction(
+                    face,
+                    face_coordinate(x, cube.size),
+                    face_coordinate(y, cube.size),
+                );
+                // Solid angle of the texel, up to a constant factor
+                let weight = direction.length_squared().powf(-1.5);
+                let color = texels[(y * cube.size + x) as usize];
+
+                for (coefficient, basis) in
+                    coefficients.iter_mut().zip(sh_basis(direction.normalize()))
+                {
+                    *coefficient += color * (basis * weight);
+                }
+                total_weight += weight;
+            }
+        }
+    }
+
+    // Normalize the weights to the full sphere and apply the cosine lobe per band
+    let normalization = 4.0 * PI / total_weight;
+    for (index, coefficient) in coefficients.iter_mut().enumerate() {
+        let band = match index {
+            0 => 1.0,
+            1..=3 => 2.0 / 3.0,
+            _ => 0.25,
+        };
+        *coefficient *= normalization * band;
+    }
+    coefficients
+}
+
+/// Convert to half precision, rounding to nearest and clamping to the finite range
+fn f32_to_f16(value: f32) -> u16 {
+    if value.is_nan() {
+        return 0;
+    }
+    let bits = value.clamp(-65504.0, 65504.0).to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent <= 0 {
+        // Subnormal half, or zero when too small
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = ((mantissa >> (shift - 1)) & 1) as u16;
+        return sign | ((mantissa >> shift) as u16 + round);
+    }
+
+    // A carry out of the mantissa correctly bumps the exponent
+    let half = sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16;
+    half + ((mantissa >> 12) & 1) as u16
+}
+
+/// Environment lighting parameters for the PBR shader
+///
+/// Matches `EnvironmentUniform` in `pbr.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct EnvironmentUniform {
+    /// Diffuse irradiance spherical harmonics (RGB, w unused)
+    pub sh: [[f32; 4]; 9],
+    /// Cosine and sine of the environment rotation
+    pub rotation: [f32; 2],
+    /// Brightness multiplier
+    pub intensity: f32,
+    /// Mip level sampled for fully rough surfaces
+    pub max_lod: f32,
+    /// Nonzero when the environment replaces the flat ambient light
+    pub enabled: u32,
+    pub _padding: [u32; 3],
+}
+
+/// Sky pass parameters, matches `SkyboxUniform` in `skybox.wgsl`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct SkyboxUniform {
+    /// Camera orientation, turns view directions into world directions
+    pub camera_rotation: [[f32; 4]; 4],
+    /// Half extents of the view plane at distance 1
+    pub view_scale: [f32; 2],
+    /// Cosine and sine of the skybox rotation
+    pub rotation: [f32; 2],
+    /// Brightness multiplier
+    pub intensity: f32,
+    pub _padding: [f32; 3],
+}
+
+impl SkyboxUniform {
+    /// Create the uniform for a camera looking at a skybox
+    ///
+    /// Only the rotation and field of view of the camera are used, the sky
+    /// is infinitely far away. Orthographic cameras look along a single
+    /// direction and see a uniform sky.
+    pub fn new(skybox: &Skybox, camera: &Camera, camera_rotation: Quat) -> Self {
+        let view_scale = match camera.projection_mode {
+            ProjectionMode::Perspective => {
+                let half_height = (camera.fov_y_radians * 0.5).tan();
+                [half_height * camera.aspect_ratio, half_height]
+            }
+            ProjectionMode::Orthographic { .. } => [0.0, 0.0],
+        };
+
+        Self {
+            camera_rotation: Mat4::from_quat(camera_rotation).to_cols_array_2d(),
+            view_scale,
+            rotation: skybox.rotation_cos_sin(),
+            intensity: skybox.intensity.max(0.0),
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// Environment cubemap on the GPU
+struct EnvironmentMap {
+    /// Cubemap texture, kept alive for the view
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    mip_count: u32,
+    sh: [Vec3; 9],
+}
+
+impl EnvironmentMap {
+    /// Upload a mip chain as a cubemap
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue, mips: &[CubeFaces], label: &str) -> Self {
+        let size = mips[0].size;
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size,
+                height: size,
+                depth_or_array_layers: 6,
+            },
+            mip_level_count: mips.len() as u32,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ENVIRONMENT_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        for (level, mip) in mips.iter().enumerate() {
+            for (layer, face) in mip.faces.iter().enumerate() {
+                let texels: Vec<u16> = face
+                    .iter()
+                    .flat_map(|color| {
+                        [
+                            f32_to_f16(color.x),
+                            f32_to_f16(color.y),
+                            f32_to_f16(color.z),
+                            f32_to_f16(1.0),
+                        ]
+                    })
+                    .collect();
+
+                queue.write_texture(
+                    wgpu::TexelCopyTextureInfo {
+                        texture: &texture,
+                        mip_level: level as u32,
+                        origin: wgpu::Origin3d {
+                            x: 0,
+                            y: 0,
+                            z: layer as u32,
+                        },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    bytemuck::cast_slice(&texels),
+                    wgpu::TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(mip.size * 8),
+                        rows_per_image: Some(mip.size),
+                    },
+                    wgpu::Extent3d {
+                        width: mip.size,
+                        height: mip.size,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Environment Cubemap View"),
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+
+        let sh_source = mips
+            .iter()
+            .find(|mip| mip.size <= SH_FACE_SIZE)
+            .unwrap_or(&mips[0]);
+
+        Self {
+            _texture: texture,
+            view,
+            mip_count: mips.len() as u32,
+            sh: irradiance_sh(sh_source),
+        }
+    }
+
+    /// Load and upload the image of a skybox
+    fn load(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        skybox: &Skybox,
+    ) -> Result<Self, SkyboxError> {
+        let faces = CubeFaces::load(skybox)?;
+        info!(path = %skybox.path, size = faces.size, "Loaded skybox");
+        Ok(Self::new(device, queue, &faces.mip_chain(), &skybox.path))
+    }
+}
+
+/// Skybox pass and the environment resources shared with the PBR pipeline
+///
+/// The environment is bound in the PBR camera bind group, so the renderer
+/// has to recreate that bind group whenever [`update`](Self::update) reports
+/// a new cubemap.
+pub struct Environment {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    skybox_buffer: wgpu::Buffer,
+    /// Environment lighting uniform read by the PBR shader
+    uniform_buffer: wgpu::Buffer,
+    /// Stand-in cubemap while no skybox is loaded
+    black_map: EnvironmentMap,
+    /// Path of the last requested skybox, also kept when loading failed
+    loaded_path: Option<String>,
+    map: Option<EnvironmentMap>,
+    bind_group: wgpu::BindGroup,
+    /// Whether the sky is drawn this frame
+    sky_visible: bool,
+}
+
+impl Environment {
+    /// Create the skybox pipeline and an empty environment
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        info!("Creating skybox pipeline");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skybox Shader"),
+            source: wgpu::ShaderSource::Wgsl(SKYBOX_SHADER.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Skybox Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Skybox Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Drawn first without touching depth, so geometry always covers the
+        // sky whatever depth it ends up at
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Skybox Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: HDR_FORMAT,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Environment Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let create_buffer = |label, size| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let skybox_buffer = create_buffer(
+            "Skybox Uniform Buffer",
+            std::mem::size_of::<SkyboxUniform>() as u64,
+        );
+        let uniform_buffer = create_buffer(
+            "Environment Uniform Buffer",
+            std::mem::size_of::<EnvironmentUniform>() as u64,
+        );
+
+        let black = CubeFaces {
+            size: 1,
+            faces: std::array::from_fn(|_| vec![Vec3::ZERO]),
+        };
+        let black_map = EnvironmentMap::new(device, queue, &[black], "Black Environment Cubemap");
+
+        let bind_group = Self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &skybox_buffer,
+            &black_map.view,
+            &sampler,
+        );
+
+        let environment = Self {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            skybox_buffer,
+            uniform_buffer,
+            black_map,
+            loaded_path: None,
+            map: None,
+            bind_group,
+            sky_visible: false,
+        };
+        environment.write_uniform(queue, None);
+        environment
+    }
+
+    fn create_bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        buffer: &wgpu::Buffer,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Skybox Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Cubemap bound for environment lighting, black without a skybox
+    pub fn cubemap_view(&self) -> &wgpu::TextureView {
+        &self.map.as_ref().unwrap_or(&self.black_map).view
+    }
+
+    /// Sampler for the environment cubemap
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    /// Environment lighting uniform for the PBR shader
+    pub fn uniform_buffer(&self) -> &wgpu::Buffer {
+        &self.uniform_buffer
+    }
+
+    /// Prepare the environment for a frame
+    ///
+    /// Loads the skybox image when its path changed and uploads the sky and
+    /// lighting uniforms for the camera. A skybox that fails to load is
+    /// reported once and the scene renders without it. Returns true when
+    /// the cubemap changed.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        skybox: Option<&Skybox>,
+        camera: Option<(&Camera, Quat)>,
+    ) -> bool {
+        let mut changed = false;
+        if let Some(skybox) = skybox {
+            if self.loaded_path.as_deref() != Some(skybox.path.as_str()) {
+                self.map = if skybox.path.is_empty() {
+                    None
+                } else {
+                    EnvironmentMap::load(device, queue, skybox)
+                        .map_err(|e| error!("Failed to load skybox {}: {}", skybox.path, e))
+                        .ok()
+                };
+                self.loaded_path = Some(skybox.path.clone());
+                self.bind_group = Self::create_bind_group(
+                    device,
+                    &self.bind_group_layout,
+                    &self.skybox_buffer,
+                    self.cubemap_view(),
+                    &self.sampler,
+                );
+                changed = true;
+            }
+        }
+
+        let skybox = skybox.filter(|_| self.map.is_some());
+        self.write_uniform(queue, skybox);
+
+        self.sky_visible = false;
+        if let (Some(skybox), Some((camera, rotation))) = (skybox, camera) {
+            let uniform = SkyboxUniform::new(skybox, camera, rotation);
+            queue.write_buffer(&self.skybox_buffer, 0, bytemuck::bytes_of(&uniform));
+            self.sky_visible = true;
+        }
+
+        changed
+    }
+
+    /// Upload the environment lighting uniform, disabled without a skybox
+    fn write_uniform(&self, queue: &wgpu::Queue, skybox: Option<&Skybox>) {
+        let uniform = match (skybox, &self.map) {
+            (Some(skybox), Some(map)) if skybox.environment_lighting => EnvironmentUniform {
+                sh: map.sh.map(|coefficient| coefficient.extend(0.0).to_array()),
+                rotation: skybox.rotation_cos_sin(),
+                intensity: skybox.environment_intensity.max(0.0),
+                max_lod: (map.mip_count - 1) as f32,
+                enabled: 1,
+                _padding: [0; 3],
+            },
+            _ => EnvironmentUniform::zeroed(),
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    /// Record the sky into a scene pass, before any geometry
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        if !self.sky_visible {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniform_image(width: u32, height: u32, color: Vec3) -> HdrImage {
+        HdrImage {
+            width,
+            height,
+            pixels: vec![color; (width * height) as usize],
+        }
+    }
+
+    fn evaluate_sh(coefficients: &[Vec3; 9], normal: Vec3) -> Vec3 {
+        coefficients
+            .iter()
+            .zip(sh_basis(normal))
+            .map(|(coefficient, basis)| *coefficient * basis)
+            .sum()
+    }
+
+    #[test]
+    fn test_uniform_layouts() {
+        assert_eq!(std::mem::size_of::<EnvironmentUniform>(), 176);
+        assert_eq!(std::mem::size_of::<SkyboxUniform>(), 96);
+    }
+
+    #[test]
+    fn test_cube_face_centers() {
+        let expected = [
+            Vec3::X,
+            Vec3::NEG_X,
+            Vec3::Y,
+            Vec3::NEG_Y,
+            Vec3::Z,
+            Vec3::NEG_Z,
+        ];
+        for (face, direction) in expected.into_iter().enumerate() {
+            assert_eq!(cube_direction(face, 0.0, 0.0), direction);
+        }
+
+        // The top row of the side faces looks up
+        for face in [0, 1, 4, 5] {
+            assert!(cube_direction(face, 0.0, -1.0).y > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_equirectangular_uv() {
+        let (u, v) = equirectangular_uv(Vec3::NEG_Z);
+        assert!((u - 0.5).abs() < 1e-6 && (v - 0.5).abs() < 1e-6);
+
+        // Turning right moves right in the image
+        let (u, _) = equirectangular_uv(Vec3::X);
+        assert!((u - 0.75).abs() < 1e-6);
+
+        assert!(equirectangular_uv(Vec3::Y).1.abs() < 1e-6);
+        assert!((equirectangular_uv(Vec3::NEG_Y).1 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_equirectangular_to_cube() {
+        // Bright upper half, dark lower half
+        let mut image = uniform_image(64, 32, Vec3::ZERO);
+        for pixel in &mut image.pixels[..64 * 16] {
+            *pixel = Vec3::ONE;
+        }
+
+        let cube = CubeFaces::from_equirectangular(&image, 8);
+        assert_eq!(cube.faces.iter().map(Vec::len).sum::<usize>(), 6 * 64);
+        assert!(cube.faces[2].iter().all(|color| *color == Vec3::ONE));
+        assert!(cube.faces[3].iter().all(|color| *color == Vec3::ZERO));
+    }
+
+    #[test]
+    fn test_mip_chain() {
+        let cube = CubeFaces::from_equirectangular(&uniform_image(32, 16, Vec3::splat(2.0)), 16);
+        let mips = cube.mip_chain();
+        let sizes: Vec<u32> = mips.iter().map(|mip| mip.size).collect();
+        assert_eq!(sizes, vec![16, 8, 4, 2, 1]);
+        assert!((mips[4].faces[0][0] - Vec3::splat(2.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_irradiance_sh() {
+        // A uniform sky lights every surface with its own radiance
+        let color = Vec3::new(0.5, 1.0, 2.0);
+        let cube = CubeFaces::from_equirectangular(&uniform_image(64, 32, color), 16);
+        let sh = irradiance_sh(&cube);
+        for normal in [Vec3::X, Vec3::NEG_Y, Vec3::new(1.0, 1.0, -1.0).normalize()] {
+            assert!((evaluate_sh(&sh, normal) - color).length() < 1e-2);
+        }
+
+        // A sky lit from above lights upward facing surfaces more
+        let mut image = uniform_image(64, 32, Vec3::ZERO);
+        for pixel in &mut image.pixels[..64 * 16] {
+            *pixel = Vec3::ONE;
+        }
+        let sh = irradiance_sh(&CubeFaces::from_equirectangular(&image, 16));
+        let up = evaluate_sh(&sh, Vec3::Y).x;
+        let side = evaluate_sh(&sh, Vec3::X).x;
+        let down = evaluate_sh(&sh, Vec3::NEG_Y).x;
+        assert!(up > side && side > down);
+        assert!((side - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_f32_to_f16() {
+        assert_eq!(f32_to_f16(0.0), 0x0000);
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        // Out of range values clamp instead of becoming infinite
+        assert_eq!(f32_to_f16(1.0e6), 0x7bff);
+        // Smallest subnormal half
+        assert_eq!(f32_to_f16(2.0f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16(f32::NAN), 0);
+    }
+
+    #[test]
+    fn test_face_paths() {
+        let skybox = Skybox::new("sky/{face}.png");
+        assert!(skybox.is_cubemap());
+        assert_eq!(skybox.face_paths()[0], "sky/px.png");
+        assert_eq!(skybox.face_paths()[5], "sky/nz.png");
+        assert!(!Skybox::new("sky.hdr").is_cubemap());
+    }
+
+    #[test]
+    fn test_skybox_uniform_galaxy_camera() {
+        // Extreme near/far ratios don't affect the sky directions
+        let galaxy = Camera::galaxy_scale(60.0, 2.0);
+        let regular = Camera::perspective(60.0, 2.0, 0.1, 100.0);
+        let skybox = Skybox::default();
+
+        let a = SkyboxUniform::new(&skybox, &galaxy, Quat::IDENTITY);
+        let b = SkyboxUniform::new(&skybox, &regular, Quat::IDENTITY);
+        assert_eq!(a, b);
+        assert!(a.view_scale.iter().all(|scale| scale.is_finite()));
+        assert!((a.view_scale[0] - 2.0 * a.view_scale[1]).abs() < 1e-6);
+    }
+}
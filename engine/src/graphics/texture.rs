@@ -0,0 +1,270 @@
+//! Texture loading and caching
+//!
+//! Textures are loaded from image files by asset path and shared between
+//! materials through the [`TextureCache`]. Missing or broken files fall back
+//! to 1x1 default textures so a material never fails to bind.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+/// Errors that can occur while loading a texture
+#[derive(Debug, thiserror::Error)]
+pub enum TextureError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Image decoding error: {0}")]
+    Image(#[from] image::ImageError),
+}
+
+/// How the texel data of a texture is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureKind {
+    /// Color data stored in sRGB (albedo, emissive)
+    Color,
+    /// Tangent space normal map stored linearly
+    Normal,
+    /// Linear non-color data (occlusion / roughness / metallic)
+    Data,
+}
+
+impl TextureKind {
+    /// GPU format used for textures of this kind
+    pub fn format(self) -> wgpu::TextureFormat {
+        match self {
+            TextureKind::Color => wgpu::TextureFormat::Rgba8UnormSrgb,
+            TextureKind::Normal | TextureKind::Data => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    /// Texel used when no texture is assigned
+    ///
+    /// White leaves color and ORM factors unchanged; the flat normal points
+    /// straight out of the surface.
+    pub fn default_texel(self) -> [u8; 4] {
+        match self {
+            TextureKind::Color | TextureKind::Data => [255, 255, 255, 255],
+            TextureKind::Normal => [128, 128, 255, 255],
+        }
+    }
+}
+
+/// A GPU texture with its default view
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub size: (u32, u32),
+}
+
+impl Texture {
+    /// Create a texture from tightly packed RGBA8 data
+    pub fn from_rgba8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        format: wgpu::TextureFormat,
+        label: Option<&str>,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            texture,
+            view,
+            size: (width, height),
+        }
+    }
+
+    /// Create a 1x1 texture filled with a single texel
+    pub fn solid(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texel: [u8; 4],
+        kind: TextureKind,
+        label: Option<&str>,
+    ) -> Self {
+        Self::from_rgba8(device, queue, 1, 1, &texel, kind.format(), label)
+    }
+
+    /// Load a texture from an image file
+    pub fn from_file(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &Path,
+        kind: TextureKind,
+    ) -> Result<Self, TextureError> {
+        let image = image::open(path)?.to_rgba8();
+        let (width, height) = image.dimensions();
+
+        debug!(path = ?path, width, height, kind = ?kind, "Loaded texture");
+
+        Ok(Self::from_rgba8(
+            device,
+            queue,
+            width,
+            height,
+            &image,
+            kind.format(),
+            path.to_str(),
+        ))
+    }
+}
+
+/// Cache of textures keyed by asset path and kind
+///
+/// Every requested path is loaded at most once. Failed loads are cached as the
+/// default texture of their kind so a broken path is only reported once.
+pub struct TextureCache {
+    textures: HashMap<(String, TextureKind), Arc<Texture>>,
+    defaults: HashMap<TextureKind, Arc<Texture>>,
+    sampler: wgpu::Sampler,
+}
+
+impl TextureCache {
+    /// Create a cache with default textures and a shared sampler
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let defaults = [TextureKind::Color, TextureKind::Normal, TextureKind::Data]
+            .into_iter()
+            .map(|kind| {
+                let label = format!("Default {kind:?} Texture");
+                let texture =
+                    Texture::solid(device, queue, kind.default_texel(), kind, Some(&label));
+                (kind, Arc::new(texture))
+            })
+            .collect();
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Material Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            textures: HashMap::new(),
+            defaults,
+            sampler,
+        }
+    }
+
+    /// Get the default texture for a kind
+    pub fn default_texture(&self, kind: TextureKind) -> Arc<Texture> {
+        self.defaults[&kind].clone()
+    }
+
+    /// Get a texture by asset path, loading it on first use
+    ///
+    /// An empty path returns the default texture for the kind.
+    pub fn get_or_load(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: &str,
+        kind: TextureKind,
+    ) -> Arc<Texture> {
+        if path.is_empty() {
+            return self.default_texture(kind);
+        }
+
+        let key = (path.to_string(), kind);
+        if let Some(texture) = self.textures.get(&key) {
+            return texture.clone();
+        }
+
+        let texture = match Texture::from_file(device, queue, Path::new(path), kind) {
+            Ok(texture) => Arc::new(texture),
+            Err(e) => {
+                error!("Failed to load texture {}: {}", path, e);
+                self.default_texture(kind)
+            }
+        };
+
+        self.textures.insert(key, texture.clone());
+        texture
+    }
+
+    /// Sampler shared by all material textures
+    pub fn sampler(&self) -> &wgpu::Sampler {
+        &self.sampler
+    }
+
+    /// Number of cached file textures
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// Check if no file textures are cached
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    /// Drop all cached file textures so they are reloaded on next use
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texture_kind_formats() {
+        assert_eq!(
+            TextureKind::Color.format(),
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        );
+        assert_eq!(
+            TextureKind::Normal.format(),
+            wgpu::TextureFormat::Rgba8Unorm
+        );
+        assert_eq!(TextureKind::Data.format(), wgpu::TextureFormat::Rgba8Unorm);
+    }
+
+    #[test]
+    fn test_default_texels() {
+        assert_eq!(TextureKind::Color.default_texel(), [255, 255, 255, 255]);
+        // Flat normal (0, 0, 1) encoded as unorm
+        assert_eq!(TextureKind::Normal.default_texel(), [128, 128, 255, 255]);
+    }
+}
@@ -3,18 +3,22 @@
 //! Provides uniform buffer structures that match the WGSL shader definitions.
 //! These structures are used to pass data from the CPU to the GPU.
 
+use crate::graphics::material::MaterialUniform;
 use bytemuck::{Pod, Zeroable};
-use glam::Mat4;
+use glam::{Mat4, Vec3};
 
 /// Camera uniform buffer data
 ///
 /// This struct matches the CameraUniform struct in the WGSL shader.
-/// Contains the view-projection matrix for transforming vertices.
+/// Contains the view-projection matrix for transforming vertices and the
+/// camera position in render space for view-dependent shading.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 pub struct CameraUniform {
     /// Combined view-projection matrix
     pub view_proj: [[f32; 4]; 4],
+    /// Camera position in render space (w unused)
+    pub position: [f32; 4],
 }
 
 impl CameraUniform {
@@ -22,15 +26,20 @@ impl CameraUniform {
     pub fn new(view_proj: Mat4) -> Self {
         Self {
             view_proj: view_proj.to_cols_array_2d(),
+            position: [0.0, 0.0, 0.0, 1.0],
         }
     }
+
+    /// Set the render space camera position
+    pub fn with_position(mut self, position: Vec3) -> Self {
+        self.position = position.extend(1.0).to_array();
+        self
+    }
 }
 
 impl Default for CameraUniform {
     fn default() -> Self {
-        Self {
-            view_proj: Mat4::IDENTITY.to_cols_array_2d(),
-        }
+        Self::new(Mat4::IDENTITY)
     }
 }
 
@@ -87,6 +96,7 @@ pub trait UniformBuffer: Pod {
 
 impl UniformBuffer for CameraUniform {}
 impl UniformBuffer for ObjectUniform {}
+impl UniformBuffer for MaterialUniform {}
 
 #[cfg(test)]
 mod tests {
@@ -95,8 +105,8 @@ mod tests {
     #[test]
     fn test_camera_uniform_size() {
         use std::mem;
-        // Camera uniform should be 64 bytes (16 floats * 4 bytes)
-        assert_eq!(mem::size_of::<CameraUniform>(), 64);
+        // Camera uniform should be 80 bytes (16 floats for matrix + 4 floats for position) * 4 bytes
+        assert_eq!(mem::size_of::<CameraUniform>(), 80);
     }
 
     #[test]
@@ -109,8 +119,9 @@ mod tests {
     #[test]
     fn test_camera_uniform_creation() {
         let view_proj = Mat4::perspective_rh(45.0_f32.to_radians(), 16.0 / 9.0, 0.1, 100.0);
-        let uniform = CameraUniform::new(view_proj);
+        let uniform = CameraUniform::new(view_proj).with_position(Vec3::new(1.0, 2.0, 3.0));
         assert_eq!(uniform.view_proj, view_proj.to_cols_array_2d());
+        assert_eq!(uniform.position, [1.0, 2.0, 3.0, 1.0]);
     }
 
     #[test]
@@ -102,9 +102,16 @@ impl ComponentRegistry {
         use crate::core::camera::{Camera, CameraWorldPosition};
         use crate::core::coordinates::WorldTransform;
         use crate::core::entity::components::{
-            GlobalTransform, GlobalWorldTransform, Name, ParentData, PreviousTransform, Transform,
+            GlobalTransform, GlobalWorldTransform, Name, ParentData, PreviousTransform,
+            PreviousWorldTransform, Transform,
+        };
+        use crate::graphics::{
+            DirectionalLight, Material, MeshId, PointLight, PostProcessSettings, Skybox, SpotLight,
+            Visibility, AABB,
+        };
+        use crate::physics::{
+            CharacterController, Collider, Joint, PhysicsMass, PhysicsVelocity, RigidBody,
         };
-        use crate::graphics::{Material, MeshId, Visibility, AABB};
         use crate::scripting::{ScriptProperties, ScriptRef};
 
         let mut registry = Self::new();
@@ -112,6 +119,7 @@ impl ComponentRegistry {
         // Register core components using the new Component trait
         Transform::register(&mut registry);
         PreviousTransform::register(&mut registry);
+        PreviousWorldTransform::register(&mut registry);
         GlobalTransform::register(&mut registry);
         GlobalWorldTransform::register(&mut registry);
         WorldTransform::register(&mut registry);
@@ -125,11 +133,28 @@ impl ComponentRegistry {
         Material::register(&mut registry);
         AABB::register(&mut registry);
         Visibility::register(&mut registry);
+        DirectionalLight::register(&mut registry);
+        PointLight::register(&mut registry);
+        SpotLight::register(&mut registry);
+        PostProcessSettings::register(&mut registry);
+        Skybox::register(&mut registry);
+
+        // Register physics components
+        RigidBody::register(&mut registry);
+        Collider::register(&mut registry);
+        PhysicsVelocity::register(&mut registry);
+        PhysicsMass::register(&mut registry);
+        Joint::register(&mut registry);
+        CharacterController::register(&mut registry);
 
         // Register scripting components
         ScriptRef::register(&mut registry);
         ScriptProperties::register(&mut registry);
 
+        // Register prefab components
+        crate::io::PrefabInstance::register(&mut registry);
+        crate::io::PrefabEntity::register(&mut registry);
+
         // Register audio components
         AudioSource::register(&mut registry);
         AudioListener::register(&mut registry);
@@ -266,6 +291,12 @@ mod tests {
         // Culling components
         assert!(registry.is_registered("AABB"));
         assert!(registry.is_registered("Visibility"));
+
+        // Physics components
+        assert!(registry.is_registered("RigidBody"));
+        assert!(registry.is_registered("Collider"));
+        assert!(registry.is_registered("PhysicsVelocity"));
+        assert!(registry.is_registered("PhysicsMass"));
     }
 
     #[test]
@@ -3,11 +3,13 @@
 pub mod component_registry;
 mod entity_mapper;
 pub mod hot_reload;
+pub mod prefab;
 mod scene;
 
 pub use component_registry::ComponentRegistry;
 pub use entity_mapper::EntityMapper;
 pub use hot_reload::{reload_scene_with_validation, ReloadCallback, SceneWatcher, WatcherConfig};
+pub use prefab::{Prefab, PrefabEntity, PrefabInstance, PrefabLibrary, PrefabOverride};
 pub use scene::{Scene, SceneError, SerializedEntity};
 
 #[cfg(test)]
@@ -147,6 +149,182 @@ mod tests {
         assert!(world.contains(mapper.remap(1).unwrap()));
     }
 
+    #[test]
+    fn test_light_round_trip() {
+        use crate::graphics::{DirectionalLight, PointLight, SpotLight};
+
+        let mut world = World::new();
+        world.spawn((
+            Transform::default(),
+            DirectionalLight {
+                color: [1.0, 0.9, 0.8],
+                intensity: 2.0,
+                cast_shadows: false,
+                ..Default::default()
+            },
+        ));
+        world.spawn((
+            Transform::default(),
+            PointLight {
+                range: 4.0,
+                ..Default::default()
+            },
+        ));
+        world.spawn((
+            Transform::default(),
+            SpotLight {
+                outer_angle: 45.0,
+                ..Default::default()
+            },
+        ));
+
+        let scene = Scene::from_world(&world);
+        let mut new_world = World::new();
+        scene.instantiate(&mut new_world).unwrap();
+
+        let directional = new_world
+            .query::<&DirectionalLight>()
+            .iter()
+            .map(|(_, light)| *light)
+            .next()
+            .unwrap();
+        assert_eq!(directional.color, [1.0, 0.9, 0.8]);
+        assert_eq!(directional.intensity, 2.0);
+        assert!(!directional.cast_shadows);
+
+        let point = new_world
+            .query::<&PointLight>()
+            .iter()
+            .map(|(_, light)| *light)
+            .next()
+            .unwrap();
+        assert_eq!(point.range, 4.0);
+
+        let spot = new_world
+            .query::<&SpotLight>()
+            .iter()
+            .map(|(_, light)| *light)
+            .next()
+            .unwrap();
+        assert_eq!(spot.outer_angle, 45.0);
+    }
+
+    #[test]
+    fn test_post_process_round_trip() {
+        use crate::core::camera::Camera;
+        use crate::graphics::{PostProcessSettings, Tonemapping};
+
+        let settings = PostProcessSettings {
+            exposure: 1.5,
+            tonemapping: Tonemapping::Reinhard,
+            fxaa: false,
+            ..Default::default()
+        };
+        let mut world = World::new();
+        world.spawn((Transform::default(), Camera::default(), settings));
+
+        let scene = Scene::from_world(&world);
+        let mut new_world = World::new();
+        scene.instantiate(&mut new_world).unwrap();
+
+        let loaded = new_world
+            .query::<&PostProcessSettings>()
+            .iter()
+            .map(|(_, settings)| *settings)
+            .next()
+            .unwrap();
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn test_skybox_round_trip() {
+        use crate::graphics::Skybox;
+
+        let skybox = Skybox {
+            rotation: 90.0,
+            environment_lighting: false,
+            ..Skybox::new("assets/sky/{face}.png")
+        };
+        let mut world = World::new();
+        world.spawn((skybox.clone(),));
+
+        let scene = Scene::from_world(&world);
+        let mut new_world = World::new();
+        scene.instantiate(&mut new_world).unwrap();
+
+        let loaded = new_world
+            .query::<&Skybox>()
+            .iter()
+            .map(|(_, skybox)| skybox.clone())
+            .next()
+            .unwrap();
+        assert_eq!(loaded, skybox);
+    }
+
+    #[test]
+    fn test_registry_round_trip() {
+        use crate::core::entity::components::Name;
+        use crate::physics::{RigidBody, RigidBodyType};
+
+        let mut world = World::new();
+        let parent = world.spawn((
+            Name::new("Parent"),
+            Transform::from_position(Vec3::new(1.0, 2.0, 3.0)),
+            RigidBody {
+                body_type: RigidBodyType::Fixed,
+                ..Default::default()
+            },
+        ));
+        world.spawn((Name::new("Child"), Transform::default(), Parent(parent)));
+
+        let registry = ComponentRegistry::with_default_components();
+        let scene = Scene::from_world_with_registry(&world, &registry);
+
+        let mut new_world = World::new();
+        let mapper = scene
+            .instantiate_with_registry(&mut new_world, &registry)
+            .unwrap();
+        assert_eq!(new_world.query::<()>().iter().count(), 2);
+
+        let new_parent = mapper.remap(0).unwrap();
+        let new_child = mapper.remap(1).unwrap();
+        assert_eq!(new_world.get::<Name>(new_parent).unwrap().0, "Parent");
+        assert_eq!(
+            new_world.get::<Transform>(new_parent).unwrap().position,
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            new_world.get::<RigidBody>(new_parent).unwrap().body_type,
+            RigidBodyType::Fixed
+        );
+        assert_eq!(new_world.get::<Parent>(new_child).unwrap().0, new_parent);
+    }
+
+    #[test]
+    fn test_restore_with_registry_keeps_handles() {
+        let mut world = World::new();
+        let entity = world.spawn((Transform::from_position(Vec3::X),));
+        let entities: Vec<_> = world.query::<()>().iter().map(|(e, _)| e).collect();
+
+        let registry = ComponentRegistry::with_default_components();
+        let snapshot = Scene::from_world_with_registry(&world, &registry);
+
+        // Simulate gameplay changing the world
+        world
+            .query_one_mut::<&mut Transform>(entity)
+            .unwrap()
+            .position = Vec3::Z;
+        let spawned = world.spawn((Transform::default(),));
+
+        world.inner_mut().clear();
+        snapshot
+            .restore_with_registry(&mut world, &registry, &entities)
+            .unwrap();
+
+        assert!(!world.contains(spawned));
+        assert_eq!(world.get::<Transform>(entity).unwrap().position, Vec3::X);
+    }
+
     #[test]
     fn test_scene_file_io() {
         let mut world = World::new();
@@ -0,0 +1,962 @@
+//! Prefab assets and their instances
+//!
+//! A prefab is a [`Scene`] file whose entities are spawned together below an
+//! instance root carrying a [`PrefabInstance`]. Each entity spawned from the
+//! prefab keeps its stable ID in a [`PrefabEntity`], so instances can be rebuilt
+//! when the prefab file changes. Properties changed on an instance are stored as
+//! overrides on its root and re-applied on every rebuild.
+//!
+//! Prefabs can contain instances of other prefabs. The members of such a nested
+//! instance belong to its root, whose `PrefabInstance` is an ordinary component
+//! of the outer prefab, so nested overrides are overridden like any other value.
+
+use super::component_registry::ComponentRegistry;
+use super::scene::{Scene, SceneError, SerializedEntity};
+use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt};
+use crate::core::entity::components::{GlobalTransform, Name, Parent, ParentData, Transform};
+use crate::core::entity::World;
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use tracing::{debug, info, warn};
+
+/// File extension of prefab assets
+pub const PREFAB_EXTENSION: &str = "prefab";
+
+/// Components that describe the hierarchy or are derived at runtime
+///
+/// They are never stored in a prefab and never become overrides.
+const UNTRACKED_COMPONENTS: &[&str] = &[
+    "Parent",
+    "PrefabEntity",
+    "GlobalTransform",
+    "GlobalWorldTransform",
+    "PreviousTransform",
+    "PreviousWorldTransform",
+    "Visibility",
+];
+
+/// Root of a spawned prefab, the prefab's root entities are its children
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, engine_derive::Component)]
+#[component(name = "PrefabInstance")]
+pub struct PrefabInstance {
+    /// Path of the prefab file
+    pub source: String,
+    /// Properties of this instance that differ from the prefab
+    #[serde(default)]
+    pub overrides: Vec<PrefabOverride>,
+}
+
+impl PrefabInstance {
+    /// Create an instance of a prefab file without overrides
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+/// A property of an instance that differs from the prefab
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefabOverride {
+    /// ID of the entity in the prefab
+    pub entity: u64,
+    /// Name of the component
+    pub component: String,
+    /// Top-level field of the component, None for the whole component
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// New value, None if the instance removed the component
+    pub value: Option<Value>,
+}
+
+/// Marks an entity spawned from a prefab with its ID in that prefab
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, engine_derive::Component,
+)]
+#[component(name = "PrefabEntity")]
+pub struct PrefabEntity {
+    /// ID of the entity within its prefab, stable across edits of the prefab
+    pub id: u64,
+}
+
+/// A reusable group of entities stored as scene JSON
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Prefab {
+    /// Entities of the prefab, including the members of nested instances
+    pub scene: Scene,
+}
+
+impl Prefab {
+    /// Load a prefab from a JSON file
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+        Ok(Self {
+            scene: Scene::load_from_file(path)?,
+        })
+    }
+
+    /// Save this prefab to a JSON file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), SceneError> {
+        self.scene.save_to_file(path)
+    }
+
+    /// Create a prefab from entities and everything parented to them
+    ///
+    /// Entities the prefab owns get a `PrefabEntity` with an ID that is unique
+    /// within the prefab. IDs they already have are kept, so applying an
+    /// instance to its prefab keeps its overrides attached to the same entities.
+    /// Members of nested instances keep the IDs of their own prefab.
+    pub fn from_entities(
+        world: &mut World,
+        registry: &ComponentRegistry,
+        roots: &[Entity],
+    ) -> Self {
+        let entities = collect_subtrees(world, roots);
+        let index: HashMap<Entity, u64> = entities
+            .iter()
+            .enumerate()
+            .map(|(id, &entity)| (entity, id as u64))
+            .collect();
+
+        // Entities below an instance root in the selection belong to that instance
+        let owned: Vec<Entity> = entities
+            .iter()
+            .copied()
+            .filter(|&entity| {
+                ancestors(world, entity)
+                    .take_while(|ancestor| index.contains_key(ancestor))
+                    .all(|ancestor| world.get::<PrefabInstance>(ancestor).is_err())
+            })
+            .collect();
+
+        let mut used = HashSet::new();
+        let mut missing = Vec::new();
+        for &entity in &owned {
+            match world.get::<PrefabEntity>(entity).map(|link| link.id) {
+                Ok(id) if used.insert(id) => {}
+                _ => missing.push(entity),
+            }
+        }
+        let mut next_id = used.iter().max().map_or(0, |id| id + 1);
+        for entity in missing {
+            let _ = world.insert_one(entity, PrefabEntity { id: next_id });
+            next_id += 1;
+        }
+
+        let serialized = entities
+            .iter()
+            .map(|&entity| {
+                let mut components = tracked_components(world, registry, entity);
+                if let Ok(link) = world.get::<PrefabEntity>(entity) {
+                    if let Ok(value) = serde_json::to_value(*link) {
+                        components.insert("PrefabEntity".to_string(), value);
+                    }
+                }
+                // Parents outside the prefab are dropped, those entities become its roots
+                if let Ok(parent) = world.get::<Parent>(entity) {
+                    if let Some(&parent_id) = index.get(&parent.0) {
+                        let parent_data = ParentData::from_parent_with_id(*parent, parent_id);
+                        if let Ok(value) = serde_json::to_value(parent_data) {
+                            components.insert("Parent".to_string(), value);
+                        }
+                    }
+                }
+                SerializedEntity { components }
+            })
+            .collect();
+
+        Self {
+            scene: Scene {
+                entities: serialized,
+            },
+        }
+    }
+
+    /// Index of the parent of a serialized entity
+    fn parent_index(&self, index: usize) -> Option<usize> {
+        let value = self.scene.entities[index].components.get("Parent")?;
+        let parent = serde_json::from_value::<ParentData>(value.clone()).ok()?;
+        let parent_index = parent.entity_id as usize;
+        (parent_index < self.scene.entities.len()).then_some(parent_index)
+    }
+
+    /// Stable ID of a serialized entity, its index if it has none
+    fn entity_id(&self, index: usize) -> u64 {
+        self.scene.entities[index]
+            .components
+            .get("PrefabEntity")
+            .and_then(|value| serde_json::from_value::<PrefabEntity>(value.clone()).ok())
+            .map_or(index as u64, |link| link.id)
+    }
+
+    /// Index of the nested instance root a serialized entity belongs to
+    fn owner_index(&self, index: usize) -> Option<usize> {
+        let mut current = self.parent_index(index);
+        // Bounded so a broken file with a parent cycle can't hang
+        for _ in 0..self.scene.entities.len() {
+            let ancestor = current?;
+            if self.scene.entities[ancestor]
+                .components
+                .contains_key("PrefabInstance")
+            {
+                return Some(ancestor);
+            }
+            current = self.parent_index(ancestor);
+        }
+        None
+    }
+
+    /// Index and ID of the entities the prefab itself owns
+    fn owned_entities(&self) -> Vec<(usize, u64)> {
+        (0..self.scene.entities.len())
+            .filter(|&index| self.owner_index(index).is_none())
+            .map(|index| (index, self.entity_id(index)))
+            .collect()
+    }
+
+    /// Components of a serialized entity that instances track
+    fn tracked_components(&self, index: usize) -> HashMap<String, Value> {
+        self.scene.entities[index]
+            .components
+            .iter()
+            .filter(|(name, _)| !UNTRACKED_COMPONENTS.contains(&name.as_str()))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+}
+
+/// Prefabs loaded from disk, by source path
+///
+/// Instances are compared against the cached prefab they were built from, so
+/// changes to a prefab file only show up after [`PrefabLibrary::clear`] or an
+/// apply through [`apply_instance`].
+#[derive(Debug, Default)]
+pub struct PrefabLibrary {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl PrefabLibrary {
+    /// Create an empty library
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a prefab, loading it from disk on first use
+    pub fn load(&mut self, source: &str) -> Result<&Prefab, SceneError> {
+        if !self.prefabs.contains_key(source) {
+            let prefab = Prefab::load_from_file(source)?;
+            debug!(source, "Loaded prefab");
+            self.prefabs.insert(source.to_string(), prefab);
+        }
+        Ok(&self.prefabs[source])
+    }
+
+    /// Add or replace a prefab without touching the disk
+    pub fn insert(&mut self, source: impl Into<String>, prefab: Prefab) {
+        self.prefabs.insert(source.into(), prefab);
+    }
+
+    /// Forget all prefabs so they are read from disk again
+    pub fn clear(&mut self) {
+        self.prefabs.clear();
+    }
+}
+
+/// Spawn an instance of a prefab, returning its root
+///
+/// The root is named after the prefab file and placed at the origin.
+pub fn instantiate(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+    source: &str,
+) -> Result<Entity, SceneError> {
+    let root = world.spawn((
+        Name::new(instance_name(source)),
+        Transform::default(),
+        GlobalTransform::default(),
+        PrefabInstance::new(source),
+    ));
+
+    if let Err(e) = refresh_instance(world, registry, library, root) {
+        despawn_recursive(world, root);
+        return Err(e);
+    }
+    info!(source, entity = ?root, "Instantiated prefab");
+    Ok(root)
+}
+
+/// Turn entities and their children into a prefab file and an instance of it
+///
+/// The entities stay where they are and are moved below a new instance root,
+/// which takes the place of the first entity in the hierarchy.
+pub fn create_prefab(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+    roots: &[Entity],
+    source: &str,
+) -> Result<Entity, SceneError> {
+    let prefab = Prefab::from_entities(world, registry, roots);
+    prefab.save_to_file(source)?;
+    library.insert(source, prefab);
+
+    let root = world.spawn((
+        Name::new(instance_name(source)),
+        Transform::default(),
+        GlobalTransform::default(),
+        PrefabInstance::new(source),
+    ));
+    let parent = roots
+        .first()
+        .and_then(|&entity| world.get::<Parent>(entity).ok().map(|parent| *parent));
+    if let Some(parent) = parent {
+        let _ = world.insert_one(root, parent);
+    }
+    for &entity in roots {
+        let _ = world.insert_one(entity, Parent(root));
+    }
+
+    info!(source, entity = ?root, "Created prefab");
+    Ok(root)
+}
+
+/// Rebuild an instance from its prefab and overrides
+///
+/// Members keep their entity handles. Entities added to the prefab are
+/// spawned, entities removed from it are despawned with their children, and
+/// nested instances are rebuilt from their own prefabs.
+pub fn refresh_instance(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+    root: Entity,
+) -> Result<(), SceneError> {
+    refresh_instance_nested(world, registry, library, root, &mut Vec::new())
+}
+
+fn refresh_instance_nested(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+    root: Entity,
+    sources: &mut Vec<String>,
+) -> Result<(), SceneError> {
+    let instance = world
+        .get::<PrefabInstance>(root)
+        .map(|instance| (*instance).clone())
+        .map_err(|_| SceneError::ComponentError(format!("{root:?} is not a prefab instance")))?;
+    if sources.contains(&instance.source) {
+        return Err(SceneError::ComponentError(format!(
+            "Prefab {} contains itself",
+            instance.source
+        )));
+    }
+    let prefab = library.load(&instance.source)?.clone();
+    let owned = prefab.owned_entities();
+
+    // Update the members and spawn the entities that are new in the prefab
+    let mut members = instance_members(world, root);
+    let mut live = HashMap::new();
+    for &(index, id) in &owned {
+        let mut components = prefab.tracked_components(index);
+        let overrides = instance.overrides.iter().filter(|o| o.entity == id);
+        apply_overrides(&mut components, overrides);
+
+        let entity = members
+            .remove(&id)
+            .unwrap_or_else(|| world.spawn((PrefabEntity { id }, GlobalTransform::default())));
+        set_tracked_components(world, registry, entity, &components);
+        live.insert(id, entity);
+    }
+
+    // Entities removed from the prefab go away with everything below them
+    for (id, entity) in members {
+        debug!(id, entity = ?entity, "Removing entity deleted from prefab");
+        despawn_recursive(world, entity);
+    }
+
+    sources.push(instance.source.clone());
+    for &(index, id) in &owned {
+        if prefab.scene.entities[index]
+            .components
+            .contains_key("PrefabInstance")
+        {
+            refresh_instance_nested(world, registry, library, live[&id], sources)?;
+        }
+    }
+    sources.pop();
+
+    // Parents last, they may be members of the nested instances rebuilt above
+    for &(index, id) in &owned {
+        let parent = prefab
+            .parent_index(index)
+            .and_then(|parent| resolve_member(world, &prefab, parent, &live))
+            .unwrap_or(root);
+        let _ = world.insert_one(live[&id], Parent(parent));
+    }
+
+    debug!(source = %instance.source, entity = ?root, "Refreshed prefab instance");
+    Ok(())
+}
+
+/// Rebuild every instance in the world, e.g. after loading a scene
+///
+/// Instances whose prefab can't be loaded are left as they were saved.
+/// Returns the number of instances rebuilt.
+pub fn refresh_instances(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+) -> usize {
+    // Outer instances rebuild the instances nested in them
+    let roots: Vec<Entity> = world
+        .query::<&PrefabInstance>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect();
+    let outermost: Vec<Entity> = roots
+        .into_iter()
+        .filter(|&root| owning_instance(world, root).is_none())
+        .collect();
+
+    let mut refreshed = 0;
+    for root in outermost {
+        match refresh_instance(world, registry, library, root) {
+            Ok(()) => refreshed += 1,
+            Err(e) => warn!(entity = ?root, error = %e, "Failed to refresh prefab instance"),
+        }
+    }
+    refreshed
+}
+
+/// Store how every instance differs from its prefab as overrides
+///
+/// Call this before saving a scene, the overrides are what lets instances
+/// pick up later changes to their prefab when the scene is loaded again.
+pub fn update_overrides(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+) {
+    // Nested instances first, their overrides are part of the outer instance
+    let mut roots: Vec<(usize, Entity)> = world
+        .query::<&PrefabInstance>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|root| (nesting_depth(world, root), root))
+        .collect();
+    roots.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (_, root) in roots {
+        let Ok(source) = world.get::<PrefabInstance>(root).map(|i| i.source.clone()) else {
+            continue;
+        };
+        let prefab = match library.load(&source) {
+            Ok(prefab) => prefab,
+            Err(e) => {
+                warn!(source, error = %e, "Keeping saved overrides of prefab instance");
+                continue;
+            }
+        };
+        let overrides = instance_overrides(world, registry, prefab, root);
+        if let Ok(mut instance) = world.query_one_mut::<&mut PrefabInstance>(root) {
+            instance.overrides = overrides;
+        }
+    }
+}
+
+/// Write an instance back to its prefab and rebuild all instances of it
+///
+/// Other instances keep their own overrides. Entities added below the
+/// instance become part of the prefab.
+pub fn apply_instance(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+    root: Entity,
+) -> Result<(), SceneError> {
+    let source = world
+        .get::<PrefabInstance>(root)
+        .map(|instance| instance.source.clone())
+        .map_err(|_| SceneError::ComponentError(format!("{root:?} is not a prefab instance")))?;
+
+    // Capture the edits of all instances against the prefab they were built from
+    update_overrides(world, registry, library);
+
+    let children = children_of(world, root);
+    let prefab = Prefab::from_entities(world, registry, &children);
+    prefab.save_to_file(&source)?;
+    library.insert(source.clone(), prefab);
+    if let Ok(mut instance) = world.query_one_mut::<&mut PrefabInstance>(root) {
+        instance.overrides.clear();
+    }
+
+    let instances: Vec<Entity> = world
+        .query::<&PrefabInstance>()
+        .iter()
+        .filter(|(_, instance)| instance.source == source)
+        .map(|(entity, _)| entity)
+        .collect();
+    for instance in instances {
+        if let Err(e) = refresh_instance(world, registry, library, instance) {
+            warn!(entity = ?instance, error = %e, "Failed to update prefab instance");
+        }
+    }
+
+    info!(source, "Applied prefab instance");
+    Ok(())
+}
+
+/// Drop the overrides of an instance so it matches its prefab again
+pub fn revert_instance(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    library: &mut PrefabLibrary,
+    root: Entity,
+) -> Result<(), SceneError> {
+    if let Ok(mut instance) = world.query_one_mut::<&mut PrefabInstance>(root) {
+        instance.overrides.clear();
+    }
+    refresh_instance(world, registry, library, root)
+}
+
+/// Overrides an instance would have if it were saved now
+pub fn instance_overrides(
+    world: &World,
+    registry: &ComponentRegistry,
+    prefab: &Prefab,
+    root: Entity,
+) -> Vec<PrefabOverride> {
+    let members = instance_members(world, root);
+    let mut overrides = Vec::new();
+    for (index, id) in prefab.owned_entities() {
+        let Some(&entity) = members.get(&id) else {
+            continue;
+        };
+        let expected = prefab.tracked_components(index);
+        let actual = tracked_components(world, registry, entity);
+        diff_components(id, &expected, &actual, &mut overrides);
+    }
+    overrides.sort_by(|a, b| {
+        (a.entity, &a.component, &a.field).cmp(&(b.entity, &b.component, &b.field))
+    });
+    overrides
+}
+
+/// Instance root an entity was spawned by, if any
+pub fn owning_instance(world: &World, entity: Entity) -> Option<Entity> {
+    world.get::<PrefabEntity>(entity).ok()?;
+    ancestors(world, entity).find(|&ancestor| world.get::<PrefabInstance>(ancestor).is_ok())
+}
+
+/// Name of new instance roots, the file name of the prefab
+fn instance_name(source: &str) -> String {
+    Path::new(source).file_stem().map_or_else(
+        || source.to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    )
+}
+
+/// Members of an instance by their ID in the prefab
+fn instance_members(world: &World, root: Entity) -> HashMap<u64, Entity> {
+    world
+        .query::<&PrefabEntity>()
+        .iter()
+        .map(|(entity, link)| (entity, link.id))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .filter(|&(entity, _)| owning_instance(world, entity) == Some(root))
+        .map(|(entity, id)| (id, entity))
+        .collect()
+}
+
+/// Live entity of a serialized prefab entity, which may be a nested member
+fn resolve_member(
+    world: &World,
+    prefab: &Prefab,
+    index: usize,
+    live: &HashMap<u64, Entity>,
+) -> Option<Entity> {
+    let id = prefab.entity_id(index);
+    match prefab.owner_index(index) {
+        None => live.get(&id).copied(),
+        Some(owner) => {
+            let owner = resolve_member(world, prefab, owner, live)?;
+            instance_members(world, owner).get(&id).copied()
+        }
+    }
+}
+
+/// Number of instances an instance root is nested in
+fn nesting_depth(world: &World, root: Entity) -> usize {
+    let mut depth = 0;
+    let mut current = root;
+    while let Some(owner) = owning_instance(world, current) {
+        depth += 1;
+        current = owner;
+    }
+    depth
+}
+
+/// Ancestors of an entity, nearest first
+fn ancestors(world: &World, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+    let mut visited = HashSet::from([entity]);
+    std::iter::successors(Some(entity), move |&current| {
+        let parent = world.get::<Parent>(current).ok()?.0;
+        // Stop at a parent cycle instead of looping forever
+        visited.insert(parent).then_some(parent)
+    })
+    .skip(1)
+}
+
+/// Direct children of an entity
+fn children_of(world: &World, entity: Entity) -> Vec<Entity> {
+    world
+        .query::<&Parent>()
+        .iter()
+        .filter(|(_, parent)| parent.0 == entity)
+        .map(|(child, _)| child)
+        .collect()
+}
+
+/// Entities and all their descendants, each entity once
+fn collect_subtrees(world: &World, roots: &[Entity]) -> Vec<Entity> {
+    let mut entities: Vec<Entity> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut next = 0;
+    entities.extend(roots.iter().copied().filter(|&root| seen.insert(root)));
+    while next < entities.len() {
+        let children = children_of(world, entities[next]);
+        entities.extend(children.into_iter().filter(|&child| seen.insert(child)));
+        next += 1;
+    }
+    entities
+}
+
+/// Despawn an entity and everything parented below it
+fn despawn_recursive(world: &mut World, entity: Entity) {
+    for entity in collect_subtrees(world, &[entity]) {
+        let _ = world.despawn(entity);
+    }
+}
+
+/// Serialized components of an entity that instances track
+fn tracked_components(
+    world: &World,
+    registry: &ComponentRegistry,
+    entity: Entity,
+) -> HashMap<String, Value> {
+    registry
+        .iter_metadata()
+        .filter(|metadata| !UNTRACKED_COMPONENTS.contains(&metadata.name))
+        .filter_map(
+            |metadata| match (metadata.serialize_component)(world, entity)? {
+                Ok(value) => Some((metadata.name.to_string(), value)),
+                Err(e) => {
+                    warn!(component = metadata.name, error = %e, "Failed to serialize component");
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Make the tracked components of an entity match `components`
+fn set_tracked_components(
+    world: &mut World,
+    registry: &ComponentRegistry,
+    entity: Entity,
+    components: &HashMap<String, Value>,
+) {
+    for metadata in registry.iter_metadata() {
+        if UNTRACKED_COMPONENTS.contains(&metadata.name) {
+            continue;
+        }
+        let result = match components.get(metadata.name) {
+            Some(value) => (metadata.insert_component)(world, entity, value),
+            None if (metadata.has_component)(world, entity) => {
+                (metadata.remove_component)(world, entity)
+            }
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            warn!(component = metadata.name, entity = ?entity, error = %e, "Failed to update prefab member");
+        }
+    }
+    for name in components.keys() {
+        if registry.get_metadata_by_name(name).is_none() {
+            warn!(component = %name, "Unknown component type in prefab, skipping");
+        }
+    }
+}
+
+/// Record how the components of one entity differ from the prefab
+///
+/// Objects are compared field by field, so changing one field of an instance
+/// still lets the other fields follow the prefab.
+fn diff_components(
+    id: u64,
+    expected: &HashMap<String, Value>,
+    actual: &HashMap<String, Value>,
+    overrides: &mut Vec<PrefabOverride>,
+) {
+    let names: BTreeSet<&String> = expected.keys().chain(actual.keys()).collect();
+    for name in names {
+        let mut push = |field: Option<&String>, value: Option<&Value>| {
+            overrides.push(PrefabOverride {
+                entity: id,
+                component: name.clone(),
+                field: field.cloned(),
+                value: value.cloned(),
+            });
+        };
+        match (expected.get(name), actual.get(name)) {
+            (Some(Value::Object(expected)), Some(Value::Object(actual))) => {
+                let fields: BTreeSet<&String> = expected.keys().chain(actual.keys()).collect();
+                for field in fields {
+                    // Fields the instance lacks keep following the prefab
+                    if let Some(value) = actual.get(field) {
+                        if expected.get(field) != Some(value) {
+                            push(Some(field), Some(value));
+                        }
+                    }
+                }
+            }
+            (Some(expected), Some(actual)) if expected != actual => push(None, Some(actual)),
+            (None, Some(actual)) => push(None, Some(actual)),
+            (Some(_), None) => push(None, None),
+            _ => {}
+        }
+    }
+}
+
+/// Apply overrides to the components of a prefab entity
+fn apply_overrides<'a>(
+    components: &mut HashMap<String, Value>,
+    overrides: impl Iterator<Item = &'a PrefabOverride>,
+) {
+    for o in overrides {
+        match (&o.field, &o.value) {
+            (None, Some(value)) => {
+                components.insert(o.component.clone(), value.clone());
+            }
+            (None, None) => {
+                components.remove(&o.component);
+            }
+            (Some(field), Some(value)) => {
+                // Field overrides of components the prefab no longer has are dropped
+                if let Some(Value::Object(object)) = components.get_mut(&o.component) {
+                    object.insert(field.clone(), value.clone());
+                }
+            }
+            (Some(_), None) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    fn name(world: &World, entity: Entity) -> String {
+        world.get::<Name>(entity).unwrap().0.clone()
+    }
+
+    fn position(world: &World, entity: Entity) -> Vec3 {
+        world.get::<Transform>(entity).unwrap().position
+    }
+
+    fn find(world: &World, wanted: &str) -> Vec<Entity> {
+        world
+            .query::<&Name>()
+            .iter()
+            .filter(|(_, name)| name.0 == wanted)
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// A lamp prefab with a post and a bulb below it
+    fn lamp(world: &mut World, registry: &ComponentRegistry) -> Prefab {
+        let post = world.spawn((
+            Name::new("Post"),
+            Transform::from_position(Vec3::new(0.0, 1.0, 0.0)),
+            GlobalTransform::default(),
+        ));
+        world.spawn((
+            Name::new("Bulb"),
+            Transform::from_position(Vec3::new(0.0, 2.0, 0.0)),
+            GlobalTransform::default(),
+            Parent(post),
+        ));
+        let prefab = Prefab::from_entities(world, registry, &[post]);
+        world.inner_mut().clear();
+        prefab
+    }
+
+    #[test]
+    fn test_instantiate_and_overrides_survive_reload() {
+        let registry = ComponentRegistry::with_default_components();
+        let mut library = PrefabLibrary::new();
+        let mut world = World::new();
+        library.insert("lamp.prefab", lamp(&mut world, &registry));
+
+        let first = instantiate(&mut world, &registry, &mut library, "lamp.prefab").unwrap();
+        let second = instantiate(&mut world, &registry, &mut library, "lamp.prefab").unwrap();
+        assert_eq!(name(&world, first), "lamp");
+        assert_eq!(find(&world, "Bulb").len(), 2);
+
+        // Move the bulb of the first instance only
+        let bulb = *instance_members(&world, first)
+            .values()
+            .find(|&&e| name(&world, e) == "Bulb")
+            .unwrap();
+        world
+            .query_one_mut::<&mut Transform>(bulb)
+            .unwrap()
+            .position
+            .x = 5.0;
+        update_overrides(&mut world, &registry, &mut library);
+        let overrides = world
+            .get::<PrefabInstance>(first)
+            .unwrap()
+            .overrides
+            .clone();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].field.as_deref(), Some("position"));
+        assert!(world
+            .get::<PrefabInstance>(second)
+            .unwrap()
+            .overrides
+            .is_empty());
+
+        // Round trip through scene JSON, then change the prefab on disk
+        let scene = Scene::from_world(&world);
+        let json = serde_json::to_string(&scene).unwrap();
+        let mut loaded = World::new();
+        serde_json::from_str::<Scene>(&json)
+            .unwrap()
+            .instantiate(&mut loaded)
+            .unwrap();
+        let mut changed = library.load("lamp.prefab").unwrap().clone();
+        for entity in &mut changed.scene.entities {
+            if let Some(Value::Object(transform)) = entity.components.get_mut("Transform") {
+                transform.insert("scale".to_string(), serde_json::json!([2.0, 2.0, 2.0]));
+            }
+        }
+        let mut reloaded = PrefabLibrary::new();
+        reloaded.insert("lamp.prefab", changed);
+        assert_eq!(refresh_instances(&mut loaded, &registry, &mut reloaded), 2);
+
+        // The override stays, the prefab change reaches every instance
+        let bulbs = find(&loaded, "Bulb");
+        assert_eq!(bulbs.len(), 2);
+        let mut xs: Vec<f32> = bulbs.iter().map(|&b| position(&loaded, b).x).collect();
+        xs.sort_by(f32::total_cmp);
+        assert_eq!(xs, vec![0.0, 5.0]);
+        for bulb in bulbs {
+            assert_eq!(
+                loaded.get::<Transform>(bulb).unwrap().scale,
+                Vec3::splat(2.0)
+            );
+            let parent = loaded.get::<Parent>(bulb).unwrap().0;
+            assert_eq!(name(&loaded, parent), "Post");
+        }
+    }
+
+    #[test]
+    fn test_nested_prefab_and_removed_component() {
+        let registry = ComponentRegistry::with_default_components();
+        let mut library = PrefabLibrary::new();
+        let mut world = World::new();
+        library.insert("lamp.prefab", lamp(&mut world, &registry));
+
+        // A street holds a lamp instance whose post is renamed
+        let street = world.spawn((
+            Name::new("Street"),
+            Transform::default(),
+            GlobalTransform::default(),
+        ));
+        let lamp = instantiate(&mut world, &registry, &mut library, "lamp.prefab").unwrap();
+        world.insert_one(lamp, Parent(street)).unwrap();
+        let post = find(&world, "Post")[0];
+        world.insert_one(post, Name::new("Tall Post")).unwrap();
+        update_overrides(&mut world, &registry, &mut library);
+        let street_prefab = Prefab::from_entities(&mut world, &registry, &[street]);
+        world.inner_mut().clear();
+        library.insert("street.prefab", street_prefab);
+
+        let instance = instantiate(&mut world, &registry, &mut library, "street.prefab").unwrap();
+        assert_eq!(find(&world, "Tall Post").len(), 1);
+        assert_eq!(find(&world, "Bulb").len(), 1);
+        let nested = find(&world, "lamp")[0];
+        assert_eq!(owning_instance(&world, nested), Some(instance));
+        assert_eq!(
+            owning_instance(&world, find(&world, "Bulb")[0]),
+            Some(nested)
+        );
+
+        // Removing a component is an override too
+        let bulb = find(&world, "Bulb")[0];
+        world.inner_mut().remove_one::<Transform>(bulb).unwrap();
+        update_overrides(&mut world, &registry, &mut library);
+        refresh_instances(&mut world, &registry, &mut library);
+        assert!(world.get::<Transform>(bulb).is_err());
+
+        // Reverting goes back to the lamp prefab, dropping the rename as well
+        revert_instance(&mut world, &registry, &mut library, nested).unwrap();
+        assert!(world.get::<Transform>(bulb).is_ok());
+        assert_eq!(find(&world, "Post").len(), 1);
+    }
+
+    #[test]
+    fn test_apply_instance_updates_other_instances() {
+        let registry = ComponentRegistry::with_default_components();
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir
+            .path()
+            .join("lamp.prefab")
+            .to_string_lossy()
+            .into_owned();
+        let mut library = PrefabLibrary::new();
+        let mut world = World::new();
+        lamp(&mut world, &registry).save_to_file(&source).unwrap();
+
+        let first = instantiate(&mut world, &registry, &mut library, &source).unwrap();
+        let second = instantiate(&mut world, &registry, &mut library, &source).unwrap();
+
+        // Add a shade to the first lamp and apply it
+        let post = *instance_members(&world, first)
+            .values()
+            .find(|&&e| name(&world, e) == "Post")
+            .unwrap();
+        world.spawn((
+            Name::new("Shade"),
+            Transform::default(),
+            GlobalTransform::default(),
+            Parent(post),
+        ));
+        apply_instance(&mut world, &registry, &mut library, first).unwrap();
+
+        assert_eq!(find(&world, "Shade").len(), 2);
+        assert_eq!(instance_members(&world, second).len(), 3);
+        assert!(world
+            .get::<PrefabInstance>(first)
+            .unwrap()
+            .overrides
+            .is_empty());
+
+        // The file on disk has the shade too
+        let mut fresh = World::new();
+        let mut fresh_library = PrefabLibrary::new();
+        instantiate(&mut fresh, &registry, &mut fresh_library, &source).unwrap();
+        assert_eq!(find(&fresh, "Shade").len(), 1);
+    }
+}
@@ -10,8 +10,13 @@ use crate::core::{
         World,
     },
 };
-use crate::graphics::{AssetManager, AssetValidationReport, Material, MeshId};
-use crate::physics::{Collider, PhysicsMass, PhysicsVelocity, RigidBody};
+use crate::graphics::{
+    AssetManager, AssetValidationReport, DirectionalLight, Material, MeshId, PointLight,
+    PostProcessSettings, Skybox, SpotLight,
+};
+use crate::physics::{
+    CharacterController, Collider, Joint, JointData, PhysicsMass, PhysicsVelocity, RigidBody,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -24,6 +29,7 @@ use crate::profile_zone;
 
 use super::component_registry::ComponentRegistry;
 use super::entity_mapper::EntityMapper;
+use super::prefab::{PrefabEntity, PrefabInstance};
 use crate::component_system::ComponentRegistryExt;
 
 /// Scene data structure containing serialized entities
@@ -88,7 +94,12 @@ impl Scene {
 
     /// Create a scene from a world using the component registry
     /// This method uses the registry to automatically serialize all registered components
+    ///
+    /// Entities are written in world iteration order, so the ID of an entity in
+    /// the scene is its index in `world.query::<()>()`.
     pub fn from_world_with_registry(world: &World, registry: &ComponentRegistry) -> Self {
+        profile_zone!("Scene::from_world_with_registry");
+
         let mut entities = Vec::new();
         let mut entity_to_id = HashMap::new();
 
@@ -104,24 +115,55 @@ impl Scene {
 
         // Second pass: serialize components using registry
         for (entity, ()) in world.query::<()>().iter() {
-            let components = HashMap::new();
+            let mut components = HashMap::new();
 
-            // Use registry to serialize all registered component types
             for metadata in registry.iter_metadata() {
-                if world.has_component_by_type_id(entity, metadata.type_id) {
-                    // For now, we'll need to handle serialization through the scene's existing logic
-                    // since get_component returns None (requires Clone constraint)
-                    // This will be addressed in a future iteration
-                    debug!(
-                        component = metadata.name,
+                // The runtime Parent and Joint components are remapped below
+                if metadata.name == "Parent" || metadata.name == "Joint" {
+                    continue;
+                }
+
+                match (metadata.serialize_component)(world, entity) {
+                    Some(Ok(value)) => {
+                        components.insert(metadata.name.to_string(), value);
+                    }
+                    Some(Err(e)) => {
+                        error!(
+                            error = %e,
+                            component = metadata.name,
+                            entity = ?entity,
+                            "Failed to serialize component via registry"
+                        );
+                    }
+                    None => {}
+                }
+            }
+
+            // Special handling for Parent component
+            if let Ok(parent) = world.get::<Parent>(entity) {
+                if let Some(&parent_id) = entity_to_id.get(&parent.0) {
+                    let parent_data = ParentData::from_parent_with_id(*parent, parent_id);
+                    match serde_json::to_value(parent_data) {
+                        Ok(value) => {
+                            components.insert("Parent".to_string(), value);
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to serialize Parent");
+                        }
+                    }
+                } else {
+                    warn!(
                         entity = ?entity,
-                        "Component detected via TypeId"
+                        parent = ?parent.0,
+                        "Parent entity not found in scene"
                     );
                 }
             }
 
-            // Fall back to manual serialization for now
-            // This will be replaced once we have proper type-erased component access
+            // Joints store their connected entity as a scene ID
+            if let Some(value) = serialize_joint(world, entity, &entity_to_id) {
+                components.insert("Joint".to_string(), value);
+            }
 
             entities.push(SerializedEntity { components });
         }
@@ -261,7 +303,7 @@ impl Scene {
 
             // Serialize Material component
             if let Ok(material) = world.get::<Material>(entity) {
-                match serde_json::to_value(*material) {
+                match serde_json::to_value(&*material) {
                     Ok(value) => {
                         components.insert("Material".to_string(), value);
                     }
@@ -271,6 +313,66 @@ impl Scene {
                 }
             }
 
+            // Serialize DirectionalLight component
+            if let Ok(light) = world.get::<DirectionalLight>(entity) {
+                match serde_json::to_value(*light) {
+                    Ok(value) => {
+                        components.insert("DirectionalLight".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize DirectionalLight");
+                    }
+                }
+            }
+
+            // Serialize PointLight component
+            if let Ok(light) = world.get::<PointLight>(entity) {
+                match serde_json::to_value(*light) {
+                    Ok(value) => {
+                        components.insert("PointLight".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize PointLight");
+                    }
+                }
+            }
+
+            // Serialize SpotLight component
+            if let Ok(light) = world.get::<SpotLight>(entity) {
+                match serde_json::to_value(*light) {
+                    Ok(value) => {
+                        components.insert("SpotLight".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize SpotLight");
+                    }
+                }
+            }
+
+            // Serialize PostProcessSettings component
+            if let Ok(settings) = world.get::<PostProcessSettings>(entity) {
+                match serde_json::to_value(*settings) {
+                    Ok(value) => {
+                        components.insert("PostProcessSettings".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize PostProcessSettings");
+                    }
+                }
+            }
+
+            // Serialize Skybox component
+            if let Ok(skybox) = world.get::<Skybox>(entity) {
+                match serde_json::to_value(&*skybox) {
+                    Ok(value) => {
+                        components.insert("Skybox".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize Skybox");
+                    }
+                }
+            }
+
             // Serialize Name component
             if let Ok(name) = world.get::<Name>(entity) {
                 match serde_json::to_value(&*name) {
@@ -307,6 +409,95 @@ impl Scene {
                 }
             }
 
+            // Serialize RigidBody component
+            if let Ok(rigid_body) = world.get::<RigidBody>(entity) {
+                match serde_json::to_value(&*rigid_body) {
+                    Ok(value) => {
+                        components.insert("RigidBody".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize RigidBody");
+                    }
+                }
+            }
+
+            // Serialize Collider component
+            if let Ok(collider) = world.get::<Collider>(entity) {
+                match serde_json::to_value(&*collider) {
+                    Ok(value) => {
+                        components.insert("Collider".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize Collider");
+                    }
+                }
+            }
+
+            // Serialize PhysicsVelocity component
+            if let Ok(velocity) = world.get::<PhysicsVelocity>(entity) {
+                match serde_json::to_value(*velocity) {
+                    Ok(value) => {
+                        components.insert("PhysicsVelocity".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize PhysicsVelocity");
+                    }
+                }
+            }
+
+            // Serialize PhysicsMass component
+            if let Ok(mass) = world.get::<PhysicsMass>(entity) {
+                match serde_json::to_value(*mass) {
+                    Ok(value) => {
+                        components.insert("PhysicsMass".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize PhysicsMass");
+                    }
+                }
+            }
+
+            // Special handling for Joint component
+            if let Some(value) = serialize_joint(world, entity, &entity_to_id) {
+                components.insert("Joint".to_string(), value);
+            }
+
+            // Serialize CharacterController component
+            if let Ok(controller) = world.get::<CharacterController>(entity) {
+                match serde_json::to_value(&*controller) {
+                    Ok(value) => {
+                        components.insert("CharacterController".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize CharacterController");
+                    }
+                }
+            }
+
+            // Serialize PrefabInstance component
+            if let Ok(instance) = world.get::<PrefabInstance>(entity) {
+                match serde_json::to_value(&*instance) {
+                    Ok(value) => {
+                        components.insert("PrefabInstance".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize PrefabInstance");
+                    }
+                }
+            }
+
+            // Serialize PrefabEntity component
+            if let Ok(link) = world.get::<PrefabEntity>(entity) {
+                match serde_json::to_value(*link) {
+                    Ok(value) => {
+                        components.insert("PrefabEntity".to_string(), value);
+                    }
+                    Err(e) => {
+                        error!(error = %e, "Failed to serialize PrefabEntity");
+                    }
+                }
+            }
+
             entities.push(SerializedEntity { components });
         }
 
@@ -467,6 +658,60 @@ impl Scene {
                             error!(error = %e, "Failed to deserialize Material");
                         }
                     },
+                    "DirectionalLight" => {
+                        match serde_json::from_value::<DirectionalLight>(value.clone()) {
+                            Ok(light) => {
+                                if let Err(e) = world.insert_one(entity, light) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert DirectionalLight");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize DirectionalLight");
+                            }
+                        }
+                    }
+                    "PointLight" => match serde_json::from_value::<PointLight>(value.clone()) {
+                        Ok(light) => {
+                            if let Err(e) = world.insert_one(entity, light) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert PointLight");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize PointLight");
+                        }
+                    },
+                    "SpotLight" => match serde_json::from_value::<SpotLight>(value.clone()) {
+                        Ok(light) => {
+                            if let Err(e) = world.insert_one(entity, light) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert SpotLight");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize SpotLight");
+                        }
+                    },
+                    "PostProcessSettings" => {
+                        match serde_json::from_value::<PostProcessSettings>(value.clone()) {
+                            Ok(settings) => {
+                                if let Err(e) = world.insert_one(entity, settings) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert PostProcessSettings");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize PostProcessSettings");
+                            }
+                        }
+                    }
+                    "Skybox" => match serde_json::from_value::<Skybox>(value.clone()) {
+                        Ok(skybox) => {
+                            if let Err(e) = world.insert_one(entity, skybox) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert Skybox");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize Skybox");
+                        }
+                    },
                     "Name" => match serde_json::from_value::<Name>(value.clone()) {
                         Ok(name) => {
                             if let Err(e) = world.insert_one(entity, name) {
@@ -532,6 +777,29 @@ impl Scene {
                             error!(error = %e, "Failed to deserialize Collider");
                         }
                     },
+                    "Joint" => match serde_json::from_value::<JointData>(value.clone()) {
+                        Ok(joint_data) => {
+                            let joint = joint_data.to_joint(|id| mapper.remap(id));
+                            if let Err(e) = world.insert_one(entity, joint) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert Joint");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize Joint");
+                        }
+                    },
+                    "CharacterController" => {
+                        match serde_json::from_value::<CharacterController>(value.clone()) {
+                            Ok(controller) => {
+                                if let Err(e) = world.insert_one(entity, controller) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert CharacterController");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize CharacterController");
+                            }
+                        }
+                    }
                     "PhysicsVelocity" => {
                         match serde_json::from_value::<PhysicsVelocity>(value.clone()) {
                             Ok(velocity) => {
@@ -603,6 +871,28 @@ impl Scene {
                             }
                         }
                     }
+                    "PrefabInstance" => {
+                        match serde_json::from_value::<PrefabInstance>(value.clone()) {
+                            Ok(instance) => {
+                                if let Err(e) = world.insert_one(entity, instance) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert PrefabInstance");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize PrefabInstance");
+                            }
+                        }
+                    }
+                    "PrefabEntity" => match serde_json::from_value::<PrefabEntity>(value.clone()) {
+                        Ok(link) => {
+                            if let Err(e) = world.insert_one(entity, link) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert PrefabEntity");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize PrefabEntity");
+                        }
+                    },
                     unknown => {
                         warn!(
                             component_type = unknown,
@@ -624,8 +914,9 @@ impl Scene {
         world: &mut World,
         registry: &ComponentRegistry,
     ) -> Result<EntityMapper, SceneError> {
+        profile_zone!("Scene::instantiate_with_registry");
+
         let mut mapper = EntityMapper::new();
-        let mut entities_to_build = Vec::new();
 
         info!(
             entity_count = self.entities.len(),
@@ -633,39 +924,124 @@ impl Scene {
         );
 
         // First pass: spawn all entities and build ID mapping
-        for (id, serialized_entity) in self.entities.iter().enumerate() {
+        for id in 0..self.entities.len() {
             let entity = world.spawn(());
             mapper.register(id as u64, entity);
-            entities_to_build.push((entity, serialized_entity));
             debug!(id = id, entity = ?entity, "Spawned entity");
         }
 
-        // Second pass: add components using registry
-        for (entity, serialized_entity) in entities_to_build {
+        self.insert_components_with_registry(world, registry, &mapper);
+
+        info!("Scene instantiation with registry complete");
+        Ok(mapper)
+    }
+
+    /// Instantiate this scene using the given entity handles, one per serialized
+    /// entity in order
+    ///
+    /// Existing entities with the same handles are replaced. This restores a
+    /// snapshot taken with [`Scene::from_world_with_registry`] without
+    /// invalidating entity handles held elsewhere.
+    pub fn restore_with_registry(
+        &self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+        entities: &[hecs::Entity],
+    ) -> Result<EntityMapper, SceneError> {
+        profile_zone!("Scene::restore_with_registry");
+
+        if entities.len() != self.entities.len() {
+            return Err(SceneError::ComponentError(format!(
+                "Expected {} entity handles, got {}",
+                self.entities.len(),
+                entities.len()
+            )));
+        }
+
+        let mut mapper = EntityMapper::new();
+        for (id, &entity) in entities.iter().enumerate() {
+            world.inner_mut().spawn_at(entity, ());
+            mapper.register(id as u64, entity);
+        }
+
+        self.insert_components_with_registry(world, registry, &mapper);
+
+        info!(
+            entity_count = entities.len(),
+            "Scene restored with registry"
+        );
+        Ok(mapper)
+    }
+
+    /// Insert the serialized components onto the mapped entities
+    fn insert_components_with_registry(
+        &self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+        mapper: &EntityMapper,
+    ) {
+        for (id, serialized_entity) in self.entities.iter().enumerate() {
+            let Some(entity) = mapper.remap(id as u64) else {
+                continue;
+            };
+
             for (component_type, value) in &serialized_entity.components {
-                // Try to deserialize using registry
-                if let Some(metadata) = registry.get_metadata_by_name(component_type) {
-                    match (metadata.deserializer)(value) {
-                        Ok(_component) => {
-                            // TODO: Need a way to insert component as dyn Any
-                            // For now, this is a placeholder
-                            debug!(component_type = component_type, entity = ?entity, "Would insert component via registry");
+                // Parent references are stored as scene IDs and need remapping
+                if component_type == "Parent" {
+                    match serde_json::from_value::<ParentData>(value.clone()) {
+                        Ok(parent_data) => {
+                            if let Some(parent) = parent_data.try_to_parent(|id| mapper.remap(id)) {
+                                if let Err(e) = world.insert_one(entity, parent) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert Parent");
+                                }
+                            } else {
+                                warn!(
+                                    parent_id = parent_data.entity_id,
+                                    "Parent entity not found in scene during instantiation"
+                                );
+                            }
                         }
                         Err(e) => {
-                            error!(error = %e, component_type = component_type, "Failed to deserialize component via registry");
+                            error!(error = %e, "Failed to deserialize Parent");
                         }
                     }
-                } else {
+                    continue;
+                }
+
+                // Joint references are remapped the same way
+                if component_type == "Joint" {
+                    match serde_json::from_value::<JointData>(value.clone()) {
+                        Ok(joint_data) => {
+                            let joint = joint_data.to_joint(|id| mapper.remap(id));
+                            if let Err(e) = world.insert_one(entity, joint) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert Joint");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize Joint");
+                        }
+                    }
+                    continue;
+                }
+
+                let Some(metadata) = registry.get_metadata_by_name(component_type) else {
                     warn!(
                         component_type = component_type,
                         "Unknown component type in scene, skipping"
                     );
+                    continue;
+                };
+
+                if let Err(e) = (metadata.insert_component)(world, entity, value) {
+                    error!(
+                        error = %e,
+                        component_type = component_type,
+                        entity = ?entity,
+                        "Failed to insert component via registry"
+                    );
                 }
             }
         }
-
-        info!("Scene instantiation with registry complete");
-        Ok(mapper)
     }
 
     /// Save this scene to a JSON file
@@ -936,6 +1312,60 @@ impl Scene {
                             }
                         }
                     },
+                    "DirectionalLight" => {
+                        match serde_json::from_value::<DirectionalLight>(value.clone()) {
+                            Ok(light) => {
+                                if let Err(e) = world.insert_one(entity, light) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert DirectionalLight");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize DirectionalLight");
+                            }
+                        }
+                    }
+                    "PointLight" => match serde_json::from_value::<PointLight>(value.clone()) {
+                        Ok(light) => {
+                            if let Err(e) = world.insert_one(entity, light) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert PointLight");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize PointLight");
+                        }
+                    },
+                    "SpotLight" => match serde_json::from_value::<SpotLight>(value.clone()) {
+                        Ok(light) => {
+                            if let Err(e) = world.insert_one(entity, light) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert SpotLight");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize SpotLight");
+                        }
+                    },
+                    "PostProcessSettings" => {
+                        match serde_json::from_value::<PostProcessSettings>(value.clone()) {
+                            Ok(settings) => {
+                                if let Err(e) = world.insert_one(entity, settings) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert PostProcessSettings");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize PostProcessSettings");
+                            }
+                        }
+                    }
+                    "Skybox" => match serde_json::from_value::<Skybox>(value.clone()) {
+                        Ok(skybox) => {
+                            if let Err(e) = world.insert_one(entity, skybox) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert Skybox");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize Skybox");
+                        }
+                    },
                     "Name" => match serde_json::from_value::<Name>(value.clone()) {
                         Ok(name) => {
                             if let Err(e) = world.insert_one(entity, name) {
@@ -1001,6 +1431,29 @@ impl Scene {
                             error!(error = %e, "Failed to deserialize Collider");
                         }
                     },
+                    "Joint" => match serde_json::from_value::<JointData>(value.clone()) {
+                        Ok(joint_data) => {
+                            let joint = joint_data.to_joint(|id| mapper.remap(id));
+                            if let Err(e) = world.insert_one(entity, joint) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert Joint");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize Joint");
+                        }
+                    },
+                    "CharacterController" => {
+                        match serde_json::from_value::<CharacterController>(value.clone()) {
+                            Ok(controller) => {
+                                if let Err(e) = world.insert_one(entity, controller) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert CharacterController");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize CharacterController");
+                            }
+                        }
+                    }
                     "PhysicsVelocity" => {
                         match serde_json::from_value::<PhysicsVelocity>(value.clone()) {
                             Ok(velocity) => {
@@ -1072,6 +1525,28 @@ impl Scene {
                             }
                         }
                     }
+                    "PrefabInstance" => {
+                        match serde_json::from_value::<PrefabInstance>(value.clone()) {
+                            Ok(instance) => {
+                                if let Err(e) = world.insert_one(entity, instance) {
+                                    error!(error = ?e, entity = ?entity, "Failed to insert PrefabInstance");
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Failed to deserialize PrefabInstance");
+                            }
+                        }
+                    }
+                    "PrefabEntity" => match serde_json::from_value::<PrefabEntity>(value.clone()) {
+                        Ok(link) => {
+                            if let Err(e) = world.insert_one(entity, link) {
+                                error!(error = ?e, entity = ?entity, "Failed to insert PrefabEntity");
+                            }
+                        }
+                        Err(e) => {
+                            error!(error = %e, "Failed to deserialize PrefabEntity");
+                        }
+                    },
                     unknown => {
                         warn!(
                             component_type = unknown,
@@ -1087,6 +1562,36 @@ impl Scene {
     }
 }
 
+/// Serialize an entity's Joint with its connected entity as a scene ID
+///
+/// A connected entity outside the scene is dropped with a warning.
+fn serialize_joint(
+    world: &World,
+    entity: hecs::Entity,
+    entity_to_id: &HashMap<hecs::Entity, u64>,
+) -> Option<serde_json::Value> {
+    let joint = world.get::<Joint>(entity).ok()?;
+    let connected_id = joint.connected.and_then(|connected| {
+        let id = entity_to_id.get(&connected).copied();
+        if id.is_none() {
+            warn!(
+                entity = ?entity,
+                connected = ?connected,
+                "Joint entity not found in scene"
+            );
+        }
+        id
+    });
+
+    match serde_json::to_value(JointData::from_joint_with_id(&joint, connected_id)) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            error!(error = %e, "Failed to serialize Joint");
+            None
+        }
+    }
+}
+
 impl Default for Scene {
     fn default() -> Self {
         Self::new()
@@ -42,10 +42,13 @@ pub mod prelude {
     pub use glam::{Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
 
     // Graphics types
-    pub use crate::graphics::{Material, Mesh, MeshId, RenderContext, Renderer, Vertex};
+    pub use crate::graphics::{
+        DirectionalLight, Material, Mesh, MeshId, PointLight, PostProcessSettings, RenderContext,
+        Renderer, Skybox, SpotLight, Tonemapping, Vertex,
+    };
 
     // IO types
-    pub use crate::io::{Scene, SceneError};
+    pub use crate::io::{Prefab, PrefabInstance, Scene, SceneError};
 
     // Config types
     pub use crate::config::AssetConfig;
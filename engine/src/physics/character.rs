@@ -0,0 +1,147 @@
+//! Kinematic character controller component
+//!
+//! A [`CharacterController`] moves a kinematic body through the level with
+//! Rapier's character controller instead of forces. Each physics step it tries
+//! to make the [`desired_motion`](CharacterController::desired_motion), sliding
+//! along walls, climbing slopes and steps and snapping down to the ground,
+//! then reports whether it ended up grounded and what it bumped into.
+
+use crate::core::entity::Entity;
+use glam::Vec3;
+use rapier3d_f64::control::{CharacterAutostep, CharacterLength, KinematicCharacterController};
+use serde::{Deserialize, Serialize};
+
+// Imports needed for the Component derive macro
+use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
+use crate::io::component_registry::ComponentRegistry;
+
+/// Something the character bumped into while moving
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharacterHit {
+    /// Entity that was hit
+    pub entity: Entity,
+    /// Contact point in world space
+    pub point: Vec3,
+    /// Surface normal of the hit entity
+    pub normal: Vec3,
+    /// Part of the step's motion made before the hit
+    pub translation_applied: Vec3,
+    /// Part of the step's motion left after the hit, before sliding
+    pub translation_remaining: Vec3,
+}
+
+/// Moves a kinematic rigid body like a player character
+///
+/// The entity needs a `KinematicPositionBased` [`RigidBody`](crate::physics::RigidBody)
+/// and a [`Collider`](crate::physics::Collider), which is used as the
+/// character's shape. Gravity is not applied; add it to the desired motion.
+#[derive(
+    Debug, Clone, Serialize, Deserialize, engine_derive::Component, engine_derive::EditorUI,
+)]
+#[component(name = "CharacterController")]
+pub struct CharacterController {
+    /// Gap kept between the character and its surroundings, in meters
+    #[ui(range = 0.001..0.5, speed = 0.001, tooltip = "Gap kept between the character and the level")]
+    pub offset: f32,
+
+    /// Whether the character slides along obstacles instead of stopping
+    #[ui(tooltip = "Slide along walls and floors instead of stopping at them")]
+    pub slide: bool,
+
+    /// Steepest slope the character can walk up, in degrees
+    #[ui(range = 0.0..90.0, speed = 0.5, tooltip = "Steepest slope the character can climb, in degrees")]
+    pub max_slope_climb_angle: f32,
+
+    /// Slope above which the character slides down, in degrees
+    #[ui(range = 0.0..90.0, speed = 0.5, tooltip = "Slopes steeper than this make the character slide down, in degrees")]
+    pub min_slope_slide_angle: f32,
+
+    /// Highest step climbed automatically, in meters (0 disables stepping)
+    #[ui(range = 0.0..2.0, speed = 0.01, tooltip = "Highest step climbed automatically, 0 disables stepping")]
+    pub autostep_height: f32,
+
+    /// Narrowest step surface climbed automatically, in meters
+    #[ui(range = 0.0..2.0, speed = 0.01, tooltip = "Narrowest step surface the character can stand on")]
+    pub autostep_min_width: f32,
+
+    /// Whether dynamic bodies can be used as steps
+    #[ui(tooltip = "Climb onto dynamic bodies as if they were steps")]
+    pub autostep_include_dynamic: bool,
+
+    /// Distance within which the character is pulled down onto the ground,
+    /// in meters (0 disables snapping)
+    #[ui(range = 0.0..2.0, speed = 0.01, tooltip = "Pull the character down onto ground this close, 0 disables snapping")]
+    pub snap_to_ground: f32,
+
+    /// Whether the character pushes dynamic bodies it walks into
+    #[ui(tooltip = "Push dynamic bodies the character walks into")]
+    pub push_dynamic_bodies: bool,
+
+    /// Mass used when pushing dynamic bodies, in kilograms
+    #[ui(range = 0.1..1000.0, speed = 0.5, tooltip = "Mass used when pushing dynamic bodies")]
+    pub mass: f32,
+
+    /// Motion the character tries to make, in meters per second
+    ///
+    /// Cleared after every physics update that steps the simulation, so it
+    /// has to be set each frame the character should move.
+    #[ui(hidden)]
+    #[serde(skip)]
+    pub desired_motion: Vec3,
+
+    /// Whether the character stood on the ground after its last move
+    #[ui(readonly)]
+    #[serde(skip)]
+    pub grounded: bool,
+
+    /// Velocity the character actually moved with in its last move
+    #[ui(hidden)]
+    #[serde(skip)]
+    pub effective_motion: Vec3,
+
+    /// Obstacles hit during the last physics update
+    #[ui(hidden)]
+    #[serde(skip)]
+    pub hits: Vec<CharacterHit>,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            offset: 0.02,
+            slide: true,
+            max_slope_climb_angle: 45.0,
+            min_slope_slide_angle: 45.0,
+            autostep_height: 0.3,
+            autostep_min_width: 0.2,
+            autostep_include_dynamic: false,
+            snap_to_ground: 0.2,
+            push_dynamic_bodies: true,
+            mass: 80.0,
+            desired_motion: Vec3::ZERO,
+            grounded: false,
+            effective_motion: Vec3::ZERO,
+            hits: Vec::new(),
+        }
+    }
+}
+
+impl CharacterController {
+    /// Build the Rapier controller for this component
+    pub fn to_rapier(&self) -> KinematicCharacterController {
+        KinematicCharacterController {
+            offset: CharacterLength::Absolute(self.offset.max(0.001) as f64),
+            slide: self.slide,
+            autostep: (self.autostep_height > 0.0).then(|| CharacterAutostep {
+                max_height: CharacterLength::Absolute(self.autostep_height as f64),
+                min_width: CharacterLength::Absolute(self.autostep_min_width as f64),
+                include_dynamic_bodies: self.autostep_include_dynamic,
+            }),
+            max_slope_climb_angle: (self.max_slope_climb_angle as f64).to_radians(),
+            min_slope_slide_angle: (self.min_slope_slide_angle as f64).to_radians(),
+            snap_to_ground: (self.snap_to_ground > 0.0)
+                .then(|| CharacterLength::Absolute(self.snap_to_ground as f64)),
+            ..Default::default()
+        }
+    }
+}
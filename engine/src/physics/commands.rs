@@ -4,10 +4,13 @@
 //! allowing scripts and other systems to safely interact with the physics world.
 
 use glam::Vec3;
-use rhai::Dynamic;
+use serde::{Deserialize, Serialize};
 // Arc and RwLock removed - using thread-local storage instead
 
 /// Physics command to be executed in the physics system
+///
+/// Commands are serializable so physics recordings can store them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PhysicsCommand {
     /// Apply a force to a rigid body
     ApplyForce {
@@ -43,31 +46,14 @@ pub enum PhysicsCommand {
         angular: Vec3,
     },
 
-    /// Perform a raycast query
-    Raycast {
-        /// Ray origin in world space
-        origin: Vec3,
-        /// Ray direction (should be normalized)
-        direction: Vec3,
-        /// Maximum distance for the ray
-        max_distance: f32,
-        /// Callback to execute with the result
-        callback: Box<dyn FnOnce(Option<RaycastHit>) -> Dynamic + Send>,
+    /// Set the motion a character controller makes this frame
+    MoveCharacter {
+        /// Entity ID of the character
+        entity: u64,
+        /// Desired motion in meters per second
+        motion: Vec3,
     },
 }
 
-/// Result of a raycast query
-#[derive(Debug, Clone)]
-pub struct RaycastHit {
-    /// Entity that was hit
-    pub entity: u64,
-    /// Distance to the hit point
-    pub distance: f32,
-    /// Hit point in world space
-    pub point: Vec3,
-    /// Surface normal at the hit point
-    pub normal: Vec3,
-}
-
 // Note: The actual physics command queue is thread-local in system.rs
-// These types are removed because PhysicsCommand contains a closure that isn't Send
+// Queries that return results are immediate, see the queries module
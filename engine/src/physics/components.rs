@@ -5,9 +5,10 @@
 
 use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt, EditorUI};
 use crate::io::component_registry::ComponentRegistry;
+use crate::physics::CollisionLayers;
 use engine_derive;
 use glam::Vec3;
-use rapier3d_f64::prelude::{ColliderHandle, RigidBodyHandle};
+use rapier3d_f64::prelude::{ColliderHandle, Group, InteractionGroups, RigidBodyHandle};
 use serde::{Deserialize, Serialize};
 
 /// Type of rigid body for physics simulation
@@ -75,6 +76,29 @@ pub enum ColliderShape {
     Capsule { half_height: f32, radius: f32 },
     /// Cylinder with half-height and radius
     Cylinder { half_height: f32, radius: f32 },
+    /// Triangles of the entity's mesh, scaled by its transform
+    ///
+    /// Meant for static level geometry. Triangle meshes have no volume, so
+    /// moving bodies should use [`ColliderShape::ConvexHull`] instead.
+    TriMesh,
+    /// Convex hull around the entity's mesh, scaled by its transform
+    ConvexHull,
+    /// Terrain from a grid of heights, centered on the entity
+    Heightfield {
+        /// Heights of the grid points, row by row, in units of `scale.y`
+        heights: Vec<f32>,
+        /// Number of grid points along Z
+        rows: usize,
+        /// Number of grid points along X
+        cols: usize,
+        /// Size of the terrain along X and Z and height multiplier along Y
+        scale: Vec3,
+    },
+    /// Union of the colliders of the entity's children
+    ///
+    /// Children with a [`Collider`] but no [`RigidBody`] become parts of this
+    /// collider, placed by their local transform.
+    Compound,
 }
 
 impl Default for ColliderShape {
@@ -109,6 +133,18 @@ pub struct Collider {
     #[ui(tooltip = "Sensor colliders detect overlaps but don't cause physical collision response")]
     pub is_sensor: bool,
 
+    /// Collision layers this collider belongs to, as a bit mask
+    #[ui(tooltip = "Bit mask of the collision layers this collider is on, -1 for all layers")]
+    #[serde(default = "default_membership")]
+    pub membership: u32,
+
+    /// Collision layers this collider interacts with, as a bit mask
+    #[ui(
+        tooltip = "Bit mask of the collision layers this collider collides with and is found by, -1 for all layers"
+    )]
+    #[serde(default = "default_filter")]
+    pub filter: u32,
+
     /// Internal handle to the Rapier collider
     #[ui(hidden)]
     #[serde(skip)]
@@ -123,11 +159,21 @@ impl Default for Collider {
             restitution: 0.0,
             density: 1.0,
             is_sensor: false,
+            membership: default_membership(),
+            filter: default_filter(),
             handle: None,
         }
     }
 }
 
+fn default_membership() -> u32 {
+    CollisionLayers::DEFAULT
+}
+
+fn default_filter() -> u32 {
+    CollisionLayers::ALL
+}
+
 impl Collider {
     /// Create a cuboid collider with the given half-extents
     pub fn cuboid(hx: f32, hy: f32, hz: f32) -> Self {
@@ -166,6 +212,58 @@ impl Collider {
             ..Default::default()
         }
     }
+
+    /// Create a collider from the triangles of the entity's mesh
+    pub fn trimesh() -> Self {
+        Self {
+            shape: ColliderShape::TriMesh,
+            ..Default::default()
+        }
+    }
+
+    /// Create a collider from the convex hull of the entity's mesh
+    pub fn convex_hull() -> Self {
+        Self {
+            shape: ColliderShape::ConvexHull,
+            ..Default::default()
+        }
+    }
+
+    /// Create a heightfield collider from `rows` by `cols` heights given row by row
+    pub fn heightfield(heights: Vec<f32>, rows: usize, cols: usize, scale: Vec3) -> Self {
+        Self {
+            shape: ColliderShape::Heightfield {
+                heights,
+                rows,
+                cols,
+                scale,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Create a collider made of the colliders of the entity's children
+    pub fn compound() -> Self {
+        Self {
+            shape: ColliderShape::Compound,
+            ..Default::default()
+        }
+    }
+
+    /// Put the collider on the `membership` layers, interacting with the `filter` layers
+    pub fn with_layers(mut self, membership: u32, filter: u32) -> Self {
+        self.membership = membership;
+        self.filter = filter;
+        self
+    }
+
+    /// Rapier groups for the collider's layers
+    ///
+    /// Used both for contacts and for queries, so a collider that ignores a
+    /// layer neither collides with nor gets hit by queries from that layer.
+    pub fn interaction_groups(&self) -> InteractionGroups {
+        InteractionGroups::new(Group::from(self.membership), Group::from(self.filter))
+    }
 }
 
 /// Physics velocity component for reading/writing velocities
@@ -1,12 +1,16 @@
 //! Physics debug visualization
 //!
 //! This module provides debug rendering for physics colliders and constraints,
-//! converting physics shapes to debug lines in camera-relative space.
+//! converting physics shapes to debug lines in camera-relative space. Mesh,
+//! heightfield and compound colliders are drawn from the shape Rapier built
+//! for them, so the lines show exactly what the simulation collides with.
 
 use crate::core::entity::World;
 use crate::dev::debug_overlay::DebugLineData;
-use crate::physics::{Collider, ColliderShape, PhysicsWorld, RigidBody};
+use crate::physics::{Collider, ColliderShape, CollisionLayers, Joint, PhysicsWorld, RigidBody};
 use glam::{DVec3, Quat, Vec3, Vec4};
+use rapier3d_f64::parry::shape::{Shape, Triangle, TypedShape};
+use rapier3d_f64::prelude::{Isometry, Point};
 use tracing::trace;
 
 /// Convert Rapier's Unit<Quaternion<f64>> to glam's Quat
@@ -20,6 +24,8 @@ fn rapier_rotation_to_quat(rotation: &rapier3d_f64::prelude::Rotation<f64>) -> Q
 pub struct PhysicsDebugSettings {
     /// Whether to show collider shapes
     pub show_colliders: bool,
+    /// Collision layers whose colliders are shown, as a bit mask
+    pub layer_mask: u32,
     /// Color for static colliders
     pub static_color: Vec4,
     /// Color for dynamic colliders
@@ -28,16 +34,23 @@ pub struct PhysicsDebugSettings {
     pub kinematic_color: Vec4,
     /// Color for sensor colliders
     pub sensor_color: Vec4,
+    /// Whether to show joints between bodies
+    pub show_joints: bool,
+    /// Color for joints
+    pub joint_color: Vec4,
 }
 
 impl Default for PhysicsDebugSettings {
     fn default() -> Self {
         Self {
             show_colliders: false,
+            layer_mask: CollisionLayers::ALL,
             static_color: Vec4::new(0.0, 1.0, 0.0, 1.0), // Green
             dynamic_color: Vec4::new(1.0, 0.0, 0.0, 1.0), // Red
             kinematic_color: Vec4::new(0.0, 0.0, 1.0, 1.0), // Blue
             sensor_color: Vec4::new(1.0, 1.0, 0.0, 0.5), // Yellow (semi-transparent)
+            show_joints: false,
+            joint_color: Vec4::new(1.0, 0.0, 1.0, 1.0), // Magenta
         }
     }
 }
@@ -50,6 +63,16 @@ pub fn draw_physics_debug(
     settings: &PhysicsDebugSettings,
     camera_world_position: DVec3,
 ) {
+    if settings.show_joints {
+        draw_joints(
+            world,
+            physics_world,
+            debug_lines,
+            settings,
+            camera_world_position,
+        );
+    }
+
     if !settings.show_colliders {
         return;
     }
@@ -58,6 +81,10 @@ pub fn draw_physics_debug(
 
     // Draw colliders
     for (_entity, (collider, rb)) in world.query::<(&Collider, &RigidBody)>().iter() {
+        if collider.membership & settings.layer_mask == 0 {
+            continue;
+        }
+
         if let Some(rb_handle) = rb.handle {
             if let Some(rigid_body) = physics_world.rigid_body_set.get(rb_handle) {
                 let pos = rigid_body.translation();
@@ -125,12 +152,185 @@ pub fn draw_physics_debug(
                             color,
                         );
                     }
+                    ColliderShape::TriMesh
+                    | ColliderShape::ConvexHull
+                    | ColliderShape::Heightfield { .. }
+                    | ColliderShape::Compound => {
+                        if let Some(rapier_collider) = collider
+                            .handle
+                            .and_then(|handle| physics_world.collider_set.get(handle))
+                        {
+                            draw_shape(
+                                debug_lines,
+                                rapier_collider.shape(),
+                                rapier_collider.position(),
+                                camera_world_position,
+                                color,
+                            );
+                        }
+                    }
                 }
             }
         }
     }
 }
 
+/// Draw a Rapier shape placed at `position` in world space
+///
+/// Compound shapes draw each of their parts. Shape types colliders are
+/// never built from are skipped.
+fn draw_shape(
+    debug_lines: &mut Vec<DebugLineData>,
+    shape: &dyn Shape,
+    position: &Isometry<f64>,
+    camera_world_position: DVec3,
+    color: Vec4,
+) {
+    let relative = |local: &Point<f64>| {
+        let world_point = position * local;
+        let relative =
+            DVec3::new(world_point.x, world_point.y, world_point.z) - camera_world_position;
+        Vec3::new(relative.x as f32, relative.y as f32, relative.z as f32)
+    };
+    let center = relative(&Point::origin());
+    let rotation = rapier_rotation_to_quat(&position.rotation);
+
+    match shape.as_typed_shape() {
+        TypedShape::Cuboid(cuboid) => {
+            let half_extents = cuboid.half_extents;
+            draw_box(
+                debug_lines,
+                center,
+                rotation,
+                Vec3::new(
+                    half_extents.x as f32,
+                    half_extents.y as f32,
+                    half_extents.z as f32,
+                ),
+                color,
+            );
+        }
+        TypedShape::Ball(ball) => {
+            draw_sphere(debug_lines, center, ball.radius as f32, color);
+        }
+        TypedShape::Capsule(capsule) => {
+            draw_capsule(
+                debug_lines,
+                center,
+                rotation,
+                capsule.half_height() as f32,
+                capsule.radius as f32,
+                color,
+            );
+        }
+        TypedShape::Cylinder(cylinder) => {
+            draw_cylinder(
+                debug_lines,
+                center,
+                rotation,
+                cylinder.half_height as f32,
+                cylinder.radius as f32,
+                color,
+            );
+        }
+        TypedShape::TriMesh(trimesh) => {
+            draw_triangles(debug_lines, trimesh.triangles(), &relative, color);
+        }
+        TypedShape::HeightField(heightfield) => {
+            draw_triangles(debug_lines, heightfield.triangles(), &relative, color);
+        }
+        TypedShape::ConvexPolyhedron(polyhedron) => {
+            let points = polyhedron.points();
+            for edge in polyhedron.edges() {
+                debug_lines.push(DebugLineData {
+                    start: relative(&points[edge.vertices.x as usize]),
+                    end: relative(&points[edge.vertices.y as usize]),
+                    color,
+                });
+            }
+        }
+        TypedShape::Compound(compound) => {
+            for (local_position, part) in compound.shapes() {
+                draw_shape(
+                    debug_lines,
+                    &**part,
+                    &(position * local_position),
+                    camera_world_position,
+                    color,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Draw the edges of triangles given in the local space of `relative`
+fn draw_triangles(
+    debug_lines: &mut Vec<DebugLineData>,
+    triangles: impl Iterator<Item = Triangle>,
+    relative: &impl Fn(&Point<f64>) -> Vec3,
+    color: Vec4,
+) {
+    for triangle in triangles {
+        let (a, b, c) = (
+            relative(&triangle.a),
+            relative(&triangle.b),
+            relative(&triangle.c),
+        );
+        for (start, end) in [(a, b), (b, c), (c, a)] {
+            debug_lines.push(DebugLineData { start, end, color });
+        }
+    }
+}
+
+/// Draw each joint as a line between its two anchors with a cross at each anchor
+fn draw_joints(
+    world: &World,
+    physics_world: &PhysicsWorld,
+    debug_lines: &mut Vec<DebugLineData>,
+    settings: &PhysicsDebugSettings,
+    camera_world_position: DVec3,
+) {
+    let color = settings.joint_color;
+    for (_entity, joint) in world.query::<&Joint>().iter() {
+        let Some(impulse_joint) = joint
+            .handle
+            .and_then(|handle| physics_world.impulse_joint_set.get(handle))
+        else {
+            continue;
+        };
+        let (Some(body1), Some(body2)) = (
+            physics_world.rigid_body_set.get(impulse_joint.body1),
+            physics_world.rigid_body_set.get(impulse_joint.body2),
+        ) else {
+            continue;
+        };
+
+        // Anchors in camera-relative space
+        let anchor = |body: &rapier3d_f64::prelude::RigidBody, local: Vec3| {
+            let local =
+                rapier3d_f64::prelude::point![local.x as f64, local.y as f64, local.z as f64];
+            let world_point = body.position() * local;
+            let relative =
+                DVec3::new(world_point.x, world_point.y, world_point.z) - camera_world_position;
+            Vec3::new(relative.x as f32, relative.y as f32, relative.z as f32)
+        };
+        let start = anchor(body1, joint.anchor);
+        let end = anchor(body2, joint.connected_anchor);
+
+        debug_lines.push(DebugLineData { start, end, color });
+        for point in [start, end] {
+            for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+                debug_lines.push(DebugLineData {
+                    start: point - axis * 0.05,
+                    end: point + axis * 0.05,
+                    color,
+                });
+            }
+        }
+    }
+}
+
 /// Draw a box wireframe
 fn draw_box(
     debug_lines: &mut Vec<DebugLineData>,
@@ -0,0 +1,215 @@
+//! Collision and trigger events reported by the physics simulation
+//!
+//! Rapier reports when two colliders start or stop touching. The events of
+//! all steps taken in a frame are collected into [`CollisionEvents`] on the
+//! [`PhysicsWorld`](crate::physics::PhysicsWorld), where systems can read
+//! them until the next physics update. The same events are handed to the
+//! script system, which calls `on_collision_enter`, `on_collision_exit`,
+//! `on_trigger_enter` and `on_trigger_exit` on scripted entities.
+
+use crate::core::entity::Entity;
+use glam::Vec3;
+use rapier3d_f64::prelude::{
+    ColliderSet, CollisionEvent as RapierCollisionEvent, ContactPair, EventHandler, Real,
+    RigidBodySet,
+};
+use std::sync::Mutex;
+
+/// What happened between two colliders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollisionEventKind {
+    /// Two solid colliders started touching
+    CollisionEnter,
+    /// Two solid colliders stopped touching
+    CollisionExit,
+    /// A collider started overlapping a sensor
+    TriggerEnter,
+    /// A collider stopped overlapping a sensor
+    TriggerExit,
+}
+
+impl CollisionEventKind {
+    /// Script function called for this kind of event
+    pub fn script_function(self) -> &'static str {
+        match self {
+            Self::CollisionEnter => "on_collision_enter",
+            Self::CollisionExit => "on_collision_exit",
+            Self::TriggerEnter => "on_trigger_enter",
+            Self::TriggerExit => "on_trigger_exit",
+        }
+    }
+
+    /// Build the kind from whether the pair started and involves a sensor
+    pub fn new(started: bool, sensor: bool) -> Self {
+        match (started, sensor) {
+            (true, false) => Self::CollisionEnter,
+            (false, false) => Self::CollisionExit,
+            (true, true) => Self::TriggerEnter,
+            (false, true) => Self::TriggerExit,
+        }
+    }
+}
+
+/// A change in contact between two entities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionEvent {
+    /// What happened
+    pub kind: CollisionEventKind,
+    /// First entity of the pair
+    pub entity: Entity,
+    /// Second entity of the pair
+    pub other: Entity,
+    /// Deepest contact point in world space, or the midpoint between the
+    /// colliders when there is no contact (exits and triggers)
+    pub point: Vec3,
+    /// Contact normal in world space, pointing from `entity` to `other`
+    pub normal: Vec3,
+    /// Total impulse applied to resolve the contact during the step
+    pub impulse: f32,
+}
+
+impl CollisionEvent {
+    /// The same event seen from the other entity
+    pub fn flipped(&self) -> Self {
+        Self {
+            entity: self.other,
+            other: self.entity,
+            normal: -self.normal,
+            ..*self
+        }
+    }
+}
+
+/// Collision and trigger events of the last physics update
+#[derive(Debug, Clone, Default)]
+pub struct CollisionEvents {
+    events: Vec<CollisionEvent>,
+}
+
+impl CollisionEvents {
+    /// All events, in the order Rapier reported them
+    pub fn iter(&self) -> impl Iterator<Item = &CollisionEvent> {
+        self.events.iter()
+    }
+
+    /// Events involving `entity`, each seen from `entity`
+    pub fn involving(&self, entity: Entity) -> impl Iterator<Item = CollisionEvent> + '_ {
+        self.events.iter().filter_map(move |event| {
+            if event.entity == entity {
+                Some(*event)
+            } else if event.other == entity {
+                Some(event.flipped())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Number of events
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether there are no events
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Add an event
+    pub fn push(&mut self, event: CollisionEvent) {
+        self.events.push(event);
+    }
+
+    /// Remove all events
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+/// Collects raw Rapier events while the pipeline steps
+#[derive(Default)]
+pub(crate) struct EventCollector {
+    events: Mutex<Vec<RapierCollisionEvent>>,
+}
+
+impl EventCollector {
+    /// Take the events collected since the last call
+    pub(crate) fn drain(&self) -> Vec<RapierCollisionEvent> {
+        self.events
+            .lock()
+            .map(|mut events| std::mem::take(&mut *events))
+            .unwrap_or_default()
+    }
+}
+
+impl EventHandler for EventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        event: RapierCollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push(event);
+        }
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        _contact_pair: &ContactPair,
+        _total_force_magnitude: Real,
+    ) {
+    }
+}
+
+thread_local! {
+    /// Events of the last physics update waiting for the script system
+    static SCRIPT_EVENTS: std::cell::RefCell<CollisionEvents> =
+        std::cell::RefCell::new(CollisionEvents::default());
+}
+
+/// Hand the events of a physics update to the next script update
+///
+/// Replaces events that were not picked up, so they never pile up when no
+/// scripts run.
+pub fn publish_script_events(events: &CollisionEvents) {
+    SCRIPT_EVENTS.with(|pending| pending.borrow_mut().clone_from(events));
+}
+
+/// Take the events published by the last physics update
+pub fn take_script_events() -> CollisionEvents {
+    SCRIPT_EVENTS.with(|pending| std::mem::take(&mut *pending.borrow_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_involving_flips_events_for_other_entity() {
+        let mut world = hecs::World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+        let c = world.spawn(());
+
+        let mut events = CollisionEvents::default();
+        events.push(CollisionEvent {
+            kind: CollisionEventKind::CollisionEnter,
+            entity: a,
+            other: b,
+            point: Vec3::ZERO,
+            normal: Vec3::Y,
+            impulse: 2.0,
+        });
+
+        let seen_by_b: Vec<_> = events.involving(b).collect();
+        assert_eq!(seen_by_b.len(), 1);
+        assert_eq!(seen_by_b[0].other, a);
+        assert_eq!(seen_by_b[0].normal, -Vec3::Y);
+        assert_eq!(events.involving(c).count(), 0);
+    }
+}
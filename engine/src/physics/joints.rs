@@ -0,0 +1,378 @@
+//! Joint components connecting two rigid bodies
+//!
+//! A [`Joint`] sits on an entity with a [`RigidBody`](crate::physics::RigidBody)
+//! and constrains it relative to the body of another entity. Scenes store
+//! the connected entity as a scene ID through [`JointData`], the same way
+//! [`ParentData`](crate::core::entity::components::ParentData) stores parents.
+
+use crate::core::entity::Entity;
+use glam::Vec3;
+use rapier3d_f64::prelude::{
+    point, vector, FixedJointBuilder, GenericJoint, ImpulseJointHandle, PrismaticJointBuilder,
+    RevoluteJointBuilder, RopeJointBuilder, SphericalJointBuilder, UnitVector,
+};
+use serde::{Deserialize, Serialize};
+
+// Imports needed for the Component derive macro
+use crate::component_system::{Component, ComponentMetadata, ComponentRegistryExt};
+use crate::io::component_registry::ComponentRegistry;
+
+/// Range a joint can move in, radians for revolute and meters for prismatic joints
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct JointLimits {
+    /// Lowest allowed position
+    pub min: f32,
+    /// Highest allowed position
+    pub max: f32,
+}
+
+/// Spring-like motor driving a joint towards a target position and velocity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct JointMotor {
+    /// Position the motor pulls towards
+    pub target_position: f32,
+    /// Velocity the motor tries to reach
+    pub target_velocity: f32,
+    /// Strength of the pull towards the target position
+    pub stiffness: f32,
+    /// Strength of the pull towards the target velocity
+    pub damping: f32,
+    /// Largest force or torque the motor can apply
+    pub max_force: f32,
+}
+
+impl JointMotor {
+    /// Motor spinning or sliding at a constant velocity
+    pub fn velocity(target_velocity: f32, damping: f32) -> Self {
+        Self {
+            target_position: 0.0,
+            target_velocity,
+            stiffness: 0.0,
+            damping,
+            max_force: f32::MAX,
+        }
+    }
+
+    /// Motor holding a target position like a spring
+    pub fn position(target_position: f32, stiffness: f32, damping: f32) -> Self {
+        Self {
+            target_position,
+            target_velocity: 0.0,
+            stiffness,
+            damping,
+            max_force: f32::MAX,
+        }
+    }
+}
+
+/// How a joint constrains the two bodies
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum JointType {
+    /// Bodies keep their relative position and rotation
+    Fixed,
+    /// Bodies rotate around a shared axis, like a hinge
+    Revolute {
+        /// Rotation axis in the local space of this entity
+        axis: Vec3,
+        /// Allowed rotation in radians
+        #[serde(default)]
+        limits: Option<JointLimits>,
+        /// Motor driving the rotation
+        #[serde(default)]
+        motor: Option<JointMotor>,
+    },
+    /// Bodies slide along a shared axis
+    Prismatic {
+        /// Slide axis in the local space of this entity
+        axis: Vec3,
+        /// Allowed travel in meters
+        #[serde(default)]
+        limits: Option<JointLimits>,
+        /// Motor driving the slide
+        #[serde(default)]
+        motor: Option<JointMotor>,
+    },
+    /// Bodies rotate freely around a shared point, like a ball and socket
+    Spherical,
+    /// Anchors stay at most `max_distance` apart
+    Rope {
+        /// Length of the rope in meters
+        max_distance: f32,
+    },
+}
+
+impl Default for JointType {
+    fn default() -> Self {
+        Self::Fixed
+    }
+}
+
+/// Joint attaching this entity's rigid body to the body of another entity
+#[derive(Debug, Clone, Serialize, Deserialize, engine_derive::Component)]
+#[component(name = "Joint")]
+pub struct Joint {
+    /// How the bodies are constrained
+    pub joint_type: JointType,
+
+    /// Entity whose body this one is attached to
+    #[serde(skip)]
+    pub connected: Option<Entity>,
+
+    /// Attachment point in the local space of this entity
+    #[serde(default)]
+    pub anchor: Vec3,
+
+    /// Attachment point in the local space of the connected entity
+    #[serde(default)]
+    pub connected_anchor: Vec3,
+
+    /// Whether the two bodies still collide with each other
+    #[serde(default)]
+    pub contacts_enabled: bool,
+
+    /// Internal handle to the Rapier joint
+    #[serde(skip)]
+    pub handle: Option<ImpulseJointHandle>,
+}
+
+impl Default for Joint {
+    fn default() -> Self {
+        Self {
+            joint_type: JointType::default(),
+            connected: None,
+            anchor: Vec3::ZERO,
+            connected_anchor: Vec3::ZERO,
+            contacts_enabled: false,
+            handle: None,
+        }
+    }
+}
+
+impl Joint {
+    /// Create a joint of the given type attached to `connected`
+    pub fn new(joint_type: JointType, connected: Entity) -> Self {
+        Self {
+            joint_type,
+            connected: Some(connected),
+            ..Default::default()
+        }
+    }
+
+    /// Weld this body to `connected`
+    pub fn fixed(connected: Entity) -> Self {
+        Self::new(JointType::Fixed, connected)
+    }
+
+    /// Hinge this body to `connected` around `axis`
+    pub fn revolute(connected: Entity, axis: Vec3) -> Self {
+        Self::new(
+            JointType::Revolute {
+                axis,
+                limits: None,
+                motor: None,
+            },
+            connected,
+        )
+    }
+
+    /// Let this body slide relative to `connected` along `axis`
+    pub fn prismatic(connected: Entity, axis: Vec3) -> Self {
+        Self::new(
+            JointType::Prismatic {
+                axis,
+                limits: None,
+                motor: None,
+            },
+            connected,
+        )
+    }
+
+    /// Attach this body to `connected` with a ball and socket
+    pub fn spherical(connected: Entity) -> Self {
+        Self::new(JointType::Spherical, connected)
+    }
+
+    /// Tie this body to `connected` with a rope of `max_distance`
+    pub fn rope(connected: Entity, max_distance: f32) -> Self {
+        Self::new(JointType::Rope { max_distance }, connected)
+    }
+
+    /// Set the attachment points in the local space of each entity
+    pub fn with_anchors(mut self, anchor: Vec3, connected_anchor: Vec3) -> Self {
+        self.anchor = anchor;
+        self.connected_anchor = connected_anchor;
+        self
+    }
+
+    /// Limit a revolute or prismatic joint, other joint types are unchanged
+    pub fn with_limits(mut self, min: f32, max: f32) -> Self {
+        if let JointType::Revolute { limits, .. } | JointType::Prismatic { limits, .. } =
+            &mut self.joint_type
+        {
+            *limits = Some(JointLimits { min, max });
+        }
+        self
+    }
+
+    /// Drive a revolute or prismatic joint, other joint types are unchanged
+    pub fn with_motor(mut self, joint_motor: JointMotor) -> Self {
+        if let JointType::Revolute { motor, .. } | JointType::Prismatic { motor, .. } =
+            &mut self.joint_type
+        {
+            *motor = Some(joint_motor);
+        }
+        self
+    }
+
+    /// Build the Rapier joint for this component
+    pub fn to_rapier(&self) -> GenericJoint {
+        let anchor1 = point![
+            self.anchor.x as f64,
+            self.anchor.y as f64,
+            self.anchor.z as f64
+        ];
+        let anchor2 = point![
+            self.connected_anchor.x as f64,
+            self.connected_anchor.y as f64,
+            self.connected_anchor.z as f64
+        ];
+        let unit_axis = |axis: Vec3| {
+            let axis = axis.try_normalize().unwrap_or(Vec3::Y);
+            UnitVector::new_normalize(vector![axis.x as f64, axis.y as f64, axis.z as f64])
+        };
+
+        let mut joint: GenericJoint = match &self.joint_type {
+            JointType::Fixed => FixedJointBuilder::new()
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            JointType::Revolute {
+                axis,
+                limits,
+                motor,
+            } => {
+                let mut builder = RevoluteJointBuilder::new(unit_axis(*axis))
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2);
+                if let Some(limits) = limits {
+                    builder = builder.limits([limits.min as f64, limits.max as f64]);
+                }
+                if let Some(motor) = motor {
+                    builder = builder
+                        .motor(
+                            motor.target_position as f64,
+                            motor.target_velocity as f64,
+                            motor.stiffness as f64,
+                            motor.damping as f64,
+                        )
+                        .motor_max_force(motor.max_force as f64);
+                }
+                builder.into()
+            }
+            JointType::Prismatic {
+                axis,
+                limits,
+                motor,
+            } => {
+                let mut builder = PrismaticJointBuilder::new(unit_axis(*axis))
+                    .local_anchor1(anchor1)
+                    .local_anchor2(anchor2);
+                if let Some(limits) = limits {
+                    builder = builder.limits([limits.min as f64, limits.max as f64]);
+                }
+                if let Some(motor) = motor {
+                    builder = builder
+                        .set_motor(
+                            motor.target_position as f64,
+                            motor.target_velocity as f64,
+                            motor.stiffness as f64,
+                            motor.damping as f64,
+                        )
+                        .motor_max_force(motor.max_force as f64);
+                }
+                builder.into()
+            }
+            JointType::Spherical => SphericalJointBuilder::new()
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            JointType::Rope { max_distance } => RopeJointBuilder::new()
+                .limits([0.0, *max_distance as f64])
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+        };
+        joint.set_contacts_enabled(self.contacts_enabled);
+        joint
+    }
+}
+
+/// Serializable form of [`Joint`] with the connected entity as a scene ID
+///
+/// The ID is remapped to the new entity when the scene is loaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JointData {
+    /// The joint settings
+    #[serde(flatten)]
+    pub joint: Joint,
+    /// Scene ID of the connected entity
+    #[serde(default)]
+    pub connected_entity: Option<u64>,
+}
+
+impl JointData {
+    /// Create JointData from a Joint and the scene ID of its connected entity
+    pub fn from_joint_with_id(joint: &Joint, connected_entity: Option<u64>) -> Self {
+        Self {
+            joint: joint.clone(),
+            connected_entity,
+        }
+    }
+
+    /// Convert back to a Joint, remapping the connected entity
+    ///
+    /// The joint stays unconnected if the entity is not in the scene.
+    pub fn to_joint<F>(&self, entity_mapper: F) -> Joint
+    where
+        F: Fn(u64) -> Option<Entity>,
+    {
+        Joint {
+            connected: self.connected_entity.and_then(entity_mapper),
+            handle: None,
+            ..self.joint.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joint_data_round_trip_remaps_connected_entity() {
+        let mut world = hecs::World::new();
+        let old = world.spawn(());
+        let new = world.spawn(());
+
+        let joint = Joint::revolute(old, Vec3::X)
+            .with_limits(-1.0, 1.0)
+            .with_motor(JointMotor::velocity(2.0, 0.5))
+            .with_anchors(Vec3::Y, Vec3::ZERO);
+        let json = serde_json::to_value(JointData::from_joint_with_id(&joint, Some(7))).unwrap();
+        assert_eq!(json["connected_entity"], 7);
+
+        let data: JointData = serde_json::from_value(json).unwrap();
+        let restored = data.to_joint(|id| (id == 7).then_some(new));
+        assert_eq!(restored.connected, Some(new));
+        assert_eq!(restored.joint_type, joint.joint_type);
+        assert_eq!(restored.anchor, Vec3::Y);
+    }
+
+    #[test]
+    fn test_limits_ignored_for_fixed_joint() {
+        let mut world = hecs::World::new();
+        let other = world.spawn(());
+        let joint = Joint::fixed(other).with_limits(0.0, 1.0);
+        assert_eq!(joint.joint_type, JointType::Fixed);
+    }
+}
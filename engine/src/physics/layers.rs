@@ -0,0 +1,205 @@
+//! Named collision layers
+//!
+//! A project names up to 32 collision layers in a `collision_layers.json`
+//! file next to its assets. Each [`Collider`](crate::physics::Collider) keeps
+//! a `membership` and a `filter` bit mask over these layers, where bit `i`
+//! stands for the layer at index `i`. Two colliders interact when each one's
+//! membership intersects the other's filter. Queries use the same masks
+//! through [`SpatialQueryFilter`](crate::physics::SpatialQueryFilter).
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Errors that can occur while defining or loading collision layers
+#[derive(Debug, thiserror::Error)]
+pub enum CollisionLayersError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("At most 32 collision layers are supported, got {count}")]
+    TooManyLayers { count: usize },
+
+    #[error("Collision layer names must not be empty")]
+    EmptyName,
+
+    #[error("Collision layer '{0}' is defined more than once")]
+    DuplicateName(String),
+
+    #[error("Unknown collision layer '{0}'")]
+    UnknownLayer(String),
+}
+
+/// Names of the collision layers of a project, indexed by bit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollisionLayers {
+    layers: Vec<String>,
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self {
+            layers: vec!["Default".to_string()],
+        }
+    }
+}
+
+impl CollisionLayers {
+    /// Most layers a project can define, one per bit of a mask
+    pub const MAX_LAYERS: usize = 32;
+
+    /// Mask containing every layer
+    pub const ALL: u32 = u32::MAX;
+
+    /// Mask containing no layer
+    pub const NONE: u32 = 0;
+
+    /// Mask of the first layer, which colliders belong to by default
+    pub const DEFAULT: u32 = 1;
+
+    /// File looked up in the asset root of a project
+    pub const FILE_NAME: &'static str = "collision_layers.json";
+
+    /// Define layers in bit order
+    pub fn new<S: Into<String>>(
+        names: impl IntoIterator<Item = S>,
+    ) -> Result<Self, CollisionLayersError> {
+        let layers: Vec<String> = names.into_iter().map(Into::into).collect();
+        if layers.len() > Self::MAX_LAYERS {
+            return Err(CollisionLayersError::TooManyLayers {
+                count: layers.len(),
+            });
+        }
+        for (index, name) in layers.iter().enumerate() {
+            if name.trim().is_empty() {
+                return Err(CollisionLayersError::EmptyName);
+            }
+            if layers[..index].contains(name) {
+                return Err(CollisionLayersError::DuplicateName(name.clone()));
+            }
+        }
+        Ok(Self { layers })
+    }
+
+    /// Layer names in bit order
+    pub fn names(&self) -> &[String] {
+        &self.layers
+    }
+
+    /// Bit index of a layer
+    pub fn index_of(&self, name: &str) -> Option<u32> {
+        self.layers
+            .iter()
+            .position(|layer| layer == name)
+            .map(|index| index as u32)
+    }
+
+    /// Mask containing only the named layer
+    pub fn layer(&self, name: &str) -> Option<u32> {
+        self.index_of(name).map(|index| 1 << index)
+    }
+
+    /// Mask containing all of the named layers
+    pub fn mask<'a>(
+        &self,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<u32, CollisionLayersError> {
+        names.into_iter().try_fold(Self::NONE, |mask, name| {
+            self.layer(name)
+                .map(|bit| mask | bit)
+                .ok_or_else(|| CollisionLayersError::UnknownLayer(name.to_string()))
+        })
+    }
+
+    /// Names of the defined layers contained in `mask`
+    pub fn names_in(&self, mask: u32) -> Vec<&str> {
+        self.layers
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| mask & (1 << index) != 0)
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+
+    /// Load layers from a JSON file
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, CollisionLayersError> {
+        let json = std::fs::read_to_string(path)?;
+        let file: Self = serde_json::from_str(&json)?;
+        Self::new(file.layers)
+    }
+
+    /// Save layers to a JSON file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), CollisionLayersError> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Load a project's layers, falling back to the default layer
+    ///
+    /// A missing file is normal for projects without custom layers. Invalid
+    /// files are reported and ignored.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        if !path.exists() {
+            debug!(path = ?path, "No collision layers file, using the default layer");
+            return Self::default();
+        }
+        match Self::load_from_file(path) {
+            Ok(layers) => {
+                debug!(path = ?path, count = layers.layers.len(), "Loaded collision layers");
+                layers
+            }
+            Err(e) => {
+                warn!(path = ?path, error = %e, "Failed to load collision layers, using the default layer");
+                Self::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_masks_from_names() {
+        let layers = CollisionLayers::new(["Default", "Player", "Enemy", "Debris"]).unwrap();
+        assert_eq!(layers.layer("Player"), Some(0b10));
+        assert_eq!(layers.mask(["Player", "Debris"]).unwrap(), 0b1010);
+        assert_eq!(layers.names_in(0b101), vec!["Default", "Enemy"]);
+        assert!(matches!(
+            layers.mask(["Water"]),
+            Err(CollisionLayersError::UnknownLayer(name)) if name == "Water"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_layer_definitions() {
+        assert!(matches!(
+            CollisionLayers::new(["Default", "Player", "Player"]),
+            Err(CollisionLayersError::DuplicateName(_))
+        ));
+        assert!(matches!(
+            CollisionLayers::new((0..33).map(|i| format!("Layer{i}"))),
+            Err(CollisionLayersError::TooManyLayers { count: 33 })
+        ));
+    }
+
+    #[test]
+    fn test_load_from_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CollisionLayers::FILE_NAME);
+        let layers = CollisionLayers::new(["Default", "Player"]).unwrap();
+        layers.save_to_file(&path).unwrap();
+
+        assert_eq!(CollisionLayers::load_from_file(&path).unwrap(), layers);
+        assert_eq!(
+            CollisionLayers::load_or_default(dir.path().join("missing.json")),
+            CollisionLayers::default()
+        );
+    }
+}
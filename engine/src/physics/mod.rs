@@ -4,22 +4,41 @@
 //! It supports both standard Transform (f32) and WorldTransform (f64) components for
 //! large world scenarios, and integrates with the scripting system.
 
+pub mod character;
 pub mod commands;
 pub mod components;
 pub mod debug;
+pub mod events;
+pub mod joints;
+pub mod layers;
+pub mod queries;
+pub mod replay;
+pub mod shapes;
 pub mod system;
+pub mod timestep;
 pub mod world;
 
 #[cfg(test)]
 mod tests;
 
 // Re-export commonly used types
+pub use character::{CharacterController, CharacterHit};
 pub use commands::PhysicsCommand;
 pub use components::{
     Collider, ColliderShape, PhysicsMass, PhysicsVelocity, RigidBody, RigidBodyType,
 };
 pub use debug::PhysicsDebugSettings;
+pub use events::{CollisionEvent, CollisionEventKind, CollisionEvents};
+pub use joints::{Joint, JointData, JointLimits, JointMotor, JointType};
+pub use layers::{CollisionLayers, CollisionLayersError};
+pub use queries::{QueryShape, RaycastHit, ShapeCastHit, SpatialQuery, SpatialQueryFilter};
+pub use replay::{
+    BodySnapshot, Divergence, PhysicsRecording, PhysicsRecordingError, PhysicsReplay,
+    PhysicsSnapshot, RecordedFrame, ReplayReport,
+};
+pub use shapes::{CollisionMesh, CollisionMeshes};
 pub use system::physics_update_system;
+pub use timestep::FixedTimestep;
 pub use world::PhysicsWorld;
 
 // Re-export commonly used Rapier types
@@ -0,0 +1,442 @@
+//! Immediate spatial queries against the physics world
+//!
+//! [`SpatialQuery`] runs raycasts, shape casts and overlap tests on the
+//! Rapier query pipeline and reports the entities that were hit. Rust code
+//! gets one from [`PhysicsWorld::spatial_query`]. Scripts cannot borrow the
+//! world, so the engine lends it to them with [`lend_to_scripts`] while they
+//! run, and the script API queries it through [`with_script_queries`].
+//!
+//! Positions are taken and returned in double precision like the simulation,
+//! so queries stay exact far from the origin.
+
+use crate::core::entity::Entity;
+use crate::physics::layers::CollisionLayers;
+use crate::physics::PhysicsWorld;
+use glam::{DVec3, Quat, Vec3};
+use rapier3d_f64::na::UnitQuaternion;
+use rapier3d_f64::parry::shape::Shape;
+use rapier3d_f64::prelude::{
+    point, vector, Ball, Capsule, Collider as RapierCollider, ColliderHandle, ColliderSet, Cuboid,
+    Group, InteractionGroups, Isometry, QueryFilter, QueryPipeline, Ray, RigidBodySet, Vector,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Closest hit of a ray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    /// Entity that was hit
+    pub entity: Entity,
+    /// Distance along the ray to the hit point
+    pub distance: f64,
+    /// Hit point in world space
+    pub point: DVec3,
+    /// Surface normal at the hit point
+    pub normal: Vec3,
+}
+
+/// First contact of a shape swept along a direction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeCastHit {
+    /// Entity that was hit
+    pub entity: Entity,
+    /// Distance the shape travelled before touching the entity
+    pub distance: f64,
+    /// Contact point on the hit entity in world space
+    pub point: DVec3,
+    /// Surface normal of the hit entity at the contact point
+    pub normal: Vec3,
+}
+
+/// Shape used for shape casts and overlap tests
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryShape {
+    /// Sphere with the given radius
+    Sphere {
+        /// Radius of the sphere
+        radius: f32,
+    },
+    /// Box with the given half extents
+    Box {
+        /// Half the size of the box along each axis
+        half_extents: Vec3,
+    },
+    /// Capsule along the Y axis
+    Capsule {
+        /// Half the length of the segment between the caps
+        half_height: f32,
+        /// Radius of the caps
+        radius: f32,
+    },
+}
+
+impl QueryShape {
+    fn to_rapier(self) -> Box<dyn Shape> {
+        match self {
+            Self::Sphere { radius } => Box::new(Ball::new(radius as f64)),
+            Self::Box { half_extents } => Box::new(Cuboid::new(vector![
+                half_extents.x as f64,
+                half_extents.y as f64,
+                half_extents.z as f64
+            ])),
+            Self::Capsule {
+                half_height,
+                radius,
+            } => Box::new(Capsule::new_y(half_height as f64, radius as f64)),
+        }
+    }
+}
+
+/// Which colliders a query can hit
+///
+/// The masks work like a collider's `membership` and `filter`: a collider is
+/// hit when its layers are in `mask` and the query's `memberships` are in its
+/// filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpatialQueryFilter {
+    /// Collision groups the query belongs to, as a bit mask
+    pub memberships: u32,
+    /// Collision groups the query can hit, as a bit mask
+    pub mask: u32,
+    /// Entity whose colliders are ignored, usually the one asking
+    pub exclude: Option<Entity>,
+    /// Whether sensor colliders can be hit
+    pub include_sensors: bool,
+}
+
+impl Default for SpatialQueryFilter {
+    fn default() -> Self {
+        Self {
+            memberships: u32::MAX,
+            mask: u32::MAX,
+            exclude: None,
+            include_sensors: false,
+        }
+    }
+}
+
+impl SpatialQueryFilter {
+    /// Only hit colliders whose groups match `memberships` and `mask`
+    pub fn with_groups(mut self, memberships: u32, mask: u32) -> Self {
+        self.memberships = memberships;
+        self.mask = mask;
+        self
+    }
+
+    /// Only hit colliders on one of the `mask` layers
+    pub fn with_mask(mut self, mask: u32) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Ignore the colliders of `entity`
+    pub fn excluding(mut self, entity: Entity) -> Self {
+        self.exclude = Some(entity);
+        self
+    }
+
+    /// Also hit sensor colliders
+    pub fn with_sensors(mut self) -> Self {
+        self.include_sensors = true;
+        self
+    }
+}
+
+/// Read-only view of the colliders for running spatial queries
+pub struct SpatialQuery<'a> {
+    bodies: &'a RigidBodySet,
+    colliders: &'a ColliderSet,
+    pipeline: &'a QueryPipeline,
+    collider_to_entity: &'a HashMap<ColliderHandle, Entity>,
+}
+
+impl<'a> SpatialQuery<'a> {
+    pub(crate) fn new(
+        bodies: &'a RigidBodySet,
+        colliders: &'a ColliderSet,
+        pipeline: &'a QueryPipeline,
+        collider_to_entity: &'a HashMap<ColliderHandle, Entity>,
+    ) -> Self {
+        Self {
+            bodies,
+            colliders,
+            pipeline,
+            collider_to_entity,
+        }
+    }
+
+    /// Closest entity hit by a ray within `max_distance`
+    pub fn raycast(
+        &self,
+        origin: DVec3,
+        direction: Vec3,
+        max_distance: f64,
+        filter: &SpatialQueryFilter,
+    ) -> Option<RaycastHit> {
+        let ray = to_ray(origin, direction)?;
+        let predicate = self.exclude_predicate(filter);
+        let (handle, intersection) = self.pipeline.cast_ray_and_get_normal(
+            self.bodies,
+            self.colliders,
+            &ray,
+            max_distance,
+            true,
+            self.rapier_filter(filter, &predicate),
+        )?;
+        self.ray_hit(&ray, handle, intersection.toi, intersection.normal)
+    }
+
+    /// Every entity hit by a ray within `max_distance`, closest first
+    ///
+    /// Entities with several colliders are reported once, at their closest hit.
+    pub fn raycast_all(
+        &self,
+        origin: DVec3,
+        direction: Vec3,
+        max_distance: f64,
+        filter: &SpatialQueryFilter,
+    ) -> Vec<RaycastHit> {
+        let Some(ray) = to_ray(origin, direction) else {
+            return Vec::new();
+        };
+        let predicate = self.exclude_predicate(filter);
+        let mut hits: Vec<RaycastHit> = Vec::new();
+        self.pipeline.intersections_with_ray(
+            self.bodies,
+            self.colliders,
+            &ray,
+            max_distance,
+            true,
+            self.rapier_filter(filter, &predicate),
+            |handle, intersection| {
+                if let Some(hit) = self.ray_hit(&ray, handle, intersection.toi, intersection.normal)
+                {
+                    hits.push(hit);
+                }
+                true
+            },
+        );
+
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        let mut seen = Vec::new();
+        hits.retain(|hit| {
+            let first = !seen.contains(&hit.entity);
+            seen.push(hit.entity);
+            first
+        });
+        hits
+    }
+
+    /// First entity touched by `shape` moving from `position` along `direction`
+    ///
+    /// Shapes that already overlap something at the start report a hit at
+    /// distance zero.
+    pub fn shape_cast(
+        &self,
+        shape: QueryShape,
+        position: DVec3,
+        rotation: Quat,
+        direction: Vec3,
+        max_distance: f64,
+        filter: &SpatialQueryFilter,
+    ) -> Option<ShapeCastHit> {
+        let direction = direction.try_normalize()?;
+        let predicate = self.exclude_predicate(filter);
+        let (handle, toi) = self.pipeline.cast_shape(
+            self.bodies,
+            self.colliders,
+            &to_isometry(position, rotation),
+            &vector![direction.x as f64, direction.y as f64, direction.z as f64],
+            &*shape.to_rapier(),
+            max_distance,
+            true,
+            self.rapier_filter(filter, &predicate),
+        )?;
+
+        // The pipeline is a composite shape at the origin, so the first
+        // witness and normal are already in world space
+        Some(ShapeCastHit {
+            entity: self.entity_for(handle)?,
+            distance: toi.toi,
+            point: DVec3::new(toi.witness1.x, toi.witness1.y, toi.witness1.z),
+            normal: Vec3::new(
+                toi.normal1.x as f32,
+                toi.normal1.y as f32,
+                toi.normal1.z as f32,
+            ),
+        })
+    }
+
+    /// Entities with a collider containing `point`
+    pub fn overlap_point(&self, point: DVec3, filter: &SpatialQueryFilter) -> Vec<Entity> {
+        let predicate = self.exclude_predicate(filter);
+        let mut entities = Vec::new();
+        self.pipeline.intersections_with_point(
+            self.bodies,
+            self.colliders,
+            &point![point.x, point.y, point.z],
+            self.rapier_filter(filter, &predicate),
+            |handle| {
+                self.collect_entity(handle, &mut entities);
+                true
+            },
+        );
+        entities
+    }
+
+    /// Entities with a collider overlapping `shape` placed at `position`
+    pub fn overlap_shape(
+        &self,
+        shape: QueryShape,
+        position: DVec3,
+        rotation: Quat,
+        filter: &SpatialQueryFilter,
+    ) -> Vec<Entity> {
+        let predicate = self.exclude_predicate(filter);
+        let mut entities = Vec::new();
+        self.pipeline.intersections_with_shape(
+            self.bodies,
+            self.colliders,
+            &to_isometry(position, rotation),
+            &*shape.to_rapier(),
+            self.rapier_filter(filter, &predicate),
+            |handle| {
+                self.collect_entity(handle, &mut entities);
+                true
+            },
+        );
+        entities
+    }
+
+    fn entity_for(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.collider_to_entity.get(&handle).copied()
+    }
+
+    fn collect_entity(&self, handle: ColliderHandle, entities: &mut Vec<Entity>) {
+        if let Some(entity) = self.entity_for(handle) {
+            if !entities.contains(&entity) {
+                entities.push(entity);
+            }
+        }
+    }
+
+    fn ray_hit(
+        &self,
+        ray: &Ray,
+        handle: ColliderHandle,
+        toi: f64,
+        normal: Vector<f64>,
+    ) -> Option<RaycastHit> {
+        let point = ray.point_at(toi);
+        Some(RaycastHit {
+            entity: self.entity_for(handle)?,
+            distance: toi,
+            point: DVec3::new(point.x, point.y, point.z),
+            normal: Vec3::new(normal.x as f32, normal.y as f32, normal.z as f32),
+        })
+    }
+
+    /// Predicate rejecting the colliders of the excluded entity
+    ///
+    /// Entities can own colliders without a rigid body, so the exclusion is
+    /// checked per collider instead of through `exclude_rigid_body`.
+    fn exclude_predicate(
+        &self,
+        filter: &SpatialQueryFilter,
+    ) -> impl Fn(ColliderHandle, &RapierCollider) -> bool + 'a {
+        let excluded = filter.exclude;
+        let collider_to_entity = self.collider_to_entity;
+        move |handle, _collider| {
+            excluded.is_none() || collider_to_entity.get(&handle).copied() != excluded
+        }
+    }
+
+    fn rapier_filter<'f>(
+        &self,
+        filter: &SpatialQueryFilter,
+        predicate: &'f impl Fn(ColliderHandle, &RapierCollider) -> bool,
+    ) -> QueryFilter<'f> {
+        let mut rapier_filter = QueryFilter::new()
+            .groups(InteractionGroups::new(
+                Group::from(filter.memberships),
+                Group::from(filter.mask),
+            ))
+            .predicate(predicate);
+        if !filter.include_sensors {
+            rapier_filter = rapier_filter.exclude_sensors();
+        }
+        rapier_filter
+    }
+}
+
+thread_local! {
+    /// Physics world lent to the script system while scripts run
+    static SCRIPT_PHYSICS: RefCell<Option<PhysicsWorld>> = const { RefCell::new(None) };
+}
+
+/// Puts a lent physics world back when the scripts are done, even on panic
+struct ScriptPhysicsLoan<'a> {
+    physics_world: &'a mut Option<PhysicsWorld>,
+}
+
+impl Drop for ScriptPhysicsLoan<'_> {
+    fn drop(&mut self) {
+        *self.physics_world = SCRIPT_PHYSICS.with(|lent| lent.borrow_mut().take());
+    }
+}
+
+/// Run `f` with the physics world available to script queries
+///
+/// The world is moved out of `physics_world` for the duration of `f` and
+/// moved back afterwards, so scripts query the live colliders without
+/// copying them. Outside of `f`, script queries hit nothing.
+pub fn lend_to_scripts<R>(physics_world: &mut Option<PhysicsWorld>, f: impl FnOnce() -> R) -> R {
+    SCRIPT_PHYSICS.with(|lent| *lent.borrow_mut() = physics_world.take());
+    let _loan = ScriptPhysicsLoan { physics_world };
+    f()
+}
+
+/// Run `f` against the physics world lent to the scripts
+pub fn with_script_queries<R>(f: impl FnOnce(&SpatialQuery) -> R) -> R {
+    SCRIPT_PHYSICS.with(|lent| match lent.borrow().as_ref() {
+        Some(physics_world) => f(&physics_world.spatial_query()),
+        None => {
+            let (bodies, colliders) = (RigidBodySet::new(), ColliderSet::new());
+            let (pipeline, collider_to_entity) = (QueryPipeline::new(), HashMap::new());
+            f(&SpatialQuery::new(
+                &bodies,
+                &colliders,
+                &pipeline,
+                &collider_to_entity,
+            ))
+        }
+    })
+}
+
+/// Run `f` with the collision layers of the physics world lent to the scripts
+pub fn with_script_layers<R>(f: impl FnOnce(&CollisionLayers) -> R) -> R {
+    SCRIPT_PHYSICS.with(|lent| match lent.borrow().as_ref() {
+        Some(physics_world) => f(&physics_world.collision_layers),
+        None => f(&CollisionLayers::default()),
+    })
+}
+
+fn to_ray(origin: DVec3, direction: Vec3) -> Option<Ray> {
+    let direction = direction.try_normalize()?;
+    Some(Ray::new(
+        point![origin.x, origin.y, origin.z],
+        vector![direction.x as f64, direction.y as f64, direction.z as f64],
+    ))
+}
+
+fn to_isometry(position: DVec3, rotation: Quat) -> Isometry<f64> {
+    Isometry::from_parts(
+        vector![position.x, position.y, position.z].into(),
+        UnitQuaternion::from_quaternion(rapier3d_f64::na::Quaternion::new(
+            rotation.w as f64,
+            rotation.x as f64,
+            rotation.y as f64,
+            rotation.z as f64,
+        )),
+    )
+}
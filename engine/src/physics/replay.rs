@@ -0,0 +1,585 @@
+//! Recording and deterministic replay of the physics simulation
+//!
+//! While a [`PhysicsWorld`] is recording (see
+//! [`PhysicsWorld::start_recording`]), every physics update stores what went
+//! into it from outside the simulation as a [`RecordedFrame`]:
+//!
+//! - the [`PhysicsCommand`]s it applied
+//! - the poses bodies were created at
+//! - poses that scripts or other systems gave kinematic and fixed bodies
+//! - velocities that scripts or other systems gave dynamic bodies
+//!
+//! Every few fixed steps the state of all rigid bodies is stored as a
+//! [`PhysicsSnapshot`]. The resulting [`PhysicsRecording`] can be saved to
+//! a JSON file and attached to a bug report.
+//!
+//! [`PhysicsReplay`] feeds the recorded inputs into a world that starts
+//! out like the recorded one did. It takes the same fixed steps and reports
+//! the first snapshot where a body ended up somewhere else. Scripts do not
+//! need to run, so replays work in headless tests.
+//!
+//! Entities are matched by their id. The replay world has to be built the
+//! same way as the recorded one, for example by loading the same scene
+//! into a fresh world.
+
+use crate::core::entity::components::{Transform, WorldTransform};
+use crate::core::entity::{Entity, World};
+use crate::physics::{PhysicsCommand, PhysicsVelocity, PhysicsWorld};
+use glam::{DQuat, DVec3, Quat, Vec3};
+use rapier3d_f64::prelude::{vector, RigidBodyHandle, RigidBodySet};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Errors that can occur while saving or loading a physics recording
+#[derive(Debug, thiserror::Error)]
+pub enum PhysicsRecordingError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Pose a body was created at, or given from outside the simulation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPose {
+    /// Entity ID of the body
+    pub entity: u64,
+    /// Position in world space
+    pub position: DVec3,
+    /// Rotation in world space
+    pub rotation: Quat,
+}
+
+/// Velocity given to a dynamic body from outside the simulation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordedVelocity {
+    /// Entity ID of the body
+    pub entity: u64,
+    /// Linear velocity in world space
+    pub linear: Vec3,
+    /// Angular velocity in world space
+    pub angular: Vec3,
+}
+
+/// Inputs of one physics update
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Fixed steps taken since the recording started, before this update
+    pub step: u64,
+    /// Fixed steps taken by this update
+    pub steps: u32,
+    /// Commands applied, in order
+    #[serde(default)]
+    pub commands: Vec<PhysicsCommand>,
+    /// Poses of bodies created in this update, and of kinematic and fixed
+    /// bodies whose transforms changed since the last update
+    #[serde(default)]
+    pub poses: Vec<RecordedPose>,
+    /// Velocities synced from [`PhysicsVelocity`] components that changed
+    /// since the last update
+    #[serde(default)]
+    pub velocities: Vec<RecordedVelocity>,
+}
+
+impl RecordedFrame {
+    /// Whether the update neither stepped nor received any input
+    fn is_empty(&self) -> bool {
+        self.steps == 0
+            && self.commands.is_empty()
+            && self.poses.is_empty()
+            && self.velocities.is_empty()
+    }
+}
+
+/// State of one rigid body
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BodySnapshot {
+    /// Entity ID of the body
+    pub entity: u64,
+    /// Position in world space
+    pub position: DVec3,
+    /// Rotation in world space
+    pub rotation: DQuat,
+    /// Linear velocity in world space
+    pub linear_velocity: DVec3,
+    /// Angular velocity in world space
+    pub angular_velocity: DVec3,
+}
+
+impl BodySnapshot {
+    /// Largest difference to another state
+    ///
+    /// Positions and velocities are compared by distance, rotations by the
+    /// distance between their quaternions, so the result mixes units but is
+    /// zero only for identical states.
+    pub fn difference(&self, other: &Self) -> f64 {
+        [
+            self.position.distance(other.position),
+            // q and -q are the same rotation
+            (self.rotation - other.rotation)
+                .length()
+                .min((self.rotation + other.rotation).length()),
+            self.linear_velocity.distance(other.linear_velocity),
+            self.angular_velocity.distance(other.angular_velocity),
+        ]
+        .into_iter()
+        .fold(0.0, f64::max)
+    }
+}
+
+/// State of all rigid bodies after a fixed step
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhysicsSnapshot {
+    /// Fixed steps taken since the recording started
+    pub step: u64,
+    /// Bodies ordered by entity ID
+    pub bodies: Vec<BodySnapshot>,
+}
+
+impl PhysicsSnapshot {
+    /// Capture the bodies that belong to entities
+    pub(crate) fn capture(
+        step: u64,
+        rigid_body_set: &RigidBodySet,
+        body_to_entity: &HashMap<RigidBodyHandle, Entity>,
+    ) -> Self {
+        let mut bodies: Vec<BodySnapshot> = body_to_entity
+            .iter()
+            .filter_map(|(handle, entity)| {
+                let body = rigid_body_set.get(*handle)?;
+                let (t, r) = (body.translation(), body.rotation());
+                let (linvel, angvel) = (body.linvel(), body.angvel());
+                Some(BodySnapshot {
+                    entity: entity.to_bits().get(),
+                    position: DVec3::new(t.x, t.y, t.z),
+                    rotation: DQuat::from_xyzw(r.i, r.j, r.k, r.w),
+                    linear_velocity: DVec3::new(linvel.x, linvel.y, linvel.z),
+                    angular_velocity: DVec3::new(angvel.x, angvel.y, angvel.z),
+                })
+            })
+            .collect();
+        bodies.sort_by_key(|body| body.entity);
+        Self { step, bodies }
+    }
+
+    /// State of the body of an entity
+    pub fn body(&self, entity: u64) -> Option<&BodySnapshot> {
+        self.bodies
+            .binary_search_by_key(&entity, |body| body.entity)
+            .ok()
+            .map(|index| &self.bodies[index])
+    }
+
+    /// First body that differs from `expected` by more than `tolerance`,
+    /// including bodies missing from either snapshot
+    fn first_difference(&self, expected: &Self, tolerance: f64) -> Option<Divergence> {
+        let divergence =
+            |entity, expected: Option<&BodySnapshot>, actual: Option<&BodySnapshot>| {
+                let difference = match (expected, actual) {
+                    (Some(expected), Some(actual)) => expected.difference(actual),
+                    _ => f64::INFINITY,
+                };
+                (difference > tolerance).then(|| Divergence {
+                    step: self.step,
+                    entity: Some(entity),
+                    expected: expected.copied(),
+                    actual: actual.copied(),
+                    difference,
+                })
+            };
+
+        expected
+            .bodies
+            .iter()
+            .find_map(|body| divergence(body.entity, Some(body), self.body(body.entity)))
+            .or_else(|| {
+                self.bodies
+                    .iter()
+                    .filter(|body| expected.body(body.entity).is_none())
+                    .find_map(|body| divergence(body.entity, None, Some(body)))
+            })
+    }
+}
+
+/// Inputs and periodic state of a recorded stretch of simulation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhysicsRecording {
+    /// Fixed steps per second
+    pub rate: f64,
+    /// Gravity of the recorded world
+    pub gravity: DVec3,
+    /// Fixed steps between snapshots
+    pub snapshot_interval: u64,
+    /// Inputs of each physics update that stepped or received input
+    pub frames: Vec<RecordedFrame>,
+    /// Body states, starting with the state when recording began and
+    /// ending with the state when it stopped
+    pub snapshots: Vec<PhysicsSnapshot>,
+}
+
+impl PhysicsRecording {
+    /// Fixed steps covered by the recording
+    pub fn steps(&self) -> u64 {
+        self.frames
+            .last()
+            .map(|frame| frame.step + frame.steps as u64)
+            .unwrap_or(0)
+    }
+
+    /// Load a recording from a JSON file
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, PhysicsRecordingError> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Save the recording to a JSON file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), PhysicsRecordingError> {
+        let json = serde_json::to_string(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Collects a [`PhysicsRecording`] while the physics world updates
+#[derive(Debug)]
+pub(crate) struct PhysicsRecorder {
+    recording: PhysicsRecording,
+    /// Fixed steps taken since the recording started
+    step: u64,
+}
+
+impl PhysicsRecorder {
+    /// Start a recording from the world's current state
+    pub(crate) fn new(
+        rate: f64,
+        gravity: DVec3,
+        snapshot_interval: u64,
+        initial: PhysicsSnapshot,
+    ) -> Self {
+        Self {
+            recording: PhysicsRecording {
+                rate,
+                gravity,
+                snapshot_interval: snapshot_interval.max(1),
+                frames: Vec::new(),
+                snapshots: vec![initial],
+            },
+            step: 0,
+        }
+    }
+
+    /// Start recording the inputs of an update taking `steps` fixed steps
+    pub(crate) fn begin_frame(&mut self, steps: u32) {
+        self.recording.frames.push(RecordedFrame {
+            step: self.step,
+            steps,
+            ..Default::default()
+        });
+    }
+
+    /// Drop the current frame again if nothing happened in it
+    pub(crate) fn end_frame(&mut self) {
+        if self
+            .recording
+            .frames
+            .last()
+            .is_some_and(RecordedFrame::is_empty)
+        {
+            self.recording.frames.pop();
+        }
+    }
+
+    fn frame(&mut self) -> Option<&mut RecordedFrame> {
+        self.recording.frames.last_mut()
+    }
+
+    /// Record a command applied in the current frame
+    pub(crate) fn record_command(&mut self, command: &PhysicsCommand) {
+        if let Some(frame) = self.frame() {
+            frame.commands.push(command.clone());
+        }
+    }
+
+    /// Record a pose synced to a kinematic or fixed body in the current frame
+    pub(crate) fn record_pose(&mut self, entity: Entity, position: DVec3, rotation: Quat) {
+        if let Some(frame) = self.frame() {
+            frame.poses.push(RecordedPose {
+                entity: entity.to_bits().get(),
+                position,
+                rotation,
+            });
+        }
+    }
+
+    /// Record a velocity synced to a dynamic body in the current frame
+    pub(crate) fn record_velocity(&mut self, entity: Entity, linear: Vec3, angular: Vec3) {
+        if let Some(frame) = self.frame() {
+            frame.velocities.push(RecordedVelocity {
+                entity: entity.to_bits().get(),
+                linear,
+                angular,
+            });
+        }
+    }
+
+    /// Count a fixed step, returning its number if a snapshot is due after it
+    pub(crate) fn finish_step(&mut self) -> Option<u64> {
+        self.step += 1;
+        (self.step % self.recording.snapshot_interval == 0).then_some(self.step)
+    }
+
+    /// Store the state after a step
+    pub(crate) fn push_snapshot(&mut self, snapshot: PhysicsSnapshot) {
+        self.recording.snapshots.push(snapshot);
+    }
+
+    /// Fixed steps taken since the recording started
+    pub(crate) fn step(&self) -> u64 {
+        self.step
+    }
+
+    /// Whether the state after the last step was stored
+    pub(crate) fn has_snapshot_of_last_step(&self) -> bool {
+        self.recording
+            .snapshots
+            .last()
+            .is_some_and(|snapshot| snapshot.step == self.step)
+    }
+
+    /// Finish the recording
+    pub(crate) fn finish(self) -> PhysicsRecording {
+        self.recording
+    }
+}
+
+/// Where a replay first differed from its recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Fixed step after which the states differed
+    pub step: u64,
+    /// Entity ID of the first body that differed, `None` if the recording
+    /// or the replay has no snapshot after this step
+    pub entity: Option<u64>,
+    /// Recorded state, `None` if the body was not in the recording
+    pub expected: Option<BodySnapshot>,
+    /// Replayed state, `None` if the body was missing from the replay
+    pub actual: Option<BodySnapshot>,
+    /// Largest difference between the two states, infinite for missing bodies
+    pub difference: f64,
+}
+
+impl Divergence {
+    /// Snapshot after `step` that only one of recording and replay has
+    fn missing_snapshot(step: u64) -> Self {
+        Self {
+            step,
+            entity: None,
+            expected: None,
+            actual: None,
+            difference: f64::INFINITY,
+        }
+    }
+}
+
+/// Outcome of a [`PhysicsReplay`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    /// Fixed steps replayed
+    pub steps: u64,
+    /// Snapshots compared before the replay diverged or ended
+    pub snapshots_compared: usize,
+    /// First difference to the recording, if any
+    pub divergence: Option<Divergence>,
+}
+
+impl ReplayReport {
+    /// Whether the replay matched every snapshot of the recording
+    pub fn is_deterministic(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// Re-simulates a [`PhysicsRecording`] and compares the result
+pub struct PhysicsReplay {
+    recording: PhysicsRecording,
+    tolerance: f64,
+}
+
+impl PhysicsReplay {
+    /// Differences up to this are not reported by default
+    pub const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+    /// Prepare a replay of `recording`
+    pub fn new(recording: PhysicsRecording) -> Self {
+        Self {
+            recording,
+            tolerance: Self::DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Set the largest difference that still counts as matching
+    ///
+    /// Replays on the machine and build that made the recording match
+    /// exactly. Other platforms may need some tolerance.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// The recording being replayed
+    pub fn recording(&self) -> &PhysicsRecording {
+        &self.recording
+    }
+
+    /// Replay the recording on a world that starts like the recorded one
+    ///
+    /// Each recorded update writes its poses and velocities to the entities'
+    /// components, queues its commands and runs the physics update with the
+    /// recorded number of steps. The world's timestep rate and gravity are
+    /// set to the recorded ones. A recording in progress on `physics_world`
+    /// is paused for the replay.
+    pub fn run(&self, world: &mut World, physics_world: &mut PhysicsWorld) -> ReplayReport {
+        let recording = &self.recording;
+        let mut timestep = physics_world.timestep;
+        timestep.rate = recording.rate;
+        physics_world.set_timestep(timestep);
+        let g = recording.gravity;
+        physics_world.set_gravity(vector![g.x, g.y, g.z]);
+
+        // Record the replay the same way to get snapshots after the same steps
+        let paused = physics_world.recorder.take();
+        physics_world.start_recording(recording.snapshot_interval);
+        for frame in &recording.frames {
+            apply_frame(world, frame);
+            crate::physics::system::update_physics(world, physics_world, frame.steps);
+        }
+        let replayed = physics_world
+            .stop_recording()
+            .expect("replay recording was started above");
+        physics_world.recorder = paused;
+
+        let mut report = ReplayReport {
+            steps: replayed.steps(),
+            snapshots_compared: 0,
+            divergence: None,
+        };
+        // A truncated recording or replay diverges where its snapshots run out
+        let count = recording.snapshots.len().max(replayed.snapshots.len());
+        for index in 0..count {
+            let expected = recording.snapshots.get(index);
+            let actual = replayed.snapshots.get(index);
+            let divergence = match (expected, actual) {
+                (Some(expected), Some(actual)) if expected.step == actual.step => {
+                    report.snapshots_compared += 1;
+                    actual.first_difference(expected, self.tolerance)
+                }
+                _ => [expected, actual]
+                    .into_iter()
+                    .flatten()
+                    .map(|snapshot| snapshot.step)
+                    .min()
+                    .map(Divergence::missing_snapshot),
+            };
+            if let Some(divergence) = divergence {
+                info!(
+                    step = divergence.step,
+                    entity = ?divergence.entity,
+                    difference = divergence.difference,
+                    "Physics replay diverged"
+                );
+                report.divergence = Some(divergence);
+                break;
+            }
+        }
+        if report.divergence.is_none() {
+            debug!(
+                steps = report.steps,
+                snapshots = report.snapshots_compared,
+                "Physics replay matched the recording"
+            );
+        }
+        report
+    }
+}
+
+/// Write a frame's recorded inputs into the world and the command queue
+fn apply_frame(world: &mut World, frame: &RecordedFrame) {
+    for pose in &frame.poses {
+        let Some(entity) = Entity::from_bits(pose.entity) else {
+            continue;
+        };
+        if let Ok(world_transform) = world.query_one_mut::<&mut WorldTransform>(entity) {
+            world_transform.position = pose.position;
+            world_transform.rotation = pose.rotation;
+        } else if let Ok(transform) = world.query_one_mut::<&mut Transform>(entity) {
+            transform.position = pose.position.as_vec3();
+            transform.rotation = pose.rotation;
+        }
+    }
+    for recorded in &frame.velocities {
+        let Some(entity) = Entity::from_bits(recorded.entity) else {
+            continue;
+        };
+        if let Ok(velocity) = world.query_one_mut::<&mut PhysicsVelocity>(entity) {
+            velocity.linear = recorded.linear;
+            velocity.angular = recorded.angular;
+        }
+    }
+    for command in &frame.commands {
+        crate::physics::system::queue_physics_command(command.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(entity: u64, x: f64) -> BodySnapshot {
+        BodySnapshot {
+            entity,
+            position: DVec3::new(x, 0.0, 0.0),
+            rotation: DQuat::IDENTITY,
+            linear_velocity: DVec3::ZERO,
+            angular_velocity: DVec3::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_first_difference_reports_moved_and_missing_bodies() {
+        let expected = PhysicsSnapshot {
+            step: 4,
+            bodies: vec![body(1, 0.0), body(2, 1.0)],
+        };
+        assert_eq!(expected.first_difference(&expected, 0.0), None);
+
+        let moved = PhysicsSnapshot {
+            step: 4,
+            bodies: vec![body(1, 0.0), body(2, 1.5)],
+        };
+        let divergence = moved.first_difference(&expected, 0.0).unwrap();
+        assert_eq!(divergence.step, 4);
+        assert_eq!(divergence.entity, Some(2));
+        assert!((divergence.difference - 0.5).abs() < 1e-12);
+        assert!(moved.first_difference(&expected, 1.0).is_none());
+
+        let missing = PhysicsSnapshot {
+            step: 4,
+            bodies: vec![body(1, 0.0)],
+        };
+        let divergence = missing.first_difference(&expected, 1.0).unwrap();
+        assert_eq!(divergence.entity, Some(2));
+        assert_eq!(divergence.actual, None);
+        assert_eq!(divergence.difference, f64::INFINITY);
+
+        let extra = PhysicsSnapshot {
+            step: 4,
+            bodies: vec![body(1, 0.0), body(2, 1.0), body(3, 0.0)],
+        };
+        let divergence = extra.first_difference(&expected, 1.0).unwrap();
+        assert_eq!(divergence.entity, Some(3));
+        assert_eq!(divergence.expected, None);
+    }
+}
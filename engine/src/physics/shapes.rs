@@ -0,0 +1,180 @@
+//! Collision geometry taken from render meshes
+//!
+//! [`ColliderShape::TriMesh`](crate::physics::ColliderShape::TriMesh) and
+//! [`ColliderShape::ConvexHull`](crate::physics::ColliderShape::ConvexHull)
+//! collide with the mesh the entity's [`MeshId`] renders. The physics world
+//! keeps the triangles of these meshes in [`CollisionMeshes`], resolving a
+//! mesh id the same way the renderer does: meshes registered at runtime
+//! (such as meshes generated by scripts) first, then mesh files, then the
+//! built-in [`MeshLibrary`] meshes.
+
+use crate::graphics::mesh_loader::{is_mesh_file_reference, load_meshes_from_file};
+use crate::graphics::{Mesh, MeshId, MeshLibrary};
+use glam::Vec3;
+use rapier3d_f64::prelude::{point, Point, SharedShape};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Triangles of a mesh, without the vertex attributes only rendering needs
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionMesh {
+    /// Vertex positions in the mesh's local space
+    pub vertices: Vec<Vec3>,
+    /// Vertex indices of each triangle
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl CollisionMesh {
+    /// Extract the triangles of a render mesh
+    ///
+    /// Trailing indices that do not form a whole triangle are ignored.
+    pub fn from_mesh(mesh: &Mesh) -> Self {
+        Self {
+            vertices: mesh
+                .vertices
+                .iter()
+                .map(|vertex| Vec3::from(vertex.position))
+                .collect(),
+            triangles: mesh
+                .indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                .collect(),
+        }
+    }
+
+    /// Vertex positions multiplied by `scale`, as Rapier points
+    fn scaled_points(&self, scale: Vec3) -> Vec<Point<f64>> {
+        self.vertices
+            .iter()
+            .map(|vertex| {
+                let v = *vertex * scale;
+                point![v.x as f64, v.y as f64, v.z as f64]
+            })
+            .collect()
+    }
+
+    /// Triangle mesh shape of the mesh scaled by `scale`
+    ///
+    /// Returns `None` for meshes without triangles or with out of range indices.
+    pub fn trimesh(&self, scale: Vec3) -> Option<SharedShape> {
+        let vertex_count = self.vertices.len() as u32;
+        if self.triangles.is_empty()
+            || self
+                .triangles
+                .iter()
+                .flatten()
+                .any(|&index| index >= vertex_count)
+        {
+            return None;
+        }
+        Some(SharedShape::trimesh(
+            self.scaled_points(scale),
+            self.triangles.clone(),
+        ))
+    }
+
+    /// Convex hull shape around the mesh scaled by `scale`
+    ///
+    /// Returns `None` when no hull can be computed, e.g. for an empty mesh.
+    pub fn convex_hull(&self, scale: Vec3) -> Option<SharedShape> {
+        SharedShape::convex_hull(&self.scaled_points(scale))
+    }
+}
+
+/// Collision meshes of the mesh ids used by mesh colliders
+#[derive(Default)]
+pub struct CollisionMeshes {
+    /// Resolved meshes, `None` for ids that could not be resolved
+    meshes: HashMap<String, Option<CollisionMesh>>,
+    /// Built-in meshes such as `cube` and `sphere`
+    library: MeshLibrary,
+}
+
+impl CollisionMeshes {
+    /// Make a mesh available under `name`, replacing any mesh resolved before
+    ///
+    /// Meshes that only exist at runtime, such as meshes generated by scripts,
+    /// have to be registered before colliders can use them.
+    pub fn register(&mut self, name: &str, mesh: &Mesh) {
+        debug!(
+            name,
+            vertices = mesh.vertices.len(),
+            "Registered collision mesh"
+        );
+        self.meshes
+            .insert(name.to_string(), Some(CollisionMesh::from_mesh(mesh)));
+    }
+
+    /// Collision mesh for a mesh id, loading it on first use
+    ///
+    /// Loading one sub-mesh of a file makes all of its sub-meshes available.
+    /// Ids that fail to resolve are reported once and remembered, so a
+    /// missing file is not read again every frame.
+    pub fn get_or_load(&mut self, mesh_id: &MeshId) -> Option<&CollisionMesh> {
+        let name = mesh_id.0.as_str();
+        if !self.meshes.contains_key(name) {
+            let mesh = if is_mesh_file_reference(name) {
+                match load_meshes_from_file(Path::new(name)) {
+                    Ok(meshes) => {
+                        for (id, mesh) in &meshes {
+                            self.meshes
+                                .entry(id.clone())
+                                .or_insert_with(|| Some(CollisionMesh::from_mesh(mesh)));
+                        }
+                        return self.meshes.get(name).and_then(Option::as_ref);
+                    }
+                    Err(e) => {
+                        warn!(mesh = name, error = %e, "Failed to load collision mesh");
+                        None
+                    }
+                }
+            } else {
+                let mesh = self.library.get_or_generate(name);
+                if mesh.is_none() {
+                    warn!(mesh = name, "Unknown mesh for mesh collider");
+                }
+                mesh
+            };
+            self.meshes.insert(
+                name.to_string(),
+                mesh.as_ref().map(CollisionMesh::from_mesh),
+            );
+        }
+        self.meshes.get(name).and_then(Option::as_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_library_mesh_resolves_to_scaled_shapes() {
+        let mut meshes = CollisionMeshes::default();
+        let cube = meshes
+            .get_or_load(&MeshId("cube".to_string()))
+            .unwrap()
+            .clone();
+        assert_eq!(cube.triangles.len(), 12);
+
+        let hull = cube.convex_hull(Vec3::new(2.0, 1.0, 1.0)).unwrap();
+        let aabb = hull.compute_local_aabb();
+        assert!((aabb.maxs.x - 1.0).abs() < 1e-6);
+        assert!((aabb.maxs.y - 0.5).abs() < 1e-6);
+        assert!(cube.trimesh(Vec3::ONE).is_some());
+    }
+
+    #[test]
+    fn test_registered_mesh_replaces_unresolved_id() {
+        let mut meshes = CollisionMeshes::default();
+        let id = MeshId("script_mesh_7".to_string());
+        assert!(meshes.get_or_load(&id).is_none());
+
+        meshes.register(&id.0, &Mesh::plane(2.0, 2.0));
+        let plane = meshes.get_or_load(&id).unwrap();
+        assert_eq!(plane.triangles.len(), 4);
+        assert!(plane.trimesh(Vec3::ONE).is_some());
+    }
+}
@@ -3,16 +3,22 @@
 //! This system synchronizes between the ECS transform components and the Rapier
 //! physics simulation, supporting both Transform (f32) and WorldTransform (f64).
 
-use crate::core::entity::components::{Transform, WorldTransform};
+use crate::core::camera::CameraWorldPosition;
+use crate::core::entity::components::{
+    GlobalTransform, GlobalWorldTransform, Parent, PreviousTransform, PreviousWorldTransform,
+    Transform, WorldTransform,
+};
 use crate::core::entity::{Entity, World};
+use crate::graphics::MeshId;
 use crate::physics::{
-    commands::RaycastHit, Collider, ColliderShape, PhysicsMass, PhysicsVelocity, PhysicsWorld,
-    RigidBody, RigidBodyType,
+    CharacterController, CharacterHit, Collider, ColliderShape, Joint, PhysicsMass,
+    PhysicsVelocity, PhysicsWorld, RigidBody, RigidBodyType,
 };
 use crate::profiling::profile_zone;
-use glam::{DVec3, Quat, Vec3};
+use glam::{DMat4, DVec3, Quat, Vec3};
 use rapier3d_f64::na::UnitQuaternion;
 use rapier3d_f64::prelude::*;
+use std::collections::{HashMap, HashSet};
 use tracing::{debug, trace, warn};
 
 /// Convert glam Quat to Rapier Unit<Quaternion<f64>>
@@ -26,7 +32,6 @@ fn quat_to_rapier_rotation(quat: Quat) -> UnitQuaternion<f64> {
 }
 
 /// Convert Rapier Unit<Quaternion<f64>> to glam Quat
-#[allow(dead_code)]
 fn rapier_rotation_to_quat(rotation: &UnitQuaternion<f64>) -> Quat {
     Quat::from_xyzw(
         rotation.i as f32,
@@ -42,17 +47,31 @@ thread_local! {
 }
 
 /// Update the physics simulation
-pub fn physics_update_system(
-    world: &mut World,
-    physics_world: &mut PhysicsWorld,
-    _delta_time: f32,
-) {
+///
+/// The simulation advances in fixed steps of [`PhysicsWorld::timestep`], as
+/// many as fit into the time accumulated so far. Transforms always hold the
+/// latest physics state; [`interpolate_physics_transforms`] places what is
+/// rendered between the last two states.
+pub fn physics_update_system(world: &mut World, physics_world: &mut PhysicsWorld, delta_time: f32) {
     profile_zone!("physics_update_system");
+    let steps = physics_world.timestep.advance(delta_time as f64);
+    update_physics(world, physics_world, steps);
+}
+
+/// Run one physics update that takes exactly `steps` fixed steps
+///
+/// [`physics_update_system`] gets the number of steps from the accumulated
+/// frame time. Replays use the number of steps that was recorded.
+pub(crate) fn update_physics(world: &mut World, physics_world: &mut PhysicsWorld, steps: u32) {
     trace!("Physics update system starting");
+    if let Some(recorder) = physics_world.recorder.as_mut() {
+        recorder.begin_frame(steps);
+    }
 
     // Step 1: Create new physics bodies and colliders for entities that need them
     {
         profile_zone!("Create physics bodies");
+        invalidate_rescaled_mesh_colliders(world, physics_world);
         create_physics_bodies(world, physics_world);
     }
 
@@ -71,13 +90,31 @@ pub fn physics_update_system(
     // Step 4: Process physics commands from scripts
     {
         profile_zone!("Process physics commands");
-        process_physics_commands(physics_world);
+        process_physics_commands(world, physics_world);
     }
 
-    // Step 5: Step the physics simulation
+    // Step 5: Take the fixed steps due this update
     {
         profile_zone!("Physics simulation step");
-        physics_world.step();
+        physics_world.collision_events.clear();
+        if steps > 0 {
+            for (_entity, controller) in world.query_mut::<&mut CharacterController>() {
+                controller.hits.clear();
+            }
+        }
+        for _ in 0..steps {
+            store_previous_transforms(world, physics_world);
+            move_characters(world, physics_world);
+            physics_world.step();
+            physics_world.record_step();
+        }
+        // Desired motion only lasts for the frame it was set in
+        if steps > 0 {
+            for (_entity, controller) in world.query_mut::<&mut CharacterController>() {
+                controller.desired_motion = Vec3::ZERO;
+            }
+        }
+        trace!(steps, "Physics steps taken");
     }
 
     // Step 6: Write physics results back to ECS
@@ -93,6 +130,12 @@ pub fn physics_update_system(
         cleanup_removed_entities(world, physics_world);
     }
 
+    // Step 8: Hand this update's contacts to the scripts
+    crate::physics::events::publish_script_events(&physics_world.collision_events);
+
+    if let Some(recorder) = physics_world.recorder.as_mut() {
+        recorder.end_frame();
+    }
     trace!("Physics update system completed");
 }
 
@@ -125,7 +168,7 @@ fn create_physics_bodies(world: &mut World, physics_world: &mut PhysicsWorld) {
             .angular_damping(rb_component.angular_damping as f64);
 
         // Set initial position from transform
-        if let Ok(world_transform) = world.get::<&WorldTransform>(entity) {
+        if let Ok(world_transform) = world.get::<WorldTransform>(entity) {
             rb_builder = rb_builder.translation(vector![
                 world_transform.position.x,
                 world_transform.position.y,
@@ -133,7 +176,7 @@ fn create_physics_bodies(world: &mut World, physics_world: &mut PhysicsWorld) {
             ]);
             // Rapier uses angle vector representation, not quaternion for builder
             // For now, just set the rotation after creation
-        } else if let Ok(transform) = world.get::<&Transform>(entity) {
+        } else if let Ok(transform) = world.get::<Transform>(entity) {
             rb_builder = rb_builder.translation(vector![
                 transform.position.x as f64,
                 transform.position.y as f64,
@@ -148,13 +191,41 @@ fn create_physics_bodies(world: &mut World, physics_world: &mut PhysicsWorld) {
 
         // Set rotation after creation
         if let Some(rb) = physics_world.rigid_body_set.get_mut(rb_handle) {
-            if let Ok(world_transform) = world.get::<&WorldTransform>(entity) {
+            if let Ok(world_transform) = world.get::<WorldTransform>(entity) {
                 rb.set_rotation(quat_to_rapier_rotation(world_transform.rotation), true);
-            } else if let Ok(transform) = world.get::<&Transform>(entity) {
+            } else if let Ok(transform) = world.get::<Transform>(entity) {
                 rb.set_rotation(quat_to_rapier_rotation(transform.rotation), true);
             }
         }
 
+        // Replays have to create the body where it was created here
+        if let Some(recorder) = physics_world.recorder.as_mut() {
+            if let Ok(world_transform) = world.get::<WorldTransform>(entity) {
+                recorder.record_pose(entity, world_transform.position, world_transform.rotation);
+            } else if let Ok(transform) = world.get::<Transform>(entity) {
+                recorder.record_pose(entity, transform.position.as_dvec3(), transform.rotation);
+            }
+        }
+
+        // Start interpolating dynamic bodies from where they are now
+        if rb_component.body_type == RigidBodyType::Dynamic {
+            let world_transform = world.get::<WorldTransform>(entity).ok().map(|t| *t);
+            if let Some(world_transform) = world_transform {
+                if world.get::<PreviousWorldTransform>(entity).is_err() {
+                    let previous = PreviousWorldTransform::from_world_transform(&world_transform);
+                    world.insert_one(entity, previous).ok();
+                }
+            } else if world.get::<PreviousTransform>(entity).is_err() {
+                let previous = world
+                    .get::<Transform>(entity)
+                    .ok()
+                    .map(|transform| PreviousTransform::from_transform(&transform));
+                if let Some(previous) = previous {
+                    world.insert_one(entity, previous).ok();
+                }
+            }
+        }
+
         // Update the component with the handle
         rb_component.handle = Some(rb_handle);
         world.insert_one(entity, rb_component).ok();
@@ -174,31 +245,28 @@ fn create_physics_bodies(world: &mut World, physics_world: &mut PhysicsWorld) {
     // Create colliders
     for (entity, mut collider_component) in colliders_to_create {
         if let Some(rb_handle) = physics_world.get_body_handle(entity) {
-            let shape = match &collider_component.shape {
-                ColliderShape::Cuboid(half_extents) => SharedShape::cuboid(
-                    half_extents.x as f64,
-                    half_extents.y as f64,
-                    half_extents.z as f64,
-                ),
-                ColliderShape::Sphere(radius) => SharedShape::ball(*radius as f64),
-                ColliderShape::Capsule {
-                    half_height,
-                    radius,
-                } => SharedShape::capsule_y(*half_height as f64, *radius as f64),
-                ColliderShape::Cylinder {
-                    half_height,
-                    radius,
-                } => SharedShape::cylinder(*half_height as f64, *radius as f64),
+            let Some(shape) =
+                build_collider_shape(world, physics_world, entity, &collider_component.shape)
+            else {
+                warn!(
+                    "Entity {:?} has a {} collider without usable geometry",
+                    entity,
+                    shape_kind(&collider_component.shape)
+                );
+                continue;
             };
 
             let mut collider_builder = ColliderBuilder::new(shape)
                 .friction(collider_component.friction as f64)
                 .restitution(collider_component.restitution as f64)
                 .density(collider_component.density as f64)
-                .sensor(collider_component.is_sensor);
+                .sensor(collider_component.is_sensor)
+                .collision_groups(collider_component.interaction_groups())
+                .solver_groups(collider_component.interaction_groups())
+                .active_events(ActiveEvents::COLLISION_EVENTS);
 
             // Apply mass properties if present
-            if let Ok(mass) = world.get::<&PhysicsMass>(entity) {
+            if let Ok(mass) = world.get::<PhysicsMass>(entity) {
                 collider_builder = collider_builder.mass(mass.mass as f64);
                 // Note: center_of_mass needs to be set on the rigid body, not collider
             }
@@ -220,12 +288,213 @@ fn create_physics_bodies(world: &mut World, physics_world: &mut PhysicsWorld) {
             warn!("Entity {:?} has Collider but no RigidBody", entity);
         }
     }
+
+    // Create joints once both connected bodies exist
+    let mut joints_to_create = Vec::new();
+
+    for (entity, joint_component) in world.query::<&Joint>().iter() {
+        if joint_component.handle.is_none() {
+            joints_to_create.push((entity, joint_component.clone()));
+        }
+    }
+
+    for (entity, mut joint_component) in joints_to_create {
+        let Some(connected) = joint_component.connected else {
+            continue;
+        };
+        let (Some(body1), Some(body2)) = (
+            physics_world.get_body_handle(entity),
+            physics_world.get_body_handle(connected),
+        ) else {
+            trace!("Joint on entity {:?} waits for both rigid bodies", entity);
+            continue;
+        };
+
+        // An edited Joint component replaces the joint it created before
+        physics_world.remove_joint(entity);
+        let joint_handle =
+            physics_world
+                .impulse_joint_set
+                .insert(body1, body2, joint_component.to_rapier(), true);
+        physics_world.register_joint(entity, joint_handle);
+
+        // Update the component with the handle
+        joint_component.handle = Some(joint_handle);
+        world.insert_one(entity, joint_component).ok();
+
+        debug!("Created joint from entity {:?} to {:?}", entity, connected);
+    }
+}
+
+/// Name of a collider shape for log messages, without its data
+fn shape_kind(shape: &ColliderShape) -> &'static str {
+    match shape {
+        ColliderShape::Cuboid(_) => "Cuboid",
+        ColliderShape::Sphere(_) => "Sphere",
+        ColliderShape::Capsule { .. } => "Capsule",
+        ColliderShape::Cylinder { .. } => "Cylinder",
+        ColliderShape::TriMesh => "TriMesh",
+        ColliderShape::ConvexHull => "ConvexHull",
+        ColliderShape::Heightfield { .. } => "Heightfield",
+        ColliderShape::Compound => "Compound",
+    }
+}
+
+/// Build the Rapier shape of the collider on `entity`
+///
+/// Mesh shapes use the entity's [`MeshId`] scaled by its [`world_scale`], and
+/// compound shapes are assembled from the colliders of child entities without
+/// a rigid body of their own, at offsets scaled by the parent's world scale.
+/// Returns `None` when the geometry is missing or invalid, e.g. a mesh that
+/// failed to load or a heightfield with the wrong number of heights.
+fn build_collider_shape(
+    world: &World,
+    physics_world: &mut PhysicsWorld,
+    entity: Entity,
+    shape: &ColliderShape,
+) -> Option<SharedShape> {
+    match shape {
+        ColliderShape::Cuboid(half_extents) => Some(SharedShape::cuboid(
+            half_extents.x as f64,
+            half_extents.y as f64,
+            half_extents.z as f64,
+        )),
+        ColliderShape::Sphere(radius) => Some(SharedShape::ball(*radius as f64)),
+        ColliderShape::Capsule {
+            half_height,
+            radius,
+        } => Some(SharedShape::capsule_y(*half_height as f64, *radius as f64)),
+        ColliderShape::Cylinder {
+            half_height,
+            radius,
+        } => Some(SharedShape::cylinder(*half_height as f64, *radius as f64)),
+        ColliderShape::TriMesh | ColliderShape::ConvexHull => {
+            let mesh_id = world.get::<MeshId>(entity).ok()?.clone();
+            let scale = world_scale(world, entity);
+            physics_world.mesh_collider_scales.insert(entity, scale);
+            let mesh = physics_world.collision_meshes.get_or_load(&mesh_id)?;
+            if *shape == ColliderShape::TriMesh {
+                mesh.trimesh(scale)
+            } else {
+                mesh.convex_hull(scale)
+            }
+        }
+        ColliderShape::Heightfield {
+            heights,
+            rows,
+            cols,
+            scale,
+        } => {
+            if *rows < 2 || *cols < 2 || heights.len() != rows * cols {
+                return None;
+            }
+            let heights: Vec<f64> = heights.iter().map(|&height| height as f64).collect();
+            Some(SharedShape::heightfield(
+                DMatrix::from_row_slice(*rows, *cols, &heights),
+                vector![scale.x as f64, scale.y as f64, scale.z as f64],
+            ))
+        }
+        ColliderShape::Compound => {
+            let children: Vec<(Entity, ColliderShape, Transform)> = world
+                .query::<(&Parent, &Collider)>()
+                .without::<&RigidBody>()
+                .iter()
+                .filter(|(_, (parent, _))| parent.0 == entity)
+                .map(|(child, (_, collider))| {
+                    let transform = world
+                        .get::<Transform>(child)
+                        .map(|transform| *transform)
+                        .unwrap_or_default();
+                    (child, collider.shape.clone(), transform)
+                })
+                .collect();
+
+            // Parts are placed in the unscaled body space, so their offsets
+            // have to be scaled like the parent. Mesh parts scale themselves.
+            let scale = world_scale(world, entity);
+            physics_world.mesh_collider_scales.insert(entity, scale);
+            let mut parts = Vec::with_capacity(children.len());
+            for (child, child_shape, transform) in children {
+                let Some(part) = build_collider_shape(world, physics_world, child, &child_shape)
+                else {
+                    warn!(
+                        "Skipping {} part of compound collider on entity {:?}, it has no usable geometry",
+                        shape_kind(&child_shape),
+                        entity
+                    );
+                    continue;
+                };
+                let offset = transform.position * scale;
+                let position = Isometry::from_parts(
+                    vector![offset.x as f64, offset.y as f64, offset.z as f64].into(),
+                    quat_to_rapier_rotation(transform.rotation),
+                );
+                parts.push((position, part));
+            }
+            (!parts.is_empty()).then(|| SharedShape::compound(parts))
+        }
+    }
+}
+
+/// World-space scale of an entity, used to build its mesh collider
+///
+/// Before the hierarchy system has computed the entity's global transform,
+/// this falls back to its local scale.
+fn world_scale(world: &World, entity: Entity) -> Vec3 {
+    if let Ok(global) = world.get::<GlobalWorldTransform>(entity) {
+        return global.matrix.to_scale_rotation_translation().0.as_vec3();
+    }
+    if let Ok(global) = world.get::<GlobalTransform>(entity) {
+        return global.matrix.to_scale_rotation_translation().0;
+    }
+    if let Ok(world_transform) = world.get::<WorldTransform>(entity) {
+        return world_transform.scale;
+    }
+    world
+        .get::<Transform>(entity)
+        .map(|transform| transform.scale)
+        .unwrap_or(Vec3::ONE)
+}
+
+/// Drop the colliders whose mesh or compound shapes were built with a scale
+/// the entity no longer has, so [`create_physics_bodies`] builds them again
+///
+/// A mesh part of a compound collider rebuilds the collider of its parent.
+fn invalidate_rescaled_mesh_colliders(world: &mut World, physics_world: &mut PhysicsWorld) {
+    let mut owners = HashSet::new();
+    physics_world
+        .mesh_collider_scales
+        .retain(|&entity, built_scale| {
+            if world.get::<Collider>(entity).is_err() {
+                return false;
+            }
+            if world_scale(world, entity).abs_diff_eq(*built_scale, 1e-4) {
+                return true;
+            }
+            if world.get::<RigidBody>(entity).is_ok() {
+                owners.insert(entity);
+            } else if let Ok(parent) = world.get::<Parent>(entity) {
+                owners.insert(parent.0);
+            }
+            false
+        });
+
+    for owner in owners {
+        physics_world.remove_colliders(owner);
+        if let Ok(collider) = world.query_one_mut::<&mut Collider>(owner) {
+            collider.handle = None;
+        }
+        debug!(
+            "Rebuilding mesh collider of entity {:?} for its new scale",
+            owner
+        );
+    }
 }
 
 /// Sync transforms from ECS to physics
 fn sync_transforms_to_physics(world: &World, physics_world: &mut PhysicsWorld) {
     // Sync entities with WorldTransform (high precision)
-    for (_entity, (world_transform, rb)) in world.query::<(&WorldTransform, &RigidBody)>().iter() {
+    for (entity, (world_transform, rb)) in world.query::<(&WorldTransform, &RigidBody)>().iter() {
         if let Some(handle) = rb.handle {
             if let Some(rigid_body) = physics_world.rigid_body_set.get_mut(handle) {
                 // Only sync kinematic and fixed bodies (dynamic bodies are controlled by physics)
@@ -233,6 +502,19 @@ fn sync_transforms_to_physics(world: &World, physics_world: &mut PhysicsWorld) {
                     rb.body_type,
                     RigidBodyType::KinematicPositionBased | RigidBodyType::Fixed
                 ) {
+                    if let Some(recorder) = physics_world.recorder.as_mut() {
+                        let t = rigid_body.translation();
+                        if DVec3::new(t.x, t.y, t.z) != world_transform.position
+                            || rapier_rotation_to_quat(rigid_body.rotation())
+                                != world_transform.rotation
+                        {
+                            recorder.record_pose(
+                                entity,
+                                world_transform.position,
+                                world_transform.rotation,
+                            );
+                        }
+                    }
                     rigid_body.set_translation(
                         vector![
                             world_transform.position.x,
@@ -251,7 +533,7 @@ fn sync_transforms_to_physics(world: &World, physics_world: &mut PhysicsWorld) {
     // Sync entities with regular Transform (standard precision)
     for (entity, (transform, rb)) in world.query::<(&Transform, &RigidBody)>().iter() {
         // Skip if entity has WorldTransform (already handled above)
-        if world.get::<&WorldTransform>(entity).is_ok() {
+        if world.get::<WorldTransform>(entity).is_ok() {
             continue;
         }
 
@@ -262,6 +544,18 @@ fn sync_transforms_to_physics(world: &World, physics_world: &mut PhysicsWorld) {
                     rb.body_type,
                     RigidBodyType::KinematicPositionBased | RigidBodyType::Fixed
                 ) {
+                    if let Some(recorder) = physics_world.recorder.as_mut() {
+                        let t = rigid_body.translation();
+                        if Vec3::new(t.x as f32, t.y as f32, t.z as f32) != transform.position
+                            || rapier_rotation_to_quat(rigid_body.rotation()) != transform.rotation
+                        {
+                            recorder.record_pose(
+                                entity,
+                                transform.position.as_dvec3(),
+                                transform.rotation,
+                            );
+                        }
+                    }
                     rigid_body.set_translation(
                         vector![
                             transform.position.x as f64,
@@ -279,11 +573,22 @@ fn sync_transforms_to_physics(world: &World, physics_world: &mut PhysicsWorld) {
 
 /// Sync velocities from ECS to physics
 fn sync_velocities_to_physics(world: &World, physics_world: &mut PhysicsWorld) {
-    for (_entity, (velocity, rb)) in world.query::<(&PhysicsVelocity, &RigidBody)>().iter() {
+    for (entity, (velocity, rb)) in world.query::<(&PhysicsVelocity, &RigidBody)>().iter() {
         if let Some(handle) = rb.handle {
             if let Some(rigid_body) = physics_world.rigid_body_set.get_mut(handle) {
                 // Only set velocities for dynamic bodies
                 if rb.body_type == RigidBodyType::Dynamic {
+                    // Velocities read back last update only differ when something changed them
+                    if let Some(recorder) = physics_world.recorder.as_mut() {
+                        let (linvel, angvel) = (rigid_body.linvel(), rigid_body.angvel());
+                        if Vec3::new(linvel.x as f32, linvel.y as f32, linvel.z as f32)
+                            != velocity.linear
+                            || Vec3::new(angvel.x as f32, angvel.y as f32, angvel.z as f32)
+                                != velocity.angular
+                        {
+                            recorder.record_velocity(entity, velocity.linear, velocity.angular);
+                        }
+                    }
                     rigid_body.set_linvel(
                         vector![
                             velocity.linear.x as f64,
@@ -307,13 +612,16 @@ fn sync_velocities_to_physics(world: &World, physics_world: &mut PhysicsWorld) {
 }
 
 /// Process physics commands from the command queue
-fn process_physics_commands(physics_world: &mut PhysicsWorld) {
+fn process_physics_commands(world: &mut World, physics_world: &mut PhysicsWorld) {
     use crate::physics::PhysicsCommand;
 
     PHYSICS_COMMAND_QUEUE.with(|queue| {
         let commands = queue.borrow_mut().drain(..).collect::<Vec<_>>();
 
         for command in commands {
+            if let Some(recorder) = physics_world.recorder.as_mut() {
+                recorder.record_command(&command);
+            }
             match command {
                 PhysicsCommand::ApplyForce { entity, force } => {
                     if let Some(handle) =
@@ -371,85 +679,164 @@ fn process_physics_commands(physics_world: &mut PhysicsWorld) {
                         }
                     }
                 }
-                PhysicsCommand::Raycast {
-                    origin,
-                    direction,
-                    max_distance,
-                    callback,
-                } => {
-                    // Create ray from origin and direction
-                    let ray_origin = point![origin.x as f64, origin.y as f64, origin.z as f64];
-                    let ray_dir =
-                        vector![direction.x as f64, direction.y as f64, direction.z as f64];
-                    let ray = Ray::new(ray_origin, ray_dir);
-
-                    // Perform the raycast
-                    let filter = QueryFilter::default();
-                    let result = physics_world.query_pipeline.cast_ray(
-                        &physics_world.rigid_body_set,
-                        &physics_world.collider_set,
-                        &ray,
-                        max_distance as f64,
-                        true, // solid = true (stop at first hit)
-                        filter,
-                    );
-
-                    // Convert result to RaycastHit
-                    let hit = result.and_then(|(collider_handle, distance)| {
-                        // Get the collider and its parent body
-                        physics_world
-                            .collider_set
-                            .get(collider_handle)
-                            .and_then(|collider| {
-                                collider.parent().and_then(|body_handle| {
-                                    physics_world
-                                        .get_entity_for_body(body_handle)
-                                        .map(|entity| {
-                                            // Calculate hit point and normal
-                                            let hit_point = ray_origin + ray_dir * distance;
-                                            let hit_point_vec3 = Vec3::new(
-                                                hit_point.x as f32,
-                                                hit_point.y as f32,
-                                                hit_point.z as f32,
-                                            );
-
-                                            // Get normal at hit point (approximate)
-                                            let normal = if let Some(body) =
-                                                physics_world.rigid_body_set.get(body_handle)
-                                            {
-                                                let body_pos = body.translation();
-                                                let to_hit = hit_point - body_pos;
-                                                let normal = to_hit.coords.normalize();
-                                                Vec3::new(
-                                                    normal.x as f32,
-                                                    normal.y as f32,
-                                                    normal.z as f32,
-                                                )
-                                            } else {
-                                                // Default normal pointing back along ray
-                                                -direction
-                                            };
-
-                                            RaycastHit {
-                                                entity: entity.to_bits().get(),
-                                                distance: distance as f32,
-                                                point: hit_point_vec3,
-                                                normal,
-                                            }
-                                        })
-                                })
-                            })
-                    });
-
-                    // Execute the callback with the result
-                    let _result = callback(hit);
+                PhysicsCommand::MoveCharacter { entity, motion } => {
+                    let Some(entity) = Entity::from_bits(entity) else {
+                        continue;
+                    };
+                    if let Ok(controller) = world.query_one_mut::<&mut CharacterController>(entity)
+                    {
+                        controller.desired_motion = motion;
+                    }
                 }
             }
         }
     });
 }
 
+/// Move character controllers for one physics step
+///
+/// The new positions become the kinematic targets of the following step and
+/// are written to the transforms right away, since kinematic bodies are not
+/// synced back from physics.
+fn move_characters(world: &mut World, physics_world: &mut PhysicsWorld) {
+    let dt = physics_world.integration_parameters.dt;
+    let to_vec3 = |v: Vector<Real>| Vec3::new(v.x as f32, v.y as f32, v.z as f32);
+    let mut moved = Vec::new();
+
+    for (entity, (controller, rb, collider)) in world
+        .query_mut::<(&mut CharacterController, &RigidBody, &Collider)>()
+        .into_iter()
+    {
+        if rb.body_type != RigidBodyType::KinematicPositionBased {
+            trace!(
+                "CharacterController on entity {:?} needs a kinematic position-based body",
+                entity
+            );
+            continue;
+        }
+        let (Some(body_handle), Some(collider_handle)) = (rb.handle, collider.handle) else {
+            continue;
+        };
+        let (Some(body), Some(shape_collider)) = (
+            physics_world.rigid_body_set.get(body_handle),
+            physics_world.collider_set.get(collider_handle),
+        ) else {
+            continue;
+        };
+        let character_pos = *body.position();
+        let shape = shape_collider.shared_shape().clone();
+
+        let rapier_controller = controller.to_rapier();
+        let filter = QueryFilter::new()
+            .groups(collider.interaction_groups())
+            .exclude_rigid_body(body_handle)
+            .exclude_sensors();
+        let motion = controller.desired_motion;
+        let mut collisions = Vec::new();
+        let movement = rapier_controller.move_shape(
+            dt,
+            &physics_world.rigid_body_set,
+            &physics_world.collider_set,
+            &physics_world.query_pipeline,
+            &*shape,
+            &character_pos,
+            vector![motion.x as f64, motion.y as f64, motion.z as f64] * dt,
+            filter,
+            |collision| collisions.push(collision),
+        );
+
+        if controller.push_dynamic_bodies {
+            for collision in &collisions {
+                rapier_controller.solve_character_collision_impulses(
+                    dt,
+                    &mut physics_world.rigid_body_set,
+                    &physics_world.collider_set,
+                    &physics_world.query_pipeline,
+                    &*shape,
+                    controller.mass as f64,
+                    collision,
+                    filter,
+                );
+            }
+        }
+
+        for collision in &collisions {
+            let Some(other) = physics_world.get_entity_for_collider(collision.handle) else {
+                continue;
+            };
+            controller.hits.push(CharacterHit {
+                entity: other,
+                point: to_vec3(collision.toi.witness1.coords),
+                normal: to_vec3(*collision.toi.normal1),
+                translation_applied: to_vec3(collision.translation_applied),
+                translation_remaining: to_vec3(collision.translation_remaining),
+            });
+        }
+
+        let translation = movement.translation;
+        if let Some(body) = physics_world.rigid_body_set.get_mut(body_handle) {
+            body.set_next_kinematic_translation(character_pos.translation.vector + translation);
+        }
+        controller.grounded = movement.grounded;
+        controller.effective_motion = to_vec3(translation / dt);
+        moved.push((
+            entity,
+            DVec3::new(translation.x, translation.y, translation.z),
+        ));
+    }
+
+    for (entity, translation) in moved {
+        if let Ok(world_transform) = world.query_one_mut::<&mut WorldTransform>(entity) {
+            world_transform.position += translation;
+        } else if let Ok(transform) = world.query_one_mut::<&mut Transform>(entity) {
+            transform.position += translation.as_vec3();
+        }
+    }
+}
+
+/// Remember the pose of dynamic bodies before a step for interpolation
+fn store_previous_transforms(world: &mut World, physics_world: &PhysicsWorld) {
+    let current_pose = |rb: &RigidBody| {
+        if rb.body_type != RigidBodyType::Dynamic {
+            return None;
+        }
+        let rigid_body = physics_world.rigid_body_set.get(rb.handle?)?;
+        let pos = rigid_body.translation();
+        Some((
+            DVec3::new(pos.x, pos.y, pos.z),
+            rapier_rotation_to_quat(rigid_body.rotation()),
+        ))
+    };
+
+    for (_entity, (previous, world_transform, rb)) in world
+        .query_mut::<(&mut PreviousWorldTransform, &WorldTransform, &RigidBody)>()
+        .into_iter()
+    {
+        if let Some((position, rotation)) = current_pose(rb) {
+            previous.position = position;
+            previous.rotation = rotation;
+            previous.scale = world_transform.scale;
+        }
+    }
+
+    for (_entity, (previous, transform, rb)) in world
+        .query_mut::<(&mut PreviousTransform, &Transform, &RigidBody)>()
+        .without::<&WorldTransform>()
+        .into_iter()
+    {
+        if let Some((position, rotation)) = current_pose(rb) {
+            previous.position = position.as_vec3();
+            previous.rotation = rotation;
+            previous.scale = transform.scale;
+        }
+    }
+}
+
 /// Sync physics results back to transforms
+///
+/// Dynamic bodies get their latest physics pose, which is what scripts and
+/// other systems read back. Interpolation only changes the rendered global
+/// transforms, see [`interpolate_physics_transforms`].
 fn sync_physics_to_transforms(world: &mut World, physics_world: &PhysicsWorld) {
     // Collect updates to avoid borrow conflicts
     let mut transform_updates = Vec::new();
@@ -462,21 +849,18 @@ fn sync_physics_to_transforms(world: &mut World, physics_world: &PhysicsWorld) {
                 // Only sync dynamic bodies (kinematic/fixed are controlled by transforms)
                 if rb.body_type == RigidBodyType::Dynamic {
                     let pos = rigid_body.translation();
-                    let rot = rigid_body.rotation();
+                    let rot = rapier_rotation_to_quat(rigid_body.rotation());
 
                     // Check if entity has WorldTransform or regular Transform
-                    if world.get::<&WorldTransform>(entity).is_ok() {
+                    if world.get::<WorldTransform>(entity).is_ok() {
                         world_transform_updates.push((
                             entity,
                             DVec3::new(pos.x, pos.y, pos.z),
-                            Quat::from_xyzw(rot.i as f32, rot.j as f32, rot.k as f32, rot.w as f32),
-                        ));
-                    } else if world.get::<&Transform>(entity).is_ok() {
-                        transform_updates.push((
-                            entity,
-                            Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32),
-                            Quat::from_xyzw(rot.i as f32, rot.j as f32, rot.k as f32, rot.w as f32),
+                            rot,
                         ));
+                    } else if world.get::<Transform>(entity).is_ok() {
+                        let position = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32);
+                        transform_updates.push((entity, position, rot));
                     }
                 }
             }
@@ -485,7 +869,7 @@ fn sync_physics_to_transforms(world: &mut World, physics_world: &PhysicsWorld) {
 
     // Apply transform updates
     for (entity, position, rotation) in transform_updates {
-        let maybe_transform = world.get::<&Transform>(entity).ok().map(|t| **t);
+        let maybe_transform = world.get::<Transform>(entity).ok().map(|t| *t);
         if let Some(mut new_transform) = maybe_transform {
             new_transform.position = position;
             new_transform.rotation = rotation;
@@ -495,7 +879,7 @@ fn sync_physics_to_transforms(world: &mut World, physics_world: &PhysicsWorld) {
 
     // Apply world transform updates
     for (entity, position, rotation) in world_transform_updates {
-        let maybe_world_transform = world.get::<&WorldTransform>(entity).ok().map(|t| **t);
+        let maybe_world_transform = world.get::<WorldTransform>(entity).ok().map(|t| *t);
         if let Some(mut new_world_transform) = maybe_world_transform {
             new_world_transform.position = position;
             new_world_transform.rotation = rotation;
@@ -504,6 +888,113 @@ fn sync_physics_to_transforms(world: &mut World, physics_world: &PhysicsWorld) {
     }
 }
 
+/// Render dynamic bodies between their last two physics states
+///
+/// Run this after [`update_hierarchy_system`](crate::core::entity::update_hierarchy_system).
+/// The global transforms of dynamic bodies with a [`PreviousTransform`] or
+/// [`PreviousWorldTransform`] are placed [`alpha`](crate::physics::FixedTimestep::alpha)
+/// of the way from the previous to the current physics state, and their
+/// descendants move along. Local transforms keep the current state.
+pub fn interpolate_physics_transforms(world: &mut World, physics_world: &PhysicsWorld) {
+    profile_zone!("interpolate_physics_transforms");
+    if !physics_world.timestep.interpolate {
+        return;
+    }
+    let alpha = physics_world.timestep.alpha();
+
+    // Rendered pose of every interpolated body
+    let mut rendered: Vec<(Entity, DMat4)> = Vec::new();
+    for (entity, (previous, world_transform, rb)) in world
+        .query::<(&PreviousWorldTransform, &WorldTransform, &RigidBody)>()
+        .iter()
+    {
+        if rb.body_type == RigidBodyType::Dynamic {
+            let interpolated = WorldTransform {
+                position: previous
+                    .position
+                    .lerp(world_transform.position, alpha as f64),
+                rotation: previous.rotation.slerp(world_transform.rotation, alpha),
+                scale: world_transform.scale,
+            };
+            rendered.push((entity, interpolated.to_matrix()));
+        }
+    }
+    for (entity, (previous, transform, rb)) in world
+        .query::<(&PreviousTransform, &Transform, &RigidBody)>()
+        .without::<&WorldTransform>()
+        .iter()
+    {
+        if rb.body_type == RigidBodyType::Dynamic {
+            let interpolated = Transform {
+                position: previous.position.lerp(transform.position, alpha),
+                rotation: previous.rotation.slerp(transform.rotation, alpha),
+                scale: transform.scale,
+            };
+            rendered.push((entity, interpolated.to_matrix().as_dmat4()));
+        }
+    }
+    if rendered.is_empty() {
+        return;
+    }
+
+    let mut children: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (child, parent) in world.query::<&Parent>().iter() {
+        children.entry(parent.0).or_default().push(child);
+    }
+    let bodies: HashSet<Entity> = rendered.iter().map(|(entity, _)| *entity).collect();
+
+    for (body, matrix) in rendered {
+        // Move the subtree by the difference between rendered and current pose
+        let Some(current) = global_matrix(world, body) else {
+            continue;
+        };
+        let offset = matrix * current.inverse();
+
+        let mut visited = HashSet::from([body]);
+        let mut queue = vec![body];
+        while let Some(entity) = queue.pop() {
+            if let Some(matrix) = global_matrix(world, entity) {
+                set_global_matrix(world, entity, offset * matrix);
+            }
+            for &child in children.get(&entity).into_iter().flatten() {
+                // Other bodies are placed by their own interpolation
+                if !bodies.contains(&child) && visited.insert(child) {
+                    queue.push(child);
+                }
+            }
+        }
+    }
+}
+
+/// Global transform the hierarchy system computed for an entity
+fn global_matrix(world: &World, entity: Entity) -> Option<DMat4> {
+    if world.get::<WorldTransform>(entity).is_ok() {
+        world
+            .get::<GlobalWorldTransform>(entity)
+            .ok()
+            .map(|global| global.matrix)
+    } else {
+        world
+            .get::<GlobalTransform>(entity)
+            .ok()
+            .map(|global| global.matrix.as_dmat4())
+    }
+}
+
+/// Overwrite the global transform of an entity for rendering
+fn set_global_matrix(world: &mut World, entity: Entity, matrix: DMat4) {
+    if world.get::<WorldTransform>(entity).is_ok() {
+        if let Ok(global) = world.query_one_mut::<&mut GlobalWorldTransform>(entity) {
+            global.matrix = matrix;
+        }
+    } else if let Ok(global) = world.query_one_mut::<&mut GlobalTransform>(entity) {
+        global.matrix = matrix.as_mat4();
+    }
+    if let Ok(camera_position) = world.query_one_mut::<&mut CameraWorldPosition>(entity) {
+        camera_position.position = matrix.w_axis.truncate();
+    }
+}
+
 /// Sync physics velocities back to ECS
 fn sync_physics_to_velocities(world: &mut World, physics_world: &PhysicsWorld) {
     let mut velocity_updates = Vec::new();
@@ -525,7 +1016,7 @@ fn sync_physics_to_velocities(world: &mut World, physics_world: &PhysicsWorld) {
 
     // Apply velocity updates
     for (entity, linear, angular) in velocity_updates {
-        let maybe_velocity = world.get::<&PhysicsVelocity>(entity).ok().map(|v| **v);
+        let maybe_velocity = world.get::<PhysicsVelocity>(entity).ok().map(|v| *v);
         if let Some(mut new_velocity) = maybe_velocity {
             new_velocity.linear = linear;
             new_velocity.angular = angular;
@@ -552,6 +1043,18 @@ fn cleanup_removed_entities(world: &World, physics_world: &mut PhysicsWorld) {
             entity
         );
     }
+
+    // Remove joints whose Joint component was removed or replaced
+    for entity in physics_world.joint_entities() {
+        let current = world
+            .get::<Joint>(entity)
+            .ok()
+            .and_then(|joint| joint.handle);
+        if current != physics_world.get_joint_handle(entity) {
+            physics_world.remove_joint(entity);
+            debug!("Removed joint of entity {:?}", entity);
+        }
+    }
 }
 
 /// Queue a physics command for execution in the next physics update
@@ -6,7 +6,7 @@ use crate::core::entity::World;
 #[cfg(test)]
 use crate::physics::*;
 #[cfg(test)]
-use glam::Vec3;
+use glam::{DVec3, Vec3};
 
 #[test]
 fn test_rigidbody_serialization() {
@@ -34,6 +34,8 @@ fn test_collider_serialization() {
         restitution: 0.3,
         density: 2.0,
         is_sensor: false,
+        membership: 0b10,
+        filter: 0b101,
         handle: Some(ColliderHandle::from_raw_parts(123, 456)), // Should skip
     };
 
@@ -45,7 +47,17 @@ fn test_collider_serialization() {
     assert_eq!(collider.restitution, deserialized.restitution);
     assert_eq!(collider.density, deserialized.density);
     assert_eq!(collider.is_sensor, deserialized.is_sensor);
+    assert_eq!(collider.membership, deserialized.membership);
+    assert_eq!(collider.filter, deserialized.filter);
     assert!(deserialized.handle.is_none()); // Handle should be skipped
+
+    // Colliders saved before layers existed are on the default layer
+    let old: Collider = serde_json::from_str(
+        r#"{"shape":{"Sphere":1.0},"friction":0.5,"restitution":0.0,"density":1.0,"is_sensor":false}"#,
+    )
+    .unwrap();
+    assert_eq!(old.membership, CollisionLayers::DEFAULT);
+    assert_eq!(old.filter, CollisionLayers::ALL);
 }
 
 #[test]
@@ -157,6 +169,27 @@ fn test_physics_world_entity_registration() {
     assert_eq!(physics_world.get_body_handle(entity), None);
 }
 
+#[test]
+fn test_physics_world_clear() {
+    use rapier3d_f64::prelude::{nalgebra, vector, RigidBodyBuilder};
+
+    let mut physics_world = PhysicsWorld::new();
+    physics_world.set_gravity(vector![0.0, -1.62, 0.0]);
+    let mut world = World::new();
+    let entity = world.spawn(());
+
+    let handle = physics_world
+        .rigid_body_set
+        .insert(RigidBodyBuilder::dynamic().build());
+    physics_world.register_body(entity, handle);
+
+    physics_world.clear();
+
+    assert!(physics_world.rigid_body_set.is_empty());
+    assert_eq!(physics_world.get_body_handle(entity), None);
+    assert_eq!(physics_world.gravity[1], -1.62);
+}
+
 // TODO: Test physics command queue using the actual thread-local implementation
 // The previous test used Arc<RwLock<>> which isn't compatible with non-Send closures
 // #[test]
@@ -209,12 +242,833 @@ fn test_large_world_physics_precision() {
     assert!(pos.y < 0.0, "Object should have fallen due to gravity");
 }
 
+/// Spawn a falling ball and run the physics system at `fps` for one second
+#[cfg(test)]
+fn fall_for_one_second(fps: u32) -> f32 {
+    use crate::core::entity::Transform;
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let ball = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 10.0, 0.0)),
+        RigidBody::default(),
+        Collider::sphere(0.5),
+    ));
+
+    for _ in 0..fps {
+        system::physics_update_system(&mut world, &mut physics_world, 1.0 / fps as f32);
+    }
+    world.get::<Transform>(ball).unwrap().position.y
+}
+
+#[test]
+fn test_fixed_timestep_is_frame_rate_independent() {
+    let slow = fall_for_one_second(30);
+    let fast = fall_for_one_second(144);
+
+    assert!(slow < 10.0, "Ball should have fallen, got {slow}");
+    assert!(
+        (slow - fast).abs() < 0.05,
+        "Fall distance depends on frame rate: {slow} at 30 fps, {fast} at 144 fps"
+    );
+}
+
+#[test]
+fn test_interpolation_between_physics_states() {
+    use crate::core::entity::components::{
+        GlobalWorldTransform, PreviousWorldTransform, WorldTransform,
+    };
+    use crate::core::entity::{GlobalTransform, Parent, PreviousTransform, Transform};
+    use glam::{DVec3, Mat4};
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let ball = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 10.0, 0.0)),
+        GlobalTransform::default(),
+        RigidBody::default(),
+        Collider::sphere(0.5),
+    ));
+    let marker = world.spawn((
+        Transform::from_position(Vec3::Y),
+        GlobalTransform::default(),
+        Parent(ball),
+    ));
+    let far_ball = world.spawn((
+        WorldTransform::from_position(DVec3::new(1_000_000_000.0, 10.0, 0.0)),
+        GlobalWorldTransform::default(),
+        RigidBody::default(),
+        Collider::sphere(0.5),
+    ));
+
+    // Two full steps, then half a step that only moves the rendered pose
+    system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    system::physics_update_system(&mut world, &mut physics_world, 0.5 / 60.0);
+
+    // Stand in for the hierarchy system
+    let ball_matrix = world.get::<Transform>(ball).unwrap().to_matrix();
+    world
+        .query_one_mut::<&mut GlobalTransform>(ball)
+        .unwrap()
+        .matrix = ball_matrix;
+    world
+        .query_one_mut::<&mut GlobalTransform>(marker)
+        .unwrap()
+        .matrix = ball_matrix * Mat4::from_translation(Vec3::Y);
+    system::interpolate_physics_transforms(&mut world, &physics_world);
+
+    // Gameplay sees the simulated pose, rendering lags behind it
+    let previous = world.get::<PreviousTransform>(ball).unwrap().position.y;
+    let simulated = world.get::<Transform>(ball).unwrap().position.y;
+    let handle = physics_world.get_body_handle(ball).unwrap();
+    assert_eq!(
+        simulated,
+        physics_world.rigid_body_set[handle].translation().y as f32
+    );
+    let rendered = world.get::<GlobalTransform>(ball).unwrap().matrix.w_axis.y;
+    assert!(simulated < previous);
+    assert!(
+        rendered < previous && rendered > simulated,
+        "Rendered {rendered} should lie between {previous} and {simulated}"
+    );
+    let marker_y = world
+        .get::<GlobalTransform>(marker)
+        .unwrap()
+        .matrix
+        .w_axis
+        .y;
+    assert!((marker_y - rendered - 1.0).abs() < 1e-4);
+
+    // Large-world bodies are interpolated in double precision
+    let previous = world
+        .get::<PreviousWorldTransform>(far_ball)
+        .unwrap()
+        .position;
+    let simulated = world.get::<WorldTransform>(far_ball).unwrap().position;
+    let rendered = world
+        .get::<GlobalWorldTransform>(far_ball)
+        .unwrap()
+        .matrix
+        .w_axis;
+    assert!(simulated.y < previous.y);
+    assert!(rendered.y < previous.y && rendered.y > simulated.y);
+    assert!((rendered.x - 1_000_000_000.0).abs() < 1e-3);
+}
+
+#[test]
+fn test_collision_and_trigger_events() {
+    use crate::core::entity::Transform;
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let ground = world.spawn((
+        Transform::default(),
+        RigidBody {
+            body_type: RigidBodyType::Fixed,
+            ..Default::default()
+        },
+        Collider::cuboid(5.0, 0.5, 5.0),
+    ));
+    let trigger = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 2.0, 0.0)),
+        RigidBody {
+            body_type: RigidBodyType::Fixed,
+            ..Default::default()
+        },
+        Collider {
+            is_sensor: true,
+            ..Collider::cuboid(1.0, 0.5, 1.0)
+        },
+    ));
+    let ball = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 4.0, 0.0)),
+        RigidBody::default(),
+        Collider::sphere(0.5),
+    ));
+
+    let mut seen = Vec::new();
+    for _ in 0..120 {
+        system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+        seen.extend(physics_world.collision_events.involving(ball));
+    }
+
+    let entered = |kind, other| {
+        seen.iter()
+            .any(|event| event.kind == kind && event.other == other)
+    };
+    assert!(entered(CollisionEventKind::TriggerEnter, trigger));
+    assert!(entered(CollisionEventKind::TriggerExit, trigger));
+    assert!(entered(CollisionEventKind::CollisionEnter, ground));
+
+    let landing = seen
+        .iter()
+        .find(|event| event.kind == CollisionEventKind::CollisionEnter)
+        .unwrap();
+    assert!(
+        landing.normal.y < -0.5,
+        "Normal should point from the ball to the ground"
+    );
+    assert!(landing.point.y < 1.0);
+}
+
+#[test]
+fn test_rope_joint_holds_body() {
+    use crate::core::entity::Transform;
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let anchor = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 10.0, 0.0)),
+        RigidBody {
+            body_type: RigidBodyType::Fixed,
+            ..Default::default()
+        },
+    ));
+    let weight = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 9.0, 0.0)),
+        RigidBody::default(),
+        Collider::sphere(0.25),
+        Joint::rope(anchor, 2.0),
+    ));
+
+    for _ in 0..120 {
+        system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    }
+
+    let joint = world.get::<Joint>(weight).unwrap();
+    assert!(joint.handle.is_some());
+    assert_eq!(physics_world.get_joint_handle(weight), joint.handle);
+
+    let height = world.get::<Transform>(weight).unwrap().position.y;
+    assert!(
+        (7.9..8.1).contains(&height),
+        "Rope should stop the fall at its length, got {height}"
+    );
+}
+
+#[test]
+fn test_character_controller_walks_until_blocked() {
+    use crate::core::entity::Transform;
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let fixed = || RigidBody {
+        body_type: RigidBodyType::Fixed,
+        ..Default::default()
+    };
+    world.spawn((
+        Transform::default(),
+        fixed(),
+        Collider::cuboid(5.0, 0.5, 5.0),
+    ));
+    let wall = world.spawn((
+        Transform::from_position(Vec3::new(2.5, 2.0, 0.0)),
+        fixed(),
+        Collider::cuboid(0.5, 2.0, 5.0),
+    ));
+    let character = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 1.6, 0.0)),
+        RigidBody {
+            body_type: RigidBodyType::KinematicPositionBased,
+            ..Default::default()
+        },
+        Collider::capsule(0.5, 0.5),
+        CharacterController::default(),
+    ));
+
+    // Walk towards the wall with gravity for two seconds
+    for _ in 0..120 {
+        system::queue_physics_command(PhysicsCommand::MoveCharacter {
+            entity: character.to_bits().get(),
+            motion: Vec3::new(3.0, -5.0, 0.0),
+        });
+        system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    }
+
+    let position = world.get::<Transform>(character).unwrap().position;
+    assert!(position.x > 1.0 && position.x < 1.55, "x = {}", position.x);
+    assert!(
+        position.y > 1.45,
+        "fell through the ground: y = {}",
+        position.y
+    );
+
+    let controller = world.get::<CharacterController>(character).unwrap();
+    assert!(controller.grounded);
+    assert!(controller.hits.iter().any(|hit| hit.entity == wall));
+    assert_eq!(controller.desired_motion, Vec3::ZERO);
+}
+
+#[test]
+fn test_collision_layers_filter_contacts_and_queries() {
+    use crate::core::entity::Transform;
+
+    let layers = CollisionLayers::new(["Default", "Debris"]).unwrap();
+    let debris = layers.layer("Debris").unwrap();
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    physics_world.set_collision_layers(layers);
+
+    // The ground only interacts with the default layer
+    let ground = world.spawn((
+        Transform::default(),
+        RigidBody {
+            body_type: RigidBodyType::Fixed,
+            ..Default::default()
+        },
+        Collider::cuboid(5.0, 0.5, 5.0)
+            .with_layers(CollisionLayers::DEFAULT, CollisionLayers::DEFAULT),
+    ));
+    let crate_box = world.spawn((
+        Transform::from_position(Vec3::new(-2.0, 2.0, 0.0)),
+        RigidBody::default(),
+        Collider::cuboid(0.5, 0.5, 0.5),
+    ));
+    let rubble = world.spawn((
+        Transform::from_position(Vec3::new(2.0, 2.0, 0.0)),
+        RigidBody::default(),
+        Collider::cuboid(0.5, 0.5, 0.5).with_layers(debris, CollisionLayers::ALL),
+    ));
+
+    for _ in 0..60 {
+        system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    }
+
+    let crate_height = world.get::<Transform>(crate_box).unwrap().position.y;
+    let rubble_height = world.get::<Transform>(rubble).unwrap().position.y;
+    assert!(
+        crate_height > 0.9,
+        "crate should rest on the ground, y = {crate_height}"
+    );
+    assert!(
+        rubble_height < 0.0,
+        "debris should fall through, y = {rubble_height}"
+    );
+
+    let query = physics_world.spatial_query();
+    let down = |x: f64, filter: SpatialQueryFilter| {
+        query
+            .raycast(DVec3::new(x, 10.0, 0.0), Vec3::NEG_Y, 20.0, &filter)
+            .map(|hit| hit.entity)
+    };
+    assert_eq!(down(-2.0, SpatialQueryFilter::default()), Some(crate_box));
+    assert_eq!(
+        down(-2.0, SpatialQueryFilter::default().with_mask(debris)),
+        None
+    );
+    assert_eq!(down(0.0, SpatialQueryFilter::default()), Some(ground));
+
+    // Queries on the debris layer are ignored by the ground like debris is
+    let from_debris = SpatialQueryFilter::default().with_groups(debris, CollisionLayers::ALL);
+    assert_eq!(down(0.0, from_debris), None);
+}
+
+#[test]
+fn test_mesh_heightfield_and_compound_colliders() {
+    use crate::core::entity::{Parent, Transform};
+    use crate::graphics::MeshId;
+
+    let fixed = || RigidBody {
+        body_type: RigidBodyType::Fixed,
+        ..Default::default()
+    };
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+
+    // A 10 x 10 floor from the built-in plane mesh, and a cube hull falling onto it
+    let floor = world.spawn((
+        Transform {
+            scale: Vec3::splat(5.0),
+            ..Default::default()
+        },
+        MeshId("plane".to_string()),
+        fixed(),
+        Collider::trimesh(),
+    ));
+    let cube = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 2.0, 0.0)),
+        MeshId("cube".to_string()),
+        RigidBody::default(),
+        Collider::convex_hull(),
+    ));
+
+    // Flat terrain two meters high, off to the side
+    let terrain = world.spawn((
+        Transform::from_position(Vec3::new(20.0, 0.0, 0.0)),
+        fixed(),
+        Collider::heightfield(vec![1.0; 4], 2, 2, Vec3::new(10.0, 2.0, 10.0)),
+    ));
+
+    // Two boxes side by side making up one collider on the parent
+    let compound = world.spawn((
+        Transform::from_position(Vec3::new(-20.0, 0.0, 0.0)),
+        fixed(),
+        Collider::compound(),
+    ));
+    for x in [-2.0, 2.0] {
+        world.spawn((
+            Parent(compound),
+            Transform::from_position(Vec3::new(x, 0.0, 0.0)),
+            Collider::cuboid(0.5, 0.5, 0.5),
+        ));
+    }
+
+    for _ in 0..90 {
+        system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    }
+
+    let cube_height = world.get::<Transform>(cube).unwrap().position.y;
+    assert!(
+        (cube_height - 0.5).abs() < 0.1,
+        "cube should rest on the mesh floor, y = {cube_height}"
+    );
+
+    let query = physics_world.spatial_query();
+    let down = |x: f64| {
+        query
+            .raycast(
+                DVec3::new(x, 10.0, 0.0),
+                Vec3::NEG_Y,
+                20.0,
+                &SpatialQueryFilter::default(),
+            )
+            .map(|hit| (hit.entity, hit.point.y))
+    };
+    assert_eq!(down(4.0).map(|(entity, _)| entity), Some(floor));
+    let (hit, height) = down(20.0).unwrap();
+    assert_eq!(hit, terrain);
+    assert!((height - 2.0).abs() < 1e-3, "terrain height = {height}");
+    assert_eq!(down(-22.0).map(|(entity, _)| entity), Some(compound));
+    assert_eq!(down(-20.0), None, "the gap between the parts is empty");
+
+    // Mesh, heightfield and compound colliders show up in the debug view
+    let mut lines = Vec::new();
+    let settings = PhysicsDebugSettings {
+        show_colliders: true,
+        ..Default::default()
+    };
+    debug::draw_physics_debug(
+        &world,
+        &physics_world,
+        &mut lines,
+        &settings,
+        glam::DVec3::ZERO,
+    );
+    assert!(lines.len() > 40, "only {} debug lines", lines.len());
+
+    // Only the parts' shapes are stored, the parts get no colliders of their own
+    assert_eq!(physics_world.collider_set.len(), 4);
+}
+
+#[test]
+fn test_compound_parts_follow_parent_scale() {
+    use crate::core::entity::{Parent, Transform};
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let compound = world.spawn((
+        Transform {
+            scale: Vec3::splat(2.0),
+            ..Default::default()
+        },
+        RigidBody {
+            body_type: RigidBodyType::Fixed,
+            ..Default::default()
+        },
+        Collider::compound(),
+    ));
+    for x in [-2.0, 2.0] {
+        world.spawn((
+            Parent(compound),
+            Transform::from_position(Vec3::new(x, 0.0, 0.0)),
+            Collider::cuboid(0.5, 0.5, 0.5),
+        ));
+    }
+    system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+
+    let query = physics_world.spatial_query();
+    let down = |x: f64| {
+        query
+            .raycast(
+                DVec3::new(x, 10.0, 0.0),
+                Vec3::NEG_Y,
+                20.0,
+                &SpatialQueryFilter::default(),
+            )
+            .map(|hit| hit.entity)
+    };
+    assert_eq!(down(-4.0), Some(compound));
+    assert_eq!(down(4.0), Some(compound));
+    assert_eq!(down(2.0), None, "parts sit at twice their local offset");
+    assert_eq!(down(0.0), None);
+}
+
+#[test]
+fn test_mesh_collider_follows_world_scale() {
+    use crate::core::entity::{GlobalTransform, Transform};
+    use crate::graphics::MeshId;
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let cube = world.spawn((
+        Transform::default(),
+        MeshId("cube".to_string()),
+        RigidBody {
+            body_type: RigidBodyType::Fixed,
+            ..Default::default()
+        },
+        Collider::convex_hull(),
+    ));
+    let top = |physics_world: &PhysicsWorld| {
+        physics_world
+            .spatial_query()
+            .raycast(
+                DVec3::new(0.0, 10.0, 0.0),
+                Vec3::NEG_Y,
+                20.0,
+                &SpatialQueryFilter::default(),
+            )
+            .map(|hit| hit.point.y)
+            .unwrap()
+    };
+
+    system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    assert!((top(&physics_world) - 0.5).abs() < 1e-3);
+
+    // A scaled parent only shows up in the global transform
+    world
+        .insert_one(
+            cube,
+            GlobalTransform::from_matrix(glam::Mat4::from_scale(Vec3::splat(4.0))),
+        )
+        .unwrap();
+    system::physics_update_system(&mut world, &mut physics_world, 1.0 / 60.0);
+    let height = top(&physics_world);
+    assert!(
+        (height - 2.0).abs() < 1e-3,
+        "top of the cube at y = {height}"
+    );
+    assert_eq!(physics_world.get_collider_handles(cube).len(), 1);
+}
+
+#[test]
+fn test_new_collider_shapes_serialize() {
+    for collider in [
+        Collider::trimesh(),
+        Collider::convex_hull(),
+        Collider::compound(),
+        Collider::heightfield(vec![0.0, 0.5, 1.0, 0.5], 2, 2, Vec3::new(4.0, 1.0, 4.0)),
+    ] {
+        let json = serde_json::to_string(&collider).unwrap();
+        let deserialized: Collider = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.shape, collider.shape);
+    }
+}
+
+/// Ground, a kinematic platform, a falling box and a ball with a velocity
+fn spawn_replay_scene(world: &mut World) -> [crate::core::entity::Entity; 3] {
+    use crate::core::entity::Transform;
+
+    world.spawn((
+        Transform::default(),
+        RigidBody {
+            body_type: RigidBodyType::Fixed,
+            ..Default::default()
+        },
+        Collider::cuboid(10.0, 0.5, 10.0),
+    ));
+    let platform = world.spawn((
+        Transform::from_position(Vec3::new(-3.0, 1.0, 0.0)),
+        RigidBody {
+            body_type: RigidBodyType::KinematicPositionBased,
+            ..Default::default()
+        },
+        Collider::cuboid(1.0, 0.1, 1.0),
+    ));
+    let crate_box = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 3.0, 0.0)),
+        RigidBody::default(),
+        Collider::cuboid(0.5, 0.5, 0.5),
+    ));
+    let ball = world.spawn((
+        Transform::from_position(Vec3::new(3.0, 1.0, 0.0)),
+        RigidBody::default(),
+        Collider::sphere(0.5),
+        PhysicsVelocity::default(),
+    ));
+    [platform, crate_box, ball]
+}
+
+#[test]
+fn test_recording_replays_deterministically() {
+    use crate::core::entity::Transform;
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let [platform, crate_box, ball] = spawn_replay_scene(&mut world);
+    physics_world.start_recording(1);
+
+    // Uneven frame times, so some updates take no step and some take two
+    for frame in 0..90 {
+        let delta_time = if frame % 3 == 0 {
+            1.0 / 30.0
+        } else {
+            1.0 / 90.0
+        };
+        if frame == 10 {
+            system::queue_physics_command(PhysicsCommand::ApplyImpulse {
+                entity: crate_box.to_bits().get(),
+                impulse: Vec3::new(2.0, 4.0, 0.0),
+            });
+        }
+        if frame == 20 {
+            world
+                .query_one_mut::<&mut PhysicsVelocity>(ball)
+                .unwrap()
+                .linear = Vec3::new(-1.0, 2.0, 0.5);
+        }
+        // A script sliding the platform along
+        world
+            .query_one_mut::<&mut Transform>(platform)
+            .unwrap()
+            .position
+            .x += 0.02;
+        system::physics_update_system(&mut world, &mut physics_world, delta_time);
+    }
+    let recording = physics_world.stop_recording().unwrap();
+    assert!(!physics_world.is_recording());
+
+    let impulse_frame = recording
+        .frames
+        .iter()
+        .position(|frame| !frame.commands.is_empty())
+        .unwrap();
+    assert!(recording.frames.iter().any(|frame| frame
+        .velocities
+        .iter()
+        .any(|velocity| velocity.linear == Vec3::new(-1.0, 2.0, 0.5))));
+    // The first update records where the bodies were created, later ones the platform's moves
+    assert_eq!(recording.frames[0].poses.len(), 4);
+    assert!(recording.frames[1..]
+        .iter()
+        .all(|frame| frame.poses.len() == 1 && frame.poses[0].entity == platform.to_bits().get()));
+    assert_eq!(
+        recording.snapshots.len() as u64,
+        recording.steps() + 1,
+        "one snapshot per step plus the initial state"
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("physics_recording.json");
+    recording.save_to_file(&path).unwrap();
+    let loaded = PhysicsRecording::load_from_file(&path).unwrap();
+    assert_eq!(loaded, recording);
+
+    // The same scene in a fresh world follows the recording exactly
+    let replay = |recording: PhysicsRecording| {
+        let mut world = World::new();
+        let mut physics_world = PhysicsWorld::new();
+        spawn_replay_scene(&mut world);
+        PhysicsReplay::new(recording)
+            .with_tolerance(0.0)
+            .run(&mut world, &mut physics_world)
+    };
+    let report = replay(loaded.clone());
+    assert!(report.is_deterministic(), "{:?}", report.divergence);
+    assert_eq!(report.steps, recording.steps());
+    assert_eq!(report.snapshots_compared, recording.snapshots.len());
+
+    // A different impulse shows up right after the step it was applied in
+    let mut altered = loaded;
+    altered.frames[impulse_frame].commands[0] = PhysicsCommand::ApplyImpulse {
+        entity: crate_box.to_bits().get(),
+        impulse: Vec3::new(2.0, 4.5, 0.0),
+    };
+    let divergence = replay(altered).divergence.unwrap();
+    assert_eq!(divergence.step, recording.frames[impulse_frame].step + 1);
+    assert_eq!(divergence.entity, Some(crate_box.to_bits().get()));
+    assert!(divergence.difference > 0.0);
+
+    // A recording cut short diverges where its snapshots run out
+    let mut truncated = recording.clone();
+    let kept = truncated.snapshots.len() - 2;
+    truncated.snapshots.truncate(kept);
+    let report = replay(truncated);
+    let divergence = report.divergence.unwrap();
+    assert_eq!(divergence.step, recording.snapshots[kept].step);
+    assert_eq!(divergence.entity, None);
+    assert_eq!(report.snapshots_compared, kept);
+
+    // So does a replay that runs out of recorded frames
+    let mut truncated = recording.clone();
+    truncated.frames.truncate(recording.frames.len() / 2);
+    let report = replay(truncated);
+    assert_eq!(report.divergence.unwrap().entity, None);
+    assert!(report.snapshots_compared < recording.snapshots.len());
+}
+
+/// Fixed ground, a box above it and a sensor in between, stepped once so
+/// the query pipeline knows about them
+fn spawn_query_scene(
+    world: &mut World,
+    physics_world: &mut PhysicsWorld,
+) -> [crate::core::entity::Entity; 3] {
+    use crate::core::entity::Transform;
+
+    let fixed = || RigidBody {
+        body_type: RigidBodyType::Fixed,
+        ..Default::default()
+    };
+    let ground = world.spawn((
+        Transform::default(),
+        fixed(),
+        Collider::cuboid(5.0, 0.5, 5.0),
+    ));
+    let crate_box = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 3.0, 0.0)),
+        fixed(),
+        Collider::cuboid(0.5, 0.5, 0.5),
+    ));
+    let sensor = world.spawn((
+        Transform::from_position(Vec3::new(0.0, 1.5, 0.0)),
+        fixed(),
+        Collider {
+            is_sensor: true,
+            ..Collider::cuboid(0.25, 0.25, 0.25)
+        },
+    ));
+    system::physics_update_system(world, physics_world, 1.0 / 60.0);
+    [ground, crate_box, sensor]
+}
+
+#[test]
+fn test_spatial_queries() {
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let [ground, crate_box, sensor] = spawn_query_scene(&mut world, &mut physics_world);
+    let query = physics_world.spatial_query();
+    let all = SpatialQueryFilter::default();
+
+    let hit = query
+        .raycast(DVec3::new(0.0, 10.0, 0.0), Vec3::NEG_Y, 20.0, &all)
+        .unwrap();
+    assert_eq!(hit.entity, crate_box);
+    assert!((hit.distance - 6.5).abs() < 1e-3);
+    assert!((hit.normal - Vec3::Y).length() < 1e-3);
+
+    let below_box = all.excluding(crate_box);
+    let hit = query
+        .raycast(DVec3::new(0.0, 10.0, 0.0), Vec3::NEG_Y, 20.0, &below_box)
+        .unwrap();
+    assert_eq!(hit.entity, ground);
+    assert!(query
+        .raycast(DVec3::new(0.0, 10.0, 0.0), Vec3::NEG_Y, 5.0, &all)
+        .is_none());
+    assert!(query
+        .raycast(
+            DVec3::new(0.0, 10.0, 0.0),
+            Vec3::NEG_Y,
+            20.0,
+            &all.with_groups(u32::MAX, 0)
+        )
+        .is_none());
+
+    let hits = query.raycast_all(DVec3::new(0.0, 10.0, 0.0), Vec3::NEG_Y, 20.0, &all);
+    let entities: Vec<_> = hits.iter().map(|hit| hit.entity).collect();
+    assert_eq!(entities, vec![crate_box, ground]);
+    let hits = query.raycast_all(
+        DVec3::new(0.0, 10.0, 0.0),
+        Vec3::NEG_Y,
+        20.0,
+        &all.with_sensors(),
+    );
+    assert_eq!(hits.len(), 3);
+    assert_eq!(hits[1].entity, sensor);
+
+    let hit = query
+        .shape_cast(
+            QueryShape::Sphere { radius: 0.5 },
+            DVec3::new(0.0, 10.0, 0.0),
+            glam::Quat::IDENTITY,
+            Vec3::NEG_Y,
+            20.0,
+            &all,
+        )
+        .unwrap();
+    assert_eq!(hit.entity, crate_box);
+    assert!((hit.distance - 6.0).abs() < 1e-3);
+    assert!((hit.point.y - 3.5).abs() < 1e-3);
+
+    assert_eq!(
+        query.overlap_point(DVec3::new(0.0, 3.0, 0.0), &all),
+        vec![crate_box]
+    );
+    let overlapping = query.overlap_shape(
+        QueryShape::Sphere { radius: 1.5 },
+        DVec3::new(0.0, 1.5, 0.0),
+        glam::Quat::IDENTITY,
+        &all,
+    );
+    assert_eq!(overlapping.len(), 2);
+    assert!(overlapping.contains(&ground) && overlapping.contains(&crate_box));
+}
+
+#[test]
+fn test_script_raycast_returns_immediately() {
+    use crate::scripting::modules::physics::create_physics_module;
+    use crate::scripting::SharedComponentCache;
+
+    let mut world = World::new();
+    let mut physics_world = PhysicsWorld::new();
+    let [_ground, crate_box, _sensor] = spawn_query_scene(&mut world, &mut physics_world);
+
+    let mut engine = rhai::Engine::new();
+    engine.register_static_module(
+        "physics",
+        create_physics_module(SharedComponentCache::default()).into(),
+    );
+
+    let mut physics_world = Some(physics_world);
+    queries::lend_to_scripts(&mut physics_world, || {
+        let hit: rhai::Map = engine
+            .eval("physics::raycast([0.0, 10.0, 0.0], [0.0, -1.0, 0.0], 20.0)")
+            .unwrap();
+        assert_eq!(
+            hit["entity"].as_int().unwrap(),
+            crate_box.to_bits().get() as i64
+        );
+
+        let excluded = format!(
+            "physics::raycast_all([0.0, 10.0, 0.0], [0.0, -1.0, 0.0], 20.0, #{{ exclude: {} }})",
+            crate_box.to_bits().get()
+        );
+        let hits: rhai::Array = engine.eval(&excluded).unwrap();
+        assert_eq!(hits.len(), 1);
+
+        let inside: rhai::Array = engine
+            .eval("physics::overlap_sphere([0.0, 3.0, 0.0], 0.25)")
+            .unwrap();
+        assert_eq!(inside.len(), 1);
+    });
+    assert!(physics_world.is_some(), "the lent world is handed back");
+
+    // Outside the loan, and after clearing the world, scripts hit nothing
+    let raycast = "physics::raycast([0.0, 10.0, 0.0], [0.0, -1.0, 0.0], 20.0)";
+    assert!(engine.eval::<rhai::Dynamic>(raycast).unwrap().is_unit());
+    physics_world.as_mut().unwrap().clear();
+    let miss: rhai::Dynamic =
+        queries::lend_to_scripts(&mut physics_world, || engine.eval(raycast).unwrap());
+    assert!(miss.is_unit());
+}
+
 #[test]
 fn test_scripting_physics_commands() {
     use crate::scripting::modules::physics::create_physics_module;
+    use crate::scripting::SharedComponentCache;
 
     // Test physics module creation
-    let module = create_physics_module();
+    let module = create_physics_module(SharedComponentCache::default());
 
     // Verify module has expected functions
     // TODO: Module::contains_fn expects u64 hash, not &str
@@ -0,0 +1,124 @@
+//! Fixed timestep accumulator for the physics simulation
+//!
+//! Rendered frames take varying amounts of time, while Rapier is stepped with
+//! a constant `dt`. The accumulator collects frame time and hands out whole
+//! physics steps, leaving the remainder for the next frame. The fraction of a
+//! step left over is used to interpolate rendered transforms between the last
+//! two physics states.
+
+use tracing::warn;
+
+/// Tolerance for frame times that are a whole number of steps up to rounding
+const STEP_EPSILON: f64 = 1e-9;
+
+/// Fixed rate at which physics is stepped, independent of the frame rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTimestep {
+    /// Simulation steps per second
+    pub rate: f64,
+    /// Most steps taken in a single frame, further time is dropped
+    pub max_substeps: u32,
+    /// Whether dynamic bodies are rendered between their last two states
+    pub interpolate: bool,
+    /// Frame time not yet simulated
+    accumulator: f64,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(60.0, 8)
+    }
+}
+
+impl FixedTimestep {
+    /// Create a timestep with `rate` steps per second
+    pub fn new(rate: f64, max_substeps: u32) -> Self {
+        Self {
+            rate,
+            max_substeps,
+            interpolate: true,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Duration of one step in seconds
+    pub fn step_duration(&self) -> f64 {
+        1.0 / self.rate
+    }
+
+    /// Add frame time and return the number of steps to simulate
+    ///
+    /// When more than `max_substeps` steps are due the extra time is dropped,
+    /// so a slow frame makes the simulation run slower instead of taking
+    /// ever longer to catch up.
+    pub fn advance(&mut self, delta_time: f64) -> u32 {
+        if self.rate <= 0.0 || !delta_time.is_finite() || delta_time <= 0.0 {
+            return 0;
+        }
+
+        let step = self.step_duration();
+        self.accumulator += delta_time;
+
+        let mut steps = 0;
+        while self.accumulator + STEP_EPSILON >= step && steps < self.max_substeps {
+            self.accumulator = (self.accumulator - step).max(0.0);
+            steps += 1;
+        }
+
+        if self.accumulator + STEP_EPSILON >= step {
+            warn!(
+                dropped = self.accumulator,
+                "Physics fell behind, dropping simulation time"
+            );
+            self.accumulator = 0.0;
+        }
+        steps
+    }
+
+    /// How far the simulation has progressed towards the next step, in `0..1`
+    pub fn alpha(&self) -> f32 {
+        if self.rate <= 0.0 {
+            return 0.0;
+        }
+        (self.accumulator / self.step_duration()).clamp(0.0, 1.0) as f32
+    }
+
+    /// Forget accumulated time, e.g. after the simulation was reset
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_are_independent_of_frame_rate() {
+        let mut fast = FixedTimestep::new(60.0, 8);
+        let mut slow = FixedTimestep::new(60.0, 8);
+
+        let fast_steps: u32 = (0..120).map(|_| fast.advance(1.0 / 120.0)).sum();
+        let slow_steps: u32 = (0..30).map(|_| slow.advance(1.0 / 30.0)).sum();
+
+        assert_eq!(fast_steps, 60);
+        assert_eq!(slow_steps, 60);
+    }
+
+    #[test]
+    fn test_alpha_tracks_remainder() {
+        let mut timestep = FixedTimestep::new(60.0, 8);
+        assert_eq!(timestep.advance(1.0 / 120.0), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(timestep.advance(1.0 / 120.0), 1);
+        assert!(timestep.alpha() < 1e-4);
+    }
+
+    #[test]
+    fn test_max_substeps_drops_time() {
+        let mut timestep = FixedTimestep::new(60.0, 4);
+        assert_eq!(timestep.advance(1.0), 4);
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(1.0 / 60.0), 1);
+    }
+}
@@ -4,6 +4,14 @@
 //! structures needed for physics simulation with f64 precision.
 
 use crate::core::entity::Entity;
+use crate::graphics::Mesh;
+use crate::physics::events::{CollisionEvent, CollisionEventKind, CollisionEvents, EventCollector};
+use crate::physics::layers::CollisionLayers;
+use crate::physics::queries::SpatialQuery;
+use crate::physics::replay::{PhysicsRecorder, PhysicsRecording, PhysicsSnapshot};
+use crate::physics::shapes::CollisionMeshes;
+use crate::physics::timestep::FixedTimestep;
+use glam::{DVec3, Vec3};
 use rapier3d_f64::prelude::*;
 use std::collections::HashMap;
 use tracing::{debug, info};
@@ -46,6 +54,27 @@ pub struct PhysicsWorld {
     /// Query pipeline for raycasts and shape queries
     pub query_pipeline: QueryPipeline,
 
+    /// Fixed rate the simulation is stepped at, see [`PhysicsWorld::set_timestep`]
+    pub timestep: FixedTimestep,
+
+    /// Collision and trigger events of the last physics update
+    pub collision_events: CollisionEvents,
+
+    /// Names of the project's collision layers, see [`PhysicsWorld::set_collision_layers`]
+    pub collision_layers: CollisionLayers,
+
+    /// Geometry of the meshes used by mesh colliders, see [`PhysicsWorld::register_mesh`]
+    pub collision_meshes: CollisionMeshes,
+
+    /// World-space scale each mesh or compound collider shape was built with, by entity
+    pub(crate) mesh_collider_scales: HashMap<Entity, Vec3>,
+
+    /// Recording in progress, see [`PhysicsWorld::start_recording`]
+    pub(crate) recorder: Option<PhysicsRecorder>,
+
+    /// Receives Rapier events while stepping
+    event_collector: EventCollector,
+
     /// Mapping from entity to rigid body handle
     entity_to_body: HashMap<Entity, RigidBodyHandle>,
 
@@ -54,6 +83,12 @@ pub struct PhysicsWorld {
 
     /// Mapping from entity to collider handles (an entity can have multiple colliders)
     entity_to_colliders: HashMap<Entity, Vec<ColliderHandle>>,
+
+    /// Mapping from collider handle to entity
+    collider_to_entity: HashMap<ColliderHandle, Entity>,
+
+    /// Mapping from entity to the joint its Joint component created
+    entity_to_joint: HashMap<Entity, ImpulseJointHandle>,
 }
 
 impl PhysicsWorld {
@@ -61,8 +96,9 @@ impl PhysicsWorld {
     pub fn new() -> Self {
         info!("Initializing physics world with f64 precision");
 
+        let timestep = FixedTimestep::default();
         let integration_parameters = IntegrationParameters {
-            dt: 1.0 / 60.0, // Set fixed timestep for deterministic simulation (60 Hz)
+            dt: timestep.step_duration(), // Fixed timestep for deterministic simulation
             ..Default::default()
         };
 
@@ -79,9 +115,18 @@ impl PhysicsWorld {
             ccd_solver: CCDSolver::new(),
             gravity: vector![0.0, -9.81, 0.0], // Standard Earth gravity
             query_pipeline: QueryPipeline::new(),
+            timestep,
+            collision_events: CollisionEvents::default(),
+            collision_layers: CollisionLayers::default(),
+            collision_meshes: CollisionMeshes::default(),
+            mesh_collider_scales: HashMap::new(),
+            recorder: None,
+            event_collector: EventCollector::default(),
             entity_to_body: HashMap::new(),
             body_to_entity: HashMap::new(),
             entity_to_colliders: HashMap::new(),
+            collider_to_entity: HashMap::new(),
+            entity_to_joint: HashMap::new(),
         }
     }
 
@@ -91,6 +136,36 @@ impl PhysicsWorld {
         debug!("Physics gravity set to: {:?}", gravity);
     }
 
+    /// Set the names of the project's collision layers
+    ///
+    /// Colliders keep their masks, so renaming layers does not change how
+    /// they interact, only how scripts and tools refer to them.
+    pub fn set_collision_layers(&mut self, layers: CollisionLayers) {
+        debug!(layers = ?layers.names(), "Physics collision layers set");
+        self.collision_layers = layers;
+    }
+
+    /// Make a mesh available to mesh colliders under `name`
+    ///
+    /// Meshes from files and the built-in mesh library are found on their
+    /// own. Meshes that only exist at runtime, like the ones scripts
+    /// generate, must be registered before colliders can use them.
+    pub fn register_mesh(&mut self, name: &str, mesh: &Mesh) {
+        self.collision_meshes.register(name, mesh);
+    }
+
+    /// Set the rate and maximum substeps of the simulation
+    pub fn set_timestep(&mut self, timestep: FixedTimestep) {
+        self.timestep = timestep;
+        self.timestep.reset();
+        self.integration_parameters.dt = timestep.step_duration();
+        debug!(
+            rate = timestep.rate,
+            max_substeps = timestep.max_substeps,
+            "Physics timestep set"
+        );
+    }
+
     /// Register a rigid body with an entity
     pub fn register_body(&mut self, entity: Entity, handle: RigidBodyHandle) {
         self.entity_to_body.insert(entity, handle);
@@ -119,6 +194,7 @@ impl PhysicsWorld {
 
     /// Register a collider with an entity
     pub fn register_collider(&mut self, entity: Entity, handle: ColliderHandle) {
+        self.collider_to_entity.insert(handle, entity);
         self.entity_to_colliders
             .entry(entity)
             .or_default()
@@ -127,7 +203,30 @@ impl PhysicsWorld {
 
     /// Unregister all colliders for an entity
     pub fn unregister_colliders(&mut self, entity: Entity) -> Vec<ColliderHandle> {
-        self.entity_to_colliders.remove(&entity).unwrap_or_default()
+        let handles = self.entity_to_colliders.remove(&entity).unwrap_or_default();
+        for handle in &handles {
+            self.collider_to_entity.remove(handle);
+        }
+        handles
+    }
+
+    /// Remove all colliders of an entity, waking the body they were attached to
+    pub fn remove_colliders(&mut self, entity: Entity) {
+        for handle in self.unregister_colliders(entity) {
+            if self.collider_set.contains(handle) {
+                self.collider_set.remove(
+                    handle,
+                    &mut self.island_manager,
+                    &mut self.rigid_body_set,
+                    true,
+                );
+            }
+        }
+    }
+
+    /// Get the entity a collider belongs to
+    pub fn get_entity_for_collider(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.collider_to_entity.get(&handle).copied()
     }
 
     /// Get all collider handles for an entity
@@ -138,7 +237,53 @@ impl PhysicsWorld {
             .unwrap_or(&[])
     }
 
-    /// Step the physics simulation
+    /// Register a joint created for an entity's Joint component
+    pub fn register_joint(&mut self, entity: Entity, handle: ImpulseJointHandle) {
+        self.entity_to_joint.insert(entity, handle);
+    }
+
+    /// Get the joint handle for an entity
+    pub fn get_joint_handle(&self, entity: Entity) -> Option<ImpulseJointHandle> {
+        self.entity_to_joint.get(&entity).copied()
+    }
+
+    /// Entities whose Joint component created a joint
+    pub fn joint_entities(&self) -> Vec<Entity> {
+        self.entity_to_joint.keys().copied().collect()
+    }
+
+    /// Remove the joint created for an entity
+    pub fn remove_joint(&mut self, entity: Entity) {
+        if let Some(handle) = self.entity_to_joint.remove(&entity) {
+            self.impulse_joint_set.remove(handle, true);
+        }
+    }
+
+    /// Remove all bodies, colliders and joints
+    ///
+    /// Gravity, integration parameters, the timestep settings, collision
+    /// layers, collision meshes and a recording in progress are kept.
+    pub fn clear(&mut self) {
+        let gravity = self.gravity;
+        let integration_parameters = self.integration_parameters;
+        let mut timestep = self.timestep;
+        timestep.reset();
+        let collision_layers = std::mem::take(&mut self.collision_layers);
+        let collision_meshes = std::mem::take(&mut self.collision_meshes);
+        let recorder = self.recorder.take();
+        *self = Self::new();
+        self.gravity = gravity;
+        self.integration_parameters = integration_parameters;
+        self.timestep = timestep;
+        self.collision_layers = collision_layers;
+        self.collision_meshes = collision_meshes;
+        self.recorder = recorder;
+        // Scripts must not see contacts of the bodies that were just removed
+        crate::physics::events::publish_script_events(&self.collision_events);
+        debug!("Physics world cleared");
+    }
+
+    /// Advance the simulation by one fixed step
     pub fn step(&mut self) {
         self.physics_pipeline.step(
             &self.gravity,
@@ -153,12 +298,129 @@ impl PhysicsWorld {
             &mut self.ccd_solver,
             None,
             &(),
-            &(),
+            &self.event_collector,
         );
 
         // Update query pipeline after physics step
         self.query_pipeline
             .update(&self.rigid_body_set, &self.collider_set);
+
+        self.collect_collision_events();
+    }
+
+    /// Start recording the inputs of every update for a later replay
+    ///
+    /// The state of all bodies is stored now and after every
+    /// `snapshot_interval` fixed steps. An interval of 1 lets a replay find
+    /// the exact step where it diverges. A recording already in progress is
+    /// discarded.
+    pub fn start_recording(&mut self, snapshot_interval: u64) {
+        let g = self.gravity;
+        self.recorder = Some(PhysicsRecorder::new(
+            self.timestep.rate,
+            DVec3::new(g.x, g.y, g.z),
+            snapshot_interval,
+            self.snapshot(0),
+        ));
+        info!(snapshot_interval, "Physics recording started");
+    }
+
+    /// Stop recording and return what was recorded
+    ///
+    /// The current state is stored as the last snapshot, so a replay
+    /// compares the end state even between snapshot intervals.
+    pub fn stop_recording(&mut self) -> Option<PhysicsRecording> {
+        let mut recorder = self.recorder.take()?;
+        if !recorder.has_snapshot_of_last_step() {
+            recorder.push_snapshot(self.snapshot(recorder.step()));
+        }
+        let recording = recorder.finish();
+        info!(
+            steps = recording.steps(),
+            frames = recording.frames.len(),
+            "Physics recording stopped"
+        );
+        Some(recording)
+    }
+
+    /// Whether updates are being recorded
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// State of all bodies that belong to entities, labelled with `step`
+    pub fn snapshot(&self, step: u64) -> PhysicsSnapshot {
+        PhysicsSnapshot::capture(step, &self.rigid_body_set, &self.body_to_entity)
+    }
+
+    /// Count a fixed step for the recording, storing a snapshot when one is due
+    pub(crate) fn record_step(&mut self) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if let Some(step) = recorder.finish_step() {
+            recorder.push_snapshot(PhysicsSnapshot::capture(
+                step,
+                &self.rigid_body_set,
+                &self.body_to_entity,
+            ));
+        }
+    }
+
+    /// Turn the Rapier events of the last step into [`CollisionEvents`]
+    ///
+    /// Pairs involving a collider that was already removed are dropped.
+    fn collect_collision_events(&mut self) {
+        for event in self.event_collector.drain() {
+            let (collider1, collider2) = (event.collider1(), event.collider2());
+            let (Some(entity), Some(other)) = (
+                self.get_entity_for_collider(collider1),
+                self.get_entity_for_collider(collider2),
+            ) else {
+                continue;
+            };
+
+            let position = |handle| {
+                self.collider_set.get(handle).map(|collider| {
+                    let t = collider.translation();
+                    Vec3::new(t.x as f32, t.y as f32, t.z as f32)
+                })
+            };
+            let (from, to) = (
+                position(collider1).unwrap_or_default(),
+                position(collider2).unwrap_or_default(),
+            );
+            let mut point = (from + to) * 0.5;
+            let mut normal = (to - from).normalize_or_zero();
+            let mut impulse = 0.0;
+
+            if event.started() && !event.sensor() {
+                if let Some(pair) = self.narrow_phase.contact_pair(collider1, collider2) {
+                    impulse = pair.total_impulse_magnitude() as f32;
+                    let deepest = pair.find_deepest_contact();
+                    let first = self.collider_set.get(pair.collider1);
+                    if let (Some((manifold, contact)), Some(first)) = (deepest, first) {
+                        let p = first.position() * contact.local_p1;
+                        point = Vec3::new(p.x as f32, p.y as f32, p.z as f32);
+                        let n = manifold.data.normal;
+                        normal = Vec3::new(n.x as f32, n.y as f32, n.z as f32);
+                        // The pair may list the colliders the other way around
+                        if pair.collider1 != collider1 {
+                            normal = -normal;
+                        }
+                    }
+                }
+            }
+
+            self.collision_events.push(CollisionEvent {
+                kind: CollisionEventKind::new(event.started(), event.sensor()),
+                entity,
+                other,
+                point,
+                normal,
+                impulse,
+            });
+        }
     }
 
     /// Perform a raycast in the physics world
@@ -181,6 +443,16 @@ impl PhysicsWorld {
         )
     }
 
+    /// Raycasts, shape casts and overlap tests against the current colliders
+    pub fn spatial_query(&self) -> SpatialQuery<'_> {
+        SpatialQuery::new(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &self.query_pipeline,
+            &self.collider_to_entity,
+        )
+    }
+
     /// Get all registered entities
     pub fn registered_entities(&self) -> Vec<Entity> {
         self.entity_to_body.keys().copied().collect()
@@ -188,6 +460,8 @@ impl PhysicsWorld {
 
     /// Clean up resources for a removed entity
     pub fn cleanup_entity(&mut self, entity: Entity) {
+        self.remove_joint(entity);
+
         // Remove rigid body if it exists
         if let Some(body_handle) = self.unregister_body(entity) {
             self.rigid_body_set.remove(
@@ -212,6 +486,7 @@ impl PhysicsWorld {
                 );
             }
         }
+        self.mesh_collider_scales.remove(&entity);
     }
 }
 
@@ -3,7 +3,7 @@ use crate::core::entity::{Entity, Name};
 use crate::graphics::material::Material;
 use crate::graphics::mesh::Mesh;
 use crate::graphics::renderer::MeshId;
-use crate::physics::{Collider, PhysicsVelocity, RigidBody};
+use crate::physics::{CharacterController, Collider, PhysicsVelocity, RigidBody};
 use crate::scripting::property_types::ScriptProperties;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -59,6 +59,7 @@ pub struct ComponentCache {
     pub rigid_bodies: HashMap<u64, RigidBody>,
     pub colliders: HashMap<u64, Collider>,
     pub velocities: HashMap<u64, PhysicsVelocity>,
+    pub character_controllers: HashMap<u64, CharacterController>,
 }
 
 pub type SharedComponentCache = Arc<RwLock<ComponentCache>>;
@@ -76,6 +77,7 @@ impl ComponentCache {
         self.rigid_bodies.clear();
         self.colliders.clear();
         self.velocities.clear();
+        self.character_controllers.clear();
     }
 }
 
@@ -103,7 +105,7 @@ impl ScriptCommand {
                 if let Some(ent) = Entity::from_bits(*entity) {
                     if world.contains(ent) {
                         world
-                            .insert_one(ent, *material)
+                            .insert_one(ent, material.clone())
                             .map_err(|e| format!("Failed to insert material: {e:?}"))?;
                         debug!(entity = *entity, "Applied material update from script");
                         Ok(())
@@ -125,7 +127,7 @@ impl ScriptCommand {
                             entity_builder.add(*t);
                         }
                         ComponentData::Material(m) => {
-                            entity_builder.add(*m);
+                            entity_builder.add(m.clone());
                         }
                         ComponentData::Name(n) => {
                             entity_builder.add(Name::new(n.clone()));
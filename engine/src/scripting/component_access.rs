@@ -2,7 +2,7 @@ use crate::core::entity::components::Transform;
 use crate::core::entity::{Entity, Name};
 use crate::graphics::material::Material;
 use crate::graphics::renderer::MeshId;
-use crate::physics::{Collider, PhysicsVelocity, RigidBody};
+use crate::physics::{CharacterController, Collider, PhysicsVelocity, RigidBody};
 use crate::scripting::commands::ComponentCache;
 use hecs::World;
 use tracing::{debug, error, trace};
@@ -35,7 +35,7 @@ pub fn query_component(
         }
         "Material" => {
             if let Ok(material) = world.get::<&Material>(entity) {
-                cache.materials.insert(entity_id, *material);
+                cache.materials.insert(entity_id, (*material).clone());
                 trace!(entity = entity_id, "Cached Material component");
                 Ok(())
             } else {
@@ -89,6 +89,19 @@ pub fn query_component(
                 ))
             }
         }
+        "CharacterController" => {
+            if let Ok(controller) = world.get::<&CharacterController>(entity) {
+                cache
+                    .character_controllers
+                    .insert(entity_id, (*controller).clone());
+                trace!(entity = entity_id, "Cached CharacterController component");
+                Ok(())
+            } else {
+                Err(format!(
+                    "Entity {entity_id} missing CharacterController component"
+                ))
+            }
+        }
         _ => Err(format!("Unknown component type: {component_type}")),
     }
 }
@@ -113,7 +126,7 @@ pub fn query_entities_with_component(
         "Material" => {
             for (entity, material) in world.query::<&Material>().iter() {
                 let entity_id = entity.to_bits().get();
-                cache.materials.insert(entity_id, *material);
+                cache.materials.insert(entity_id, material.clone());
                 entities.push(entity_id);
             }
             debug!(count = entities.len(), "Queried entities with Material");
@@ -161,6 +174,19 @@ pub fn query_entities_with_component(
                 "Queried entities with PhysicsVelocity"
             );
         }
+        "CharacterController" => {
+            for (entity, controller) in world.query::<&CharacterController>().iter() {
+                let entity_id = entity.to_bits().get();
+                cache
+                    .character_controllers
+                    .insert(entity_id, controller.clone());
+                entities.push(entity_id);
+            }
+            debug!(
+                count = entities.len(),
+                "Queried entities with CharacterController"
+            );
+        }
         _ => {
             error!(component_type, "Unknown component type in query");
         }
@@ -190,7 +216,7 @@ pub fn populate_cache_for_scripts(world: &World, cache: &mut ComponentCache) {
 
         // Cache material if present
         if let Some(mat) = material {
-            cache.materials.insert(entity_id, *mat);
+            cache.materials.insert(entity_id, mat.clone());
         }
 
         // Cache name if present
@@ -205,11 +231,12 @@ pub fn populate_cache_for_scripts(world: &World, cache: &mut ComponentCache) {
     }
 
     // Query all entities with physics components
-    for (entity, (rigid_body, collider, velocity)) in world
+    for (entity, (rigid_body, collider, velocity, controller)) in world
         .query::<(
             Option<&RigidBody>,
             Option<&Collider>,
             Option<&PhysicsVelocity>,
+            Option<&CharacterController>,
         )>()
         .iter()
     {
@@ -229,6 +256,13 @@ pub fn populate_cache_for_scripts(world: &World, cache: &mut ComponentCache) {
         if let Some(vel) = velocity {
             cache.velocities.insert(entity_id, *vel);
         }
+
+        // Cache character controller if present
+        if let Some(controller) = controller {
+            cache
+                .character_controllers
+                .insert(entity_id, controller.clone());
+        }
     }
 
     debug!(
@@ -239,6 +273,7 @@ pub fn populate_cache_for_scripts(world: &World, cache: &mut ComponentCache) {
         rigid_bodies = cache.rigid_bodies.len(),
         colliders = cache.colliders.len(),
         velocities = cache.velocities.len(),
+        character_controllers = cache.character_controllers.len(),
         "Populated component cache for scripts"
     );
 }
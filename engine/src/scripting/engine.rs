@@ -296,6 +296,20 @@ impl ScriptEngine {
         self.cache.read().unwrap().len()
     }
 
+    /// Check whether a script defines a function taking `arity` parameters
+    pub fn has_function(&self, script_name: &str, function_name: &str, arity: usize) -> bool {
+        self.cache
+            .read()
+            .unwrap()
+            .get(script_name)
+            .is_some_and(|cached| {
+                cached
+                    .ast
+                    .iter_functions()
+                    .any(|f| f.name == function_name && f.params.len() == arity)
+            })
+    }
+
     /// Get property definitions for a script
     pub fn get_property_definitions(&self, script_name: &str) -> Option<Vec<PropertyDefinition>> {
         self.cache
@@ -1,15 +1,21 @@
 //! System for processing mesh uploads from scripts
 //!
 //! This system processes pending mesh uploads from the script mesh registry
-//! and uploads them to the renderer.
+//! and uploads them to the renderer. The meshes are also registered with the
+//! physics world so mesh colliders can use them.
 
 use crate::graphics::Renderer;
+use crate::physics::PhysicsWorld;
 use crate::profiling::profile_zone;
 use crate::scripting::ScriptEngine;
 use tracing::debug;
 
 /// Process pending mesh uploads from scripts
-pub fn process_script_mesh_uploads(script_engine: &ScriptEngine, renderer: &mut Renderer) {
+pub fn process_script_mesh_uploads(
+    script_engine: &ScriptEngine,
+    renderer: &mut Renderer,
+    mut physics_world: Option<&mut PhysicsWorld>,
+) {
     profile_zone!("process_script_mesh_uploads");
 
     let pending_meshes = script_engine.mesh_registry.take_pending_meshes();
@@ -33,6 +39,11 @@ pub fn process_script_mesh_uploads(script_engine: &ScriptEngine, renderer: &mut
         // Upload the mesh to the renderer
         let mesh_id = renderer.upload_mesh(&pending.mesh, &pending.name);
 
+        // Let TriMesh and ConvexHull colliders use it too
+        if let Some(physics_world) = physics_world.as_deref_mut() {
+            physics_world.register_mesh(&mesh_id.0, &pending.mesh);
+        }
+
         debug!(
             name = pending.name,
             callback_id = pending.callback_id,
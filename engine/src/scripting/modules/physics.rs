@@ -1,11 +1,18 @@
 //! Physics API for Rhai scripts
 //!
 //! This module exposes physics functionality to scripts through a safe,
-//! command-based API that integrates with the physics system.
+//! command-based API that integrates with the physics system. Forces,
+//! velocities and character motion are queued for the next physics update,
+//! while raycasts, shape casts, overlap tests and character state answer
+//! immediately from the live physics state. Query positions and hit points
+//! use double precision, like the simulation.
 
-use crate::physics::PhysicsCommand;
-use glam::Vec3;
-use rhai::{Dynamic, Engine, EvalAltResult, Module};
+use crate::core::entity::Entity;
+use crate::physics::queries::{with_script_layers, with_script_queries};
+use crate::physics::{CharacterHit, PhysicsCommand, QueryShape, SpatialQueryFilter};
+use crate::scripting::commands::SharedComponentCache;
+use glam::{DVec3, Quat, Vec3};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Module, FLOAT};
 use tracing::{debug, trace};
 
 /// Register physics types and global functions with Rhai engine
@@ -39,7 +46,7 @@ pub fn register_physics_api(engine: &mut Engine) {
 }
 
 /// Create a physics module for scripts
-pub fn create_physics_module() -> Module {
+pub fn create_physics_module(component_cache: SharedComponentCache) -> Module {
     let mut module = Module::new();
 
     // Apply force to entity
@@ -100,82 +107,457 @@ pub fn create_physics_module() -> Module {
         },
     );
 
-    // Raycast - performs a physics raycast and returns hit information
+    // Set the motion of a character controller for this frame, in meters per second
+    module.set_native_fn(
+        "move_character",
+        move |entity: i64, motion: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let motion_vec = parse_vec3_from_dynamic(motion)?;
+            crate::physics::system::queue_physics_command(PhysicsCommand::MoveCharacter {
+                entity: entity as u64,
+                motion: motion_vec,
+            });
+            trace!(entity = entity, motion = ?motion_vec, "Queued move_character command");
+            Ok(())
+        },
+    );
+
+    // Whether a character controller stood on the ground after its last move
+    let cache = component_cache.clone();
+    module.set_native_fn(
+        "is_grounded",
+        move |entity: i64| -> Result<bool, Box<EvalAltResult>> {
+            let entity_id = entity as u64;
+            let cache_guard = cache.read().unwrap();
+            cache_guard
+                .character_controllers
+                .get(&entity_id)
+                .map(|controller| controller.grounded)
+                .ok_or_else(|| {
+                    format!("Entity {entity_id} not found or missing CharacterController component")
+                        .into()
+                })
+        },
+    );
+
+    // Obstacles a character controller hit during the last physics update
+    let cache = component_cache;
+    module.set_native_fn(
+        "character_hits",
+        move |entity: i64| -> Result<Array, Box<EvalAltResult>> {
+            let entity_id = entity as u64;
+            let cache_guard = cache.read().unwrap();
+            cache_guard
+                .character_controllers
+                .get(&entity_id)
+                .map(|controller| controller.hits.iter().map(character_hit_map).collect())
+                .ok_or_else(|| {
+                    format!("Entity {entity_id} not found or missing CharacterController component")
+                        .into()
+                })
+        },
+    );
+
+    // Bit mask of one or more named collision layers, for query filters
+    module.set_native_fn(
+        "layer_mask",
+        |layers: Dynamic| -> Result<i64, Box<EvalAltResult>> {
+            Ok(parse_layer_mask(&layers, "layer_mask")? as i64)
+        },
+    );
+
+    // Raycasts - closest hit as a map, or () when nothing is hit
     module.set_native_fn(
         "raycast",
-        move |origin: Dynamic,
-              direction: Dynamic,
-              max_distance: f32|
-              -> Result<Dynamic, Box<EvalAltResult>> {
-            let origin_vec = parse_vec3_from_dynamic(origin)?;
-            let direction_vec = parse_vec3_from_dynamic(direction)?;
-
-            // Use a channel to receive the result from the physics thread
-            let (tx, rx) = std::sync::mpsc::channel();
-            crate::physics::system::queue_physics_command(PhysicsCommand::Raycast {
-                origin: origin_vec,
-                direction: direction_vec.normalize(),
+        |origin: Dynamic, direction: Dynamic, max_distance: FLOAT| {
+            raycast(
+                origin,
+                direction,
                 max_distance,
-                callback: Box::new(move |hit| {
-                    let result = match hit {
-                        Some(hit) => {
-                            // Create a map with hit information
-                            let mut map = rhai::Map::new();
-                            map.insert("hit".into(), true.into());
-                            map.insert("entity".into(), Dynamic::from(hit.entity as i64));
-                            map.insert("distance".into(), Dynamic::from(hit.distance));
-
-                            // Convert Vec3 to array for Rhai
-                            let point_array = vec![
-                                Dynamic::from(hit.point.x),
-                                Dynamic::from(hit.point.y),
-                                Dynamic::from(hit.point.z),
-                            ];
-                            map.insert("point".into(), Dynamic::from(point_array));
-
-                            let normal_array = vec![
-                                Dynamic::from(hit.normal.x),
-                                Dynamic::from(hit.normal.y),
-                                Dynamic::from(hit.normal.z),
-                            ];
-                            map.insert("normal".into(), Dynamic::from(normal_array));
-
-                            Dynamic::from_map(map)
-                        }
-                        None => {
-                            // No hit - return null
-                            Dynamic::UNIT
-                        }
-                    };
-
-                    // Send result through channel
-                    let _ = tx.send(result.clone());
-                    result
-                }),
-            });
+                SpatialQueryFilter::default(),
+            )
+        },
+    );
+    module.set_native_fn(
+        "raycast",
+        |origin: Dynamic, direction: Dynamic, max_distance: FLOAT, filter: Map| {
+            raycast(origin, direction, max_distance, parse_filter(filter)?)
+        },
+    );
 
-            trace!(origin = ?origin_vec, direction = ?direction_vec, max_distance, "Queued raycast command");
+    // Every hit along the ray, closest first
+    module.set_native_fn(
+        "raycast_all",
+        |origin: Dynamic, direction: Dynamic, max_distance: FLOAT| {
+            raycast_all(
+                origin,
+                direction,
+                max_distance,
+                SpatialQueryFilter::default(),
+            )
+        },
+    );
+    module.set_native_fn(
+        "raycast_all",
+        |origin: Dynamic, direction: Dynamic, max_distance: FLOAT, filter: Map| {
+            raycast_all(origin, direction, max_distance, parse_filter(filter)?)
+        },
+    );
 
-            // Wait for result with a timeout
-            match rx.recv_timeout(std::time::Duration::from_millis(100)) {
-                Ok(result) => Ok(result),
-                Err(_) => {
-                    // Timeout or error - return null
-                    debug!("Raycast timeout or channel error");
-                    Ok(Dynamic::UNIT)
-                }
-            }
+    // Shape sweeps - first hit as a map, or () when nothing is hit
+    module.set_native_fn(
+        "sphere_cast",
+        |origin: Dynamic, radius: FLOAT, direction: Dynamic, max_distance: FLOAT| {
+            let shape = QueryShape::Sphere {
+                radius: radius as f32,
+            };
+            shape_cast(
+                shape,
+                origin,
+                direction,
+                max_distance,
+                SpatialQueryFilter::default(),
+            )
+        },
+    );
+    module.set_native_fn(
+        "sphere_cast",
+        |origin: Dynamic, radius: FLOAT, direction: Dynamic, max_distance: FLOAT, filter: Map| {
+            let shape = QueryShape::Sphere {
+                radius: radius as f32,
+            };
+            shape_cast(
+                shape,
+                origin,
+                direction,
+                max_distance,
+                parse_filter(filter)?,
+            )
+        },
+    );
+    module.set_native_fn(
+        "box_cast",
+        |origin: Dynamic, half_extents: Dynamic, direction: Dynamic, max_distance: FLOAT| {
+            let shape = QueryShape::Box {
+                half_extents: parse_vec3_from_dynamic(half_extents)?,
+            };
+            shape_cast(
+                shape,
+                origin,
+                direction,
+                max_distance,
+                SpatialQueryFilter::default(),
+            )
+        },
+    );
+    module.set_native_fn(
+        "box_cast",
+        |origin: Dynamic,
+         half_extents: Dynamic,
+         direction: Dynamic,
+         max_distance: FLOAT,
+         filter: Map| {
+            let shape = QueryShape::Box {
+                half_extents: parse_vec3_from_dynamic(half_extents)?,
+            };
+            shape_cast(
+                shape,
+                origin,
+                direction,
+                max_distance,
+                parse_filter(filter)?,
+            )
+        },
+    );
+    module.set_native_fn(
+        "capsule_cast",
+        |origin: Dynamic,
+         half_height: FLOAT,
+         radius: FLOAT,
+         direction: Dynamic,
+         max_distance: FLOAT| {
+            let shape = QueryShape::Capsule {
+                half_height: half_height as f32,
+                radius: radius as f32,
+            };
+            shape_cast(
+                shape,
+                origin,
+                direction,
+                max_distance,
+                SpatialQueryFilter::default(),
+            )
+        },
+    );
+    module.set_native_fn(
+        "capsule_cast",
+        |origin: Dynamic,
+         half_height: FLOAT,
+         radius: FLOAT,
+         direction: Dynamic,
+         max_distance: FLOAT,
+         filter: Map| {
+            let shape = QueryShape::Capsule {
+                half_height: half_height as f32,
+                radius: radius as f32,
+            };
+            shape_cast(
+                shape,
+                origin,
+                direction,
+                max_distance,
+                parse_filter(filter)?,
+            )
+        },
+    );
+
+    // Overlaps - array of the entity IDs found
+    module.set_native_fn("overlap_point", |point: Dynamic| {
+        overlap_point(point, SpatialQueryFilter::default())
+    });
+    module.set_native_fn("overlap_point", |point: Dynamic, filter: Map| {
+        overlap_point(point, parse_filter(filter)?)
+    });
+    module.set_native_fn("overlap_sphere", |center: Dynamic, radius: FLOAT| {
+        let shape = QueryShape::Sphere {
+            radius: radius as f32,
+        };
+        overlap_shape(shape, center, SpatialQueryFilter::default())
+    });
+    module.set_native_fn(
+        "overlap_sphere",
+        |center: Dynamic, radius: FLOAT, filter: Map| {
+            let shape = QueryShape::Sphere {
+                radius: radius as f32,
+            };
+            overlap_shape(shape, center, parse_filter(filter)?)
+        },
+    );
+    module.set_native_fn("overlap_box", |center: Dynamic, half_extents: Dynamic| {
+        let shape = QueryShape::Box {
+            half_extents: parse_vec3_from_dynamic(half_extents)?,
+        };
+        overlap_shape(shape, center, SpatialQueryFilter::default())
+    });
+    module.set_native_fn(
+        "overlap_box",
+        |center: Dynamic, half_extents: Dynamic, filter: Map| {
+            let shape = QueryShape::Box {
+                half_extents: parse_vec3_from_dynamic(half_extents)?,
+            };
+            overlap_shape(shape, center, parse_filter(filter)?)
+        },
+    );
+    module.set_native_fn(
+        "overlap_capsule",
+        |center: Dynamic, half_height: FLOAT, radius: FLOAT| {
+            let shape = QueryShape::Capsule {
+                half_height: half_height as f32,
+                radius: radius as f32,
+            };
+            overlap_shape(shape, center, SpatialQueryFilter::default())
+        },
+    );
+    module.set_native_fn(
+        "overlap_capsule",
+        |center: Dynamic, half_height: FLOAT, radius: FLOAT, filter: Map| {
+            let shape = QueryShape::Capsule {
+                half_height: half_height as f32,
+                radius: radius as f32,
+            };
+            overlap_shape(shape, center, parse_filter(filter)?)
         },
     );
 
     module
 }
 
+fn raycast(
+    origin: Dynamic,
+    direction: Dynamic,
+    max_distance: FLOAT,
+    filter: SpatialQueryFilter,
+) -> Result<Dynamic, Box<EvalAltResult>> {
+    let origin = parse_dvec3_from_dynamic(origin)?;
+    let direction = parse_vec3_from_dynamic(direction)?;
+    let hit = with_script_queries(|query| query.raycast(origin, direction, max_distance, &filter));
+    trace!(
+        ?origin,
+        ?direction,
+        max_distance,
+        hit = hit.is_some(),
+        "Script raycast"
+    );
+    Ok(hit.map_or(Dynamic::UNIT, |hit| {
+        hit_map(hit.entity, hit.distance, hit.point, hit.normal)
+    }))
+}
+
+fn raycast_all(
+    origin: Dynamic,
+    direction: Dynamic,
+    max_distance: FLOAT,
+    filter: SpatialQueryFilter,
+) -> Result<Array, Box<EvalAltResult>> {
+    let origin = parse_dvec3_from_dynamic(origin)?;
+    let direction = parse_vec3_from_dynamic(direction)?;
+    let hits =
+        with_script_queries(|query| query.raycast_all(origin, direction, max_distance, &filter));
+    Ok(hits
+        .into_iter()
+        .map(|hit| hit_map(hit.entity, hit.distance, hit.point, hit.normal))
+        .collect())
+}
+
+fn shape_cast(
+    shape: QueryShape,
+    origin: Dynamic,
+    direction: Dynamic,
+    max_distance: FLOAT,
+    filter: SpatialQueryFilter,
+) -> Result<Dynamic, Box<EvalAltResult>> {
+    let origin = parse_dvec3_from_dynamic(origin)?;
+    let direction = parse_vec3_from_dynamic(direction)?;
+    let hit = with_script_queries(|query| {
+        query.shape_cast(
+            shape,
+            origin,
+            Quat::IDENTITY,
+            direction,
+            max_distance,
+            &filter,
+        )
+    });
+    trace!(
+        ?shape,
+        ?origin,
+        ?direction,
+        max_distance,
+        hit = hit.is_some(),
+        "Script shape cast"
+    );
+    Ok(hit.map_or(Dynamic::UNIT, |hit| {
+        hit_map(hit.entity, hit.distance, hit.point, hit.normal)
+    }))
+}
+
+fn overlap_point(point: Dynamic, filter: SpatialQueryFilter) -> Result<Array, Box<EvalAltResult>> {
+    let point = parse_dvec3_from_dynamic(point)?;
+    let entities = with_script_queries(|query| query.overlap_point(point, &filter));
+    Ok(entity_array(entities))
+}
+
+fn overlap_shape(
+    shape: QueryShape,
+    center: Dynamic,
+    filter: SpatialQueryFilter,
+) -> Result<Array, Box<EvalAltResult>> {
+    let center = parse_dvec3_from_dynamic(center)?;
+    let entities =
+        with_script_queries(|query| query.overlap_shape(shape, center, Quat::IDENTITY, &filter));
+    Ok(entity_array(entities))
+}
+
+/// Build the map scripts receive for a ray or shape cast hit
+fn hit_map(entity: Entity, distance: f64, point: DVec3, normal: Vec3) -> Dynamic {
+    let mut map = Map::new();
+    map.insert("hit".into(), true.into());
+    map.insert(
+        "entity".into(),
+        Dynamic::from(entity.to_bits().get() as i64),
+    );
+    map.insert("distance".into(), Dynamic::from(distance as FLOAT));
+    map.insert("point".into(), vec3_array(point));
+    map.insert("normal".into(), vec3_array(normal.as_dvec3()));
+    Dynamic::from_map(map)
+}
+
+/// Build the map scripts receive for an obstacle hit by a character
+fn character_hit_map(hit: &CharacterHit) -> Dynamic {
+    let mut map = Map::new();
+    map.insert(
+        "entity".into(),
+        Dynamic::from(hit.entity.to_bits().get() as i64),
+    );
+    map.insert("point".into(), vec3_array(hit.point.as_dvec3()));
+    map.insert("normal".into(), vec3_array(hit.normal.as_dvec3()));
+    Dynamic::from_map(map)
+}
+
+fn vec3_array(v: DVec3) -> Dynamic {
+    Dynamic::from_array(vec![
+        Dynamic::from(v.x as FLOAT),
+        Dynamic::from(v.y as FLOAT),
+        Dynamic::from(v.z as FLOAT),
+    ])
+}
+
+fn entity_array(entities: Vec<Entity>) -> Array {
+    entities
+        .into_iter()
+        .map(|entity| Dynamic::from(entity.to_bits().get() as i64))
+        .collect()
+}
+
+/// Parse a query filter map with optional `groups`, `mask`, `exclude` and
+/// `sensors` keys
+fn parse_filter(map: Map) -> Result<SpatialQueryFilter, Box<EvalAltResult>> {
+    let mut filter = SpatialQueryFilter::default();
+    if let Some(groups) = map.get("groups") {
+        filter.memberships = parse_layer_mask(groups, "groups")?;
+    }
+    if let Some(mask) = map.get("mask") {
+        filter.mask = parse_layer_mask(mask, "mask")?;
+    }
+    if let Some(exclude) = map.get("exclude") {
+        let id = exclude
+            .as_int()
+            .map_err(|_| "Expected entity ID for exclude")?;
+        filter.exclude = Entity::from_bits(id as u64);
+    }
+    if let Some(sensors) = map.get("sensors") {
+        filter.include_sensors = sensors
+            .as_bool()
+            .map_err(|_| "Expected boolean for sensors")?;
+    }
+    Ok(filter)
+}
+
+/// Parse a layer mask given as an integer bit mask, a layer name or an
+/// array of layer names
+fn parse_layer_mask(value: &Dynamic, what: &str) -> Result<u32, Box<EvalAltResult>> {
+    if let Ok(bits) = value.as_int() {
+        return Ok(bits as u32);
+    }
+
+    let names: Vec<String> = if value.is_string() {
+        vec![value.clone().into_string()?]
+    } else if value.is_array() {
+        value
+            .clone()
+            .into_array()?
+            .into_iter()
+            .map(|name| name.into_string())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Expected layer names for {what}"))?
+    } else {
+        return Err(format!("Expected bit mask or layer names for {what}").into());
+    };
+
+    with_script_layers(|layers| layers.mask(names.iter().map(String::as_str)))
+        .map_err(|e| e.to_string().into())
+}
+
 /// Parse a Vec3 from various Dynamic representations
 fn parse_vec3_from_dynamic(value: Dynamic) -> Result<Vec3, Box<EvalAltResult>> {
+    parse_dvec3_from_dynamic(value).map(DVec3::as_vec3)
+}
+
+/// Parse a position from various Dynamic representations, keeping the
+/// precision of script floats
+fn parse_dvec3_from_dynamic(value: Dynamic) -> Result<DVec3, Box<EvalAltResult>> {
     // Try to cast directly to Vec3 first
     if let Some(vec3) = value.clone().try_cast::<Vec3>() {
-        return Ok(vec3);
+        return Ok(vec3.as_dvec3());
     }
 
     // Try to parse as array of 3 floats
@@ -193,7 +575,7 @@ fn parse_vec3_from_dynamic(value: Dynamic) -> Result<Vec3, Box<EvalAltResult>> {
                 .clone()
                 .as_float()
                 .map_err(|_| "Expected float for z component")?;
-            return Ok(Vec3::new(x as f32, y as f32, z as f32));
+            return Ok(DVec3::new(x, y, z));
         }
     }
 
@@ -219,7 +601,7 @@ mod tests {
 
     #[test]
     fn test_physics_module_creation() {
-        let module = create_physics_module();
+        let module = create_physics_module(SharedComponentCache::default());
 
         // Module should have the expected functions
         // TODO: Module::contains_fn expects u64 hash, not &str
@@ -233,7 +233,7 @@ pub fn create_world_module(
 
             if let Some(material) = cache_guard.materials.get(&entity_id) {
                 trace!(entity = entity_id, "Retrieved Material from cache");
-                Ok(Dynamic::from(*material))
+                Ok(Dynamic::from(material.clone()))
             } else {
                 Err(format!("Entity {entity_id} not found or missing Material component").into())
             }
@@ -309,7 +309,7 @@ pub fn create_world_module(
                 }
                 "Material" => {
                     if let Some(material) = cache_guard.materials.get(&entity_id) {
-                        Ok(Dynamic::from(*material))
+                        Ok(Dynamic::from(material.clone()))
                     } else {
                         Err(format!("Entity {entity_id} missing Material").into())
                     }
@@ -610,7 +610,15 @@ pub fn register_material_type(engine: &mut Engine) {
             m.color[2] = (b as f32).clamp(0.0, 1.0);
             // Keep existing alpha
         })
-        .register_fn("clone", |m: &mut Material| *m);
+        .register_get("metallic", |m: &mut Material| m.metallic as f64)
+        .register_set("metallic", |m: &mut Material, value: f64| {
+            m.metallic = (value as f32).clamp(0.0, 1.0);
+        })
+        .register_get("roughness", |m: &mut Material| m.roughness as f64)
+        .register_set("roughness", |m: &mut Material, value: f64| {
+            m.roughness = (value as f32).clamp(0.0, 1.0);
+        })
+        .register_fn("clone", |m: &mut Material| m.clone());
 
     // Create a material module with constructor functions
     let mut material_module = Module::new();
@@ -1,6 +1,7 @@
 //! Script execution system
 
 use crate::core::entity::World;
+use crate::physics::events::{take_script_events, CollisionEvent};
 use crate::scripting::commands::{CommandQueue, ScriptCommand, SharedComponentCache};
 use crate::scripting::component_access::populate_cache_for_scripts;
 use crate::scripting::lifecycle_tracker::get_tracker;
@@ -52,6 +53,9 @@ pub fn script_execution_system(
         populate_cache_for_scripts(world.inner(), &mut cache);
     }
 
+    // Contacts reported by the last physics update
+    let collision_events = take_script_events();
+
     // Collect entities with scripts first using compound query to avoid borrow conflicts
     let mut entities_with_scripts = Vec::new();
     for (entity, (script_ref, properties)) in world
@@ -140,8 +144,9 @@ pub fn script_execution_system(
         let mesh_module =
             create_mesh_module(script_engine.mesh_registry.clone(), command_queue.clone());
 
-        // Create physics module
-        let physics_module = crate::scripting::modules::physics::create_physics_module();
+        // Create physics module with the cache for character state
+        let physics_module =
+            crate::scripting::modules::physics::create_physics_module(component_cache.clone());
 
         // Create profiling module
         let profiling_module = crate::scripting::modules::profiling::create_profiling_module();
@@ -219,6 +224,25 @@ pub fn script_execution_system(
             }
         }
 
+        // Call on_collision_* and on_trigger_* for contacts of this entity
+        for event in collision_events.involving(entity) {
+            let function = event.kind.script_function();
+            if !script_engine.has_function(&script_ref.name, function, 2) {
+                continue;
+            }
+
+            profile_zone!("Script::collision_event");
+            let other = event.other.to_bits().get() as i64;
+            if let Err(e) = script_engine.call_script_function::<Dynamic>(
+                &script_ref.name,
+                function,
+                &mut scope,
+                (other, contact_map(&event)),
+            ) {
+                warn!(entity = ?entity, script = script_ref.name, function, error = %e, "Script collision callback failed");
+            }
+        }
+
         // Check if properties were modified and persist changes
         if let Some(ref original_properties) = script_properties {
             // Try to get modified properties from scope
@@ -335,6 +359,29 @@ pub fn script_execution_system(
     debug!("Script execution system completed");
 }
 
+/// Describe a contact for collision callbacks
+///
+/// Vectors are arrays of three floats, like raycast hits.
+fn contact_map(event: &CollisionEvent) -> rhai::Map {
+    let vector = |v: glam::Vec3| {
+        Dynamic::from(vec![
+            Dynamic::from(v.x as f64),
+            Dynamic::from(v.y as f64),
+            Dynamic::from(v.z as f64),
+        ])
+    };
+
+    let mut map = rhai::Map::new();
+    map.insert(
+        "entity".into(),
+        Dynamic::from(event.other.to_bits().get() as i64),
+    );
+    map.insert("point".into(), vector(event.point));
+    map.insert("normal".into(), vector(event.normal));
+    map.insert("impulse".into(), Dynamic::from(event.impulse as f64));
+    map
+}
+
 /// Create an input module with current input state
 fn create_input_module(input_state: &ScriptInputState) -> Module {
     let mut module = Module::new();
@@ -5,8 +5,23 @@
 /// Basic vertex and fragment shader for 3D rendering
 pub const BASIC_SHADER: &str = include_str!("basic.wgsl");
 
+/// PBR shader (metallic-roughness) with albedo, normal and ORM textures
+pub const PBR_SHADER: &str = include_str!("pbr.wgsl");
+
 /// Outline shader for selection highlighting
 pub const OUTLINE_SHADER: &str = include_str!("outline.wgsl");
 
 /// Debug lines shader for visualizing colliders and debug geometry
 pub const DEBUG_LINES_SHADER: &str = include_str!("debug_lines.wgsl");
+
+/// Depth-only shader for rendering shadow maps
+pub const SHADOW_SHADER: &str = include_str!("shadow.wgsl");
+
+/// Fullscreen post-processing passes (bloom, tonemapping, FXAA)
+pub const POST_PROCESS_SHADER: &str = include_str!("post_process.wgsl");
+
+/// Skybox drawn behind the scene from the environment cubemap
+pub const SKYBOX_SHADER: &str = include_str!("skybox.wgsl");
+
+/// Entity ID shader for GPU picking
+pub const PICK_SHADER: &str = include_str!("pick.wgsl");
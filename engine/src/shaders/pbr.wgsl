@@ -0,0 +1,394 @@
+// PBR vertex and fragment shaders (glTF metallic-roughness model)
+
+// Camera uniform buffer containing view-projection matrix and render space position
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    position: vec4<f32>,
+};
+
+// Material factors, multiplied with the material textures. The base color
+// comes from the instance data.
+struct MaterialUniform {
+    emissive: vec4<f32>,
+    metallic: f32,
+    roughness: f32,
+    _padding: vec2<f32>,
+};
+
+// Scene light, positions are camera-relative like object positions
+struct Light {
+    position: vec3<f32>,
+    light_type: u32,
+    direction: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+    cos_inner: f32,
+    cos_outer: f32,
+    // First shadow map layer, negative when the light casts no shadows
+    shadow_index: i32,
+    shadow_bias: f32,
+    // Far distance of each directional light cascade
+    cascade_splits: vec4<f32>,
+    shadow_normal_bias: f32,
+    cascade_count: u32,
+    _padding: vec2<f32>,
+};
+
+// Lights gathered by the renderer each frame
+struct LightBuffer {
+    count: u32,
+    ambient: vec3<f32>,
+    lights: array<Light>,
+};
+
+// Image based lighting from the skybox, see EnvironmentUniform in skybox.rs
+struct EnvironmentUniform {
+    // Diffuse irradiance spherical harmonics, premultiplied by 1 / pi
+    sh: array<vec4<f32>, 9>,
+    // Cosine and sine of the environment rotation around Y
+    rotation: vec2<f32>,
+    intensity: f32,
+    // Cubemap mip level for fully rough surfaces
+    max_lod: f32,
+    // Nonzero when the environment replaces the flat ambient light
+    enabled: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+};
+
+// Bind groups
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+@group(0) @binding(1)
+var<storage, read> light_buffer: LightBuffer;
+@group(0) @binding(2)
+var shadow_maps: texture_depth_2d_array;
+@group(0) @binding(3)
+var shadow_sampler: sampler_comparison;
+@group(0) @binding(4)
+var<storage, read> shadow_matrices: array<mat4x4<f32>>;
+@group(0) @binding(5)
+var<uniform> environment: EnvironmentUniform;
+@group(0) @binding(6)
+var environment_map: texture_cube<f32>;
+@group(0) @binding(7)
+var environment_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> material: MaterialUniform;
+@group(1) @binding(1)
+var albedo_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var normal_texture: texture_2d<f32>;
+@group(1) @binding(3)
+var orm_texture: texture_2d<f32>;
+@group(1) @binding(4)
+var material_sampler: sampler;
+
+const PI: f32 = 3.14159265359;
+
+// Light types, must match the LIGHT_TYPE_* constants in light.rs
+const LIGHT_TYPE_DIRECTIONAL: u32 = 0u;
+const LIGHT_TYPE_POINT: u32 = 1u;
+const LIGHT_TYPE_SPOT: u32 = 2u;
+
+// Vertex input structure
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+// Per-instance data: model matrix columns and base color
+struct InstanceInput {
+    @location(3) model_0: vec4<f32>,
+    @location(4) model_1: vec4<f32>,
+    @location(5) model_2: vec4<f32>,
+    @location(6) model_3: vec4<f32>,
+    @location(7) color: vec4<f32>,
+};
+
+// Vertex output / Fragment input structure
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) color: vec4<f32>,
+};
+
+// Vertex shader
+@vertex
+fn vs_main(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    let world_position = model * vec4<f32>(in.position, 1.0);
+    out.world_position = world_position.xyz;
+
+    // Assuming uniform scale, so the model matrix can transform normals directly
+    out.world_normal = normalize((model * vec4<f32>(in.normal, 0.0)).xyz);
+
+    out.uv = in.uv;
+    out.color = instance.color;
+    out.clip_position = camera.view_proj * world_position;
+
+    return out;
+}
+
+// GGX / Trowbridge-Reitz normal distribution
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// Smith geometry term with Schlick-GGX
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let r = roughness + 1.0;
+    let k = (r * r) / 8.0;
+    let g_v = n_dot_v / (n_dot_v * (1.0 - k) + k);
+    let g_l = n_dot_l / (n_dot_l * (1.0 - k) + k);
+    return g_v * g_l;
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (vec3<f32>(1.0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Surface parameters shared by all lights
+struct Surface {
+    albedo: vec3<f32>,
+    normal: vec3<f32>,
+    view: vec3<f32>,
+    f0: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+};
+
+// Cook-Torrance BRDF for one light, returns outgoing radiance
+fn shade_light(surface: Surface, light_dir: vec3<f32>, radiance: vec3<f32>) -> vec3<f32> {
+    let l = normalize(light_dir);
+    let h = normalize(surface.view + l);
+
+    let n_dot_l = max(dot(surface.normal, l), 0.0);
+    let n_dot_v = max(dot(surface.normal, surface.view), 1e-4);
+    let n_dot_h = max(dot(surface.normal, h), 0.0);
+    let h_dot_v = max(dot(h, surface.view), 0.0);
+
+    let d = distribution_ggx(n_dot_h, surface.roughness);
+    let g = geometry_smith(n_dot_v, n_dot_l, surface.roughness);
+    let f = fresnel_schlick(h_dot_v, surface.f0);
+
+    let specular = d * g * f / (4.0 * n_dot_v * n_dot_l + 1e-4);
+    let k_d = (vec3<f32>(1.0) - f) * (1.0 - surface.metallic);
+    let diffuse = k_d * surface.albedo / PI;
+
+    return (diffuse + specular) * radiance * n_dot_l;
+}
+
+// Smooth inverse square falloff that reaches zero at the light range
+fn range_attenuation(distance: f32, range: f32) -> f32 {
+    let ratio = distance / range;
+    let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / max(distance * distance, 1e-4);
+}
+
+// Filtered shadow lookup in one shadow map layer, 1.0 is fully lit
+fn sample_shadow_layer(layer: u32, world_position: vec3<f32>, bias: f32) -> f32 {
+    let clip = shadow_matrices[layer] * vec4<f32>(world_position, 1.0);
+    if (clip.w <= 0.0) {
+        return 1.0;
+    }
+    let ndc = clip.xyz / clip.w;
+    let uv = ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5);
+    if (any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0) {
+        return 1.0;
+    }
+
+    // 3x3 percentage closer filtering
+    let texel = 1.0 / vec2<f32>(textureDimensions(shadow_maps));
+    let depth = ndc.z - bias;
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_maps, shadow_sampler, uv + offset, layer, depth);
+        }
+    }
+    return lit / 9.0;
+}
+
+// Shadow factor of a light at a surface point, 1.0 is fully lit
+fn light_shadow(light: Light, world_position: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if (light.shadow_index < 0) {
+        return 1.0;
+    }
+
+    // Push the lookup position off the surface against self shadowing
+    let position = world_position + normal * light.shadow_normal_bias;
+    var layer = u32(light.shadow_index);
+
+    if (light.light_type == LIGHT_TYPE_DIRECTIONAL) {
+        // The camera sits at the origin, pick the first cascade reaching this far
+        let distance = length(world_position);
+        var cascade = 0u;
+        while (cascade < light.cascade_count && distance > light.cascade_splits[cascade]) {
+            cascade++;
+        }
+        if (cascade >= light.cascade_count) {
+            return 1.0;
+        }
+        layer += cascade;
+    }
+
+    return sample_shadow_layer(layer, position, light.shadow_bias);
+}
+
+// Outgoing radiance from one scene light
+fn shade_scene_light(
+    surface: Surface,
+    light: Light,
+    world_position: vec3<f32>,
+    geometric_normal: vec3<f32>,
+) -> vec3<f32> {
+    let radiance = light.color * light.intensity * light_shadow(light, world_position, geometric_normal);
+
+    if (light.light_type == LIGHT_TYPE_DIRECTIONAL) {
+        return shade_light(surface, -light.direction, radiance);
+    }
+
+    let to_light = light.position - world_position;
+    let distance = length(to_light);
+    let light_dir = to_light / max(distance, 1e-4);
+    var attenuation = range_attenuation(distance, light.range);
+
+    if (light.light_type == LIGHT_TYPE_SPOT) {
+        let cos_angle = dot(-light_dir, light.direction);
+        attenuation *= smoothstep(light.cos_outer, max(light.cos_inner, light.cos_outer + 1e-4), cos_angle);
+    }
+
+    return shade_light(surface, light_dir, radiance * attenuation);
+}
+
+// Rotate a world direction into the space of the environment map
+fn environment_direction(direction: vec3<f32>) -> vec3<f32> {
+    let c = environment.rotation.x;
+    let s = environment.rotation.y;
+    return vec3<f32>(c * direction.x - s * direction.z, direction.y, s * direction.x + c * direction.z);
+}
+
+// Irradiance / pi arriving at a surface with this normal
+fn environment_irradiance(normal: vec3<f32>) -> vec3<f32> {
+    let n = environment_direction(normal);
+    var result = environment.sh[0].rgb * 0.282095;
+    result += environment.sh[1].rgb * (0.488603 * n.y);
+    result += environment.sh[2].rgb * (0.488603 * n.z);
+    result += environment.sh[3].rgb * (0.488603 * n.x);
+    result += environment.sh[4].rgb * (1.092548 * n.x * n.y);
+    result += environment.sh[5].rgb * (1.092548 * n.y * n.z);
+    result += environment.sh[6].rgb * (0.315392 * (3.0 * n.z * n.z - 1.0));
+    result += environment.sh[7].rgb * (1.092548 * n.x * n.z);
+    result += environment.sh[8].rgb * (0.546274 * (n.x * n.x - n.y * n.y));
+    return max(result, vec3<f32>(0.0));
+}
+
+// Analytic fit of the split-sum environment BRDF (Karis), avoids a lookup texture
+fn environment_brdf(f0: vec3<f32>, roughness: f32, n_dot_v: f32) -> vec3<f32> {
+    let c0 = vec4<f32>(-1.0, -0.0275, -0.572, 0.022);
+    let c1 = vec4<f32>(1.0, 0.0425, 1.04, -0.04);
+    let r = roughness * c0 + c1;
+    let a004 = min(r.x * r.x, exp2(-9.28 * n_dot_v)) * r.x + r.y;
+    let ab = vec2<f32>(-1.04, 1.04) * a004 + r.zw;
+    return f0 * ab.x + ab.y;
+}
+
+// Ambient light from the environment: irradiance for the diffuse part,
+// the cubemap mip matching the roughness for the specular part
+fn environment_light(surface: Surface) -> vec3<f32> {
+    let n_dot_v = max(dot(surface.normal, surface.view), 1e-4);
+    let specular_color = environment_brdf(surface.f0, surface.roughness, n_dot_v);
+
+    let reflected = reflect(-surface.view, surface.normal);
+    let lod = surface.roughness * environment.max_lod;
+    let prefiltered = textureSampleLevel(
+        environment_map,
+        environment_sampler,
+        environment_direction(reflected),
+        lod,
+    ).rgb;
+
+    let k_d = (vec3<f32>(1.0) - specular_color) * (1.0 - surface.metallic);
+    let diffuse = k_d * surface.albedo * environment_irradiance(surface.normal);
+    return (diffuse + prefiltered * specular_color) * environment.intensity;
+}
+
+// Build a tangent frame from screen-space derivatives so normal maps work
+// without per-vertex tangents
+fn cotangent_frame(
+    normal: vec3<f32>,
+    dp1: vec3<f32>,
+    dp2: vec3<f32>,
+    duv1: vec2<f32>,
+    duv2: vec2<f32>,
+) -> mat3x3<f32> {
+    let dp2_perp = cross(dp2, normal);
+    let dp1_perp = cross(normal, dp1);
+    let tangent = dp2_perp * duv1.x + dp1_perp * duv2.x;
+    let bitangent = dp2_perp * duv1.y + dp1_perp * duv2.y;
+    let scale_sq = max(dot(tangent, tangent), dot(bitangent, bitangent));
+    if (scale_sq < 1e-12) {
+        // Degenerate UVs: no usable frame, keep the geometric normal
+        return mat3x3<f32>(vec3<f32>(0.0), vec3<f32>(0.0), normal);
+    }
+    let inv_scale = inverseSqrt(scale_sq);
+    return mat3x3<f32>(tangent * inv_scale, bitangent * inv_scale, normal);
+}
+
+// Fragment shader
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Sample textures and derivatives in uniform control flow
+    let albedo_sample = textureSample(albedo_texture, material_sampler, in.uv);
+    let normal_sample = textureSample(normal_texture, material_sampler, in.uv).xyz * 2.0 - 1.0;
+    let orm_sample = textureSample(orm_texture, material_sampler, in.uv);
+    let dp1 = dpdx(in.world_position);
+    let dp2 = dpdy(in.world_position);
+    let duv1 = dpdx(in.uv);
+    let duv2 = dpdy(in.uv);
+
+    let base_color = in.color * albedo_sample;
+    let occlusion = orm_sample.r;
+    let roughness = clamp(material.roughness * orm_sample.g, 0.04, 1.0);
+    let metallic = clamp(material.metallic * orm_sample.b, 0.0, 1.0);
+
+    let geometric_normal = normalize(in.world_normal);
+    let tbn = cotangent_frame(geometric_normal, dp1, dp2, duv1, duv2);
+    let normal = normalize(tbn * vec3<f32>(normal_sample.xy, max(normal_sample.z, 1e-3)));
+
+    var surface: Surface;
+    surface.albedo = base_color.rgb;
+    surface.normal = normal;
+    surface.view = normalize(camera.position.xyz - in.world_position);
+    surface.f0 = mix(vec3<f32>(0.04), base_color.rgb, metallic);
+    surface.metallic = metallic;
+    surface.roughness = roughness;
+
+    var direct = vec3<f32>(0.0);
+    let light_count = min(light_buffer.count, arrayLength(&light_buffer.lights));
+    for (var i = 0u; i < light_count; i++) {
+        direct += shade_scene_light(surface, light_buffer.lights[i], in.world_position, geometric_normal);
+    }
+
+    var ambient = light_buffer.ambient * base_color.rgb;
+    if (environment.enabled != 0u) {
+        ambient = environment_light(surface);
+    }
+    ambient *= occlusion;
+    let result = ambient + direct + material.emissive.rgb;
+
+    return vec4<f32>(result, base_color.a);
+}
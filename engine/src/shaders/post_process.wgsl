@@ -0,0 +1,220 @@
+// Fullscreen post-processing passes: bloom, tonemapping and FXAA
+
+// Settings shared by all passes, see PostProcessUniform in post_process.rs
+struct PostProcessUniform {
+    exposure: f32,
+    gamma: f32,
+    tonemapping: u32,
+    bloom_intensity: f32,
+    bloom_threshold: f32,
+    bloom_knee: f32,
+    _padding: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> settings: PostProcessUniform;
+@group(0) @binding(1)
+var linear_sampler: sampler;
+
+// Texture read by the pass, plus the bloom texture for the composite pass
+@group(1) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var bloom_texture: texture_2d<f32>;
+
+// Whether the render target encodes to sRGB itself
+override OUTPUT_SRGB: bool = true;
+
+// Tonemapping operators, must match the Tonemapping enum in post_process.rs
+const TONEMAPPING_NONE: u32 = 0u;
+const TONEMAPPING_REINHARD: u32 = 1u;
+const TONEMAPPING_ACES: u32 = 2u;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// Single triangle covering the whole screen
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// 13-tap downsample filter, keeps small bright spots from flickering
+fn downsample(uv: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let a = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(-2.0, -2.0), 0.0).rgb;
+    let b = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(0.0, -2.0), 0.0).rgb;
+    let c = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(2.0, -2.0), 0.0).rgb;
+    let d = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(-2.0, 0.0), 0.0).rgb;
+    let e = textureSampleLevel(source_texture, linear_sampler, uv, 0.0).rgb;
+    let f = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(2.0, 0.0), 0.0).rgb;
+    let g = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(-2.0, 2.0), 0.0).rgb;
+    let h = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(0.0, 2.0), 0.0).rgb;
+    let i = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(2.0, 2.0), 0.0).rgb;
+    let j = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(-1.0, -1.0), 0.0).rgb;
+    let k = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(1.0, -1.0), 0.0).rgb;
+    let l = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(-1.0, 1.0), 0.0).rgb;
+    let m = textureSampleLevel(source_texture, linear_sampler, uv + texel * vec2<f32>(1.0, 1.0), 0.0).rgb;
+
+    var color = e * 0.125;
+    color += (a + c + g + i) * 0.03125;
+    color += (b + d + f + h) * 0.0625;
+    color += (j + k + l + m) * 0.125;
+    return color;
+}
+
+// Keep only the part of the color above the bloom threshold, with a soft knee
+fn bloom_threshold(color: vec3<f32>) -> vec3<f32> {
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = settings.bloom_knee;
+    var soft = clamp(brightness - settings.bloom_threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-5);
+    let contribution = max(soft, brightness - settings.bloom_threshold) / max(brightness, 1e-5);
+    return color * contribution;
+}
+
+// First bloom pass: exposed scene color above the threshold, at half resolution
+@fragment
+fn fs_bloom_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = downsample(in.uv) * settings.exposure;
+    return vec4<f32>(bloom_threshold(color), 1.0);
+}
+
+@fragment
+fn fs_bloom_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(downsample(in.uv), 1.0);
+}
+
+// 3x3 tent filter, blended additively onto the next larger bloom level
+@fragment
+fn fs_bloom_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    var color = textureSampleLevel(source_texture, linear_sampler, in.uv, 0.0).rgb * 4.0;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(-1.0, 0.0), 0.0).rgb * 2.0;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(1.0, 0.0), 0.0).rgb * 2.0;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(0.0, -1.0), 0.0).rgb * 2.0;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(0.0, 1.0), 0.0).rgb * 2.0;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(-1.0, -1.0), 0.0).rgb;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(1.0, -1.0), 0.0).rgb;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(-1.0, 1.0), 0.0).rgb;
+    color += textureSampleLevel(source_texture, linear_sampler, in.uv + texel * vec2<f32>(1.0, 1.0), 0.0).rgb;
+    return vec4<f32>(color / 16.0, 1.0);
+}
+
+// Narkowicz's fit of the ACES filmic curve
+fn tonemap_aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    switch settings.tonemapping {
+        case TONEMAPPING_REINHARD: {
+            return color / (vec3<f32>(1.0) + luminance(color));
+        }
+        case TONEMAPPING_ACES: {
+            return tonemap_aces(color);
+        }
+        default: {
+            return clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+    }
+}
+
+// Exposure, bloom, tonemapping and gamma from the HDR scene to display color
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSampleLevel(source_texture, linear_sampler, in.uv, 0.0);
+    let bloom = textureSampleLevel(bloom_texture, linear_sampler, in.uv, 0.0).rgb;
+
+    var color = scene.rgb * settings.exposure + bloom * settings.bloom_intensity;
+    color = tonemap(max(color, vec3<f32>(0.0)));
+
+    // A gamma of 2.2 leaves the sRGB encoding unchanged
+    color = pow(color, vec3<f32>(2.2 / settings.gamma));
+
+    if (!OUTPUT_SRGB) {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+// FXAA on the sRGB encoded composite output
+const FXAA_EDGE_THRESHOLD: f32 = 0.125;
+const FXAA_EDGE_THRESHOLD_MIN: f32 = 0.0312;
+const FXAA_SPAN_MAX: f32 = 8.0;
+const FXAA_REDUCE_MUL: f32 = 0.125;
+const FXAA_REDUCE_MIN: f32 = 0.0078125;
+
+fn fxaa_luma(uv: vec2<f32>) -> f32 {
+    return luminance(textureSampleLevel(source_texture, linear_sampler, uv, 0.0).rgb);
+}
+
+@fragment
+fn fs_fxaa(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let center = textureSampleLevel(source_texture, linear_sampler, in.uv, 0.0).rgb;
+
+    let luma_nw = fxaa_luma(in.uv + texel * vec2<f32>(-1.0, -1.0));
+    let luma_ne = fxaa_luma(in.uv + texel * vec2<f32>(1.0, -1.0));
+    let luma_sw = fxaa_luma(in.uv + texel * vec2<f32>(-1.0, 1.0));
+    let luma_se = fxaa_luma(in.uv + texel * vec2<f32>(1.0, 1.0));
+    let luma_m = luminance(center);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var color = center;
+    if (luma_max - luma_min >= max(FXAA_EDGE_THRESHOLD_MIN, luma_max * FXAA_EDGE_THRESHOLD)) {
+        // Blur along the edge direction
+        var dir = vec2<f32>(
+            -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+            (luma_nw + luma_sw) - (luma_ne + luma_se),
+        );
+        let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * FXAA_REDUCE_MUL, FXAA_REDUCE_MIN);
+        let inverse_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+        dir = clamp(dir * inverse_dir_min, vec2<f32>(-FXAA_SPAN_MAX), vec2<f32>(FXAA_SPAN_MAX)) * texel;
+
+        let rgb_a = 0.5 * (
+            textureSampleLevel(source_texture, linear_sampler, in.uv + dir * (1.0 / 3.0 - 0.5), 0.0).rgb +
+            textureSampleLevel(source_texture, linear_sampler, in.uv + dir * (2.0 / 3.0 - 0.5), 0.0).rgb
+        );
+        let rgb_b = rgb_a * 0.5 + 0.25 * (
+            textureSampleLevel(source_texture, linear_sampler, in.uv - dir * 0.5, 0.0).rgb +
+            textureSampleLevel(source_texture, linear_sampler, in.uv + dir * 0.5, 0.0).rgb
+        );
+        let luma_b = luminance(rgb_b);
+        color = select(rgb_b, rgb_a, luma_b < luma_min || luma_b > luma_max);
+    }
+
+    if (OUTPUT_SRGB) {
+        color = srgb_to_linear(color);
+    }
+    return vec4<f32>(color, 1.0);
+}
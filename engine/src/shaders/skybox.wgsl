@@ -0,0 +1,55 @@
+// Skybox drawn behind the scene from the environment cubemap
+
+// See SkyboxUniform in skybox.rs
+struct SkyboxUniform {
+    // Camera orientation, turns view directions into world directions
+    camera_rotation: mat4x4<f32>,
+    // Half extents of the view plane at distance 1
+    view_scale: vec2<f32>,
+    // Cosine and sine of the skybox rotation around Y
+    rotation: vec2<f32>,
+    intensity: f32,
+    _padding0: f32,
+    _padding1: f32,
+    _padding2: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> skybox: SkyboxUniform;
+@group(0) @binding(1)
+var environment_map: texture_cube<f32>;
+@group(0) @binding(2)
+var environment_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+// Single triangle covering the whole screen. The view direction is rebuilt
+// from the camera rotation and field of view alone, so neither the depth
+// encoding nor extreme near/far ratios affect the sky.
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    let ndc = uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0);
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+// Rotate a world direction into the space of the environment map
+fn environment_direction(direction: vec3<f32>) -> vec3<f32> {
+    let c = skybox.rotation.x;
+    let s = skybox.rotation.y;
+    return vec3<f32>(c * direction.x - s * direction.z, direction.y, s * direction.x + c * direction.z);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let view_direction = vec3<f32>(in.ndc * skybox.view_scale, -1.0);
+    let direction = normalize((skybox.camera_rotation * vec4<f32>(view_direction, 0.0)).xyz);
+    let color = textureSampleLevel(environment_map, environment_sampler, environment_direction(direction), 0.0).rgb;
+    return vec4<f32>(color * skybox.intensity, 1.0);
+}
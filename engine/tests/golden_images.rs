@@ -0,0 +1,218 @@
+//! Golden-image regression tests for the renderer
+//!
+//! Each test renders a small scene headlessly and compares it against a
+//! reference PNG in `tests/golden`. Missing references are written on the
+//! first run; set `UPDATE_GOLDEN=1` to regenerate them after an intended
+//! rendering change. Machines without any adapter, not even a software one,
+//! skip the tests.
+
+use engine::graphics::{CaptureError, CapturedImage, RenderContext};
+use engine::prelude::*;
+use glam::EulerRot;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const IMAGE_SIZE: u32 = 128;
+
+/// Largest per-channel difference still treated as equal
+const CHANNEL_TOLERANCE: u8 = 8;
+
+/// Fraction of pixels allowed to differ, absorbs edge rasterization differences
+const MAX_MISMATCH_RATIO: f64 = 0.005;
+
+fn create_renderer() -> Option<Renderer> {
+    match pollster::block_on(RenderContext::new_headless()) {
+        Ok(context) => Some(Renderer::new(Arc::new(context))),
+        Err(e) => {
+            eprintln!("Skipping golden image test, no adapter available: {e}");
+            None
+        }
+    }
+}
+
+fn spawn_object(world: &mut World, mesh: &str, material: Material, transform: Transform) {
+    world.spawn((
+        MeshId(mesh.to_string()),
+        material,
+        GlobalTransform::from_matrix(transform.to_matrix()),
+        transform,
+    ));
+}
+
+fn spawn_camera(world: &mut World, position: Vec3, target: Vec3) -> Entity {
+    let transform = Transform::from_position_rotation(
+        position,
+        Quat::from_mat4(&Mat4::look_at_rh(position, target, Vec3::Y).inverse()),
+    );
+    world.spawn((
+        Camera::perspective(60.0, 1.0, 0.1, 100.0),
+        GlobalTransform::from_matrix(transform.to_matrix()),
+        transform,
+    ))
+}
+
+/// Ground plane with a red cube, lit by a shadow casting sun
+fn cube_scene() -> World {
+    let mut world = World::new();
+
+    spawn_object(
+        &mut world,
+        "plane",
+        Material::gray(0.8),
+        Transform::default().with_scale(Vec3::splat(5.0)),
+    );
+    spawn_object(
+        &mut world,
+        "cube",
+        Material::red().with_metallic_roughness(0.0, 0.6),
+        Transform::from_position(Vec3::new(0.0, 0.5, 0.0)),
+    );
+
+    let sun = Transform::from_position_rotation(
+        Vec3::ZERO,
+        Quat::from_euler(EulerRot::YXZ, 0.6, -0.9, 0.0),
+    );
+    world.spawn((
+        DirectionalLight::default(),
+        GlobalTransform::from_matrix(sun.to_matrix()),
+        sun,
+    ));
+
+    world
+}
+
+/// Equirectangular sky image, blue above the horizon and brown below
+fn write_sky_image(dir: &Path) -> PathBuf {
+    let (width, height) = (64, 32);
+    let mut pixels = Vec::new();
+    for y in 0..height {
+        let color = if y < height / 2 {
+            [90, 150, 230, 255]
+        } else {
+            [110, 80, 50, 255]
+        };
+        for _ in 0..width {
+            pixels.extend_from_slice(&color);
+        }
+    }
+
+    let path = dir.join("sky.png");
+    CapturedImage {
+        width,
+        height,
+        pixels,
+    }
+    .save_png(&path)
+    .unwrap();
+    path
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+/// Compare a capture against its golden image, writing it when missing
+fn assert_golden(name: &str, image: &CapturedImage) {
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() || !path.exists() {
+        image.save_png(&path).unwrap();
+        eprintln!("Wrote golden image {}", path.display());
+        return;
+    }
+
+    let golden = CapturedImage::load_png(&path).unwrap();
+    let diff = image.compare(&golden, CHANNEL_TOLERANCE);
+    let allowed = (image.pixels.len() / 4) as f64 * MAX_MISMATCH_RATIO;
+    if !diff.size_matches || diff.mismatched_pixels as f64 > allowed {
+        let actual_path = std::env::temp_dir().join(format!("{name}.actual.png"));
+        image.save_png(&actual_path).unwrap();
+        panic!(
+            "{name} differs from golden image: {} mismatched pixels, max difference {}, \
+             actual image saved to {}",
+            diff.mismatched_pixels,
+            diff.max_difference,
+            actual_path.display()
+        );
+    }
+}
+
+#[test]
+fn golden_lit_cube() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+
+    let mut world = cube_scene();
+    spawn_camera(
+        &mut world,
+        Vec3::new(3.0, 2.5, 4.0),
+        Vec3::new(0.0, 0.5, 0.0),
+    );
+
+    let image = renderer
+        .render_to_image(&world, None, IMAGE_SIZE, IMAGE_SIZE)
+        .unwrap();
+    assert_golden("lit_cube", &image);
+}
+
+#[test]
+fn golden_named_camera() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+
+    // The first camera is ignored in favor of the requested one
+    let mut world = cube_scene();
+    spawn_camera(
+        &mut world,
+        Vec3::new(3.0, 2.5, 4.0),
+        Vec3::new(0.0, 0.5, 0.0),
+    );
+    let top_camera = spawn_camera(&mut world, Vec3::new(0.0, 6.0, 0.1), Vec3::ZERO);
+
+    let image = renderer
+        .render_to_image(&world, Some(top_camera), IMAGE_SIZE * 2, IMAGE_SIZE)
+        .unwrap();
+    assert_eq!((image.width, image.height), (IMAGE_SIZE * 2, IMAGE_SIZE));
+    assert_golden("top_down_wide", &image);
+}
+
+#[test]
+fn golden_skybox_galaxy_camera() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut world = cube_scene();
+    world.spawn((Skybox::new(write_sky_image(dir.path()).to_string_lossy()),));
+
+    // Galaxy-scale near/far ratio, the sky still fills the background
+    let camera = spawn_camera(
+        &mut world,
+        Vec3::new(3.0, 2.5, 4.0),
+        Vec3::new(0.0, 0.5, 0.0),
+    );
+    world
+        .insert_one(camera, Camera::galaxy_scale(60.0, 1.0))
+        .unwrap();
+
+    let image = renderer
+        .render_to_image(&world, Some(camera), IMAGE_SIZE, IMAGE_SIZE)
+        .unwrap();
+    let sky = image.pixel(IMAGE_SIZE / 2, 0);
+    assert!(sky[2] > sky[0] + 50, "expected blue sky, got {sky:?}");
+    assert_golden("skybox_galaxy_camera", &image);
+}
+
+#[test]
+fn render_without_camera_fails() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+
+    let result = renderer.render_to_image(&cube_scene(), None, IMAGE_SIZE, IMAGE_SIZE);
+    assert!(matches!(result, Err(CaptureError::NoCamera)));
+}
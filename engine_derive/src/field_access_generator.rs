@@ -171,7 +171,8 @@ fn generate_set_field_arms(fields: &[(&Field, UIFieldAttribute, UIWidget)]) -> V
                                         false
                                     }
                                 }
-                            } else if ident_str.contains("64")
+                            } else if ident_str == "u32"
+                                || ident_str.contains("64")
                                 || ident_str.contains("16")
                                 || ident_str.contains("8")
                                 || ident_str == "isize"
@@ -147,14 +147,24 @@ impl GameApp {
             match operation {
                 SceneOperation::NewScene => {
                     debug!("Creating new default scene");
+                    editor_state.play_mode.discard();
                     editor_state.shared_state.with_world_write(|world| {
                         editor::scene_operations::create_default_scene(world, renderer);
                     });
+                    editor_state.shared_state.clear_history();
+                    editor_state.shared_state.clear_prefabs();
+                    editor_state.reset_editor_camera();
                 }
                 SceneOperation::LoadScene(path) => {
-                    let result = editor_state.shared_state.with_world_write(|world| {
-                        editor::scene_operations::load_scene_from_file(world, renderer, &path)
-                    });
+                    editor_state.play_mode.discard();
+                    let result = editor::scene_operations::load_scene(
+                        &editor_state.shared_state,
+                        renderer,
+                        &path,
+                    );
+                    // Entity handles from the previous scene are no longer valid
+                    editor_state.shared_state.clear_history();
+                    editor_state.reset_editor_camera();
                     match result.unwrap_or(Err("Failed to access world".into())) {
                         Ok(_) => info!("Scene loaded successfully"),
                         Err(e) => {
@@ -164,9 +174,8 @@ impl GameApp {
                     }
                 }
                 SceneOperation::SaveScene(path) => {
-                    let result = editor_state.shared_state.with_world_read(|world| {
-                        editor::scene_operations::save_scene_to_file(world, &path)
-                    });
+                    let result =
+                        editor::scene_operations::save_scene(&editor_state.shared_state, &path);
                     match result.unwrap_or(Err("Failed to access world".into())) {
                         Ok(_) => info!("Scene saved successfully"),
                         Err(e) => {
@@ -386,68 +395,35 @@ impl ApplicationHandler for GameApp {
                     // Clear per-frame input data
                     self.engine.input_state.clear_frame_data();
 
-                    let script_input_state = self.engine.input_state.to_script_input_state();
-
                     // Get references we need
-                    let editor_state = self.editor_state.as_ref().unwrap();
+                    let editor_state = self.editor_state.as_mut().unwrap();
+
+                    // Drop physics bodies and script lifecycle state from the
+                    // play session that just ended
+                    if editor_state.play_mode.take_reset_request() {
+                        if let Some(physics_world) = &mut self.engine.physics_world {
+                            physics_world.clear();
+                        }
+                        if let Ok(mut tracker) =
+                            engine::scripting::lifecycle_tracker::get_tracker().lock()
+                        {
+                            tracker.clear();
+                        }
+                    }
 
                     // First update hierarchy to ensure GlobalTransform components exist
                     editor_state.shared_state.with_world_write(|world| {
                         update_hierarchy_system(world);
                     });
 
-                    // Then handle scripting with the world
-                    if let Some(script_engine) = &mut self.engine.script_engine {
-                        editor_state.shared_state.with_world_write(|world| {
-                            // Initialize script properties for new scripts
-                            engine::scripting::script_initialization_system(world, script_engine);
-
-                            // Execute scripts
-                            engine::scripting::script_execution_system(
-                                world,
-                                script_engine,
-                                &script_input_state,
-                                delta_time,
-                            );
-                        });
-                    }
-
-                    // Update physics simulation
-                    if let Some(physics_world) = &mut self.engine.physics_world {
+                    // Scripts, physics and audio only run in play mode
+                    if let Some(delta_time) = editor_state.play_mode.update_delta(delta_time) {
+                        let engine = &mut self.engine;
                         editor_state.shared_state.with_world_write(|world| {
-                            engine::physics::system::physics_update_system(
-                                world,
-                                physics_world,
-                                delta_time,
-                            );
+                            engine.update_world(world, delta_time);
                         });
                     }
 
-                    // Update audio system
-                    #[cfg(feature = "audio")]
-                    if let Some(audio_engine) = &mut self.engine.audio_engine {
-                        editor_state.shared_state.with_world_write(|world| {
-                            engine::audio::system::audio_update_system(
-                                world,
-                                audio_engine,
-                                &mut self.engine.audio_system_state,
-                                delta_time,
-                            );
-                        });
-                    }
-
-                    // Update hierarchy system to maintain transform propagation
-                    editor_state.shared_state.with_world_write(|world| {
-                        update_hierarchy_system(world);
-                    });
-
-                    // Process mesh uploads from scripts
-                    if let (Some(script_engine), Some(renderer)) =
-                        (&mut self.engine.script_engine, &mut self.engine.renderer)
-                    {
-                        engine::scripting::process_script_mesh_uploads(script_engine, renderer);
-                    }
-
                     // Render with editor
                     self.render_frame(window_id);
                     return;